@@ -0,0 +1,195 @@
+//! Tokenizing and parsing the fixture corpus, and the latency of indexing it
+//! and of completions as a client sees them, through the server binary.
+
+use std::{
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use css_language_server::css::{parser::parse, token::tokenize};
+use serde_json::{Value, json};
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("benches/fixtures")
+}
+
+/// The stylesheets of the corpus, by file name.
+fn stylesheets() -> Vec<(String, String)> {
+    let mut sheets: Vec<(String, String)> = fs::read_dir(fixtures())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "css"))
+        .map(|path| {
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            (name, fs::read_to_string(&path).unwrap())
+        })
+        .collect();
+    sheets.sort();
+    sheets
+}
+
+fn uri(path: &Path) -> String {
+    format!("file://{}", path.display())
+}
+
+/// A server process driven over stdio.
+struct Server {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+}
+
+impl Server {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_css-language-server"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Self {
+            child,
+            stdin,
+            stdout,
+            next_id: 0,
+        }
+    }
+
+    fn send(&mut self, message: Value) {
+        let body = message.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{body}", body.len()).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            self.stdout.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("Content-Length: ") {
+                length = value.parse().unwrap();
+            }
+        }
+        let mut body = vec![0; length];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    /// Sends a request and waits for its response, skipping the
+    /// notifications sent meanwhile.
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        loop {
+            let message = self.receive();
+            if message.get("id") == Some(&json!(id)) && message.get("method").is_none() {
+                return message;
+            }
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    /// Initializes the server with the fixtures as the workspace folder,
+    /// which indexes them.
+    fn initialize(&mut self) {
+        let folder = uri(&fixtures());
+        let params = json!({
+            "capabilities": {},
+            "workspaceFolders": [{ "uri": folder, "name": "fixtures" }],
+        });
+        self.request("initialize", params);
+        self.notify("initialized", json!({}));
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn tokenizing_and_parsing(c: &mut Criterion) {
+    let sheets = stylesheets();
+    let mut group = c.benchmark_group("tokenize");
+    for (name, text) in &sheets {
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), text, |b, text| {
+            b.iter(|| tokenize(text))
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("parse");
+    for (name, text) in &sheets {
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), text, |b, text| {
+            b.iter(|| parse(text))
+        });
+    }
+    group.finish();
+}
+
+/// The time from `initialize` to its response, which is sent once the
+/// workspace folder is indexed. Starting the process is not counted.
+fn indexing(c: &mut Criterion) {
+    c.bench_function("index", |b| {
+        b.iter_custom(|iterations| {
+            let mut total = Duration::ZERO;
+            for _ in 0..iterations {
+                let mut server = Server::start();
+                let start = Instant::now();
+                server.initialize();
+                total += start.elapsed();
+            }
+            total
+        })
+    });
+}
+
+/// Round trips of completion requests for descriptors in `@font-face`, on an
+/// open document of the indexed folder.
+fn completion(c: &mut Criterion) {
+    let mut server = Server::start();
+    server.initialize();
+    let path = fixtures().join("components.css");
+    let mut text = fs::read_to_string(&path).unwrap();
+    let line = text.lines().position(|line| line.starts_with("@font-face")).unwrap() + 1;
+    let offset: usize = text.lines().take(line).map(|line| line.len() + 1).sum();
+    text.insert_str(offset, "  fo\n");
+    let document = uri(&path);
+    server.notify(
+        "textDocument/didOpen",
+        json!({ "textDocument": { "uri": document, "languageId": "css", "version": 1, "text": text } }),
+    );
+
+    let mut group = c.benchmark_group("completion");
+    group.bench_function("descriptors", |b| {
+        b.iter(|| {
+            let position = json!({ "line": line, "character": 4 });
+            server.request(
+                "textDocument/completion",
+                json!({ "textDocument": { "uri": document }, "position": position }),
+            )
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, tokenizing_and_parsing, indexing, completion);
+criterion_main!(benches);
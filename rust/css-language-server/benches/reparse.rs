@@ -0,0 +1,58 @@
+//! The cost of a keystroke in a large generated stylesheet, compared with
+//! parsing it from scratch.
+
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use css_language_server::css::{
+    Span,
+    parser::{parse, reparse},
+    symbols::{symbols, update},
+};
+
+/// About 50,000 lines of rules, media queries and custom properties, like the
+/// output of a utility class generator.
+fn stylesheet() -> String {
+    let mut text = String::from(":root {\n  --brand: #0b5;\n  --gap: 4px;\n}\n\n");
+    for i in 0..8_000 {
+        text.push_str(&format!(
+            ".u-{i}, .u-{i}:hover {{\n  margin: calc(var(--gap) * {i});\n  color: var(--brand);\n  animation: spin 1s;\n}}\n\n"
+        ));
+        if i % 10 == 0 {
+            text.push_str(&format!(
+                "@media (min-width: {i}px) {{\n  .u-{i} {{ display: none }}\n}}\n\n"
+            ));
+        }
+    }
+    text
+}
+
+fn keystroke(c: &mut Criterion) {
+    let text = stylesheet();
+    let middle = text[..text.len() / 2].rfind("color: ").unwrap() + "color: ".len();
+
+    c.bench_function("parse", |b| b.iter(|| parse(&text)));
+    c.bench_function("reparse", |b| {
+        b.iter_batched(
+            || parse(&text),
+            |sheet| reparse(sheet, Span::new(middle, middle), "x").0,
+            BatchSize::LargeInput,
+        )
+    });
+    c.bench_function("reparse and update symbols", |b| {
+        b.iter_batched(
+            || {
+                let sheet = parse(&text);
+                let symbols = symbols(&sheet);
+                (sheet, symbols)
+            },
+            |(sheet, mut symbols)| {
+                let (sheet, reparsed) = reparse(sheet, Span::new(middle, middle), "x");
+                update(&mut symbols, &sheet, reparsed);
+                (sheet, symbols)
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(benches, keystroke);
+criterion_main!(benches);
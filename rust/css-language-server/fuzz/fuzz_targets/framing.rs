@@ -0,0 +1,28 @@
+//! Reads arbitrary bytes as a stream of messages. Reading must neither panic
+//! nor stall, and every message read must survive being written and read
+//! again.
+
+#![no_main]
+
+use css_language_server::framing::parse_message;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    runtime.block_on(async {
+        let mut reader = data;
+        loop {
+            // Every call consumes at least one line, so this ends.
+            match parse_message(&mut reader).await {
+                Ok(Some(message)) => {
+                    let framed = message.to_string();
+                    let again = parse_message(&mut framed.as_bytes()).await.unwrap().unwrap();
+                    assert_eq!(again.body, message.body);
+                }
+                Ok(None) => break,
+                // Malformed frames are skipped, as the server does.
+                Err(_) => {}
+            }
+        }
+    });
+});
@@ -0,0 +1,61 @@
+//! Tokenizes, parses and prints arbitrary text, then reparses it after an
+//! arbitrary edit. Tokens must reproduce the text exactly, and reparsing only
+//! the edited rules must give the same stylesheet as parsing the edited text.
+
+#![no_main]
+
+use css_language_server::css::{
+    Span,
+    format::{format, minify},
+    parser::{parse, reparse},
+    token::tokenize,
+};
+use libfuzzer_sys::{
+    arbitrary::{self, Arbitrary},
+    fuzz_target,
+};
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    text: String,
+    start: usize,
+    len: usize,
+    replacement: String,
+}
+
+/// The largest char boundary of `text` at or before `offset`.
+fn boundary(text: &str, offset: usize) -> usize {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+fuzz_target!(|input: Input| {
+    let Input {
+        text,
+        start,
+        len,
+        replacement,
+    } = input;
+
+    let tokens = tokenize(&text);
+    let joined: String = tokens.iter().map(|token| token.text(&text)).collect();
+    assert_eq!(joined, text);
+    for pair in tokens.windows(2) {
+        assert_eq!(pair[0].span.end, pair[1].span.start);
+    }
+
+    let sheet = parse(&text);
+    assert_eq!(sheet.text, text);
+    format(&sheet, "  ");
+    minify(&sheet);
+
+    let start = boundary(&text, start);
+    let end = boundary(&text, start.saturating_add(len % 64));
+    let mut edited = text.clone();
+    edited.replace_range(start..end, &replacement);
+    let (reparsed, _) = reparse(sheet, Span::new(start, end), &replacement);
+    assert_eq!(format!("{reparsed:?}"), format!("{:?}", parse(&edited)));
+});
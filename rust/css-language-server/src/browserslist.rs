@@ -0,0 +1,804 @@
+//! Resolves the project's browserslist configuration to `Targets`.
+//!
+//! Queries are evaluated against a bundled snapshot of browser releases and
+//! usage, so no network access or Node installation is needed. Only the
+//! browsers in `Browser` are considered; queries for others select nothing.
+
+use std::{
+    collections::BTreeSet,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result, bail};
+use serde_json::Value;
+
+use crate::css::compat::{
+    Browser::{self, *},
+    Targets, Version,
+};
+
+/// Reads the browserslist query of the project at `root` from
+/// `.browserslistrc`, `browserslist` or the `browserslist` key of
+/// `package.json`, and resolves it.
+pub async fn load(root: &Path) -> Result<Option<Targets>> {
+    let Some(query) = read_query(root).await? else {
+        return Ok(None);
+    };
+    resolve(&query).map(Some)
+}
+
+async fn read_query(root: &Path) -> Result<Option<String>> {
+    for name in [".browserslistrc", "browserslist"] {
+        if let Ok(text) = tokio::fs::read_to_string(root.join(name)).await {
+            return Ok(Some(parse_config(&text)));
+        }
+    }
+    let Ok(text) = tokio::fs::read_to_string(root.join("package.json")).await else {
+        return Ok(None);
+    };
+    let package: Value = serde_json::from_str(&text).context("invalid package.json")?;
+    let query = match package.get("browserslist") {
+        // Environments are keyed by name; use the same default as browserslist.
+        Some(Value::Object(envs)) => envs.get("production").or_else(|| envs.get("defaults")),
+        other => other,
+    };
+    Ok(match query {
+        Some(Value::String(query)) => Some(query.clone()),
+        Some(Value::Array(queries)) => {
+            let queries: Vec<_> = queries.iter().filter_map(Value::as_str).collect();
+            Some(queries.join(", "))
+        }
+        _ => None,
+    })
+}
+
+/// Joins the queries of a `.browserslistrc` file. Only queries outside of
+/// `[env]` sections and in the `[production]` section are used.
+fn parse_config(text: &str) -> String {
+    let mut queries = vec![];
+    let mut active = true;
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            active = section.split_whitespace().any(|env| env == "production");
+        } else if active && !line.is_empty() {
+            queries.push(line);
+        }
+    }
+    queries.join(", ")
+}
+
+type Release = (Browser, Version);
+
+/// Resolves a query such as `> 0.5%, last 2 versions, not dead` to the oldest
+/// selected version of each browser.
+pub fn resolve(query: &str) -> Result<Targets> {
+    let selected = select(query)?;
+    let mut targets = Targets(Default::default());
+    for (browser, version) in selected {
+        targets.0.entry(browser).or_insert(version);
+    }
+    Ok(targets)
+}
+
+fn select(query: &str) -> Result<BTreeSet<Release>> {
+    let mut selected = BTreeSet::new();
+    for (i, clause) in clauses(query).into_iter().enumerate() {
+        match clause.strip_prefix("not ") {
+            Some(_) if i == 0 => bail!("`{clause}` cannot be the first query"),
+            Some(negated) => {
+                let excluded = intersection(negated)?;
+                selected.retain(|release| !excluded.contains(release));
+            }
+            None => selected.extend(intersection(&clause)?),
+        }
+    }
+    Ok(selected)
+}
+
+/// Splits a query on `,` and `or`, lowercasing each clause.
+fn clauses(query: &str) -> Vec<String> {
+    let query = query.to_ascii_lowercase();
+    query
+        .split(',')
+        .flat_map(|part| part.split(" or "))
+        .map(|clause| clause.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|clause| !clause.is_empty())
+        .collect()
+}
+
+fn intersection(clause: &str) -> Result<BTreeSet<Release>> {
+    let mut parts = clause.split(" and ");
+    let mut selected = atom(parts.next().unwrap_or_default())?;
+    for part in parts {
+        let selected_part = match part.strip_prefix("not ") {
+            Some(negated) => {
+                let excluded = atom(negated)?;
+                RELEASES
+                    .iter()
+                    .map(|&(browser, version, ..)| (browser, version))
+                    .filter(|release| !excluded.contains(release))
+                    .collect()
+            }
+            None => atom(part)?,
+        };
+        selected.retain(|release| selected_part.contains(release));
+    }
+    Ok(selected)
+}
+
+fn atom(query: &str) -> Result<BTreeSet<Release>> {
+    let words: Vec<&str> = query.split_whitespace().collect();
+    let releases = RELEASES.iter().map(|&(browser, version, ..)| (browser, version));
+    let selected = match words.as_slice() {
+        ["defaults"] => select("> 0.5%, last 2 versions, firefox esr, not dead")?,
+        // None of the bundled browsers is dead, and none is unreleased.
+        ["dead"] | ["unreleased", "versions"] => BTreeSet::new(),
+        ["firefox" | "ff", "esr"] => releases.filter(|release| FIREFOX_ESR.contains(release)).collect(),
+        ["last", n, "versions"] => last_all(count(n)?, false),
+        ["last", n, "major", "versions"] => last_all(count(n)?, true),
+        ["last", n, name, "versions"] => match browser(name)? {
+            Some(browser) => last(browser, count(n)?, false),
+            None => BTreeSet::new(),
+        },
+        ["last", n, name, "major", "versions"] => match browser(name)? {
+            Some(browser) => last(browser, count(n)?, true),
+            None => BTreeSet::new(),
+        },
+        ["last", n, "years" | "year"] => {
+            let days = count(n)? as u32 * 365;
+            let since = date_before(today(), days);
+            releases_since(since)
+        }
+        ["since", date] => releases_since(parse_date(date)?),
+        [op @ (">" | ">=" | "<" | "<="), usage] => {
+            let usage: f32 = usage
+                .strip_suffix('%')
+                .and_then(|usage| usage.parse().ok())
+                .with_context(|| format!("invalid usage in `{query}`"))?;
+            RELEASES
+                .iter()
+                .filter(|(.., share)| compare(op, *share, usage))
+                .map(|&(browser, version, ..)| (browser, version))
+                .collect()
+        }
+        [name, op @ (">" | ">=" | "<" | "<="), version] => {
+            let Some(browser) = browser(name)? else {
+                return Ok(BTreeSet::new());
+            };
+            let version = parse_version(version)?;
+            releases
+                .filter(|&(b, v)| b == browser && compare(op, v, version))
+                .collect()
+        }
+        [name, "all"] => match browser(name)? {
+            Some(browser) => releases.filter(|&(b, _)| b == browser).collect(),
+            None => BTreeSet::new(),
+        },
+        [name, version] => {
+            let Some(browser) = browser(name)? else {
+                return Ok(BTreeSet::new());
+            };
+            let (from, to) = match version.split_once('-') {
+                Some((from, to)) => (parse_version(from)?, parse_version(to)?),
+                None => {
+                    let version = parse_version(version)?;
+                    (version, version)
+                }
+            };
+            // A major version such as `safari 15` covers its minor releases.
+            let to = match version.contains('.') {
+                true => to,
+                false => Version(to.0, u16::MAX),
+            };
+            releases
+                .filter(|&(b, v)| b == browser && from <= v && v <= to)
+                .collect()
+        }
+        _ => bail!("unsupported browserslist query `{query}`"),
+    };
+    Ok(selected)
+}
+
+fn last_all(n: usize, major: bool) -> BTreeSet<Release> {
+    [Chrome, Edge, Firefox, Safari, IosSafari]
+        .into_iter()
+        .flat_map(|browser| last(browser, n, major))
+        .collect()
+}
+
+/// The last `n` releases of `browser`, or its releases with the last `n` major
+/// versions.
+fn last(browser: Browser, n: usize, major: bool) -> BTreeSet<Release> {
+    let versions: Vec<Version> = RELEASES
+        .iter()
+        .filter(|(b, ..)| *b == browser)
+        .map(|(_, version, ..)| *version)
+        .collect();
+    let oldest = match major {
+        true => {
+            let mut majors: Vec<u16> = versions.iter().map(|v| v.0).collect();
+            majors.dedup();
+            let Some(&major) = majors.iter().rev().take(n).next_back() else {
+                return BTreeSet::new();
+            };
+            Version(major, 0)
+        }
+        false => {
+            let Some(&oldest) = versions.iter().rev().take(n).next_back() else {
+                return BTreeSet::new();
+            };
+            oldest
+        }
+    };
+    versions
+        .into_iter()
+        .filter(|version| *version >= oldest)
+        .map(|version| (browser, version))
+        .collect()
+}
+
+fn releases_since(date: u32) -> BTreeSet<Release> {
+    RELEASES
+        .iter()
+        .filter(|(_, _, released, _)| *released >= date)
+        .map(|&(browser, version, ..)| (browser, version))
+        .collect()
+}
+
+fn compare<T: PartialOrd>(op: &str, a: T, b: T) -> bool {
+    match op {
+        ">" => a > b,
+        ">=" => a >= b,
+        "<" => a < b,
+        _ => a <= b,
+    }
+}
+
+/// Resolves a browser name. Browsers that browserslist knows but that have no
+/// bundled data resolve to `None`.
+fn browser(name: &str) -> Result<Option<Browser>> {
+    if let Some(browser) = Browser::from_name(name) {
+        return Ok(Some(browser));
+    }
+    match OTHER_BROWSERS.contains(&name) {
+        true => Ok(None),
+        false => bail!("unknown browser `{name}`"),
+    }
+}
+
+fn count(n: &str) -> Result<usize> {
+    n.parse().with_context(|| format!("invalid number `{n}`"))
+}
+
+fn parse_version(version: &str) -> Result<Version> {
+    version
+        .parse()
+        .map_err(|()| anyhow::anyhow!("invalid version `{version}`"))
+}
+
+/// Parses `YYYY`, `YYYY-MM` or `YYYY-MM-DD` to `YYYYMMDD`.
+fn parse_date(date: &str) -> Result<u32> {
+    let mut parts = date.split('-').map(|part| part.parse::<u32>());
+    let invalid = || anyhow::anyhow!("invalid date `{date}`");
+    let year = parts.next().ok_or_else(invalid)?.map_err(|_| invalid())?;
+    let month = parts.next().unwrap_or(Ok(1)).map_err(|_| invalid())?;
+    let day = parts.next().unwrap_or(Ok(1)).map_err(|_| invalid())?;
+    Ok(year * 10000 + month * 100 + day)
+}
+
+/// Today as `YYYYMMDD`.
+fn today() -> u32 {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    civil_date((secs / 86400) as i64)
+}
+
+/// The date `days` before `date`, both as `YYYYMMDD`.
+fn date_before(date: u32, days: u32) -> u32 {
+    let (year, month, day) = (date / 10000, date / 100 % 100, date % 100);
+    civil_date(days_from_civil(year as i64, month as i64, day as i64) - days as i64)
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// The `YYYYMMDD` date `days` after 1970-01-01.
+fn civil_date(days: i64) -> u32 {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year * 10000 + month * 100 + day) as u32
+}
+
+const OTHER_BROWSERS: &[&str] = &[
+    "and_chr",
+    "and_ff",
+    "and_qq",
+    "and_uc",
+    "android",
+    "baidu",
+    "bb",
+    "blackberry",
+    "chromeandroid",
+    "electron",
+    "explorer",
+    "explorermobile",
+    "firefoxandroid",
+    "ie",
+    "ie_mob",
+    "kaios",
+    "node",
+    "op_mini",
+    "op_mob",
+    "opera",
+    "operamini",
+    "operamobile",
+    "qqandroid",
+    "samsung",
+    "ucandroid",
+];
+
+/// Firefox Extended Support Releases that are still maintained.
+const FIREFOX_ESR: &[Release] = &[
+    (Firefox, Version(115, 0)),
+    (Firefox, Version(128, 0)),
+    (Firefox, Version(140, 0)),
+];
+
+/// Browser releases with their release date as `YYYYMMDD` and global usage
+/// share in percent, as of September 2025. Sorted by browser, then version.
+const RELEASES: &[(Browser, Version, u32, f32)] = &[
+    (Chrome, Version(49, 0), 20160302, 0.0),
+    (Chrome, Version(50, 0), 20160413, 0.0),
+    (Chrome, Version(51, 0), 20160525, 0.0),
+    (Chrome, Version(52, 0), 20160720, 0.0),
+    (Chrome, Version(53, 0), 20160831, 0.0),
+    (Chrome, Version(54, 0), 20161012, 0.0),
+    (Chrome, Version(55, 0), 20161201, 0.0),
+    (Chrome, Version(56, 0), 20170125, 0.0),
+    (Chrome, Version(57, 0), 20170309, 0.0),
+    (Chrome, Version(58, 0), 20170419, 0.0),
+    (Chrome, Version(59, 0), 20170605, 0.0),
+    (Chrome, Version(60, 0), 20170725, 0.0),
+    (Chrome, Version(61, 0), 20170905, 0.0),
+    (Chrome, Version(62, 0), 20171017, 0.0),
+    (Chrome, Version(63, 0), 20171206, 0.0),
+    (Chrome, Version(64, 0), 20180124, 0.0),
+    (Chrome, Version(65, 0), 20180306, 0.0),
+    (Chrome, Version(66, 0), 20180417, 0.0),
+    (Chrome, Version(67, 0), 20180529, 0.0),
+    (Chrome, Version(68, 0), 20180724, 0.0),
+    (Chrome, Version(69, 0), 20180904, 0.0),
+    (Chrome, Version(70, 0), 20181016, 0.0),
+    (Chrome, Version(71, 0), 20181204, 0.0),
+    (Chrome, Version(72, 0), 20190129, 0.0),
+    (Chrome, Version(73, 0), 20190312, 0.0),
+    (Chrome, Version(74, 0), 20190423, 0.0),
+    (Chrome, Version(75, 0), 20190604, 0.0),
+    (Chrome, Version(76, 0), 20190730, 0.0),
+    (Chrome, Version(77, 0), 20190910, 0.0),
+    (Chrome, Version(78, 0), 20191022, 0.0),
+    (Chrome, Version(79, 0), 20191210, 0.0),
+    (Chrome, Version(80, 0), 20200204, 0.0),
+    (Chrome, Version(81, 0), 20200407, 0.0),
+    (Chrome, Version(83, 0), 20200519, 0.0),
+    (Chrome, Version(84, 0), 20200714, 0.0),
+    (Chrome, Version(85, 0), 20200825, 0.0),
+    (Chrome, Version(86, 0), 20201006, 0.0),
+    (Chrome, Version(87, 0), 20201117, 0.0),
+    (Chrome, Version(88, 0), 20210119, 0.0),
+    (Chrome, Version(89, 0), 20210302, 0.0),
+    (Chrome, Version(90, 0), 20210414, 0.0),
+    (Chrome, Version(91, 0), 20210525, 0.0),
+    (Chrome, Version(92, 0), 20210720, 0.0),
+    (Chrome, Version(93, 0), 20210831, 0.0),
+    (Chrome, Version(94, 0), 20210921, 0.0),
+    (Chrome, Version(95, 0), 20211019, 0.0),
+    (Chrome, Version(96, 0), 20211115, 0.0),
+    (Chrome, Version(97, 0), 20220104, 0.0),
+    (Chrome, Version(98, 0), 20220201, 0.0),
+    (Chrome, Version(99, 0), 20220301, 0.0),
+    (Chrome, Version(100, 0), 20220329, 0.0),
+    (Chrome, Version(101, 0), 20220426, 0.0),
+    (Chrome, Version(102, 0), 20220524, 0.0),
+    (Chrome, Version(103, 0), 20220621, 0.2),
+    (Chrome, Version(104, 0), 20220802, 0.0),
+    (Chrome, Version(105, 0), 20220830, 0.0),
+    (Chrome, Version(106, 0), 20220927, 0.0),
+    (Chrome, Version(107, 0), 20221025, 0.0),
+    (Chrome, Version(108, 0), 20221129, 0.0),
+    (Chrome, Version(109, 0), 20230110, 0.6),
+    (Chrome, Version(110, 0), 20230207, 0.0),
+    (Chrome, Version(111, 0), 20230307, 0.0),
+    (Chrome, Version(112, 0), 20230404, 0.0),
+    (Chrome, Version(113, 0), 20230502, 0.0),
+    (Chrome, Version(114, 0), 20230530, 0.0),
+    (Chrome, Version(115, 0), 20230718, 0.0),
+    (Chrome, Version(116, 0), 20230815, 0.0),
+    (Chrome, Version(117, 0), 20230912, 0.0),
+    (Chrome, Version(118, 0), 20231010, 0.0),
+    (Chrome, Version(119, 0), 20231031, 0.0),
+    (Chrome, Version(120, 0), 20231205, 0.2),
+    (Chrome, Version(121, 0), 20240123, 0.0),
+    (Chrome, Version(122, 0), 20240220, 0.0),
+    (Chrome, Version(123, 0), 20240319, 0.0),
+    (Chrome, Version(124, 0), 20240416, 0.0),
+    (Chrome, Version(125, 0), 20240514, 0.2),
+    (Chrome, Version(126, 0), 20240611, 0.0),
+    (Chrome, Version(127, 0), 20240723, 0.0),
+    (Chrome, Version(128, 0), 20240820, 0.3),
+    (Chrome, Version(129, 0), 20240917, 0.0),
+    (Chrome, Version(130, 0), 20241015, 0.0),
+    (Chrome, Version(131, 0), 20241112, 0.4),
+    (Chrome, Version(132, 0), 20250114, 0.0),
+    (Chrome, Version(133, 0), 20250204, 0.4),
+    (Chrome, Version(134, 0), 20250304, 0.5),
+    (Chrome, Version(135, 0), 20250401, 0.6),
+    (Chrome, Version(136, 0), 20250429, 0.9),
+    (Chrome, Version(137, 0), 20250527, 1.1),
+    (Chrome, Version(138, 0), 20250624, 2.5),
+    (Chrome, Version(139, 0), 20250805, 14.0),
+    (Chrome, Version(140, 0), 20250902, 9.5),
+    (Edge, Version(79, 0), 20191213, 0.0),
+    (Edge, Version(80, 0), 20200207, 0.0),
+    (Edge, Version(81, 0), 20200410, 0.0),
+    (Edge, Version(83, 0), 20200522, 0.0),
+    (Edge, Version(84, 0), 20200717, 0.0),
+    (Edge, Version(85, 0), 20200828, 0.0),
+    (Edge, Version(86, 0), 20201009, 0.0),
+    (Edge, Version(87, 0), 20201120, 0.0),
+    (Edge, Version(88, 0), 20210122, 0.0),
+    (Edge, Version(89, 0), 20210305, 0.0),
+    (Edge, Version(90, 0), 20210417, 0.0),
+    (Edge, Version(91, 0), 20210528, 0.0),
+    (Edge, Version(92, 0), 20210723, 0.0),
+    (Edge, Version(93, 0), 20210903, 0.0),
+    (Edge, Version(94, 0), 20210924, 0.0),
+    (Edge, Version(95, 0), 20211022, 0.0),
+    (Edge, Version(96, 0), 20211118, 0.0),
+    (Edge, Version(97, 0), 20220107, 0.0),
+    (Edge, Version(98, 0), 20220204, 0.0),
+    (Edge, Version(99, 0), 20220304, 0.0),
+    (Edge, Version(100, 0), 20220401, 0.0),
+    (Edge, Version(101, 0), 20220429, 0.0),
+    (Edge, Version(102, 0), 20220527, 0.0),
+    (Edge, Version(103, 0), 20220624, 0.0),
+    (Edge, Version(104, 0), 20220805, 0.0),
+    (Edge, Version(105, 0), 20220902, 0.0),
+    (Edge, Version(106, 0), 20220930, 0.0),
+    (Edge, Version(107, 0), 20221028, 0.0),
+    (Edge, Version(108, 0), 20221202, 0.0),
+    (Edge, Version(109, 0), 20230113, 0.0),
+    (Edge, Version(110, 0), 20230210, 0.0),
+    (Edge, Version(111, 0), 20230310, 0.0),
+    (Edge, Version(112, 0), 20230407, 0.0),
+    (Edge, Version(113, 0), 20230505, 0.0),
+    (Edge, Version(114, 0), 20230602, 0.0),
+    (Edge, Version(115, 0), 20230721, 0.0),
+    (Edge, Version(116, 0), 20230818, 0.0),
+    (Edge, Version(117, 0), 20230915, 0.0),
+    (Edge, Version(118, 0), 20231013, 0.0),
+    (Edge, Version(119, 0), 20231103, 0.0),
+    (Edge, Version(120, 0), 20231208, 0.0),
+    (Edge, Version(121, 0), 20240126, 0.0),
+    (Edge, Version(122, 0), 20240223, 0.0),
+    (Edge, Version(123, 0), 20240322, 0.0),
+    (Edge, Version(124, 0), 20240419, 0.0),
+    (Edge, Version(125, 0), 20240517, 0.0),
+    (Edge, Version(126, 0), 20240614, 0.0),
+    (Edge, Version(127, 0), 20240726, 0.0),
+    (Edge, Version(128, 0), 20240823, 0.0),
+    (Edge, Version(129, 0), 20240920, 0.0),
+    (Edge, Version(130, 0), 20241018, 0.0),
+    (Edge, Version(131, 0), 20241115, 0.0),
+    (Edge, Version(132, 0), 20250117, 0.0),
+    (Edge, Version(133, 0), 20250207, 0.0),
+    (Edge, Version(134, 0), 20250307, 0.0),
+    (Edge, Version(135, 0), 20250404, 0.0),
+    (Edge, Version(136, 0), 20250502, 0.0),
+    (Edge, Version(137, 0), 20250530, 0.0),
+    (Edge, Version(138, 0), 20250627, 0.2),
+    (Edge, Version(139, 0), 20250808, 0.7),
+    (Edge, Version(140, 0), 20250905, 3.8),
+    (Firefox, Version(52, 0), 20170307, 0.0),
+    (Firefox, Version(53, 0), 20170419, 0.0),
+    (Firefox, Version(54, 0), 20170613, 0.0),
+    (Firefox, Version(55, 0), 20170808, 0.0),
+    (Firefox, Version(56, 0), 20170928, 0.0),
+    (Firefox, Version(57, 0), 20171114, 0.0),
+    (Firefox, Version(58, 0), 20180123, 0.0),
+    (Firefox, Version(59, 0), 20180313, 0.0),
+    (Firefox, Version(60, 0), 20180509, 0.0),
+    (Firefox, Version(61, 0), 20180626, 0.0),
+    (Firefox, Version(62, 0), 20180905, 0.0),
+    (Firefox, Version(63, 0), 20181023, 0.0),
+    (Firefox, Version(64, 0), 20181211, 0.0),
+    (Firefox, Version(65, 0), 20190129, 0.0),
+    (Firefox, Version(66, 0), 20190319, 0.0),
+    (Firefox, Version(67, 0), 20190521, 0.0),
+    (Firefox, Version(68, 0), 20190709, 0.0),
+    (Firefox, Version(69, 0), 20190903, 0.0),
+    (Firefox, Version(70, 0), 20191022, 0.0),
+    (Firefox, Version(71, 0), 20191210, 0.0),
+    (Firefox, Version(72, 0), 20200107, 0.0),
+    (Firefox, Version(73, 0), 20200204, 0.0),
+    (Firefox, Version(74, 0), 20200303, 0.0),
+    (Firefox, Version(75, 0), 20200331, 0.0),
+    (Firefox, Version(76, 0), 20200428, 0.0),
+    (Firefox, Version(77, 0), 20200526, 0.0),
+    (Firefox, Version(78, 0), 20200623, 0.0),
+    (Firefox, Version(79, 0), 20200721, 0.0),
+    (Firefox, Version(80, 0), 20200818, 0.0),
+    (Firefox, Version(81, 0), 20200915, 0.0),
+    (Firefox, Version(82, 0), 20201013, 0.0),
+    (Firefox, Version(83, 0), 20201110, 0.0),
+    (Firefox, Version(84, 0), 20201208, 0.0),
+    (Firefox, Version(85, 0), 20210105, 0.0),
+    (Firefox, Version(86, 0), 20210202, 0.0),
+    (Firefox, Version(87, 0), 20210302, 0.0),
+    (Firefox, Version(88, 0), 20210330, 0.0),
+    (Firefox, Version(89, 0), 20210427, 0.0),
+    (Firefox, Version(90, 0), 20210525, 0.0),
+    (Firefox, Version(91, 0), 20210622, 0.0),
+    (Firefox, Version(92, 0), 20210720, 0.0),
+    (Firefox, Version(93, 0), 20210817, 0.0),
+    (Firefox, Version(94, 0), 20210914, 0.0),
+    (Firefox, Version(95, 0), 20211012, 0.0),
+    (Firefox, Version(96, 0), 20211109, 0.0),
+    (Firefox, Version(97, 0), 20211207, 0.0),
+    (Firefox, Version(98, 0), 20220104, 0.0),
+    (Firefox, Version(99, 0), 20220201, 0.0),
+    (Firefox, Version(100, 0), 20220301, 0.0),
+    (Firefox, Version(101, 0), 20220329, 0.0),
+    (Firefox, Version(102, 0), 20220426, 0.0),
+    (Firefox, Version(103, 0), 20220524, 0.0),
+    (Firefox, Version(104, 0), 20220621, 0.0),
+    (Firefox, Version(105, 0), 20220719, 0.0),
+    (Firefox, Version(106, 0), 20220816, 0.0),
+    (Firefox, Version(107, 0), 20220913, 0.0),
+    (Firefox, Version(108, 0), 20221011, 0.0),
+    (Firefox, Version(109, 0), 20221108, 0.0),
+    (Firefox, Version(110, 0), 20221206, 0.0),
+    (Firefox, Version(111, 0), 20230103, 0.0),
+    (Firefox, Version(112, 0), 20230131, 0.0),
+    (Firefox, Version(113, 0), 20230228, 0.0),
+    (Firefox, Version(114, 0), 20230328, 0.0),
+    (Firefox, Version(115, 0), 20230425, 0.2),
+    (Firefox, Version(116, 0), 20230523, 0.0),
+    (Firefox, Version(117, 0), 20230620, 0.0),
+    (Firefox, Version(118, 0), 20230718, 0.0),
+    (Firefox, Version(119, 0), 20230815, 0.0),
+    (Firefox, Version(120, 0), 20230912, 0.0),
+    (Firefox, Version(121, 0), 20231010, 0.0),
+    (Firefox, Version(122, 0), 20231107, 0.0),
+    (Firefox, Version(123, 0), 20231205, 0.0),
+    (Firefox, Version(124, 0), 20240102, 0.0),
+    (Firefox, Version(125, 0), 20240130, 0.0),
+    (Firefox, Version(126, 0), 20240227, 0.0),
+    (Firefox, Version(127, 0), 20240326, 0.0),
+    (Firefox, Version(128, 0), 20240423, 0.2),
+    (Firefox, Version(129, 0), 20240521, 0.0),
+    (Firefox, Version(130, 0), 20240618, 0.0),
+    (Firefox, Version(131, 0), 20240716, 0.0),
+    (Firefox, Version(132, 0), 20240813, 0.0),
+    (Firefox, Version(133, 0), 20240910, 0.0),
+    (Firefox, Version(134, 0), 20241008, 0.0),
+    (Firefox, Version(135, 0), 20241105, 0.0),
+    (Firefox, Version(136, 0), 20241203, 0.0),
+    (Firefox, Version(137, 0), 20241231, 0.0),
+    (Firefox, Version(138, 0), 20250128, 0.0),
+    (Firefox, Version(139, 0), 20250225, 0.0),
+    (Firefox, Version(140, 0), 20250325, 0.3),
+    (Firefox, Version(141, 0), 20250422, 0.2),
+    (Firefox, Version(142, 0), 20250520, 0.7),
+    (Firefox, Version(143, 0), 20250617, 1.1),
+    (Safari, Version(9, 0), 20150930, 0.0),
+    (Safari, Version(9, 1), 20160321, 0.0),
+    (Safari, Version(10, 0), 20160920, 0.0),
+    (Safari, Version(10, 1), 20170327, 0.0),
+    (Safari, Version(11, 0), 20170919, 0.0),
+    (Safari, Version(11, 1), 20180329, 0.0),
+    (Safari, Version(12, 0), 20180917, 0.0),
+    (Safari, Version(12, 1), 20190325, 0.0),
+    (Safari, Version(13, 0), 20190919, 0.0),
+    (Safari, Version(13, 1), 20200324, 0.0),
+    (Safari, Version(14, 0), 20200916, 0.0),
+    (Safari, Version(14, 1), 20210426, 0.0),
+    (Safari, Version(15, 0), 20210920, 0.0),
+    (Safari, Version(15, 1), 20211025, 0.0),
+    (Safari, Version(15, 2), 20211213, 0.0),
+    (Safari, Version(15, 3), 20220126, 0.0),
+    (Safari, Version(15, 4), 20220314, 0.0),
+    (Safari, Version(15, 5), 20220516, 0.0),
+    (Safari, Version(15, 6), 20220720, 0.0),
+    (Safari, Version(16, 0), 20220912, 0.0),
+    (Safari, Version(16, 1), 20221024, 0.0),
+    (Safari, Version(16, 2), 20221213, 0.0),
+    (Safari, Version(16, 3), 20230123, 0.0),
+    (Safari, Version(16, 4), 20230327, 0.0),
+    (Safari, Version(16, 5), 20230518, 0.0),
+    (Safari, Version(16, 6), 20230724, 0.1),
+    (Safari, Version(17, 0), 20230918, 0.0),
+    (Safari, Version(17, 1), 20231025, 0.0),
+    (Safari, Version(17, 2), 20231211, 0.0),
+    (Safari, Version(17, 3), 20240122, 0.0),
+    (Safari, Version(17, 4), 20240305, 0.0),
+    (Safari, Version(17, 5), 20240513, 0.0),
+    (Safari, Version(17, 6), 20240729, 0.4),
+    (Safari, Version(18, 0), 20240916, 0.0),
+    (Safari, Version(18, 1), 20241028, 0.0),
+    (Safari, Version(18, 2), 20241211, 0.0),
+    (Safari, Version(18, 3), 20250127, 0.0),
+    (Safari, Version(18, 4), 20250331, 0.2),
+    (Safari, Version(18, 5), 20250512, 0.5),
+    (Safari, Version(18, 6), 20250729, 0.9),
+    (Safari, Version(26, 0), 20250915, 0.3),
+    (IosSafari, Version(9, 0), 20150930, 0.0),
+    (IosSafari, Version(9, 1), 20160321, 0.0),
+    (IosSafari, Version(10, 0), 20160920, 0.0),
+    (IosSafari, Version(10, 1), 20170327, 0.0),
+    (IosSafari, Version(11, 0), 20170919, 0.0),
+    (IosSafari, Version(11, 1), 20180329, 0.0),
+    (IosSafari, Version(12, 0), 20180917, 0.0),
+    (IosSafari, Version(12, 1), 20190325, 0.0),
+    (IosSafari, Version(13, 0), 20190919, 0.0),
+    (IosSafari, Version(13, 1), 20200324, 0.0),
+    (IosSafari, Version(14, 0), 20200916, 0.0),
+    (IosSafari, Version(14, 1), 20210426, 0.0),
+    (IosSafari, Version(15, 0), 20210920, 0.0),
+    (IosSafari, Version(15, 1), 20211025, 0.0),
+    (IosSafari, Version(15, 2), 20211213, 0.0),
+    (IosSafari, Version(15, 3), 20220126, 0.0),
+    (IosSafari, Version(15, 4), 20220314, 0.0),
+    (IosSafari, Version(15, 5), 20220516, 0.0),
+    (IosSafari, Version(15, 6), 20220720, 0.0),
+    (IosSafari, Version(16, 0), 20220912, 0.0),
+    (IosSafari, Version(16, 1), 20221024, 0.0),
+    (IosSafari, Version(16, 2), 20221213, 0.0),
+    (IosSafari, Version(16, 3), 20230123, 0.0),
+    (IosSafari, Version(16, 4), 20230327, 0.0),
+    (IosSafari, Version(16, 5), 20230518, 0.0),
+    (IosSafari, Version(16, 6), 20230724, 0.6),
+    (IosSafari, Version(17, 0), 20230918, 0.0),
+    (IosSafari, Version(17, 1), 20231025, 0.0),
+    (IosSafari, Version(17, 2), 20231211, 0.0),
+    (IosSafari, Version(17, 3), 20240122, 0.0),
+    (IosSafari, Version(17, 4), 20240305, 0.0),
+    (IosSafari, Version(17, 5), 20240513, 0.0),
+    (IosSafari, Version(17, 6), 20240729, 1.4),
+    (IosSafari, Version(18, 0), 20240916, 0.0),
+    (IosSafari, Version(18, 1), 20241028, 0.0),
+    (IosSafari, Version(18, 2), 20241211, 0.0),
+    (IosSafari, Version(18, 3), 20250127, 0.5),
+    (IosSafari, Version(18, 4), 20250331, 0.6),
+    (IosSafari, Version(18, 5), 20250512, 1.8),
+    (IosSafari, Version(18, 6), 20250729, 7.5),
+    (IosSafari, Version(26, 0), 20250915, 1.6),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versions(query: &str, browser: Browser) -> Vec<Version> {
+        select(query)
+            .unwrap()
+            .into_iter()
+            .filter(|&(b, _)| b == browser)
+            .map(|(_, version)| version)
+            .collect()
+    }
+
+    #[test]
+    fn clauses_split_on_commas_and_or() {
+        assert_eq!(
+            clauses("Chrome  >= 120, safari 17 OR last 2 versions,,"),
+            ["chrome >= 120", "safari 17", "last 2 versions"]
+        );
+    }
+
+    #[test]
+    fn versions_and_ranges() {
+        assert_eq!(versions("chrome 120", Chrome), [Version(120, 0)]);
+        // A major version covers its minor releases.
+        let safari = versions("safari 15", Safari);
+        assert_eq!(safari.first(), Some(&Version(15, 0)));
+        assert!(safari.len() > 1 && safari.iter().all(|v| v.0 == 15));
+        assert_eq!(
+            versions("safari 15.2-15.4", Safari),
+            [Version(15, 2), Version(15, 3), Version(15, 4)]
+        );
+        assert!(versions("chrome > 120", Chrome).iter().all(|v| *v > Version(120, 0)));
+        assert_eq!(versions("chrome >= 120", Chrome)[0], Version(120, 0));
+        assert!(versions("chrome all", Firefox).is_empty());
+    }
+
+    #[test]
+    fn last_versions() {
+        assert_eq!(versions("last 2 chrome versions", Chrome).len(), 2);
+        let majors = versions("last 2 safari major versions", Safari);
+        let mut distinct: Vec<u16> = majors.iter().map(|v| v.0).collect();
+        distinct.dedup();
+        assert_eq!(distinct.len(), 2);
+        assert!(majors.len() >= 2);
+        for browser in [Chrome, Edge, Firefox, Safari, IosSafari] {
+            assert_eq!(versions("last 1 versions", browser).len(), 1);
+        }
+    }
+
+    #[test]
+    fn negation_and_intersection() {
+        assert_eq!(
+            select("chrome all, not chrome < 121").unwrap(),
+            select("chrome >= 121").unwrap()
+        );
+        assert_eq!(
+            select("last 3 versions and chrome all").unwrap(),
+            select("last 3 chrome versions").unwrap()
+        );
+        assert_eq!(
+            select("chrome 120-122 and not chrome 121").unwrap(),
+            select("chrome 120, chrome 122").unwrap()
+        );
+        assert!(select("not dead").is_err());
+        assert!(select("dead").unwrap().is_empty());
+    }
+
+    #[test]
+    fn usage_and_dates() {
+        assert!(!select("> 0.1%").unwrap().is_empty());
+        assert!(select("> 100%").unwrap().is_empty());
+        let since = select("since 2024-01").unwrap();
+        assert!(since.contains(&(Chrome, Version(122, 0))));
+        assert!(!since.contains(&(Chrome, Version(120, 0))));
+    }
+
+    #[test]
+    fn browsers() {
+        assert!(select("android 4").unwrap().is_empty());
+        assert!(select("netscape 4").is_err());
+        assert!(select("chrome abc").is_err());
+        assert!(select("most of them").is_err());
+    }
+
+    #[test]
+    fn resolves_to_the_oldest_version() {
+        let targets = resolve("chrome >= 121, safari 16, not chrome 122").unwrap();
+        assert_eq!(targets.0.get(&Chrome), Some(&Version(121, 0)));
+        assert_eq!(targets.0.get(&Safari), Some(&Version(16, 0)));
+        assert_eq!(targets.0.get(&Firefox), None);
+    }
+
+    #[test]
+    fn config_sections() {
+        let config =
+            "# comment\nchrome 120\n[development]\nlast 1 chrome version\n[production staging]\nsafari 17 # eol\n";
+        assert_eq!(parse_config(config), "chrome 120, safari 17");
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(parse_date("2024").unwrap(), 20240101);
+        assert_eq!(parse_date("2024-03-05").unwrap(), 20240305);
+        assert!(parse_date("March").is_err());
+        assert_eq!(date_before(20240301, 1), 20240229);
+        assert_eq!(date_before(20240101, 365), 20230101);
+        assert_eq!(civil_date(days_from_civil(2000, 2, 29)), 20000229);
+    }
+}
@@ -0,0 +1,385 @@
+use std::cmp::Reverse;
+
+use lsp_types::{Position, Uri};
+use serde_json::{Value, json};
+
+use crate::{
+    css::{
+        selector::{Specificity, resolve, specificity, subject},
+        syntax::{Declaration, Item, Rule},
+    },
+    document::{Document, Region},
+    state::State,
+};
+
+/// A declaration in a style rule, with the at-rules around it.
+struct Candidate<'a> {
+    doc: &'a Document,
+    region: &'a Region,
+    /// The complex selectors of the rule, resolved if it is nested.
+    selectors: Vec<String>,
+    declaration: &'a Declaration,
+    /// The full name of the enclosing layer, e.g. `base.reset`.
+    layer: Option<String>,
+    /// Conditional at-rules around the rule, e.g. `@media (width > 600px)`.
+    conditions: Vec<String>,
+    /// Order of the document among all documents.
+    file: usize,
+}
+
+/// Layer names in the order they were first declared.
+#[derive(Default)]
+struct Layers {
+    names: Vec<String>,
+    anonymous: usize,
+}
+
+impl Layers {
+    fn declare(&mut self, name: String) {
+        if !self.names.contains(&name) {
+            self.names.push(name);
+        }
+    }
+
+    /// The position of each part of a layer name among its siblings. The
+    /// declarations directly in a layer come after those of its sublayers,
+    /// and unlayered declarations after every layer.
+    fn rank(&self, layer: Option<&str>) -> Vec<usize> {
+        let Some(layer) = layer else {
+            return vec![usize::MAX];
+        };
+        let mut rank = vec![];
+        let mut parent = String::new();
+        for part in layer.split('.') {
+            let name = match parent.is_empty() {
+                true => part.to_string(),
+                false => format!("{parent}.{part}"),
+            };
+            let siblings = self.names.iter().filter(|other| {
+                let other_parent = other.rsplit_once('.').map_or("", |(parent, _)| parent);
+                other_parent == parent
+            });
+            rank.push(siblings.take_while(|other| **other != name).count());
+            parent = name;
+        }
+        rank.push(usize::MAX);
+        rank
+    }
+}
+
+/// Lists the declarations in the workspace that set the same property as
+/// the declaration at `position`, on selectors whose subjects may match the
+/// same elements. They are ordered by precedence, winner first: importance
+/// and `@layer` order, then specificity, then source order. Across files,
+/// source order follows the file URIs, since the order in which pages load
+/// stylesheets is unknown.
+pub fn cascade_conflicts(state: &State, uri: &Uri, position: Position) -> Value {
+    let mut docs: Vec<&Document> = state.folder_documents(uri).collect();
+    docs.sort_by(|a, b| a.uri.as_str().cmp(b.uri.as_str()));
+
+    let mut layers = Layers::default();
+    let mut candidates = vec![];
+    for (file, doc) in docs.iter().enumerate() {
+        for region in &doc.regions {
+            let mut context = Context {
+                doc,
+                region,
+                file,
+                layer: vec![],
+                conditions: vec![],
+                selectors: vec![],
+            };
+            context.rules(&region.stylesheet.rules, &mut layers, &mut candidates);
+        }
+    }
+
+    let Some(target) = docs.iter().find(|doc| doc.uri == *uri).and_then(|doc| {
+        let offset = doc.line_index.offset(position);
+        candidates.iter().position(|candidate| {
+            let Some(offset) = candidate.region.to_virtual(offset) else {
+                return false;
+            };
+            let span = candidate.declaration.span;
+            candidate.doc.uri == doc.uri && span.start <= offset && offset <= span.end
+        })
+    }) else {
+        return Value::Null;
+    };
+
+    let property = property_name(candidates[target].declaration);
+    let target_subjects: Vec<_> = candidates[target]
+        .selectors
+        .iter()
+        .map(|selector| subject(selector))
+        .collect();
+
+    let mut conflicts = vec![];
+    for (i, candidate) in candidates.iter().enumerate() {
+        if property_name(candidate.declaration) != property {
+            continue;
+        }
+        let selector = candidate
+            .selectors
+            .iter()
+            .filter(|selector| {
+                let subject = subject(selector);
+                target_subjects.iter().any(|target| target.overlaps(&subject))
+            })
+            .max_by_key(|selector| specificity(selector));
+        if let Some(selector) = selector {
+            conflicts.push((i, selector, specificity(selector)));
+        }
+    }
+
+    let important = |candidate: &Candidate| candidate.declaration.important;
+    conflicts.sort_by_cached_key(|&(i, _, specificity)| {
+        let candidate = &candidates[i];
+        let important = important(candidate);
+        let mut layer = layers.rank(candidate.layer.as_deref());
+        // Important declarations reverse the order of layers.
+        if important {
+            layer.iter_mut().for_each(|rank| *rank = usize::MAX - *rank);
+        }
+        let offset = candidate.declaration.span.start;
+        Reverse((
+            important,
+            layer,
+            specificity,
+            candidate.file,
+            candidate.region.to_host(offset),
+        ))
+    });
+
+    let declarations: Vec<Value> = conflicts
+        .iter()
+        .enumerate()
+        .map(|(rank, &(i, selector, Specificity(a, b, c)))| {
+            let candidate = &candidates[i];
+            let text = &candidate.region.stylesheet.text;
+            json!({
+                "uri": candidate.doc.uri,
+                "range": candidate.doc.range(candidate.region, candidate.declaration.span),
+                "selector": selector,
+                "value": candidate.declaration.value.text(text),
+                "important": important(candidate),
+                "layer": candidate.layer,
+                "conditions": candidate.conditions,
+                "specificity": [a, b, c],
+                "wins": rank == 0,
+                "current": i == target,
+            })
+        })
+        .collect();
+    json!({
+        "property": property,
+        "declarations": declarations,
+    })
+}
+
+/// Property names are case-insensitive, except for custom properties.
+fn property_name(decl: &Declaration) -> String {
+    match decl.name.starts_with("--") {
+        true => decl.name.clone(),
+        false => decl.name.to_ascii_lowercase(),
+    }
+}
+
+struct Context<'a> {
+    doc: &'a Document,
+    region: &'a Region,
+    file: usize,
+    layer: Vec<String>,
+    conditions: Vec<String>,
+    /// The resolved selectors of the enclosing style rule, if any.
+    selectors: Vec<String>,
+}
+
+impl<'a> Context<'a> {
+    fn rules(&mut self, rules: impl IntoIterator<Item = &'a Rule>, layers: &mut Layers, out: &mut Vec<Candidate<'a>>) {
+        for rule in rules {
+            self.rule(rule, layers, out);
+        }
+    }
+
+    /// The declarations and nested rules of a block. Declarations only apply
+    /// inside a style rule, including in the conditional rules nested in it.
+    fn items(&mut self, items: &'a [Item], layers: &mut Layers, out: &mut Vec<Candidate<'a>>) {
+        for item in items {
+            match item {
+                Item::Declaration(declaration) if !self.selectors.is_empty() => out.push(Candidate {
+                    doc: self.doc,
+                    region: self.region,
+                    selectors: self.selectors.clone(),
+                    declaration,
+                    layer: (!self.layer.is_empty()).then(|| self.layer.join(".")),
+                    conditions: self.conditions.clone(),
+                    file: self.file,
+                }),
+                Item::Declaration(_) => {}
+                Item::Rule(rule) => self.rule(rule, layers, out),
+            }
+        }
+    }
+
+    fn rule(&mut self, rule: &'a Rule, layers: &mut Layers, out: &mut Vec<Candidate<'a>>) {
+        let text = self.region.stylesheet.text.as_str();
+        match rule {
+            // Inline styles are wrapped in a synthetic rule that matches
+            // nothing else.
+            Rule::Style(style) if self.region.contains(style.selector) => {
+                let selectors = resolve(&self.region.stylesheet, style.selector, &self.selectors);
+                let outer = std::mem::replace(&mut self.selectors, selectors);
+                self.items(&style.block.items, layers, out);
+                self.selectors = outer;
+            }
+            Rule::Style(_) => {}
+            Rule::At(at_rule) => {
+                let Some(block) = &at_rule.block else {
+                    if at_rule.name == "layer" {
+                        for name in at_rule.prelude.text(text).split(',') {
+                            layers.declare(self.full_layer_name(name.trim()));
+                        }
+                    }
+                    return;
+                };
+                match at_rule.name.as_str() {
+                    "layer" => {
+                        let name = match at_rule.prelude.text(text).trim() {
+                            "" => {
+                                layers.anonymous += 1;
+                                format!("<anonymous {}>", layers.anonymous)
+                            }
+                            name => name.to_string(),
+                        };
+                        let name = self.full_layer_name(&name);
+                        layers.declare(name.clone());
+                        let outer = std::mem::replace(&mut self.layer, name.split('.').map(String::from).collect());
+                        self.items(&block.items, layers, out);
+                        self.layer = outer;
+                    }
+                    "media" | "supports" | "container" | "scope" | "starting-style" | "document" | "-moz-document" => {
+                        let prelude = at_rule.prelude.text(text);
+                        self.conditions
+                            .push(format!("@{} {prelude}", at_rule.name).trim_end().to_string());
+                        self.items(&block.items, layers, out);
+                        self.conditions.pop();
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn full_layer_name(&self, name: &str) -> String {
+        match self.layer.is_empty() {
+            true => name.to_string(),
+            false => format!("{}.{name}", self.layer.join(".")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line_index::Encoding;
+
+    /// The selector and value of each conflicting declaration, winner first,
+    /// for the declaration at `|` in one of `files`.
+    fn conflicts(files: &[&str]) -> Vec<(String, String)> {
+        let mut state = State::default();
+        let mut target = None;
+        for (i, text) in files.iter().enumerate() {
+            let uri: Uri = format!("file:///{i}.css").parse().unwrap();
+            let offset = text.find('|');
+            let doc = Document::new(
+                uri.clone(),
+                "css".to_string(),
+                0,
+                text.replace('|', ""),
+                Encoding::default(),
+            );
+            if let Some(offset) = offset {
+                target = Some((uri.clone(), doc.line_index.position(offset)));
+            }
+            state.documents.insert(uri, doc);
+        }
+        let (uri, position) = target.unwrap();
+        let result = cascade_conflicts(&state, &uri, position);
+        result["declarations"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|declaration| {
+                let selector = declaration["selector"].as_str().unwrap().to_string();
+                let mut value = declaration["value"].as_str().unwrap().trim().to_string();
+                if declaration["important"] == true {
+                    value.push_str(" !important");
+                }
+                (selector, value)
+            })
+            .collect()
+    }
+
+    fn values(files: &[&str]) -> Vec<String> {
+        conflicts(files).into_iter().map(|(_, value)| value).collect()
+    }
+
+    #[test]
+    fn layer_order() {
+        // Later layers win, in the order they were first declared.
+        let text = "@layer a, b;\n@layer b { p { co|lor: blue } }\n@layer a { p { color: red } }";
+        assert_eq!(values(&[text]), ["blue", "red"]);
+        let text = "@layer b { p { co|lor: blue } }\n@layer a { p { color: red } }";
+        assert_eq!(values(&[text]), ["red", "blue"]);
+        // A layer's own declarations come after its sublayers'.
+        let text = "@layer a { p { co|lor: red } @layer b { p { color: blue } } }";
+        assert_eq!(values(&[text]), ["red", "blue"]);
+    }
+
+    #[test]
+    fn important_reverses_layer_order() {
+        let text = "@layer a, b;\n\
+                    @layer a { p { co|lor: red !important } }\n\
+                    @layer b { p { color: blue !important; color: green } }\n\
+                    p { color: black !important }";
+        assert_eq!(
+            values(&[text]),
+            ["red !important", "blue !important", "black !important", "green"]
+        );
+    }
+
+    #[test]
+    fn unlayered_beats_layered() {
+        // Regardless of source order and specificity.
+        let text = "p { co|lor: green }\n@layer a { #x.y p { color: red } }";
+        assert_eq!(values(&[text]), ["green", "red"]);
+    }
+
+    #[test]
+    fn specificity_then_source_order() {
+        let text = "p#x { color: red }\n.a p { co|lor: blue }\np.b { color: green }\np { color: black }";
+        assert_eq!(
+            conflicts(&[text]),
+            [
+                ("p#x".to_string(), "red".to_string()),
+                ("p.b".to_string(), "green".to_string()),
+                (".a p".to_string(), "blue".to_string()),
+                ("p".to_string(), "black".to_string()),
+            ]
+        );
+        // Across files, ties go to the later URI.
+        assert_eq!(values(&[".a { co|lor: red }", ".a { color: blue }"]), ["blue", "red"]);
+    }
+
+    #[test]
+    fn nested_selectors() {
+        let text = ".a { & p { co|lor: red } }\n.a p { color: blue }\n.b { color: green }";
+        assert_eq!(
+            conflicts(&[text]),
+            [
+                (".a p".to_string(), "blue".to_string()),
+                (".a p".to_string(), "red".to_string()),
+            ]
+        );
+    }
+}
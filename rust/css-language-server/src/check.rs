@@ -0,0 +1,375 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Uri};
+use serde_json::{Value, json};
+
+use crate::{
+    config::Config,
+    diagnostics::diagnostics,
+    document::Document,
+    line_index::Encoding,
+    uri,
+    workspace::{self, Workspace},
+};
+
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum Format {
+    /// Messages with source snippets, like rustc.
+    #[default]
+    Human,
+    /// An array of the diagnostics of every file.
+    Json,
+    /// A SARIF 2.1.0 log, for code scanning tools.
+    Sarif,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Files and directories to check. Directories are searched like the
+    /// editor indexes a workspace.
+    #[arg(default_value = ".")]
+    paths: Vec<PathBuf>,
+    #[arg(long, value_enum, default_value_t)]
+    format: Format,
+}
+
+/// A file with the diagnostics an editor would show for it.
+struct Report<'a> {
+    /// The path as shown in the output, relative to the working directory
+    /// when possible.
+    path: String,
+    /// The canonical path.
+    file: PathBuf,
+    document: &'a Document,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// The files under a directory, checked against each other like the files
+/// of a workspace folder in the editor.
+struct Folder {
+    root: PathBuf,
+    workspace: Workspace,
+}
+
+/// Checks the files under `args.paths` with the working directory as the
+/// workspace root, so that its browserslist configuration, templates and
+/// scripts are taken into account. A directory outside of it is a workspace
+/// of its own, and a file outside of both is checked alone, so that a
+/// selector is only reported unused by the templates next to it. Returns
+/// whether any error was found.
+pub async fn run(args: Args) -> Result<bool> {
+    // Paths are canonical, so that they can be compared.
+    let root = std::fs::canonicalize(std::env::current_dir()?)?;
+    let (config, warnings) = Config::read(Some(&root), None).await;
+    for warning in warnings {
+        eprintln!("warning: {warning}");
+    }
+    let mut folders = vec![Folder {
+        workspace: Workspace::index(&root, Encoding::default()).await?,
+        root: root.clone(),
+    }];
+
+    let mut documents = vec![];
+    for path in &args.paths {
+        let path = std::fs::canonicalize(path).with_context(|| format!("cannot read `{}`", path.display()))?;
+        let folder = match folders.iter().position(|folder| path.starts_with(&folder.root)) {
+            Some(folder) => folder,
+            None => {
+                let workspace = match path.is_dir() {
+                    true => Workspace::index(&path, Encoding::default()).await?,
+                    false => Workspace::default(),
+                };
+                folders.push(Folder {
+                    root: path.clone(),
+                    workspace,
+                });
+                folders.len() - 1
+            }
+        };
+        let workspace = &mut folders[folder].workspace;
+        if path.is_dir() {
+            // Unlike indexing, this searches directories such as
+            // `node_modules` when they are given explicitly.
+            let mut files: Vec<PathBuf> = workspace::walk(&path)
+                .await?
+                .into_iter()
+                .filter(|file| language_id(file).is_some())
+                .collect();
+            if files.is_empty() {
+                bail!("no CSS, HTML or Vue files found in `{}`", path.display());
+            }
+            files.sort();
+            for file in files {
+                documents.push((add(workspace, &file).await?, folder));
+            }
+            continue;
+        }
+        if language_id(&path).is_none() {
+            bail!("`{}` is not a CSS, HTML or Vue file", path.display());
+        }
+        documents.push((add(workspace, &path).await?, folder));
+    }
+    documents.sort_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+    documents.dedup_by(|(a, _), (b, _)| a == b);
+
+    let mut reports = vec![];
+    for (uri, folder) in documents {
+        let workspace = &folders[folder].workspace;
+        let Some(document) = workspace.files.get(&uri) else {
+            continue;
+        };
+        let diagnostics = diagnostics(document, &config, workspace);
+        let file = uri::to_path(&uri).unwrap_or_default();
+        let path = file.strip_prefix(&root).unwrap_or(&file).display().to_string();
+        reports.push(Report {
+            path,
+            file,
+            document,
+            diagnostics,
+        });
+    }
+
+    let output = match args.format {
+        Format::Human => human(&reports),
+        Format::Json => format!("{:#}\n", json(&reports)),
+        Format::Sarif => format!("{:#}\n", sarif(&reports, &root)),
+    };
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(output.as_bytes())?;
+    stdout.flush()?;
+
+    let errors = reports
+        .iter()
+        .flat_map(|report| &report.diagnostics)
+        .any(|diagnostic| diagnostic.severity == Some(DiagnosticSeverity::ERROR));
+    Ok(errors)
+}
+
+fn language_id(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_string_lossy().to_ascii_lowercase();
+    workspace::language_id(&extension)
+}
+
+/// The URI of a file to check, reading it into the workspace unless it was
+/// indexed. Explicit paths are checked even where indexing does not look,
+/// such as in `node_modules`.
+async fn add(workspace: &mut Workspace, path: &Path) -> Result<Uri> {
+    let Some(uri) = uri::from_path(path) else {
+        bail!("cannot check `{}`", path.display());
+    };
+    if !workspace.files.contains_key(&uri) {
+        let language_id = language_id(path).unwrap_or("css");
+        let text = tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("cannot read `{}`", path.display()))?;
+        let document = Document::new(uri.clone(), language_id.to_string(), 0, text, workspace.encoding);
+        workspace.files.insert(uri.clone(), document);
+    }
+    Ok(uri)
+}
+
+fn severity(diagnostic: &Diagnostic) -> &'static str {
+    match diagnostic.severity {
+        Some(DiagnosticSeverity::ERROR) => "error",
+        Some(DiagnosticSeverity::WARNING) => "warning",
+        Some(DiagnosticSeverity::INFORMATION) => "info",
+        _ => "hint",
+    }
+}
+
+fn code(diagnostic: &Diagnostic) -> Option<String> {
+    match &diagnostic.code {
+        Some(NumberOrString::String(code)) => Some(code.clone()),
+        Some(NumberOrString::Number(code)) => Some(code.to_string()),
+        None => None,
+    }
+}
+
+/// The 1-based line and column of an LSP position, counting columns in
+/// characters.
+fn line_column(document: &Document, position: lsp_types::Position) -> (usize, usize) {
+    let text = &document.text;
+    let offset = document.line_index.offset(position);
+    let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    (position.line as usize + 1, text[line_start..offset].chars().count() + 1)
+}
+
+/// ```text
+/// error[invalid-math]: Cannot add length and time
+///  --> styles/a.css:3:13
+///   |
+/// 3 | .a { width: calc(10px + 2s); }
+///   |             ^^^^^^^^^
+/// ```
+fn human(reports: &[Report<'_>]) -> String {
+    let mut out = String::new();
+    let mut counts = [0; 4];
+    for report in reports {
+        let text = &report.document.text;
+        for diagnostic in &report.diagnostics {
+            let severity = severity(diagnostic);
+            counts[["error", "warning", "info", "hint"]
+                .iter()
+                .position(|s| *s == severity)
+                .unwrap_or(3)] += 1;
+            match code(diagnostic) {
+                Some(code) => out.push_str(&format!("{severity}[{code}]: {}\n", diagnostic.message)),
+                None => out.push_str(&format!("{severity}: {}\n", diagnostic.message)),
+            }
+
+            let (line, column) = line_column(report.document, diagnostic.range.start);
+            let (end_line, end_column) = line_column(report.document, diagnostic.range.end);
+            let source = text.lines().nth(line - 1).unwrap_or_default();
+            let width = line.to_string().len();
+            let end_column = match end_line == line {
+                true => end_column,
+                false => source.chars().count() + 1,
+            };
+            let underline = "^".repeat(end_column.saturating_sub(column).max(1));
+            out.push_str(&format!("{:width$}--> {}:{line}:{column}\n", "", report.path));
+            out.push_str(&format!("{:width$} |\n", ""));
+            out.push_str(&format!("{line} | {source}\n"));
+            out.push_str(&format!("{:width$} | {}{underline}\n\n", "", " ".repeat(column - 1)));
+        }
+    }
+
+    let summary: Vec<String> = ["error", "warning", "info", "hint"]
+        .iter()
+        .zip(counts)
+        .filter(|(_, count)| *count > 0)
+        .map(|(severity, count)| match count {
+            1 => format!("1 {severity}"),
+            _ => format!("{count} {severity}s"),
+        })
+        .collect();
+    let files = match reports.len() {
+        1 => "1 file".to_string(),
+        n => format!("{n} files"),
+    };
+    match summary.is_empty() {
+        true => out.push_str(&format!("checked {files}, no problems found\n")),
+        false => out.push_str(&format!("checked {files}: {}\n", summary.join(", "))),
+    }
+    out
+}
+
+fn json(reports: &[Report<'_>]) -> Value {
+    let diagnostics: Vec<Value> = reports
+        .iter()
+        .flat_map(|report| {
+            report.diagnostics.iter().map(|diagnostic| {
+                let (line, column) = line_column(report.document, diagnostic.range.start);
+                json!({
+                    "path": report.path,
+                    "line": line,
+                    "column": column,
+                    "range": diagnostic.range,
+                    "severity": severity(diagnostic),
+                    "code": code(diagnostic),
+                    "message": diagnostic.message,
+                })
+            })
+        })
+        .collect();
+    Value::from(diagnostics)
+}
+
+/// Diagnostics without a code are syntax errors.
+const SYNTAX_RULE: &str = "syntax-error";
+
+/// The base of the URIs of files under the working directory.
+const SRCROOT: &str = "%SRCROOT%";
+
+fn sarif(reports: &[Report<'_>], root: &Path) -> Value {
+    let mut rules: Vec<String> = vec![];
+    let mut results = vec![];
+    for report in reports {
+        for diagnostic in &report.diagnostics {
+            let rule = code(diagnostic).unwrap_or_else(|| SYNTAX_RULE.to_string());
+            if !rules.contains(&rule) {
+                rules.push(rule.clone());
+            }
+            let (start_line, start_column) = line_column(report.document, diagnostic.range.start);
+            let (end_line, end_column) = line_column(report.document, diagnostic.range.end);
+            let level = match diagnostic.severity {
+                Some(DiagnosticSeverity::ERROR) => "error",
+                Some(DiagnosticSeverity::WARNING) => "warning",
+                _ => "note",
+            };
+            results.push(json!({
+                "ruleId": rule,
+                "level": level,
+                "message": { "text": diagnostic.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": artifact_location(&report.file, root),
+                        "region": {
+                            "startLine": start_line,
+                            "startColumn": start_column,
+                            "endLine": end_line,
+                            "endColumn": end_column,
+                        },
+                    },
+                }],
+            }));
+        }
+    }
+    json!({
+        "version": "2.1.0",
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules.iter().map(|rule| json!({ "id": rule })).collect::<Vec<_>>(),
+                },
+            },
+            "originalUriBaseIds": {
+                SRCROOT: { "uri": uri::from_path(root).map(|uri| format!("{}/", uri.as_str().trim_end_matches('/'))) },
+            },
+            "results": results,
+            // `line_column` counts characters.
+            "columnKind": "unicodeCodePoints",
+        }],
+    })
+}
+
+/// Where a file is: relative to the working directory when it is under it,
+/// and otherwise its `file:` URI.
+fn artifact_location(file: &Path, root: &Path) -> Value {
+    match file.strip_prefix(root) {
+        Ok(relative) => json!({ "uri": sarif_uri(relative), "uriBaseId": SRCROOT }),
+        Err(_) => json!({ "uri": uri::from_path(file).map(|uri| uri.as_str().to_string()) }),
+    }
+}
+
+/// A relative path as a URI reference: with forward slashes, and
+/// percent-encoded.
+fn sarif_uri(path: &Path) -> String {
+    let parts: Vec<String> = path.iter().map(|part| uri::escape(&part.to_string_lossy())).collect();
+    parts.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn artifact_locations() {
+        let root = Path::new("/work/site");
+        assert_eq!(
+            artifact_location(Path::new("/work/site/styles/a b%.css"), root),
+            json!({ "uri": "styles/a%20b%25.css", "uriBaseId": SRCROOT })
+        );
+        assert_eq!(
+            artifact_location(Path::new("/tmp/chk/a.css"), root),
+            json!({ "uri": "file:///tmp/chk/a.css" })
+        );
+    }
+}
@@ -0,0 +1,571 @@
+use std::collections::HashMap;
+
+use lsp_types::{CodeAction, CodeActionKind, Command, Range, TextEdit, WorkspaceEdit};
+use serde_json::json;
+
+use crate::{
+    commands::{EXTRACT_TO_FILE, extractable},
+    css::{
+        Span,
+        compat::Targets,
+        prefixes, properties,
+        syntax::{Block, Declaration, Item, Rule, Stylesheet, matching_close},
+        token::TokenKind,
+    },
+    document::Document,
+};
+
+/// A replacement of `Span` with `String` in the stylesheet.
+type Edit = (Span, String);
+
+/// Refactorings available for the declarations in `range`.
+pub fn code_actions(doc: &Document, range: Range, targets: &Targets) -> Vec<CodeAction> {
+    let Some((region, span)) = doc.region_span(range) else {
+        return vec![];
+    };
+    let sheet = &region.stylesheet;
+
+    let mut actions = vec![];
+    let mut push = |title: &str, edits: Vec<Edit>| {
+        if edits.is_empty() {
+            return;
+        }
+        let edits = edits
+            .into_iter()
+            .map(|(span, text)| TextEdit::new(doc.range(region, span), text))
+            .collect();
+        actions.push(CodeAction {
+            title: title.to_string(),
+            kind: Some(CodeActionKind::REFACTOR_REWRITE),
+            edit: Some(WorkspaceEdit {
+                changes: Some(HashMap::from([(doc.uri.clone(), edits)])),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+    };
+
+    let blocks = blocks(sheet);
+    let mut add = vec![];
+    let mut remove = vec![];
+    let mut colors = vec![];
+    let mut logical = vec![];
+    for block in &blocks {
+        let decls = declarations(block);
+        for decl in decls.iter().filter(|d| overlaps(d.span, span)) {
+            add.extend(add_prefixes(sheet, decl, &decls, targets));
+            remove.extend(remove_prefix(sheet, decl, &decls, targets));
+            colors.extend(modern_colors(sheet, decl));
+            logical.extend(logical_property(sheet, decl));
+        }
+    }
+    push("Add vendor prefixes", add);
+    push("Remove obsolete vendor prefixes", remove);
+    push("Convert colors to modern syntax", colors);
+    push("Convert to logical properties", logical);
+
+    let innermost = blocks
+        .iter()
+        .filter(|b| b.span.start < span.start && span.start <= b.span.end)
+        .filter(|b| declarations(b).len() > 1)
+        .min_by_key(|b| b.span.end - b.span.start);
+    if let Some(block) = innermost {
+        push("Sort declarations alphabetically", sort(sheet, block, alphabetical_key));
+        push("Sort declarations by group", sort(sheet, block, group_key));
+    }
+
+    if doc.language_id == "css"
+        && let Some(rules) = extractable(sheet, span)
+    {
+        let title = match rules.len() {
+            1 => "Extract rule to a new file".to_string(),
+            n => format!("Extract {n} rules to a new file"),
+        };
+        actions.push(CodeAction {
+            title: title.clone(),
+            kind: Some(CodeActionKind::REFACTOR_EXTRACT),
+            command: Some(Command {
+                title,
+                command: EXTRACT_TO_FILE.to_string(),
+                arguments: Some(vec![json!(doc.uri), json!(range)]),
+            }),
+            ..Default::default()
+        });
+    }
+
+    actions
+}
+
+fn overlaps(a: Span, b: Span) -> bool {
+    a.start <= b.end && b.start <= a.end
+}
+
+fn blocks(sheet: &Stylesheet) -> Vec<&Block> {
+    fn walk<'a>(rule: &'a Rule, out: &mut Vec<&'a Block>) {
+        let Some(block) = rule.block() else { return };
+        out.push(block);
+        for item in &block.items {
+            if let Item::Rule(rule) = item {
+                walk(rule, out);
+            }
+        }
+    }
+
+    let mut out = vec![];
+    for rule in &sheet.rules {
+        walk(rule, &mut out);
+    }
+    out
+}
+
+fn declarations(block: &Block) -> Vec<&Declaration> {
+    block
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Declaration(decl) => Some(decl),
+            Item::Rule(_) => None,
+        })
+        .collect()
+}
+
+fn has_property(decls: &[&Declaration], name: &str) -> bool {
+    decls.iter().any(|d| d.name.eq_ignore_ascii_case(name))
+}
+
+/// Whitespace to put between a declaration inserted before `decl` and `decl`
+/// itself: a newline with the same indentation, or a space if `decl` shares its
+/// line with something else.
+fn separator(sheet: &Stylesheet, decl: &Declaration) -> String {
+    let before = &sheet.text[..decl.span.start];
+    let indent = &before[before.rfind('\n').map_or(0, |i| i + 1)..];
+    if before.contains('\n') && indent.chars().all(char::is_whitespace) {
+        format!("\n{indent}")
+    } else {
+        " ".to_string()
+    }
+}
+
+fn add_prefixes(sheet: &Stylesheet, decl: &Declaration, siblings: &[&Declaration], targets: &Targets) -> Option<Edit> {
+    if decl.name.starts_with('-') {
+        return None;
+    }
+    // The value including `!important`.
+    let value = &sheet.text[decl.value.start..decl.span.end];
+    let separator = separator(sheet, decl);
+    let text = prefixes::needed(&decl.name, targets)
+        .into_iter()
+        .map(|prefix| format!("{prefix}{}", decl.name))
+        .filter(|name| !has_property(siblings, name))
+        .map(|name| format!("{name}: {value};{separator}"))
+        .collect::<String>();
+    (!text.is_empty()).then(|| (Span::new(decl.span.start, decl.span.start), text))
+}
+
+fn remove_prefix(sheet: &Stylesheet, decl: &Declaration, siblings: &[&Declaration], targets: &Targets) -> Option<Edit> {
+    if !prefixes::is_obsolete(&decl.name, targets) {
+        return None;
+    }
+    let unprefixed = properties::strip_vendor_prefix(&decl.name);
+    if !has_property(siblings, unprefixed) {
+        return Some((decl.name_span, unprefixed.to_string()));
+    }
+
+    // Delete the declaration with its `;`, and its whole line if it has one
+    // to itself.
+    let text = sheet.text.as_str();
+    let is_blank = |c: char| c == ' ' || c == '\t';
+    let line_start = text[..decl.span.start].trim_end_matches(is_blank).len();
+    let own_line = line_start == 0 || text[..line_start].ends_with('\n');
+    let mut end = decl.span.end;
+    end = text.len() - text[end..].trim_start_matches(is_blank).len();
+    if text[end..].starts_with(';') {
+        end += 1;
+    }
+    end = text.len() - text[end..].trim_start_matches(is_blank).len();
+    let start = if own_line && text[end..].starts_with('\n') {
+        end += 1;
+        line_start
+    } else {
+        decl.span.start
+    };
+    Some((Span::new(start, end), String::new()))
+}
+
+/// Rewrites `rgba(0, 0, 0, .5)` as `rgb(0 0 0 / .5)`, and likewise for `hsla()`.
+fn modern_colors(sheet: &Stylesheet, decl: &Declaration) -> Vec<Edit> {
+    let text = sheet.text.as_str();
+    let tokens = sheet.tokens_in(decl.value);
+    let mut edits = vec![];
+    for (i, token) in tokens.iter().enumerate() {
+        if token.kind != TokenKind::Function {
+            continue;
+        }
+        let name = token.name(text).to_ascii_lowercase();
+        let modern = match name.as_str() {
+            "rgb" | "rgba" => "rgb",
+            "hsl" | "hsla" => "hsl",
+            _ => continue,
+        };
+        let close = matching_close(tokens, i);
+        let Some(close_token) = tokens.get(close) else { continue };
+
+        let mut args = vec![];
+        let mut arg_start = i + 1;
+        let mut depth = 0;
+        for (j, t) in tokens.iter().enumerate().take(close + 1).skip(i + 1) {
+            match t.kind {
+                TokenKind::Function | TokenKind::LeftParen => depth += 1,
+                TokenKind::RightParen if depth > 0 => depth -= 1,
+                TokenKind::Comma | TokenKind::RightParen if depth == 0 => {
+                    let mut arg = tokens[arg_start..j].iter().filter(|t| !t.is_trivia());
+                    let first = arg.next();
+                    let last = arg.next_back().or(first);
+                    if let (Some(first), Some(last)) = (first, last) {
+                        args.push(&text[first.span.start..last.span.end]);
+                    }
+                    arg_start = j + 1;
+                }
+                _ => {}
+            }
+        }
+
+        let has_commas = tokens[i..close].iter().any(|t| t.kind == TokenKind::Comma);
+        let new = match (has_commas, &args[..]) {
+            (true, [a, b, c]) => format!("{modern}({a} {b} {c})"),
+            (true, [a, b, c, alpha]) => format!("{modern}({a} {b} {c} / {alpha})"),
+            (false, _) if name != modern => {
+                let args = &text[token.span.end..close_token.span.start];
+                format!("{modern}({args})")
+            }
+            _ => continue,
+        };
+        edits.push((Span::new(token.span.start, close_token.span.end), new));
+    }
+    edits
+}
+
+fn logical_property(sheet: &Stylesheet, decl: &Declaration) -> Vec<Edit> {
+    let name = decl.name.to_ascii_lowercase();
+    let mut edits = vec![];
+    if let Some(logical) = logical_name(&name) {
+        edits.push((decl.name_span, logical));
+    }
+
+    let value = decl.value.text(&sheet.text).to_ascii_lowercase();
+    let logical_value = match (name.as_str(), value.as_str()) {
+        ("text-align", "left") => Some("start"),
+        ("text-align", "right") => Some("end"),
+        ("float" | "clear", "left") => Some("inline-start"),
+        ("float" | "clear", "right") => Some("inline-end"),
+        _ => None,
+    };
+    if let Some(value) = logical_value {
+        edits.push((decl.value, value.to_string()));
+    }
+    edits
+}
+
+/// The flow-relative equivalent of a physical property, assuming a horizontal
+/// writing mode.
+fn logical_name(name: &str) -> Option<String> {
+    let logical = match name {
+        "top" => "inset-block-start",
+        "bottom" => "inset-block-end",
+        "left" => "inset-inline-start",
+        "right" => "inset-inline-end",
+        "width" => "inline-size",
+        "height" => "block-size",
+        "min-width" => "min-inline-size",
+        "max-width" => "max-inline-size",
+        "min-height" => "min-block-size",
+        "max-height" => "max-block-size",
+        "overflow-x" => "overflow-inline",
+        "overflow-y" => "overflow-block",
+        "overscroll-behavior-x" => "overscroll-behavior-inline",
+        "overscroll-behavior-y" => "overscroll-behavior-block",
+        "border-top-left-radius" => "border-start-start-radius",
+        "border-top-right-radius" => "border-start-end-radius",
+        "border-bottom-left-radius" => "border-end-start-radius",
+        "border-bottom-right-radius" => "border-end-end-radius",
+        _ => {
+            for base in ["margin", "padding", "scroll-margin", "scroll-padding", "border"] {
+                let Some(rest) = name.strip_prefix(base).and_then(|r| r.strip_prefix('-')) else {
+                    continue;
+                };
+                let (side, suffix) = match rest.split_once('-') {
+                    Some((side, suffix)) if base == "border" && matches!(suffix, "color" | "style" | "width") => {
+                        (side, format!("-{suffix}"))
+                    }
+                    Some(_) => return None,
+                    None => (rest, String::new()),
+                };
+                let side = match side {
+                    "top" => "block-start",
+                    "bottom" => "block-end",
+                    "left" => "inline-start",
+                    "right" => "inline-end",
+                    _ => return None,
+                };
+                return Some(format!("{base}-{side}{suffix}"));
+            }
+            return None;
+        }
+    };
+    Some(logical.to_string())
+}
+
+/// Reorders the declarations of `block` by `key`, keeping whitespace, comments
+/// and nested rules where they are.
+fn sort<K: Ord>(sheet: &Stylesheet, block: &Block, key: impl Fn(&str) -> K) -> Vec<Edit> {
+    let decls = declarations(block);
+    let mut order = (0..decls.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| key(&decls[i].name.to_ascii_lowercase()));
+
+    order
+        .iter()
+        .enumerate()
+        .filter(|(slot, from)| slot != *from)
+        .map(|(slot, &from)| (decls[slot].span, decls[from].span.text(&sheet.text).to_string()))
+        .collect()
+}
+
+/// Sorts by name, keeping prefixed properties just before their standard form.
+fn alphabetical_key(name: &str) -> (String, bool) {
+    let unprefixed = properties::strip_vendor_prefix(name);
+    (unprefixed.to_string(), unprefixed == name)
+}
+
+/// Property groups in the order they are sorted in. A property belongs to the
+/// first entry that equals it or that it extends with a `-`.
+const GROUPS: &[&[&str]] = &[
+    &["position", "inset", "top", "right", "bottom", "left", "z-index"],
+    &[
+        "display",
+        "flex",
+        "grid",
+        "place",
+        "align",
+        "justify",
+        "order",
+        "gap",
+        "row-gap",
+        "column-gap",
+        "float",
+        "clear",
+        "box-sizing",
+        "width",
+        "min-width",
+        "max-width",
+        "height",
+        "min-height",
+        "max-height",
+        "inline-size",
+        "block-size",
+        "aspect-ratio",
+        "margin",
+        "padding",
+        "overflow",
+        "container",
+    ],
+    &[
+        "font",
+        "line-height",
+        "letter-spacing",
+        "word-spacing",
+        "text",
+        "color",
+        "white-space",
+        "word-break",
+        "hyphens",
+        "vertical-align",
+        "list-style",
+    ],
+    &[
+        "background",
+        "border",
+        "outline",
+        "box-shadow",
+        "opacity",
+        "visibility",
+        "cursor",
+        "filter",
+        "backdrop-filter",
+        "mask",
+        "clip-path",
+        "object-fit",
+        "object-position",
+    ],
+    &["transition", "animation", "transform", "will-change"],
+];
+
+/// Sorts custom properties first, then by `GROUPS`, then everything else.
+/// The sort is stable, so properties with equal keys keep their order.
+fn group_key(name: &str) -> (usize, usize) {
+    if name.starts_with("--") {
+        return (0, 0);
+    }
+    let unprefixed = properties::strip_vendor_prefix(name);
+    GROUPS
+        .iter()
+        .enumerate()
+        .find_map(|(group, entries)| {
+            entries
+                .iter()
+                .position(|e| unprefixed == *e || unprefixed.strip_prefix(e).is_some_and(|rest| rest.starts_with('-')))
+                .map(|entry| (group + 1, entry))
+        })
+        .unwrap_or((GROUPS.len() + 1, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{css::compat::Targets, line_index::Encoding};
+
+    /// The text after each action offered for the text between the `|`
+    /// markers, by title.
+    fn actions(marked: &str, targets: &Targets) -> Vec<(String, String)> {
+        let start = marked.find('|').unwrap();
+        let end = marked.rfind('|').unwrap() - 1;
+        let text = marked.replace('|', "");
+        let doc = Document::new(
+            "file:///a.css".parse().unwrap(),
+            "css".to_string(),
+            0,
+            text.clone(),
+            Encoding::default(),
+        );
+        let range = Range::new(doc.line_index.position(start), doc.line_index.position(end));
+        code_actions(&doc, range, targets)
+            .into_iter()
+            .filter_map(|action| {
+                let mut edits = action.edit?.changes?.remove(&doc.uri)?;
+                edits.sort_by_key(|edit| std::cmp::Reverse(doc.line_index.offset(edit.range.start)));
+                let mut text = text.clone();
+                for edit in edits {
+                    let span = doc.line_index.offset(edit.range.start)..doc.line_index.offset(edit.range.end);
+                    text.replace_range(span, &edit.new_text);
+                }
+                Some((action.title, text))
+            })
+            .collect()
+    }
+
+    fn action(marked: &str, title: &str) -> Option<String> {
+        actions(marked, &Targets::default())
+            .into_iter()
+            .find(|(t, _)| t == title)
+            .map(|(_, text)| text)
+    }
+
+    const REMOVE: &str = "Remove obsolete vendor prefixes";
+
+    #[test]
+    fn add_prefixes() {
+        let old = Targets::from_json(&serde_json::json!({ "chrome": "30", "firefox": "60" })).unwrap();
+        let added = actions("a {\n  |user-select: none !important;|\n}", &old)
+            .into_iter()
+            .find(|(title, _)| title == "Add vendor prefixes");
+        assert_eq!(
+            added.unwrap().1,
+            "a {\n  \
+               -moz-user-select: none !important;\n  \
+               -webkit-user-select: none !important;\n  \
+               user-select: none !important;\n\
+             }"
+        );
+        // Prefixes already there are not added again.
+        assert_eq!(
+            actions("a { -webkit-user-select: none; |user-select: none| }", &old)
+                .into_iter()
+                .find(|(title, _)| title == "Add vendor prefixes")
+                .unwrap()
+                .1,
+            "a { -webkit-user-select: none; -moz-user-select: none; user-select: none }"
+        );
+        assert_eq!(action("a { |transform: none| }", "Add vendor prefixes"), None);
+    }
+
+    #[test]
+    fn remove_prefixes() {
+        // Renamed when the standard property is missing, deleted otherwise.
+        assert_eq!(
+            action("a { |-webkit-transform: none| }", REMOVE).unwrap(),
+            "a { transform: none }"
+        );
+        assert_eq!(
+            action("a {\n  |-webkit-transform: none;\n  transform: none;|\n}", REMOVE).unwrap(),
+            "a {\n  transform: none;\n}"
+        );
+        // Still needed by Safari, or without support data.
+        assert_eq!(action("a { |-webkit-user-select: none| }", REMOVE), None);
+        assert_eq!(action("a { |-webkit-background-clip: text| }", REMOVE), None);
+    }
+
+    #[test]
+    fn modern_colors() {
+        assert_eq!(
+            action(
+                "a { |color: rgba(0, 0, 0, .5); background: hsl(0, 0%, 0%)| }",
+                "Convert colors to modern syntax"
+            )
+            .unwrap(),
+            "a { color: rgb(0 0 0 / .5); background: hsl(0 0% 0%) }"
+        );
+        assert_eq!(
+            action("a { |color: rgba(0 0 0 / .5)| }", "Convert colors to modern syntax").unwrap(),
+            "a { color: rgb(0 0 0 / .5) }"
+        );
+        assert_eq!(
+            action("a { |color: rgb(0 0 0)| }", "Convert colors to modern syntax"),
+            None
+        );
+    }
+
+    #[test]
+    fn logical_properties() {
+        assert_eq!(
+            action(
+                "a { |margin-left: 0; border-top-color: red; text-align: left; width: 1px| }",
+                "Convert to logical properties"
+            )
+            .unwrap(),
+            "a { margin-inline-start: 0; border-block-start-color: red; text-align: start; inline-size: 1px }"
+        );
+        assert_eq!(
+            action(
+                "a { |margin-left-x: 0; border-top-radius: 0| }",
+                "Convert to logical properties"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn sort_declarations() {
+        let text = "a {\n  |color: red;\n  display: block;\n  /* kept */\n  background: blue;\n}";
+        assert_eq!(
+            action(text, "Sort declarations alphabetically").unwrap(),
+            "a {\n  background: blue;\n  color: red;\n  /* kept */\n  display: block;\n}"
+        );
+        assert_eq!(
+            action(text, "Sort declarations by group").unwrap(),
+            "a {\n  display: block;\n  color: red;\n  /* kept */\n  background: blue;\n}"
+        );
+        assert_eq!(action("a { |color: red| }", "Sort declarations alphabetically"), None);
+    }
+
+    #[test]
+    fn only_the_declarations_in_range() {
+        let titles: Vec<String> = actions(
+            "a { -webkit-transform: none; |color: rgba(0, 0, 0, 1)| }",
+            &Targets::default(),
+        )
+        .into_iter()
+        .map(|(title, _)| title)
+        .collect();
+        assert!(titles.contains(&"Convert colors to modern syntax".to_string()));
+        assert!(!titles.contains(&REMOVE.to_string()));
+    }
+}
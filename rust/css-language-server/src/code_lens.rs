@@ -0,0 +1,168 @@
+use lsp_types::{CodeLens, Command, Position, Uri};
+use serde_json::json;
+
+use crate::{css::symbols::SymbolKind, document::Document, references::symbol_references, state::State};
+
+/// The command the resolved lenses run, which shows a list of locations. It
+/// is built into VS Code; other clients can handle it themselves.
+const SHOW_REFERENCES: &str = "editor.action.showReferences";
+
+/// A lens above each custom property declaration, `@keyframes` and `@layer`
+/// statement. Their references are counted when the lens is resolved, since
+/// that searches the whole workspace.
+pub fn code_lenses(doc: &Document) -> Vec<CodeLens> {
+    let mut lenses = vec![];
+    for region in &doc.regions {
+        for symbol in &region.symbols {
+            if !symbol.declaration || !region.contains(symbol.span) {
+                continue;
+            }
+            let range = doc.range(region, symbol.span);
+            lenses.push(CodeLens {
+                range,
+                command: None,
+                data: Some(json!({ "uri": doc.uri, "position": range.start })),
+            });
+        }
+    }
+    lenses
+}
+
+/// Adds the "N references" command to a lens from `code_lenses`. A lens whose
+/// declaration is gone is returned unchanged.
+pub fn resolve(state: &State, mut lens: CodeLens) -> CodeLens {
+    let Some((uri, position)) = lens.data.as_ref().and_then(|data| {
+        let uri: Uri = serde_json::from_value(data.get("uri")?.clone()).ok()?;
+        let position: Position = serde_json::from_value(data.get("position")?.clone()).ok()?;
+        Some((uri, position))
+    }) else {
+        return lens;
+    };
+    let Some((kind, name)) = declaration_at(state, &uri, position) else {
+        return lens;
+    };
+    let locations = symbol_references(state, &uri, kind, &name);
+    let title = match locations.len() {
+        1 => "1 reference".to_string(),
+        n => format!("{n} references"),
+    };
+    lens.command = Some(Command {
+        title,
+        command: SHOW_REFERENCES.to_string(),
+        arguments: Some(vec![json!(uri), json!(position), json!(locations)]),
+    });
+    lens
+}
+
+fn declaration_at(state: &State, uri: &Uri, position: Position) -> Option<(SymbolKind, String)> {
+    let doc = state.document(uri)?;
+    let offset = doc.line_index.offset(position);
+    doc.regions.iter().find_map(|region| {
+        let offset = region.to_virtual(offset)?;
+        let symbol = region
+            .symbols
+            .iter()
+            .find(|symbol| symbol.declaration && symbol.span.start == offset)?;
+        Some((symbol.kind, symbol.name.clone()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::Range;
+
+    use super::*;
+    use crate::line_index::Encoding;
+
+    fn document(uri: &str, language_id: &str, text: &str) -> Document {
+        Document::new(
+            uri.parse().unwrap(),
+            language_id.to_string(),
+            0,
+            text.to_string(),
+            Encoding::default(),
+        )
+    }
+
+    /// `a.css` and `b.html` open, and `c.css` indexed but closed.
+    fn state() -> State {
+        let mut state = State::default();
+        let a = document(
+            "file:///a.css",
+            "css",
+            "@layer base;\n@keyframes spin {}\na { --x: 1; animation: spin; width: var(--x) }",
+        );
+        let b = document(
+            "file:///b.html",
+            "html",
+            "<style>@layer base {}</style>\n<p style=\"animation: 1s spin\"></p>",
+        );
+        let c = document("file:///c.css", "css", "b { animation-name: spin }");
+        for doc in [a, b] {
+            state.documents.insert(doc.uri.clone(), doc);
+        }
+        let files = &mut state.default_folder.workspace.files;
+        files.insert(c.uri.clone(), c);
+        state
+    }
+
+    fn titles(state: &State, uri: &str) -> Vec<Option<String>> {
+        let lenses = code_lenses(state.document(&uri.parse().unwrap()).unwrap());
+        lenses
+            .into_iter()
+            .map(|lens| resolve(state, lens).command.map(|command| command.title))
+            .collect()
+    }
+
+    #[test]
+    fn reference_counts() {
+        let state = state();
+        // `base`, `spin` and `--x`.
+        assert_eq!(
+            titles(&state, "file:///a.css"),
+            [
+                Some("1 reference".to_string()),
+                Some("3 references".to_string()),
+                Some("1 reference".to_string()),
+            ]
+        );
+        assert_eq!(titles(&state, "file:///b.html"), []);
+    }
+
+    #[test]
+    fn command() {
+        let state = state();
+        let doc = state.document(&"file:///a.css".parse().unwrap()).unwrap();
+        let lens = resolve(&state, code_lenses(doc).remove(0));
+        let command = lens.command.unwrap();
+        assert_eq!(command.command, SHOW_REFERENCES);
+        let arguments = command.arguments.unwrap();
+        assert_eq!(arguments[0], json!("file:///a.css"));
+        assert_eq!(arguments[1], json!(Position::new(0, 7)));
+        assert_eq!(arguments[2][0]["uri"], json!("file:///b.html"));
+    }
+
+    #[test]
+    fn declaration_edited_away() {
+        let mut state = state();
+        let uri: Uri = "file:///a.css".parse().unwrap();
+        let lenses = code_lenses(state.document(&uri).unwrap());
+        let doc = state.documents.get_mut(&uri).unwrap();
+        doc.edit(Range::new(Position::new(1, 0), Position::new(1, 18)), "");
+        let resolved: Vec<_> = lenses.iter().map(|lens| resolve(&state, lens.clone())).collect();
+        // The line of `@keyframes spin` is now empty.
+        assert!(resolved[0].command.is_some());
+        assert!(resolved[1].command.is_none());
+        // Nor is a lens without data, or on a document that is not known.
+        let lens = CodeLens {
+            data: None,
+            ..lenses[0].clone()
+        };
+        assert!(resolve(&state, lens).command.is_none());
+        let lens = CodeLens {
+            data: Some(json!({ "uri": "file:///d.css", "position": Position::new(0, 0) })),
+            ..lenses[0].clone()
+        };
+        assert!(resolve(&state, lens).command.is_none());
+    }
+}
@@ -0,0 +1,263 @@
+use std::ops::Range as Indices;
+
+use anyhow::{Context, Result, bail};
+use lsp_types::{
+    CreateFile, CreateFileOptions, DocumentChangeOperation, DocumentChanges, OneOf,
+    OptionalVersionedTextDocumentIdentifier, Range, ResourceOp, TextDocumentEdit, TextEdit, Uri, WorkspaceEdit,
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::{
+    css::{
+        Span,
+        format::minify,
+        source_map::source_map,
+        syntax::{Rule, Stylesheet},
+        token::TokenKind,
+    },
+    document::{Document, Region},
+    state::State,
+    uri,
+};
+
+/// Writes a minified copy of a stylesheet and its source map next to it, as
+/// `a.min.css` and `a.min.css.map`. Arguments: the URI of the stylesheet.
+pub const MINIFY: &str = "css.minify";
+
+/// Moves the top-level rules in a range of a stylesheet to a new file and
+/// imports it in their place. Arguments: the URI of the stylesheet, the
+/// range, and optionally the path of the new file relative to the
+/// stylesheet, which defaults to `a-extracted.css`.
+pub const EXTRACT_TO_FILE: &str = "css.extractToFile";
+
+pub const COMMANDS: [&str; 2] = [MINIFY, EXTRACT_TO_FILE];
+
+/// What a command asks of the client.
+pub struct Outcome {
+    /// Shown by the client, e.g. to undo the edit.
+    pub label: String,
+    pub edit: WorkspaceEdit,
+    /// The file the command creates, which is the result of the request.
+    pub uri: Uri,
+}
+
+/// Runs one of `COMMANDS`. Fails with a message for the user if it does not
+/// apply to its arguments.
+pub async fn execute(state: &State, command: &str, arguments: &[Value]) -> Result<Outcome> {
+    let uri: Uri = argument(arguments, 0, "the URI of the stylesheet")?;
+    let doc = state.document(&uri).context("the stylesheet is not open")?;
+    match command {
+        MINIFY => minify_to_file(doc),
+        EXTRACT_TO_FILE => {
+            let range: Range = argument(arguments, 1, "the range of the rules")?;
+            let path = match arguments.get(2) {
+                Some(_) => Some(argument(arguments, 2, "the path of the new file")?),
+                None => None,
+            };
+            extract_to_file(doc, range, path).await
+        }
+        _ => bail!("unknown command `{command}`"),
+    }
+}
+
+fn argument<T: DeserializeOwned>(arguments: &[Value], index: usize, expected: &str) -> Result<T> {
+    let argument = arguments.get(index).cloned().unwrap_or_default();
+    serde_json::from_value(argument).with_context(|| format!("expected {expected} as argument {}", index + 1))
+}
+
+/// The region of a CSS document. Stylesheets embedded in markup have no file
+/// of their own to write next to or import into.
+fn css_region(doc: &Document) -> Result<&Region> {
+    match &doc.regions[..] {
+        [region] if doc.language_id == "css" => Ok(region),
+        _ => bail!("only CSS files are supported"),
+    }
+}
+
+fn minify_to_file(doc: &Document) -> Result<Outcome> {
+    let sheet = &css_region(doc)?.stylesheet;
+    let minified = minify(sheet).context("the stylesheet has syntax errors")?;
+    let path = uri::to_path(&doc.uri).context("the stylesheet is not a file")?;
+    let name = path
+        .file_name()
+        .context("the stylesheet has no file name")?
+        .to_string_lossy();
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let minified_name = format!("{stem}.min.css");
+    let map_name = format!("{minified_name}.map");
+
+    let map = source_map(&minified_name, &minified.text, &name, &sheet.text, &minified.mappings);
+    let text = format!("{}\n/*# sourceMappingURL={map_name} */\n", minified.text);
+    let minified_uri = uri::from_path(&path.with_file_name(&minified_name)).context("invalid file name")?;
+    let map_uri = uri::from_path(&path.with_file_name(&map_name)).context("invalid file name")?;
+    let mut changes = create(&minified_uri, text, true);
+    changes.extend(create(&map_uri, map.to_string(), true));
+    Ok(Outcome {
+        label: format!("Minify {name}"),
+        edit: WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Operations(changes)),
+            ..Default::default()
+        },
+        uri: minified_uri,
+    })
+}
+
+/// Creates a file with `text`, replacing any file there if `overwrite`.
+fn create(uri: &Uri, text: String, overwrite: bool) -> Vec<DocumentChangeOperation> {
+    let options = CreateFileOptions {
+        overwrite: Some(overwrite),
+        ignore_if_exists: Some(false),
+    };
+    vec![
+        DocumentChangeOperation::Op(ResourceOp::Create(CreateFile {
+            uri: uri.clone(),
+            options: Some(options),
+            annotation_id: None,
+        })),
+        edit(uri, None, vec![TextEdit::new(Range::default(), text)]),
+    ]
+}
+
+fn edit(uri: &Uri, version: Option<i32>, edits: Vec<TextEdit>) -> DocumentChangeOperation {
+    DocumentChangeOperation::Edit(TextDocumentEdit {
+        text_document: OptionalVersionedTextDocumentIdentifier {
+            uri: uri.clone(),
+            version,
+        },
+        edits: edits.into_iter().map(OneOf::Left).collect(),
+    })
+}
+
+/// The top-level rules overlapping `span` that can be moved to another file,
+/// as indices into the rules of the stylesheet. `@charset`, `@import` and
+/// `@namespace` only apply to the stylesheet they are in, so a selection
+/// containing one cannot be moved.
+pub fn extractable(sheet: &Stylesheet, span: Span) -> Option<Indices<usize>> {
+    let start = sheet
+        .rules
+        .iter()
+        .position(|rule| rule.start() <= span.end && span.start <= end(sheet, rule))?;
+    let count = sheet.rules[start..]
+        .iter()
+        .take_while(|rule| rule.start() <= span.end)
+        .count();
+    let rules = start..start + count;
+    let movable = sheet.rules[rules.clone()].iter().all(|rule| match rule {
+        Rule::At(rule) => !matches!(rule.name.as_str(), "charset" | "import" | "namespace"),
+        Rule::Style(_) => true,
+    });
+    movable.then_some(rules)
+}
+
+/// The end of a rule, including the `;` of an at-rule without a block.
+fn end(sheet: &Stylesheet, rule: &Rule) -> usize {
+    match rule {
+        Rule::Style(rule) => rule.block.span.end,
+        Rule::At(rule) => match &rule.block {
+            Some(block) => block.span.end,
+            None => {
+                let end = rule.prelude.end.max(rule.name_span.end);
+                let next = sheet
+                    .tokens_in(Span::new(end, sheet.text.len()))
+                    .iter()
+                    .find(|t| !t.is_trivia());
+                match next {
+                    Some(token) if token.kind == TokenKind::Semicolon => token.span.end,
+                    _ => end,
+                }
+            }
+        },
+    }
+}
+
+/// Moves the rules to the new file, with the comments between them, and
+/// imports it where the rules were or, if other rules come first, after the
+/// imports at the top. Either way the rules now come earlier in the cascade,
+/// as imported rules do.
+async fn extract_to_file(doc: &Document, range: Range, path: Option<String>) -> Result<Outcome> {
+    let region = css_region(doc)?;
+    let sheet = &region.stylesheet;
+    let (_, span) = doc
+        .region_span(range)
+        .context("the range is outside of the stylesheet")?;
+    let rules = extractable(sheet, span).context("no rules in the range can be extracted")?;
+    let first = sheet.rules[rules.start].start();
+    let last = end(sheet, &sheet.rules[rules.end - 1]);
+    let moved = format!("{}\n", &sheet.text[first..last]);
+
+    let source = uri::to_path(&doc.uri).context("the stylesheet is not a file")?;
+    let dir = source.parent().context("the stylesheet has no directory")?;
+    let path = match path {
+        Some(path) => {
+            if !path.ends_with(".css") || path.starts_with('/') || path.contains(['"', '\\']) {
+                bail!("`{path}` is not the relative path of a CSS file");
+            }
+            path
+        }
+        None => {
+            let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+            let mut path = format!("{stem}-extracted.css");
+            let mut n = 1;
+            while tokio::fs::try_exists(dir.join(&path)).await.unwrap_or(false) {
+                n += 1;
+                path = format!("{stem}-extracted-{n}.css");
+            }
+            path
+        }
+    };
+    let new_uri = uri::from_path(&dir.join(&path)).context("invalid file name")?;
+
+    // The line of the last rule goes too, if nothing else is on it.
+    let rest = &sheet.text[last..];
+    let blank = rest.len() - rest.trim_start_matches([' ', '\t']).len();
+    let newline = match &rest[blank..] {
+        rest if rest.starts_with("\r\n") => 2,
+        rest if rest.starts_with('\n') => 1,
+        _ => 0,
+    };
+    let removed = Span::new(first, last + blank + newline);
+
+    // An `@import` may only follow `@charset`, other imports and `@layer`
+    // statements.
+    let import = format!("@import \"{path}\";");
+    let leading = sheet.rules[..rules.start]
+        .iter()
+        .take_while(|rule| match rule {
+            Rule::At(rule) => rule.block.is_none() && matches!(rule.name.as_str(), "charset" | "import" | "layer"),
+            Rule::Style(_) => false,
+        })
+        .count();
+    let edits = match leading {
+        0 if sheet.rules[0].start() == first => vec![(removed, format!("{import}\n"))],
+        0 => {
+            let start = sheet.rules[0].start();
+            vec![
+                (Span::new(start, start), format!("{import}\n")),
+                (removed, String::new()),
+            ]
+        }
+        n => {
+            let end = end(sheet, &sheet.rules[n - 1]);
+            vec![(Span::new(end, end), format!("\n{import}")), (removed, String::new())]
+        }
+    };
+    let edits = edits
+        .into_iter()
+        .map(|(span, text)| TextEdit::new(doc.range(region, span), text))
+        .collect();
+
+    let mut changes = create(&new_uri, moved, false);
+    changes.push(edit(&doc.uri, Some(doc.version), edits));
+    Ok(Outcome {
+        label: match rules.len() {
+            1 => format!("Extract rule to {path}"),
+            n => format!("Extract {n} rules to {path}"),
+        },
+        edit: WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Operations(changes)),
+            ..Default::default()
+        },
+        uri: new_uri,
+    })
+}
@@ -0,0 +1,267 @@
+use lsp_types::{CompletionItem, CompletionItemKind, CompletionTextEdit, Position, Range, TextEdit, Uri};
+
+use crate::{
+    css::{
+        descriptors::descriptors,
+        frameworks::AtRuleKind,
+        properties::PROPERTIES,
+        syntax::{Item, Rule},
+    },
+    css_modules::{Script, classes},
+    document::Document,
+    state::State,
+};
+
+pub fn completions(state: &State, uri: &Uri, position: Position) -> Vec<CompletionItem> {
+    if let Some(items) = state
+        .folder(uri)
+        .workspace
+        .scripts
+        .get(uri)
+        .and_then(|script| module_classes(state, script, position))
+    {
+        return items;
+    }
+    match state.document(uri) {
+        Some(doc) => declaration_names(doc, position).unwrap_or_else(|| utility_classes(state, doc, position)),
+        None => vec![],
+    }
+}
+
+/// The classes of a CSS Module after `styles.`.
+fn module_classes(state: &State, script: &Script, position: Position) -> Option<Vec<CompletionItem>> {
+    let offset = script.line_index.offset(position);
+    let (import, dot) = script.member_access_at(offset)?;
+    let module = state.document(&import.module)?;
+    let replace = Range::new(script.line_index.position(dot), position);
+    Some(
+        classes(module)
+            .into_iter()
+            .map(|class| module_class(class, replace))
+            .collect(),
+    )
+}
+
+/// A class exported by a CSS Module, read after `styles.`. Names that are not
+/// identifiers are inserted as `styles["a-b"]` instead, replacing the `.`.
+fn module_class(class: String, replace: Range) -> CompletionItem {
+    let identifier = class.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+        && !class.starts_with(|c: char| c.is_ascii_digit());
+    let text_edit = (!identifier).then(|| {
+        let key = serde_json::Value::from(class.as_str());
+        CompletionTextEdit::Edit(TextEdit::new(replace, format!("[{key}]")))
+    });
+    CompletionItem {
+        kind: Some(CompletionItemKind::FIELD),
+        detail: Some("CSS Module class".to_string()),
+        filter_text: text_edit.as_ref().map(|_| format!(".{class}")),
+        text_edit,
+        label: class,
+        ..Default::default()
+    }
+}
+
+/// The names a declaration can start with, where one starts in a block:
+/// properties in a style rule, including the `style` attributes of HTML, and
+/// the descriptors of an at-rule such as `@font-face`. Descriptors already
+/// given are left out.
+fn declaration_names(doc: &Document, position: Position) -> Option<Vec<CompletionItem>> {
+    let offset = doc.line_index.offset(position);
+    doc.regions.iter().find_map(|region| {
+        let offset = region.to_virtual(offset)?;
+        let sheet = &region.stylesheet;
+        let rule = innermost_rule(&sheet.rules, &sheet.text, offset)?;
+        let block = rule.block()?;
+        let before = &sheet.text[block.span.start + 1..offset];
+        let typed = before.rsplit([';', '{', '}']).next()?.trim_start();
+        if !typed.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return None;
+        }
+        let start = Position::new(position.line, position.character - typed.len() as u32);
+        let replace = Range::new(start, position);
+        let item = |name: &str, detail: Option<&str>| CompletionItem {
+            label: name.to_string(),
+            kind: Some(CompletionItemKind::PROPERTY),
+            detail: detail.map(str::to_string),
+            text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(replace, format!("{name}: ")))),
+            ..Default::default()
+        };
+        let Rule::At(at_rule) = rule else {
+            return Some(PROPERTIES.iter().map(|name| item(name, None)).collect());
+        };
+        let descriptors = descriptors(&at_rule.name)?;
+        let given: Vec<String> = block
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Declaration(decl) if !(decl.name_span.start..=decl.name_span.end).contains(&offset) => {
+                    Some(decl.name.to_ascii_lowercase())
+                }
+                _ => None,
+            })
+            .collect();
+        let items = descriptors
+            .list
+            .iter()
+            .filter(|descriptor| !given.iter().any(|name| name == descriptor.name))
+            .map(|descriptor| CompletionItem {
+                // Required descriptors first.
+                sort_text: Some(format!(
+                    "{}{}",
+                    if descriptor.required { 0 } else { 1 },
+                    descriptor.name
+                )),
+                ..item(descriptor.name, Some(descriptor.value))
+            })
+            .collect();
+        Some(items)
+    })
+}
+
+/// The innermost rule whose block `offset` is in.
+fn innermost_rule<'a>(rules: &'a [Rule], text: &str, offset: usize) -> Option<&'a Rule> {
+    rules.iter().find_map(|rule| {
+        let block = rule.block()?;
+        let closed = text[..block.span.end].ends_with('}');
+        if offset <= block.span.start || offset > block.span.end || (closed && offset == block.span.end) {
+            return None;
+        }
+        let nested = block.items.iter().find_map(|item| match item {
+            Item::Rule(rule) => innermost_rule(std::slice::from_ref(rule), text, offset),
+            Item::Declaration(_) => None,
+        });
+        Some(nested.unwrap_or(rule))
+    })
+}
+
+/// Utility classes in the prelude of a framework at-rule such as `@apply`:
+/// the configured classes of the framework and those defined with at-rules
+/// such as `@utility` anywhere in the workspace.
+fn utility_classes(state: &State, doc: &Document, position: Position) -> Vec<CompletionItem> {
+    let offset = doc.line_index.offset(position);
+    let Some(name) = doc.regions.iter().find_map(|region| {
+        let offset = region.to_virtual(offset)?;
+        let sheet = &region.stylesheet;
+        let at_rule = sheet.at_rules().into_iter().find(|at_rule| {
+            let end = at_rule.prelude.end.max(at_rule.name_span.end);
+            at_rule.name_span.end < offset && (offset <= end || sheet.text[end..offset].trim().is_empty())
+        })?;
+        Some(at_rule.name.clone())
+    }) else {
+        return vec![];
+    };
+
+    let mut classes: Vec<(String, &str)> = vec![];
+    for config in &state.folder(&doc.uri).config.frameworks {
+        if config
+            .framework
+            .at_rule(&name)
+            .is_none_or(|at_rule| at_rule.kind != AtRuleKind::Apply)
+        {
+            continue;
+        }
+        for class in &config.classes {
+            classes.push((class.clone(), config.framework.name));
+        }
+        for other in state.folder_documents(&doc.uri) {
+            for region in &other.regions {
+                let sheet = &region.stylesheet;
+                for at_rule in sheet.at_rules() {
+                    let defines = config
+                        .framework
+                        .at_rule(&at_rule.name)
+                        .is_some_and(|at_rule| at_rule.kind == AtRuleKind::Utility);
+                    let class = at_rule.prelude.text(&sheet.text);
+                    if defines && !class.is_empty() && !class.contains('*') {
+                        classes.push((class.to_string(), "@utility"));
+                    }
+                }
+            }
+        }
+    }
+
+    // Replace the whole class being typed, which may contain `:` or `/`.
+    let before = &doc.text[..offset];
+    let start = before.trim_end_matches(|c: char| !c.is_whitespace() && c != ';').len();
+    let replace = Range::new(doc.line_index.position(start), position);
+    let mut items: Vec<CompletionItem> = vec![];
+    for (class, detail) in classes {
+        if items.iter().any(|item| item.label == class) {
+            continue;
+        }
+        items.push(CompletionItem {
+            kind: Some(CompletionItemKind::CLASS),
+            detail: Some(detail.to_string()),
+            text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(replace, class.clone()))),
+            label: class,
+            ..Default::default()
+        });
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line_index::Encoding;
+
+    /// The labels completed at the `|` marker, and the text the first one
+    /// replaces.
+    fn names(language_id: &str, marked: &str) -> Option<(Vec<String>, String)> {
+        let offset = marked.find('|').unwrap();
+        let text = marked.replace('|', "");
+        let doc = Document::new(
+            "file:///a".parse().unwrap(),
+            language_id.to_string(),
+            0,
+            text.clone(),
+            Encoding::default(),
+        );
+        let items = declaration_names(&doc, doc.line_index.position(offset))?;
+        let replaced = match &items.first()?.text_edit {
+            Some(CompletionTextEdit::Edit(edit)) => {
+                let start = doc.line_index.offset(edit.range.start);
+                text[start..doc.line_index.offset(edit.range.end)].to_string()
+            }
+            _ => String::new(),
+        };
+        Some((items.into_iter().map(|item| item.label).collect(), replaced))
+    }
+
+    #[test]
+    fn properties() {
+        let (labels, replaced) = names("css", "a {\n  col|\n}").unwrap();
+        assert!(labels.contains(&"color".to_string()));
+        assert_eq!(labels.len(), PROPERTIES.len());
+        assert_eq!(replaced, "col");
+        let (_, replaced) = names("css", "a { color: red; |}").unwrap();
+        assert_eq!(replaced, "");
+        // Not in values, selectors or between rules.
+        assert_eq!(names("css", "a { color: r| }"), None);
+        assert_eq!(names("css", "a| { }"), None);
+        assert_eq!(names("css", "a { } |"), None);
+        assert_eq!(names("css", "@media print { | }"), None);
+    }
+
+    #[test]
+    fn properties_in_html() {
+        let (labels, replaced) = names("html", "<style>\n  a { marg| }\n</style>").unwrap();
+        assert!(labels.contains(&"margin".to_string()));
+        assert_eq!(replaced, "marg");
+        let (labels, replaced) = names("html", "<p style=\"color: red; padd|\"></p>").unwrap();
+        assert!(labels.contains(&"padding".to_string()));
+        assert_eq!(replaced, "padd");
+        let (_, replaced) = names("html", "<p style=\"|\"></p>").unwrap();
+        assert_eq!(replaced, "");
+        assert_eq!(names("html", "<p style=\"color: |\"></p>"), None);
+        assert_eq!(names("html", "<p cla|ss=\"a\"></p>"), None);
+    }
+
+    #[test]
+    fn descriptors() {
+        let (labels, _) = names("css", "@font-face { font-family: A; | }").unwrap();
+        assert!(labels.contains(&"src".to_string()));
+        assert!(!labels.contains(&"font-family".to_string()));
+        assert!(!labels.contains(&"color".to_string()));
+    }
+}
@@ -0,0 +1,109 @@
+use std::path::Path;
+
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::{
+    browserslist,
+    css::{
+        compat::Targets,
+        frameworks::{self, FRAMEWORKS, Framework},
+    },
+    response::log_message,
+};
+
+#[derive(Debug, Default)]
+pub struct Config {
+    /// Browsers that compatibility diagnostics and vendor prefix refactorings
+    /// are computed for.
+    pub targets: Targets,
+    /// Whether saving a CSS Module writes a `.d.ts` declaring its classes.
+    pub module_declarations: bool,
+    /// Frameworks whose at-rules are recognized.
+    pub frameworks: Vec<FrameworkConfig>,
+}
+
+#[derive(Debug)]
+pub struct FrameworkConfig {
+    pub framework: &'static Framework,
+    /// Utility classes to complete in at-rules such as `@apply`.
+    pub classes: Vec<String>,
+}
+
+impl Config {
+    /// Reads the `initializationOptions` sent with `initialize`, e.g.
+    /// `{ "targets": { "safari": "14", "chrome": "100" }, "cssModules": {
+    /// "declarations": true }, "frameworks": { "tailwind": { "classes": [...] }
+    /// } }`. Without explicit targets, the browserslist configuration of the
+    /// workspace at `root` is used. Without `frameworks`, every built-in
+    /// framework is enabled.
+    pub async fn load(root: Option<&Path>, options: Option<&Value>) -> Result<Self> {
+        let (config, warnings) = Self::read(root, options).await;
+        for warning in warnings {
+            log_message(&warning).await?;
+        }
+        Ok(config)
+    }
+
+    /// Like `load`, but returns the warnings instead of logging them to the
+    /// client.
+    pub async fn read(root: Option<&Path>, options: Option<&Value>) -> (Self, Vec<String>) {
+        let mut warnings = vec![];
+        let targets = options
+            .and_then(|options| options.get("targets"))
+            .and_then(Targets::from_json);
+        let targets = match (targets, root) {
+            (Some(targets), _) => targets,
+            (None, Some(root)) => match browserslist::load(root).await {
+                Ok(targets) => targets.unwrap_or_default(),
+                Err(err) => {
+                    warnings.push(format!("ignoring browserslist configuration: {err:#}"));
+                    Targets::default()
+                }
+            },
+            (None, None) => Targets::default(),
+        };
+        let module_declarations = options
+            .and_then(|options| options.pointer("/cssModules/declarations"))
+            .and_then(Value::as_bool)
+            .unwrap_or_default();
+        let frameworks = match options.and_then(|options| options.get("frameworks")) {
+            Some(Value::Object(configs)) => {
+                let mut frameworks = vec![];
+                for (name, config) in configs {
+                    let Some(framework) = frameworks::find(name) else {
+                        warnings.push(format!("ignoring unknown framework `{name}`"));
+                        continue;
+                    };
+                    let classes = config
+                        .get("classes")
+                        .and_then(Value::as_array)
+                        .map(|classes| classes.iter().filter_map(Value::as_str).map(String::from).collect())
+                        .unwrap_or_default();
+                    frameworks.push(FrameworkConfig { framework, classes });
+                }
+                frameworks
+            }
+            _ => FRAMEWORKS
+                .iter()
+                .map(|framework| FrameworkConfig {
+                    framework,
+                    classes: vec![],
+                })
+                .collect(),
+        };
+        let config = Config {
+            targets,
+            module_declarations,
+            frameworks,
+        };
+        (config, warnings)
+    }
+
+    /// Whether an enabled framework defines the at-rule.
+    pub fn is_framework_at_rule(&self, name: &str) -> bool {
+        self.frameworks
+            .iter()
+            .any(|config| config.framework.at_rule(name).is_some())
+    }
+}
@@ -0,0 +1,44 @@
+pub mod at_rules;
+pub mod color;
+pub mod compat;
+pub mod descriptors;
+pub mod features;
+pub mod format;
+pub mod frameworks;
+pub mod functions;
+pub mod highlight;
+pub mod math;
+pub mod media;
+pub mod parser;
+pub mod prefixes;
+pub mod properties;
+pub mod registered;
+pub mod selector;
+pub mod source_map;
+pub mod symbols;
+pub mod syntax;
+pub mod token;
+
+/// Byte range into the text of a stylesheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.start..self.end]
+    }
+
+    /// Moves the span by `delta` bytes, for text inserted or removed before
+    /// it.
+    pub fn shift(&mut self, delta: isize) {
+        self.start = self.start.wrapping_add_signed(delta);
+        self.end = self.end.wrapping_add_signed(delta);
+    }
+}
@@ -0,0 +1,56 @@
+/// Whether an at-rule is defined by a CSS specification, including vendor
+/// prefixed forms and the at-rules only valid inside other at-rules.
+pub fn is_standard(name: &str) -> bool {
+    let name = name
+        .strip_prefix("-webkit-")
+        .or_else(|| name.strip_prefix("-moz-"))
+        .unwrap_or(name);
+    STANDARD.binary_search(&name).is_ok()
+}
+
+/// Sorted.
+const STANDARD: &[&str] = &[
+    "annotation",
+    "bottom-center",
+    "bottom-left",
+    "bottom-left-corner",
+    "bottom-right",
+    "bottom-right-corner",
+    "character-variant",
+    "charset",
+    "container",
+    "counter-style",
+    "custom-media",
+    "document",
+    "font-face",
+    "font-feature-values",
+    "font-palette-values",
+    "import",
+    "keyframes",
+    "layer",
+    "left-bottom",
+    "left-middle",
+    "left-top",
+    "media",
+    "namespace",
+    "ornaments",
+    "page",
+    "position-try",
+    "property",
+    "right-bottom",
+    "right-middle",
+    "right-top",
+    "scope",
+    "starting-style",
+    "styleset",
+    "stylistic",
+    "supports",
+    "swash",
+    "top-center",
+    "top-left",
+    "top-left-corner",
+    "top-right",
+    "top-right-corner",
+    "view-transition",
+    "viewport",
+];
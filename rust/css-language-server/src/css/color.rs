@@ -0,0 +1,396 @@
+use super::{
+    Span,
+    syntax::{Stylesheet, matching_close},
+    token::{Token, TokenKind},
+};
+
+/// An sRGB color with every channel in `0.0..=1.0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgba {
+    pub red: f32,
+    pub green: f32,
+    pub blue: f32,
+    pub alpha: f32,
+}
+
+impl Rgba {
+    fn from_u32(rgb: u32) -> Self {
+        Self {
+            red: ((rgb >> 16) & 0xff) as f32 / 255.0,
+            green: ((rgb >> 8) & 0xff) as f32 / 255.0,
+            blue: (rgb & 0xff) as f32 / 255.0,
+            alpha: 1.0,
+        }
+    }
+}
+
+/// Every color literal that appears in a declaration value.
+pub fn document_colors(sheet: &Stylesheet) -> Vec<(Span, Rgba)> {
+    let mut colors = vec![];
+    for decl in sheet.declarations() {
+        let tokens = sheet.tokens_in(decl.value);
+        let mut i = 0;
+        while i < tokens.len() {
+            let token = &tokens[i];
+            match token.kind {
+                TokenKind::Hash => {
+                    if let Some(color) = parse_hex(token.name(&sheet.text)) {
+                        colors.push((token.span, color));
+                    }
+                }
+                TokenKind::Ident => {
+                    if let Some(color) = named(token.text(&sheet.text)) {
+                        colors.push((token.span, color));
+                    }
+                }
+                TokenKind::Function => {
+                    let close = matching_close(tokens, i);
+                    let args = &tokens[i + 1..close.min(tokens.len())];
+                    if let Some(color) = parse_function(token.name(&sheet.text), args, &sheet.text) {
+                        let end = tokens.get(close).map_or(token.span.end, |t| t.span.end);
+                        colors.push((Span::new(token.span.start, end), color));
+                        i = close;
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+    colors
+}
+
+/// Ways to write `color`, most preferred first.
+pub fn presentations(color: Rgba) -> Vec<String> {
+    let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let (r, g, b, a) = (
+        channel(color.red),
+        channel(color.green),
+        channel(color.blue),
+        channel(color.alpha),
+    );
+    let alpha = round(color.alpha.clamp(0.0, 1.0), 2);
+    let (h, s, l) = to_hsl(color);
+
+    if a == 255 {
+        vec![
+            format!("rgb({r} {g} {b})"),
+            format!("#{r:02x}{g:02x}{b:02x}"),
+            format!("hsl({h} {s}% {l}%)"),
+        ]
+    } else {
+        vec![
+            format!("rgb({r} {g} {b} / {alpha})"),
+            format!("#{r:02x}{g:02x}{b:02x}{a:02x}"),
+            format!("hsl({h} {s}% {l}% / {alpha})"),
+        ]
+    }
+}
+
+fn round(value: f32, digits: i32) -> f32 {
+    let factor = 10f32.powi(digits);
+    (value * factor).round() / factor
+}
+
+pub fn parse_hex(hex: &str) -> Option<Rgba> {
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok().map(|d| d * 17);
+    let pair = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    let (r, g, b, a) = match hex.len() {
+        3 => (digit(0)?, digit(1)?, digit(2)?, 255),
+        4 => (digit(0)?, digit(1)?, digit(2)?, digit(3)?),
+        6 => (pair(0)?, pair(2)?, pair(4)?, 255),
+        8 => (pair(0)?, pair(2)?, pair(4)?, pair(6)?),
+        _ => return None,
+    };
+    Some(Rgba {
+        red: r as f32 / 255.0,
+        green: g as f32 / 255.0,
+        blue: b as f32 / 255.0,
+        alpha: a as f32 / 255.0,
+    })
+}
+
+/// Parses `rgb()`, `rgba()`, `hsl()` and `hsla()` in both the legacy comma
+/// separated and the modern space separated syntax. `args` are the tokens
+/// between the parentheses.
+pub fn parse_function(name: &str, args: &[Token], text: &str) -> Option<Rgba> {
+    let mut values = vec![];
+    for token in args.iter().filter(|t| !t.is_trivia()) {
+        match token.kind {
+            TokenKind::Number | TokenKind::Percentage | TokenKind::Dimension { .. } => values.push(*token),
+            TokenKind::Comma => {}
+            TokenKind::Delim if token.text(text) == "/" => {}
+            _ => return None,
+        }
+    }
+    if !(3..=4).contains(&values.len()) {
+        return None;
+    }
+
+    let number = |t: &Token| -> Option<f32> {
+        let s = t.text(text);
+        match t.kind {
+            TokenKind::Number => s.parse().ok(),
+            TokenKind::Percentage => s[..s.len() - 1].parse().ok(),
+            TokenKind::Dimension { unit } => s[..unit].parse().ok(),
+            _ => None,
+        }
+    };
+    let fraction = |t: &Token, scale: f32| -> Option<f32> {
+        let n = number(t)?;
+        let f = match t.kind {
+            TokenKind::Percentage => n / 100.0,
+            TokenKind::Number => n / scale,
+            _ => return None,
+        };
+        Some(f.clamp(0.0, 1.0))
+    };
+    let alpha = match values.get(3) {
+        Some(t) => fraction(t, 1.0)?,
+        None => 1.0,
+    };
+
+    match name.to_ascii_lowercase().as_str() {
+        "rgb" | "rgba" => Some(Rgba {
+            red: fraction(&values[0], 255.0)?,
+            green: fraction(&values[1], 255.0)?,
+            blue: fraction(&values[2], 255.0)?,
+            alpha,
+        }),
+        "hsl" | "hsla" => {
+            let hue = number(&values[0])?;
+            let degrees = match values[0].kind {
+                TokenKind::Number => hue,
+                TokenKind::Dimension { unit } => match values[0].text(text)[unit..].to_ascii_lowercase().as_str() {
+                    "deg" => hue,
+                    "rad" => hue.to_degrees(),
+                    "grad" => hue * 0.9,
+                    "turn" => hue * 360.0,
+                    _ => return None,
+                },
+                _ => return None,
+            };
+            let s = fraction(&values[1], 100.0)?;
+            let l = fraction(&values[2], 100.0)?;
+            Some(from_hsl(degrees, s, l, alpha))
+        }
+        _ => None,
+    }
+}
+
+fn from_hsl(hue: f32, s: f32, l: f32, alpha: f32) -> Rgba {
+    let hue = hue.rem_euclid(360.0) / 60.0;
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let x = c * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = l - c / 2.0;
+    Rgba {
+        red: r + m,
+        green: g + m,
+        blue: b + m,
+        alpha,
+    }
+}
+
+/// Hue in degrees, saturation and lightness in percent, all rounded.
+fn to_hsl(color: Rgba) -> (i32, i32, i32) {
+    let (r, g, b) = (color.red, color.green, color.blue);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+    let d = max - min;
+    if d == 0.0 {
+        return (0, 0, (l * 100.0).round() as i32);
+    }
+    let s = d / (1.0 - (2.0 * l - 1.0).abs());
+    let h = if max == r {
+        ((g - b) / d).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / d + 2.0
+    } else {
+        (r - g) / d + 4.0
+    };
+    (
+        (h * 60.0).round() as i32 % 360,
+        (s * 100.0).round() as i32,
+        (l * 100.0).round() as i32,
+    )
+}
+
+pub fn named(name: &str) -> Option<Rgba> {
+    let name = name.to_ascii_lowercase();
+    if name == "transparent" {
+        return Some(Rgba {
+            red: 0.0,
+            green: 0.0,
+            blue: 0.0,
+            alpha: 0.0,
+        });
+    }
+    NAMED_COLORS
+        .binary_search_by(|(n, _)| n.cmp(&name.as_str()))
+        .ok()
+        .map(|i| Rgba::from_u32(NAMED_COLORS[i].1))
+}
+
+/// CSS Color Level 4 named colors, sorted by name.
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
@@ -0,0 +1,110 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Browser {
+    Chrome,
+    Edge,
+    Firefox,
+    Safari,
+    IosSafari,
+}
+
+impl Browser {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "chrome" => Some(Browser::Chrome),
+            "edge" => Some(Browser::Edge),
+            "firefox" | "ff" => Some(Browser::Firefox),
+            "safari" => Some(Browser::Safari),
+            "ios_saf" | "ios" => Some(Browser::IosSafari),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Browser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Browser::Chrome => "Chrome",
+            Browser::Edge => "Edge",
+            Browser::Firefox => "Firefox",
+            Browser::Safari => "Safari",
+            Browser::IosSafari => "iOS Safari",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A `major.minor` browser version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version(pub u16, pub u16);
+
+impl FromStr for Version {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().split('.');
+        let major = parts.next().ok_or(())?.parse().map_err(|_| ())?;
+        let minor = match parts.next() {
+            Some(minor) => minor.parse().map_err(|_| ())?,
+            None => 0,
+        };
+        Ok(Version(major, minor))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.1 {
+            0 => write!(f, "{}", self.0),
+            minor => write!(f, "{}.{minor}", self.0),
+        }
+    }
+}
+
+/// The browsers a project supports: the oldest supported version of each.
+/// Browsers that are absent are not supported at all.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Targets(pub BTreeMap<Browser, Version>);
+
+impl Default for Targets {
+    fn default() -> Self {
+        Targets(BTreeMap::from([
+            (Browser::Chrome, Version(109, 0)),
+            (Browser::Edge, Version(109, 0)),
+            (Browser::Firefox, Version(115, 0)),
+            (Browser::Safari, Version(15, 6)),
+            (Browser::IosSafari, Version(15, 6)),
+        ]))
+    }
+}
+
+impl Targets {
+    /// Reads targets such as `{ "safari": "14", "chrome": "100" }`. Unknown
+    /// browsers and malformed versions are ignored.
+    pub fn from_json(value: &serde_json::Value) -> Option<Self> {
+        let targets = value
+            .as_object()?
+            .iter()
+            .filter_map(|(name, version)| {
+                let version = match version {
+                    serde_json::Value::String(s) => s.parse().ok()?,
+                    serde_json::Value::Number(n) => n.to_string().parse().ok()?,
+                    _ => return None,
+                };
+                Some((Browser::from_name(name)?, version))
+            })
+            .collect();
+        Some(Targets(targets))
+    }
+
+    /// Whether any supported version of a target browser is older than
+    /// `fixed_in`. `None` means no version is fixed.
+    pub fn any_older(&self, browser: Browser, fixed_in: Option<Version>) -> bool {
+        match (self.0.get(&browser), fixed_in) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(min), Some(fixed_in)) => *min < fixed_in,
+        }
+    }
+}
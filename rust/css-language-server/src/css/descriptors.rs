@@ -0,0 +1,627 @@
+use super::{
+    Span, math, properties,
+    registered::{CSS_WIDE_KEYWORDS, Syntax, string_contents},
+    syntax::{AtRule, Declaration, Item, Stylesheet},
+    token::TokenKind,
+};
+
+/// A descriptor of an at-rule, such as `src` in `@font-face`.
+#[derive(Debug, Clone, Copy)]
+pub struct Descriptor {
+    pub name: &'static str,
+    /// The grammar of its value, as shown in completions.
+    pub value: &'static str,
+    /// Whether the rule is ignored without it.
+    pub required: bool,
+}
+
+/// What the block of an at-rule that takes descriptors accepts.
+#[derive(Debug)]
+pub struct Descriptors {
+    /// Sorted by name.
+    pub list: &'static [Descriptor],
+    /// Whether properties are accepted too, as `@page` accepts those that
+    /// apply to the page box.
+    pub properties: bool,
+}
+
+impl Descriptors {
+    pub fn accepts(&self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        self.list.binary_search_by(|d| d.name.cmp(&name)).is_ok() || (self.properties && properties::is_known(&name))
+    }
+}
+
+const fn descriptor(name: &'static str, value: &'static str) -> Descriptor {
+    Descriptor {
+        name,
+        value,
+        required: false,
+    }
+}
+
+const fn required(name: &'static str, value: &'static str) -> Descriptor {
+    Descriptor {
+        name,
+        value,
+        required: true,
+    }
+}
+
+const FONT_FACE: Descriptors = Descriptors {
+    list: &[
+        descriptor("ascent-override", "normal | <percentage>"),
+        descriptor("descent-override", "normal | <percentage>"),
+        descriptor("font-display", "auto | block | swap | fallback | optional"),
+        required("font-family", "<family-name>"),
+        descriptor("font-feature-settings", "normal | <feature-tag-value>#"),
+        descriptor("font-language-override", "normal | <string>"),
+        descriptor("font-named-instance", "auto | <string>"),
+        descriptor("font-stretch", "auto | <font-stretch-absolute>{1,2}"),
+        descriptor("font-style", "auto | normal | italic | oblique [ <angle>{1,2} ]?"),
+        descriptor("font-variation-settings", "normal | [ <string> <number> ]#"),
+        descriptor("font-weight", "auto | <font-weight-absolute>{1,2}"),
+        descriptor("line-gap-override", "normal | <percentage>"),
+        descriptor("size-adjust", "<percentage>"),
+        required(
+            "src",
+            "[ <url> [ format( <font-format> ) ]? | local( <family-name> ) ]#",
+        ),
+        descriptor("unicode-range", "<unicode-range-token>#"),
+    ],
+    properties: false,
+};
+
+const PROPERTY: Descriptors = Descriptors {
+    list: &[
+        required("inherits", "true | false"),
+        descriptor("initial-value", "<declaration-value>"),
+        required("syntax", "<string>"),
+    ],
+    properties: false,
+};
+
+const COUNTER_STYLE: Descriptors = Descriptors {
+    list: &[
+        descriptor("additive-symbols", "[ <integer [0,∞]> && <symbol> ]#"),
+        descriptor("fallback", "<counter-style-name>"),
+        descriptor("negative", "<symbol> <symbol>?"),
+        descriptor("pad", "<integer [0,∞]> && <symbol>"),
+        descriptor("prefix", "<symbol>"),
+        descriptor("range", "[ [ <integer> | infinite ]{2} ]# | auto"),
+        descriptor(
+            "speak-as",
+            "auto | bullets | numbers | words | spell-out | <counter-style-name>",
+        ),
+        descriptor("suffix", "<symbol>"),
+        descriptor("symbols", "<symbol>+"),
+        descriptor(
+            "system",
+            "cyclic | numeric | alphabetic | symbolic | additive | [ fixed <integer>? ] | [ extends <counter-style-name> ]",
+        ),
+    ],
+    properties: false,
+};
+
+const PAGE: Descriptors = Descriptors {
+    list: &[
+        descriptor("bleed", "auto | <length>"),
+        descriptor("margin", "<'margin-top'>{1,4}"),
+        descriptor("margin-bottom", "<length-percentage> | auto"),
+        descriptor("margin-left", "<length-percentage> | auto"),
+        descriptor("margin-right", "<length-percentage> | auto"),
+        descriptor("margin-top", "<length-percentage> | auto"),
+        descriptor("marks", "none | [ crop || cross ]"),
+        descriptor("page-orientation", "upright | rotate-left | rotate-right"),
+        descriptor(
+            "size",
+            "<length [0,∞]>{1,2} | auto | [ <page-size> || [ portrait | landscape ] ]",
+        ),
+    ],
+    properties: true,
+};
+
+/// The descriptors of an at-rule, if it takes descriptors rather than
+/// properties.
+pub fn descriptors(at_rule: &str) -> Option<&'static Descriptors> {
+    match at_rule {
+        "font-face" => Some(&FONT_FACE),
+        "property" => Some(&PROPERTY),
+        "counter-style" => Some(&COUNTER_STYLE),
+        "page" => Some(&PAGE),
+        _ => None,
+    }
+}
+
+/// Counter styles that cannot be redefined.
+const PREDEFINED_COUNTER_STYLES: &[&str] = &[
+    "circle",
+    "decimal",
+    "disc",
+    "disclosure-closed",
+    "disclosure-open",
+    "none",
+    "square",
+];
+
+const COUNTER_SYSTEMS: &[&str] = &[
+    "additive",
+    "alphabetic",
+    "cyclic",
+    "extends",
+    "fixed",
+    "numeric",
+    "symbolic",
+];
+
+/// A problem in the block of an at-rule that takes descriptors.
+#[derive(Debug, Clone)]
+pub struct DescriptorIssue {
+    pub span: Span,
+    pub message: String,
+    pub kind: IssueKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueKind {
+    /// A descriptor the at-rule does not take, which is ignored.
+    Unknown,
+    /// A required descriptor that is missing, which makes the whole rule
+    /// ignored.
+    Missing,
+    /// A descriptor with an invalid value, or an invalid prelude.
+    Invalid,
+}
+
+/// Checks the descriptors of `@font-face`, `@property`, `@counter-style` and
+/// `@page` rules.
+pub fn check(sheet: &Stylesheet) -> Vec<DescriptorIssue> {
+    let mut issues = vec![];
+    for at_rule in sheet.at_rules() {
+        let (Some(descriptors), Some(block)) = (descriptors(&at_rule.name), &at_rule.block) else {
+            continue;
+        };
+        let declarations: Vec<&Declaration> = block
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Declaration(decl) => Some(decl),
+                Item::Rule(_) => None,
+            })
+            .collect();
+        for decl in &declarations {
+            if !descriptors.accepts(&decl.name) {
+                issues.push(DescriptorIssue {
+                    span: decl.name_span,
+                    message: format!("Unknown descriptor `{}` in `@{}`", decl.name, at_rule.name),
+                    kind: IssueKind::Unknown,
+                });
+            }
+        }
+        let context = Context {
+            sheet,
+            at_rule,
+            declarations,
+        };
+        for descriptor in descriptors.list.iter().filter(|d| d.required) {
+            if context.get(descriptor.name).is_none() {
+                context.missing(&format!("the `{}` descriptor", descriptor.name), &mut issues);
+            }
+        }
+        match at_rule.name.as_str() {
+            "property" => context.property(&mut issues),
+            "counter-style" => context.counter_style(&mut issues),
+            _ => {}
+        }
+    }
+    issues
+}
+
+/// An at-rule being checked, with the declarations of its block.
+struct Context<'a> {
+    sheet: &'a Stylesheet,
+    at_rule: &'a AtRule,
+    declarations: Vec<&'a Declaration>,
+}
+
+impl Context<'_> {
+    /// The last declaration of a descriptor, which is the one that applies.
+    fn get(&self, name: &str) -> Option<&Declaration> {
+        self.declarations
+            .iter()
+            .rev()
+            .find(|decl| decl.name.eq_ignore_ascii_case(name))
+            .copied()
+    }
+
+    fn text(&self, span: Span) -> &str {
+        span.text(&self.sheet.text)
+    }
+
+    fn missing(&self, what: &str, issues: &mut Vec<DescriptorIssue>) {
+        issues.push(DescriptorIssue {
+            span: self.at_rule.name_span,
+            message: format!("`@{}` requires {what}", self.at_rule.name),
+            kind: IssueKind::Missing,
+        });
+    }
+
+    fn invalid(&self, span: Span, message: String, issues: &mut Vec<DescriptorIssue>) {
+        issues.push(DescriptorIssue {
+            span,
+            message,
+            kind: IssueKind::Invalid,
+        });
+    }
+
+    /// The name of the rule in its prelude, or the at-keyword when it has
+    /// none.
+    fn name_span(&self) -> Span {
+        match self.at_rule.prelude.start == self.at_rule.prelude.end {
+            true => self.at_rule.name_span,
+            false => self.at_rule.prelude,
+        }
+    }
+
+    fn property(&self, issues: &mut Vec<DescriptorIssue>) {
+        let name = self.text(self.at_rule.prelude);
+        if !name.starts_with("--") || name.contains(char::is_whitespace) {
+            let message = match name {
+                "" => "Expected the name of a custom property, such as `--size`".to_string(),
+                _ => format!("`{name}` is not a custom property name, which starts with `--`"),
+            };
+            self.invalid(self.name_span(), message, issues);
+        }
+
+        let syntax = self.get("syntax").and_then(|decl| {
+            let tokens = self.sheet.tokens_in(decl.value);
+            let [string] = tokens else {
+                let message = "`syntax` must be a string, such as `\"<length>\"`".to_string();
+                self.invalid(
+                    if tokens.is_empty() { decl.name_span } else { decl.value },
+                    message,
+                    issues,
+                );
+                return None;
+            };
+            if string.kind != TokenKind::String {
+                let message = "`syntax` must be a string, such as `\"<length>\"`".to_string();
+                self.invalid(decl.value, message, issues);
+                return None;
+            }
+            match Syntax::parse(string_contents(string.text(&self.sheet.text))) {
+                Ok(syntax) => Some(syntax),
+                Err(message) => {
+                    self.invalid(decl.value, message, issues);
+                    None
+                }
+            }
+        });
+
+        if let Some(decl) = self.get("inherits") {
+            let value = self.text(decl.value);
+            if !value.eq_ignore_ascii_case("true") && !value.eq_ignore_ascii_case("false") {
+                let span = if value.is_empty() { decl.name_span } else { decl.value };
+                self.invalid(span, "`inherits` must be `true` or `false`".to_string(), issues);
+            }
+        }
+
+        let Some(syntax) = syntax else { return };
+        let Some(decl) = self.get("initial-value") else {
+            if syntax != Syntax::Universal {
+                self.missing("an `initial-value` unless its syntax is `\"*\"`", issues);
+            }
+            return;
+        };
+        if syntax == Syntax::Universal {
+            return;
+        }
+        let text = self.sheet.text.as_str();
+        let tokens = self.sheet.tokens_in(decl.value);
+        let value = self.text(decl.value);
+        if CSS_WIDE_KEYWORDS.contains(&value.to_ascii_lowercase().as_str()) {
+            let message = format!("`initial-value` cannot be the CSS-wide keyword `{value}`");
+            self.invalid(decl.value, message, issues);
+            return;
+        }
+        // The initial value must be computationally independent: the same
+        // for every element.
+        for token in tokens {
+            let dependency = match token.kind {
+                TokenKind::Function if token.name(text).eq_ignore_ascii_case("var") => Some("`var()`".to_string()),
+                TokenKind::Dimension { unit } => {
+                    let unit = &token.text(text)[unit..];
+                    math::unit(unit)
+                        .is_some_and(|(base, conversion)| base == "length" && conversion.is_none())
+                        .then(|| format!("the relative unit `{unit}`"))
+                }
+                _ => None,
+            };
+            if let Some(dependency) = dependency {
+                let message =
+                    format!("`initial-value` must be the same for every element, so it cannot use {dependency}");
+                self.invalid(token.span, message, issues);
+                return;
+            }
+        }
+        if syntax.matches(self.sheet, decl.value) == Some(false) {
+            let syntax = self
+                .get("syntax")
+                .map_or("", |decl| string_contents(self.text(decl.value)).trim());
+            let message = match value {
+                "" => format!("An empty `initial-value` does not match the syntax `{syntax}`"),
+                _ => format!("`initial-value` does not match the syntax `{syntax}`"),
+            };
+            self.invalid(
+                if value.is_empty() { decl.name_span } else { decl.value },
+                message,
+                issues,
+            );
+        }
+    }
+
+    fn counter_style(&self, issues: &mut Vec<DescriptorIssue>) {
+        let name = self.text(self.at_rule.prelude);
+        let lower = name.to_ascii_lowercase();
+        if name.is_empty() {
+            let message = "Expected the name of the counter style".to_string();
+            self.invalid(self.at_rule.name_span, message, issues);
+        } else if PREDEFINED_COUNTER_STYLES.contains(&lower.as_str()) || CSS_WIDE_KEYWORDS.contains(&lower.as_str()) {
+            let message = format!("`{name}` cannot be used as the name of a counter style");
+            self.invalid(self.at_rule.prelude, message, issues);
+        }
+
+        let system = match self.get("system") {
+            Some(decl) => {
+                let value = self.text(decl.value);
+                let system = value.split_whitespace().next().unwrap_or_default().to_ascii_lowercase();
+                if !COUNTER_SYSTEMS.contains(&system.as_str()) {
+                    let message = match value {
+                        "" => "Expected a counter system, such as `cyclic`".to_string(),
+                        _ => format!("Unknown counter system `{value}`"),
+                    };
+                    self.invalid(
+                        if value.is_empty() { decl.name_span } else { decl.value },
+                        message,
+                        issues,
+                    );
+                    return;
+                }
+                system
+            }
+            None => "symbolic".to_string(),
+        };
+        let symbols = self.get("symbols");
+        let additive_symbols = self.get("additive-symbols");
+        match system.as_str() {
+            "extends" => {
+                for decl in [symbols, additive_symbols].into_iter().flatten() {
+                    let message = format!("`{}` cannot be used with `system: extends`", decl.name);
+                    self.invalid(decl.name_span, message, issues);
+                }
+            }
+            "additive" if additive_symbols.is_none() => {
+                self.missing("the `additive-symbols` descriptor with `system: additive`", issues);
+            }
+            "additive" => {}
+            _ => match symbols {
+                None => self.missing(&format!("the `symbols` descriptor with `system: {system}`"), issues),
+                Some(decl) if system == "numeric" || system == "alphabetic" => {
+                    let count = self
+                        .sheet
+                        .tokens_in(decl.value)
+                        .iter()
+                        .filter(|t| !t.is_trivia())
+                        .count();
+                    if count < 2 {
+                        let message = format!("`system: {system}` needs at least two symbols");
+                        self.invalid(decl.name_span, message, issues);
+                    }
+                }
+                Some(_) => {}
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::parser::parse;
+
+    fn issues(text: &str) -> Vec<(String, String, IssueKind)> {
+        check(&parse(text))
+            .into_iter()
+            .map(|issue| (issue.span.text(text).to_string(), issue.message, issue.kind))
+            .collect()
+    }
+
+    fn messages(text: &str) -> Vec<String> {
+        issues(text).into_iter().map(|(_, message, _)| message).collect()
+    }
+
+    #[test]
+    fn lists_are_sorted() {
+        for descriptors in [&FONT_FACE, &PROPERTY, &COUNTER_STYLE, &PAGE] {
+            assert!(descriptors.list.windows(2).all(|w| w[0].name < w[1].name));
+        }
+    }
+
+    #[test]
+    fn accepts() {
+        let font_face = descriptors("font-face").unwrap();
+        assert!(font_face.accepts("font-family"));
+        assert!(font_face.accepts("SRC"));
+        assert!(!font_face.accepts("color"));
+        // `@page` takes the properties of the page box too.
+        let page = descriptors("page").unwrap();
+        assert!(page.accepts("size"));
+        assert!(page.accepts("margin-top"));
+        assert!(!page.accepts("colour"));
+        assert!(descriptors("media").is_none());
+    }
+
+    #[test]
+    fn unknown_and_missing() {
+        assert_eq!(
+            issues("@font-face { font-family: A; src: url(a.woff2); color: red }"),
+            vec![(
+                "color".to_string(),
+                "Unknown descriptor `color` in `@font-face`".to_string(),
+                IssueKind::Unknown
+            )]
+        );
+        assert_eq!(
+            issues("@font-face { font-display: swap }"),
+            vec![
+                (
+                    "@font-face".to_string(),
+                    "`@font-face` requires the `font-family` descriptor".to_string(),
+                    IssueKind::Missing
+                ),
+                (
+                    "@font-face".to_string(),
+                    "`@font-face` requires the `src` descriptor".to_string(),
+                    IssueKind::Missing
+                ),
+            ]
+        );
+        assert!(issues("@page :first { size: A4; margin: 1in }").is_empty());
+        // Other at-rules hold properties, which are checked elsewhere.
+        assert!(issues("@media print { colour: red }").is_empty());
+    }
+
+    #[test]
+    fn property() {
+        let valid = [
+            "@property --a { syntax: '<length>'; inherits: false; initial-value: 0px }",
+            "@property --a { syntax: '*'; inherits: true }",
+            "@property --a { syntax: '<color>+ | none'; inherits: false; initial-value: red blue }",
+            "@property --a { syntax: '<length>'; inherits: false; initial-value: calc(1px + 2px) }",
+        ];
+        for text in valid {
+            assert_eq!(messages(text), Vec::<String>::new(), "{text}");
+        }
+        assert_eq!(
+            issues("@property a { syntax: '*'; inherits: false }"),
+            vec![(
+                "a".to_string(),
+                "`a` is not a custom property name, which starts with `--`".to_string(),
+                IssueKind::Invalid
+            )]
+        );
+        assert_eq!(
+            messages("@property --a { syntax: <length>; inherits: maybe; initial-value: 0 }"),
+            vec![
+                "`syntax` must be a string, such as `\"<length>\"`",
+                "`inherits` must be `true` or `false`",
+            ]
+        );
+        assert_eq!(
+            messages("@property --a { syntax: '<size>'; inherits: false; initial-value: 0 }"),
+            vec!["Unknown data type `<size>`"]
+        );
+        assert_eq!(
+            issues("@property --a { syntax: '<length>' }"),
+            vec![
+                (
+                    "@property".to_string(),
+                    "`@property` requires the `inherits` descriptor".to_string(),
+                    IssueKind::Missing
+                ),
+                (
+                    "@property".to_string(),
+                    "`@property` requires an `initial-value` unless its syntax is `\"*\"`".to_string(),
+                    IssueKind::Missing
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn property_initial_value() {
+        let initial = |value: &str| {
+            let text = format!("@property --a {{ syntax: '<length>'; inherits: false; initial-value: {value} }}");
+            issues(&text)
+                .into_iter()
+                .map(|(span, message, _)| (span, message))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            initial("inherit"),
+            vec![(
+                "inherit".to_string(),
+                "`initial-value` cannot be the CSS-wide keyword `inherit`".to_string()
+            )]
+        );
+        assert_eq!(
+            initial("2em"),
+            vec![(
+                "2em".to_string(),
+                "`initial-value` must be the same for every element, so it cannot use the relative unit `em`"
+                    .to_string()
+            )]
+        );
+        assert_eq!(
+            initial("var(--b)"),
+            vec![(
+                "var(".to_string(),
+                "`initial-value` must be the same for every element, so it cannot use `var()`".to_string()
+            )]
+        );
+        assert_eq!(
+            initial("red"),
+            vec![(
+                "red".to_string(),
+                "`initial-value` does not match the syntax `<length>`".to_string()
+            )]
+        );
+        // Absolute units convert to `px`, so they do not depend on the element.
+        assert!(initial("1in").is_empty());
+    }
+
+    #[test]
+    fn counter_style() {
+        let valid = [
+            "@counter-style thumbs { system: cyclic; symbols: '👍'; suffix: ' ' }",
+            "@counter-style roman { system: additive; additive-symbols: 10 X, 5 V, 1 I }",
+            "@counter-style mine { system: extends decimal; suffix: ') ' }",
+            "@counter-style digits { system: numeric; symbols: '0' '1' }",
+        ];
+        for text in valid {
+            assert_eq!(messages(text), Vec::<String>::new(), "{text}");
+        }
+        assert_eq!(
+            issues("@counter-style decimal { symbols: a }"),
+            vec![(
+                "decimal".to_string(),
+                "`decimal` cannot be used as the name of a counter style".to_string(),
+                IssueKind::Invalid
+            )]
+        );
+        assert_eq!(
+            messages("@counter-style a { system: spiral }"),
+            vec!["Unknown counter system `spiral`"]
+        );
+        assert_eq!(
+            messages("@counter-style a { system: extends decimal; symbols: a }"),
+            vec!["`symbols` cannot be used with `system: extends`"]
+        );
+        assert_eq!(
+            issues("@counter-style a { system: additive }"),
+            vec![(
+                "@counter-style".to_string(),
+                "`@counter-style` requires the `additive-symbols` descriptor with `system: additive`".to_string(),
+                IssueKind::Missing
+            )]
+        );
+        assert_eq!(
+            messages("@counter-style a { suffix: '.' }"),
+            vec!["`@counter-style` requires the `symbols` descriptor with `system: symbolic`"]
+        );
+        assert_eq!(
+            messages("@counter-style a { system: numeric; symbols: '0' }"),
+            vec!["`system: numeric` needs at least two symbols"]
+        );
+    }
+}
@@ -0,0 +1,1405 @@
+use super::{
+    Span,
+    compat::{
+        Browser::{self, *},
+        Targets, Version,
+    },
+    properties,
+    syntax::{Block, Declaration, Item, Rule, Stylesheet},
+    token::TokenKind,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    AtRule,
+    Function,
+    Property,
+    PseudoClass,
+    PseudoElement,
+    Unit,
+    /// A keyword value of a property, named `property:value`.
+    Value,
+}
+
+type Support = &'static [(Browser, Option<Version>)];
+
+/// Features that are not available in every browser, with the first version of
+/// each browser that supports them. `None` means no version does. Sorted by
+/// kind, then name.
+const FEATURES: &[(Kind, &str, Support)] = &[
+    (
+        Kind::AtRule,
+        "container",
+        &[
+            (Chrome, Some(Version(105, 0))),
+            (Edge, Some(Version(105, 0))),
+            (Firefox, Some(Version(110, 0))),
+            (Safari, Some(Version(16, 0))),
+            (IosSafari, Some(Version(16, 0))),
+        ],
+    ),
+    (
+        Kind::AtRule,
+        "counter-style",
+        &[
+            (Chrome, Some(Version(91, 0))),
+            (Edge, Some(Version(91, 0))),
+            (Firefox, Some(Version(33, 0))),
+            (Safari, Some(Version(17, 0))),
+            (IosSafari, Some(Version(17, 0))),
+        ],
+    ),
+    (
+        Kind::AtRule,
+        "font-palette-values",
+        &[
+            (Chrome, Some(Version(101, 0))),
+            (Edge, Some(Version(101, 0))),
+            (Firefox, Some(Version(107, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::AtRule,
+        "layer",
+        &[
+            (Chrome, Some(Version(99, 0))),
+            (Edge, Some(Version(99, 0))),
+            (Firefox, Some(Version(97, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::AtRule,
+        "position-try",
+        &[
+            (Chrome, Some(Version(125, 0))),
+            (Edge, Some(Version(125, 0))),
+            (Firefox, None),
+            (Safari, None),
+            (IosSafari, None),
+        ],
+    ),
+    (
+        Kind::AtRule,
+        "property",
+        &[
+            (Chrome, Some(Version(85, 0))),
+            (Edge, Some(Version(85, 0))),
+            (Firefox, Some(Version(128, 0))),
+            (Safari, Some(Version(16, 4))),
+            (IosSafari, Some(Version(16, 4))),
+        ],
+    ),
+    (
+        Kind::AtRule,
+        "scope",
+        &[
+            (Chrome, Some(Version(118, 0))),
+            (Edge, Some(Version(118, 0))),
+            (Firefox, Some(Version(146, 0))),
+            (Safari, Some(Version(17, 4))),
+            (IosSafari, Some(Version(17, 4))),
+        ],
+    ),
+    (
+        Kind::AtRule,
+        "starting-style",
+        &[
+            (Chrome, Some(Version(117, 0))),
+            (Edge, Some(Version(117, 0))),
+            (Firefox, Some(Version(129, 0))),
+            (Safari, Some(Version(17, 5))),
+            (IosSafari, Some(Version(17, 5))),
+        ],
+    ),
+    (
+        Kind::AtRule,
+        "view-transition",
+        &[
+            (Chrome, Some(Version(126, 0))),
+            (Edge, Some(Version(126, 0))),
+            (Firefox, None),
+            (Safari, Some(Version(18, 2))),
+            (IosSafari, Some(Version(18, 2))),
+        ],
+    ),
+    (
+        Kind::Function,
+        "anchor",
+        &[
+            (Chrome, Some(Version(125, 0))),
+            (Edge, Some(Version(125, 0))),
+            (Firefox, None),
+            (Safari, Some(Version(26, 0))),
+            (IosSafari, Some(Version(26, 0))),
+        ],
+    ),
+    (
+        Kind::Function,
+        "anchor-size",
+        &[
+            (Chrome, Some(Version(125, 0))),
+            (Edge, Some(Version(125, 0))),
+            (Firefox, None),
+            (Safari, Some(Version(26, 0))),
+            (IosSafari, Some(Version(26, 0))),
+        ],
+    ),
+    (
+        Kind::Function,
+        "clamp",
+        &[
+            (Chrome, Some(Version(79, 0))),
+            (Edge, Some(Version(79, 0))),
+            (Firefox, Some(Version(75, 0))),
+            (Safari, Some(Version(13, 1))),
+            (IosSafari, Some(Version(13, 4))),
+        ],
+    ),
+    (
+        Kind::Function,
+        "color-mix",
+        &[
+            (Chrome, Some(Version(111, 0))),
+            (Edge, Some(Version(111, 0))),
+            (Firefox, Some(Version(113, 0))),
+            (Safari, Some(Version(16, 2))),
+            (IosSafari, Some(Version(16, 2))),
+        ],
+    ),
+    (
+        Kind::Function,
+        "cos",
+        &[
+            (Chrome, Some(Version(111, 0))),
+            (Edge, Some(Version(111, 0))),
+            (Firefox, Some(Version(108, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::Function,
+        "env",
+        &[
+            (Chrome, Some(Version(69, 0))),
+            (Edge, Some(Version(79, 0))),
+            (Firefox, Some(Version(65, 0))),
+            (Safari, Some(Version(11, 1))),
+            (IosSafari, Some(Version(11, 2))),
+        ],
+    ),
+    (
+        Kind::Function,
+        "lab",
+        &[
+            (Chrome, Some(Version(111, 0))),
+            (Edge, Some(Version(111, 0))),
+            (Firefox, Some(Version(113, 0))),
+            (Safari, Some(Version(15, 0))),
+            (IosSafari, Some(Version(15, 0))),
+        ],
+    ),
+    (
+        Kind::Function,
+        "lch",
+        &[
+            (Chrome, Some(Version(111, 0))),
+            (Edge, Some(Version(111, 0))),
+            (Firefox, Some(Version(113, 0))),
+            (Safari, Some(Version(15, 0))),
+            (IosSafari, Some(Version(15, 0))),
+        ],
+    ),
+    (
+        Kind::Function,
+        "light-dark",
+        &[
+            (Chrome, Some(Version(123, 0))),
+            (Edge, Some(Version(123, 0))),
+            (Firefox, Some(Version(120, 0))),
+            (Safari, Some(Version(17, 5))),
+            (IosSafari, Some(Version(17, 5))),
+        ],
+    ),
+    (
+        Kind::Function,
+        "max",
+        &[
+            (Chrome, Some(Version(79, 0))),
+            (Edge, Some(Version(79, 0))),
+            (Firefox, Some(Version(75, 0))),
+            (Safari, Some(Version(11, 1))),
+            (IosSafari, Some(Version(11, 3))),
+        ],
+    ),
+    (
+        Kind::Function,
+        "min",
+        &[
+            (Chrome, Some(Version(79, 0))),
+            (Edge, Some(Version(79, 0))),
+            (Firefox, Some(Version(75, 0))),
+            (Safari, Some(Version(11, 1))),
+            (IosSafari, Some(Version(11, 3))),
+        ],
+    ),
+    (
+        Kind::Function,
+        "mod",
+        &[
+            (Chrome, Some(Version(125, 0))),
+            (Edge, Some(Version(125, 0))),
+            (Firefox, Some(Version(118, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::Function,
+        "oklab",
+        &[
+            (Chrome, Some(Version(111, 0))),
+            (Edge, Some(Version(111, 0))),
+            (Firefox, Some(Version(113, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::Function,
+        "oklch",
+        &[
+            (Chrome, Some(Version(111, 0))),
+            (Edge, Some(Version(111, 0))),
+            (Firefox, Some(Version(113, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::Function,
+        "rem",
+        &[
+            (Chrome, Some(Version(125, 0))),
+            (Edge, Some(Version(125, 0))),
+            (Firefox, Some(Version(118, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::Function,
+        "round",
+        &[
+            (Chrome, Some(Version(125, 0))),
+            (Edge, Some(Version(125, 0))),
+            (Firefox, Some(Version(118, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::Function,
+        "sin",
+        &[
+            (Chrome, Some(Version(111, 0))),
+            (Edge, Some(Version(111, 0))),
+            (Firefox, Some(Version(108, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::Function,
+        "tan",
+        &[
+            (Chrome, Some(Version(111, 0))),
+            (Edge, Some(Version(111, 0))),
+            (Firefox, Some(Version(108, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "accent-color",
+        &[
+            (Chrome, Some(Version(93, 0))),
+            (Edge, Some(Version(93, 0))),
+            (Firefox, Some(Version(92, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "anchor-name",
+        &[
+            (Chrome, Some(Version(125, 0))),
+            (Edge, Some(Version(125, 0))),
+            (Firefox, None),
+            (Safari, Some(Version(26, 0))),
+            (IosSafari, Some(Version(26, 0))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "animation-range",
+        &[
+            (Chrome, Some(Version(115, 0))),
+            (Edge, Some(Version(115, 0))),
+            (Firefox, None),
+            (Safari, Some(Version(26, 0))),
+            (IosSafari, Some(Version(26, 0))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "animation-timeline",
+        &[
+            (Chrome, Some(Version(115, 0))),
+            (Edge, Some(Version(115, 0))),
+            (Firefox, None),
+            (Safari, Some(Version(26, 0))),
+            (IosSafari, Some(Version(26, 0))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "aspect-ratio",
+        &[
+            (Chrome, Some(Version(88, 0))),
+            (Edge, Some(Version(88, 0))),
+            (Firefox, Some(Version(89, 0))),
+            (Safari, Some(Version(15, 0))),
+            (IosSafari, Some(Version(15, 0))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "color-scheme",
+        &[
+            (Chrome, Some(Version(81, 0))),
+            (Edge, Some(Version(81, 0))),
+            (Firefox, Some(Version(96, 0))),
+            (Safari, Some(Version(13, 0))),
+            (IosSafari, Some(Version(13, 0))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "contain",
+        &[
+            (Chrome, Some(Version(52, 0))),
+            (Edge, Some(Version(79, 0))),
+            (Firefox, Some(Version(69, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "contain-intrinsic-size",
+        &[
+            (Chrome, Some(Version(83, 0))),
+            (Edge, Some(Version(83, 0))),
+            (Firefox, Some(Version(107, 0))),
+            (Safari, Some(Version(17, 0))),
+            (IosSafari, Some(Version(17, 0))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "container",
+        &[
+            (Chrome, Some(Version(105, 0))),
+            (Edge, Some(Version(105, 0))),
+            (Firefox, Some(Version(110, 0))),
+            (Safari, Some(Version(16, 0))),
+            (IosSafari, Some(Version(16, 0))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "container-name",
+        &[
+            (Chrome, Some(Version(105, 0))),
+            (Edge, Some(Version(105, 0))),
+            (Firefox, Some(Version(110, 0))),
+            (Safari, Some(Version(16, 0))),
+            (IosSafari, Some(Version(16, 0))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "container-type",
+        &[
+            (Chrome, Some(Version(105, 0))),
+            (Edge, Some(Version(105, 0))),
+            (Firefox, Some(Version(110, 0))),
+            (Safari, Some(Version(16, 0))),
+            (IosSafari, Some(Version(16, 0))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "content-visibility",
+        &[
+            (Chrome, Some(Version(85, 0))),
+            (Edge, Some(Version(85, 0))),
+            (Firefox, Some(Version(125, 0))),
+            (Safari, Some(Version(18, 0))),
+            (IosSafari, Some(Version(18, 0))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "field-sizing",
+        &[
+            (Chrome, Some(Version(123, 0))),
+            (Edge, Some(Version(123, 0))),
+            (Firefox, None),
+            (Safari, None),
+            (IosSafari, None),
+        ],
+    ),
+    (
+        Kind::Property,
+        "font-palette",
+        &[
+            (Chrome, Some(Version(101, 0))),
+            (Edge, Some(Version(101, 0))),
+            (Firefox, Some(Version(107, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "hyphenate-character",
+        &[
+            (Chrome, Some(Version(106, 0))),
+            (Edge, Some(Version(106, 0))),
+            (Firefox, Some(Version(98, 0))),
+            (Safari, Some(Version(17, 0))),
+            (IosSafari, Some(Version(17, 0))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "inset",
+        &[
+            (Chrome, Some(Version(87, 0))),
+            (Edge, Some(Version(87, 0))),
+            (Firefox, Some(Version(66, 0))),
+            (Safari, Some(Version(14, 1))),
+            (IosSafari, Some(Version(14, 5))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "inset-block",
+        &[
+            (Chrome, Some(Version(87, 0))),
+            (Edge, Some(Version(87, 0))),
+            (Firefox, Some(Version(63, 0))),
+            (Safari, Some(Version(14, 1))),
+            (IosSafari, Some(Version(14, 5))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "inset-inline",
+        &[
+            (Chrome, Some(Version(87, 0))),
+            (Edge, Some(Version(87, 0))),
+            (Firefox, Some(Version(63, 0))),
+            (Safari, Some(Version(14, 1))),
+            (IosSafari, Some(Version(14, 5))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "interpolate-size",
+        &[
+            (Chrome, Some(Version(129, 0))),
+            (Edge, Some(Version(129, 0))),
+            (Firefox, None),
+            (Safari, None),
+            (IosSafari, None),
+        ],
+    ),
+    (
+        Kind::Property,
+        "margin-block",
+        &[
+            (Chrome, Some(Version(87, 0))),
+            (Edge, Some(Version(87, 0))),
+            (Firefox, Some(Version(66, 0))),
+            (Safari, Some(Version(14, 1))),
+            (IosSafari, Some(Version(14, 5))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "margin-inline",
+        &[
+            (Chrome, Some(Version(87, 0))),
+            (Edge, Some(Version(87, 0))),
+            (Firefox, Some(Version(66, 0))),
+            (Safari, Some(Version(14, 1))),
+            (IosSafari, Some(Version(14, 5))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "math-depth",
+        &[
+            (Chrome, Some(Version(109, 0))),
+            (Edge, Some(Version(109, 0))),
+            (Firefox, Some(Version(117, 0))),
+            (Safari, None),
+            (IosSafari, None),
+        ],
+    ),
+    (
+        Kind::Property,
+        "overflow-clip-margin",
+        &[
+            (Chrome, Some(Version(90, 0))),
+            (Edge, Some(Version(90, 0))),
+            (Firefox, Some(Version(102, 0))),
+            (Safari, None),
+            (IosSafari, None),
+        ],
+    ),
+    (
+        Kind::Property,
+        "overscroll-behavior",
+        &[
+            (Chrome, Some(Version(63, 0))),
+            (Edge, Some(Version(79, 0))),
+            (Firefox, Some(Version(59, 0))),
+            (Safari, Some(Version(16, 0))),
+            (IosSafari, Some(Version(16, 0))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "padding-block",
+        &[
+            (Chrome, Some(Version(87, 0))),
+            (Edge, Some(Version(87, 0))),
+            (Firefox, Some(Version(66, 0))),
+            (Safari, Some(Version(14, 1))),
+            (IosSafari, Some(Version(14, 5))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "padding-inline",
+        &[
+            (Chrome, Some(Version(87, 0))),
+            (Edge, Some(Version(87, 0))),
+            (Firefox, Some(Version(66, 0))),
+            (Safari, Some(Version(14, 1))),
+            (IosSafari, Some(Version(14, 5))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "position-anchor",
+        &[
+            (Chrome, Some(Version(125, 0))),
+            (Edge, Some(Version(125, 0))),
+            (Firefox, None),
+            (Safari, Some(Version(26, 0))),
+            (IosSafari, Some(Version(26, 0))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "position-area",
+        &[
+            (Chrome, Some(Version(129, 0))),
+            (Edge, Some(Version(129, 0))),
+            (Firefox, None),
+            (Safari, Some(Version(26, 0))),
+            (IosSafari, Some(Version(26, 0))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "position-try-fallbacks",
+        &[
+            (Chrome, Some(Version(128, 0))),
+            (Edge, Some(Version(128, 0))),
+            (Firefox, None),
+            (Safari, Some(Version(26, 0))),
+            (IosSafari, Some(Version(26, 0))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "rotate",
+        &[
+            (Chrome, Some(Version(104, 0))),
+            (Edge, Some(Version(104, 0))),
+            (Firefox, Some(Version(72, 0))),
+            (Safari, Some(Version(14, 1))),
+            (IosSafari, Some(Version(14, 5))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "scale",
+        &[
+            (Chrome, Some(Version(104, 0))),
+            (Edge, Some(Version(104, 0))),
+            (Firefox, Some(Version(72, 0))),
+            (Safari, Some(Version(14, 1))),
+            (IosSafari, Some(Version(14, 5))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "scroll-timeline",
+        &[
+            (Chrome, Some(Version(115, 0))),
+            (Edge, Some(Version(115, 0))),
+            (Firefox, None),
+            (Safari, Some(Version(26, 0))),
+            (IosSafari, Some(Version(26, 0))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "scrollbar-color",
+        &[
+            (Chrome, Some(Version(121, 0))),
+            (Edge, Some(Version(121, 0))),
+            (Firefox, Some(Version(64, 0))),
+            (Safari, None),
+            (IosSafari, None),
+        ],
+    ),
+    (
+        Kind::Property,
+        "scrollbar-gutter",
+        &[
+            (Chrome, Some(Version(94, 0))),
+            (Edge, Some(Version(94, 0))),
+            (Firefox, Some(Version(97, 0))),
+            (Safari, Some(Version(18, 2))),
+            (IosSafari, Some(Version(18, 2))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "scrollbar-width",
+        &[
+            (Chrome, Some(Version(121, 0))),
+            (Edge, Some(Version(121, 0))),
+            (Firefox, Some(Version(64, 0))),
+            (Safari, None),
+            (IosSafari, None),
+        ],
+    ),
+    (
+        Kind::Property,
+        "text-box",
+        &[
+            (Chrome, Some(Version(133, 0))),
+            (Edge, Some(Version(133, 0))),
+            (Firefox, None),
+            (Safari, Some(Version(18, 2))),
+            (IosSafari, Some(Version(18, 2))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "text-box-trim",
+        &[
+            (Chrome, Some(Version(133, 0))),
+            (Edge, Some(Version(133, 0))),
+            (Firefox, None),
+            (Safari, Some(Version(18, 2))),
+            (IosSafari, Some(Version(18, 2))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "text-decoration-thickness",
+        &[
+            (Chrome, Some(Version(89, 0))),
+            (Edge, Some(Version(89, 0))),
+            (Firefox, Some(Version(70, 0))),
+            (Safari, Some(Version(12, 1))),
+            (IosSafari, Some(Version(12, 2))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "text-underline-offset",
+        &[
+            (Chrome, Some(Version(87, 0))),
+            (Edge, Some(Version(87, 0))),
+            (Firefox, Some(Version(70, 0))),
+            (Safari, Some(Version(12, 1))),
+            (IosSafari, Some(Version(12, 2))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "text-wrap",
+        &[
+            (Chrome, Some(Version(114, 0))),
+            (Edge, Some(Version(114, 0))),
+            (Firefox, Some(Version(121, 0))),
+            (Safari, Some(Version(17, 4))),
+            (IosSafari, Some(Version(17, 4))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "text-wrap-mode",
+        &[
+            (Chrome, Some(Version(130, 0))),
+            (Edge, Some(Version(130, 0))),
+            (Firefox, Some(Version(124, 0))),
+            (Safari, Some(Version(17, 4))),
+            (IosSafari, Some(Version(17, 4))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "text-wrap-style",
+        &[
+            (Chrome, Some(Version(130, 0))),
+            (Edge, Some(Version(130, 0))),
+            (Firefox, Some(Version(124, 0))),
+            (Safari, Some(Version(17, 5))),
+            (IosSafari, Some(Version(17, 5))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "transition-behavior",
+        &[
+            (Chrome, Some(Version(117, 0))),
+            (Edge, Some(Version(117, 0))),
+            (Firefox, Some(Version(129, 0))),
+            (Safari, Some(Version(17, 4))),
+            (IosSafari, Some(Version(17, 4))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "translate",
+        &[
+            (Chrome, Some(Version(104, 0))),
+            (Edge, Some(Version(104, 0))),
+            (Firefox, Some(Version(72, 0))),
+            (Safari, Some(Version(14, 1))),
+            (IosSafari, Some(Version(14, 5))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "view-timeline",
+        &[
+            (Chrome, Some(Version(115, 0))),
+            (Edge, Some(Version(115, 0))),
+            (Firefox, None),
+            (Safari, Some(Version(26, 0))),
+            (IosSafari, Some(Version(26, 0))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "view-transition-name",
+        &[
+            (Chrome, Some(Version(111, 0))),
+            (Edge, Some(Version(111, 0))),
+            (Firefox, Some(Version(144, 0))),
+            (Safari, Some(Version(18, 0))),
+            (IosSafari, Some(Version(18, 0))),
+        ],
+    ),
+    (
+        Kind::Property,
+        "zoom",
+        &[
+            (Chrome, Some(Version(1, 0))),
+            (Edge, Some(Version(12, 0))),
+            (Firefox, Some(Version(126, 0))),
+            (Safari, Some(Version(3, 1))),
+            (IosSafari, Some(Version(3, 2))),
+        ],
+    ),
+    (
+        Kind::PseudoClass,
+        "dir",
+        &[
+            (Chrome, Some(Version(120, 0))),
+            (Edge, Some(Version(120, 0))),
+            (Firefox, Some(Version(49, 0))),
+            (Safari, Some(Version(16, 4))),
+            (IosSafari, Some(Version(16, 4))),
+        ],
+    ),
+    (
+        Kind::PseudoClass,
+        "focus-visible",
+        &[
+            (Chrome, Some(Version(86, 0))),
+            (Edge, Some(Version(86, 0))),
+            (Firefox, Some(Version(85, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::PseudoClass,
+        "focus-within",
+        &[
+            (Chrome, Some(Version(60, 0))),
+            (Edge, Some(Version(79, 0))),
+            (Firefox, Some(Version(52, 0))),
+            (Safari, Some(Version(10, 1))),
+            (IosSafari, Some(Version(10, 3))),
+        ],
+    ),
+    (
+        Kind::PseudoClass,
+        "has",
+        &[
+            (Chrome, Some(Version(105, 0))),
+            (Edge, Some(Version(105, 0))),
+            (Firefox, Some(Version(121, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::PseudoClass,
+        "is",
+        &[
+            (Chrome, Some(Version(88, 0))),
+            (Edge, Some(Version(88, 0))),
+            (Firefox, Some(Version(78, 0))),
+            (Safari, Some(Version(14, 0))),
+            (IosSafari, Some(Version(14, 0))),
+        ],
+    ),
+    (
+        Kind::PseudoClass,
+        "modal",
+        &[
+            (Chrome, Some(Version(105, 0))),
+            (Edge, Some(Version(105, 0))),
+            (Firefox, Some(Version(103, 0))),
+            (Safari, Some(Version(15, 6))),
+            (IosSafari, Some(Version(15, 6))),
+        ],
+    ),
+    (
+        Kind::PseudoClass,
+        "popover-open",
+        &[
+            (Chrome, Some(Version(114, 0))),
+            (Edge, Some(Version(114, 0))),
+            (Firefox, Some(Version(125, 0))),
+            (Safari, Some(Version(17, 0))),
+            (IosSafari, Some(Version(17, 0))),
+        ],
+    ),
+    (
+        Kind::PseudoClass,
+        "state",
+        &[
+            (Chrome, Some(Version(125, 0))),
+            (Edge, Some(Version(125, 0))),
+            (Firefox, Some(Version(126, 0))),
+            (Safari, Some(Version(17, 4))),
+            (IosSafari, Some(Version(17, 4))),
+        ],
+    ),
+    (
+        Kind::PseudoClass,
+        "user-invalid",
+        &[
+            (Chrome, Some(Version(119, 0))),
+            (Edge, Some(Version(119, 0))),
+            (Firefox, Some(Version(88, 0))),
+            (Safari, Some(Version(16, 5))),
+            (IosSafari, Some(Version(16, 5))),
+        ],
+    ),
+    (
+        Kind::PseudoClass,
+        "user-valid",
+        &[
+            (Chrome, Some(Version(119, 0))),
+            (Edge, Some(Version(119, 0))),
+            (Firefox, Some(Version(88, 0))),
+            (Safari, Some(Version(16, 5))),
+            (IosSafari, Some(Version(16, 5))),
+        ],
+    ),
+    (
+        Kind::PseudoClass,
+        "where",
+        &[
+            (Chrome, Some(Version(88, 0))),
+            (Edge, Some(Version(88, 0))),
+            (Firefox, Some(Version(78, 0))),
+            (Safari, Some(Version(14, 0))),
+            (IosSafari, Some(Version(14, 0))),
+        ],
+    ),
+    (
+        Kind::PseudoElement,
+        "marker",
+        &[
+            (Chrome, Some(Version(86, 0))),
+            (Edge, Some(Version(86, 0))),
+            (Firefox, Some(Version(68, 0))),
+            (Safari, Some(Version(11, 1))),
+            (IosSafari, Some(Version(11, 3))),
+        ],
+    ),
+    (
+        Kind::PseudoElement,
+        "target-text",
+        &[
+            (Chrome, Some(Version(89, 0))),
+            (Edge, Some(Version(89, 0))),
+            (Firefox, Some(Version(131, 0))),
+            (Safari, Some(Version(18, 2))),
+            (IosSafari, Some(Version(18, 2))),
+        ],
+    ),
+    (
+        Kind::PseudoElement,
+        "view-transition",
+        &[
+            (Chrome, Some(Version(111, 0))),
+            (Edge, Some(Version(111, 0))),
+            (Firefox, Some(Version(144, 0))),
+            (Safari, Some(Version(18, 0))),
+            (IosSafari, Some(Version(18, 0))),
+        ],
+    ),
+    (
+        Kind::Unit,
+        "cap",
+        &[
+            (Chrome, Some(Version(118, 0))),
+            (Edge, Some(Version(118, 0))),
+            (Firefox, Some(Version(97, 0))),
+            (Safari, Some(Version(17, 0))),
+            (IosSafari, Some(Version(17, 0))),
+        ],
+    ),
+    (
+        Kind::Unit,
+        "cqb",
+        &[
+            (Chrome, Some(Version(105, 0))),
+            (Edge, Some(Version(105, 0))),
+            (Firefox, Some(Version(110, 0))),
+            (Safari, Some(Version(16, 0))),
+            (IosSafari, Some(Version(16, 0))),
+        ],
+    ),
+    (
+        Kind::Unit,
+        "cqh",
+        &[
+            (Chrome, Some(Version(105, 0))),
+            (Edge, Some(Version(105, 0))),
+            (Firefox, Some(Version(110, 0))),
+            (Safari, Some(Version(16, 0))),
+            (IosSafari, Some(Version(16, 0))),
+        ],
+    ),
+    (
+        Kind::Unit,
+        "cqi",
+        &[
+            (Chrome, Some(Version(105, 0))),
+            (Edge, Some(Version(105, 0))),
+            (Firefox, Some(Version(110, 0))),
+            (Safari, Some(Version(16, 0))),
+            (IosSafari, Some(Version(16, 0))),
+        ],
+    ),
+    (
+        Kind::Unit,
+        "cqmax",
+        &[
+            (Chrome, Some(Version(105, 0))),
+            (Edge, Some(Version(105, 0))),
+            (Firefox, Some(Version(110, 0))),
+            (Safari, Some(Version(16, 0))),
+            (IosSafari, Some(Version(16, 0))),
+        ],
+    ),
+    (
+        Kind::Unit,
+        "cqmin",
+        &[
+            (Chrome, Some(Version(105, 0))),
+            (Edge, Some(Version(105, 0))),
+            (Firefox, Some(Version(110, 0))),
+            (Safari, Some(Version(16, 0))),
+            (IosSafari, Some(Version(16, 0))),
+        ],
+    ),
+    (
+        Kind::Unit,
+        "cqw",
+        &[
+            (Chrome, Some(Version(105, 0))),
+            (Edge, Some(Version(105, 0))),
+            (Firefox, Some(Version(110, 0))),
+            (Safari, Some(Version(16, 0))),
+            (IosSafari, Some(Version(16, 0))),
+        ],
+    ),
+    (
+        Kind::Unit,
+        "dvb",
+        &[
+            (Chrome, Some(Version(108, 0))),
+            (Edge, Some(Version(108, 0))),
+            (Firefox, Some(Version(101, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::Unit,
+        "dvh",
+        &[
+            (Chrome, Some(Version(108, 0))),
+            (Edge, Some(Version(108, 0))),
+            (Firefox, Some(Version(101, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::Unit,
+        "dvi",
+        &[
+            (Chrome, Some(Version(108, 0))),
+            (Edge, Some(Version(108, 0))),
+            (Firefox, Some(Version(101, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::Unit,
+        "dvw",
+        &[
+            (Chrome, Some(Version(108, 0))),
+            (Edge, Some(Version(108, 0))),
+            (Firefox, Some(Version(101, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::Unit,
+        "ic",
+        &[
+            (Chrome, Some(Version(106, 0))),
+            (Edge, Some(Version(106, 0))),
+            (Firefox, Some(Version(97, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::Unit,
+        "lh",
+        &[
+            (Chrome, Some(Version(109, 0))),
+            (Edge, Some(Version(109, 0))),
+            (Firefox, Some(Version(120, 0))),
+            (Safari, Some(Version(16, 4))),
+            (IosSafari, Some(Version(16, 4))),
+        ],
+    ),
+    (
+        Kind::Unit,
+        "lvh",
+        &[
+            (Chrome, Some(Version(108, 0))),
+            (Edge, Some(Version(108, 0))),
+            (Firefox, Some(Version(101, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::Unit,
+        "lvw",
+        &[
+            (Chrome, Some(Version(108, 0))),
+            (Edge, Some(Version(108, 0))),
+            (Firefox, Some(Version(101, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::Unit,
+        "rlh",
+        &[
+            (Chrome, Some(Version(111, 0))),
+            (Edge, Some(Version(111, 0))),
+            (Firefox, Some(Version(120, 0))),
+            (Safari, Some(Version(16, 4))),
+            (IosSafari, Some(Version(16, 4))),
+        ],
+    ),
+    (
+        Kind::Unit,
+        "svh",
+        &[
+            (Chrome, Some(Version(108, 0))),
+            (Edge, Some(Version(108, 0))),
+            (Firefox, Some(Version(101, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::Unit,
+        "svw",
+        &[
+            (Chrome, Some(Version(108, 0))),
+            (Edge, Some(Version(108, 0))),
+            (Firefox, Some(Version(101, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        Kind::Value,
+        "display:contents",
+        &[
+            (Chrome, Some(Version(65, 0))),
+            (Edge, Some(Version(79, 0))),
+            (Firefox, Some(Version(37, 0))),
+            (Safari, Some(Version(11, 1))),
+            (IosSafari, Some(Version(11, 3))),
+        ],
+    ),
+    (
+        Kind::Value,
+        "overflow:clip",
+        &[
+            (Chrome, Some(Version(90, 0))),
+            (Edge, Some(Version(90, 0))),
+            (Firefox, Some(Version(81, 0))),
+            (Safari, Some(Version(16, 0))),
+            (IosSafari, Some(Version(16, 0))),
+        ],
+    ),
+    (
+        Kind::Value,
+        "position:sticky",
+        &[
+            (Chrome, Some(Version(56, 0))),
+            (Edge, Some(Version(79, 0))),
+            (Firefox, Some(Version(59, 0))),
+            (Safari, Some(Version(13, 0))),
+            (IosSafari, Some(Version(13, 0))),
+        ],
+    ),
+    (
+        Kind::Value,
+        "text-wrap:balance",
+        &[
+            (Chrome, Some(Version(114, 0))),
+            (Edge, Some(Version(114, 0))),
+            (Firefox, Some(Version(121, 0))),
+            (Safari, Some(Version(17, 5))),
+            (IosSafari, Some(Version(17, 5))),
+        ],
+    ),
+    (
+        Kind::Value,
+        "text-wrap:pretty",
+        &[
+            (Chrome, Some(Version(117, 0))),
+            (Edge, Some(Version(117, 0))),
+            (Firefox, None),
+            (Safari, Some(Version(26, 0))),
+            (IosSafari, Some(Version(26, 0))),
+        ],
+    ),
+];
+
+/// A use of a feature that some target browsers lack.
+#[derive(Debug)]
+pub struct Unsupported {
+    pub span: Span,
+    /// The feature as it would be written, e.g. `:has()` or `@container`.
+    pub feature: String,
+    /// The target browsers lacking the feature and the version that added it.
+    pub browsers: Vec<(Browser, Option<Version>)>,
+}
+
+/// Finds features used by the stylesheet that are unsupported by some of
+/// `targets`. Rules inside `@supports` are skipped since they are already
+/// guarded, as are properties that come with a vendor prefixed fallback.
+pub fn unsupported(sheet: &Stylesheet, targets: &Targets) -> Vec<Unsupported> {
+    let mut checker = Checker {
+        sheet,
+        targets,
+        out: vec![],
+    };
+    for rule in &sheet.rules {
+        checker.rule(rule);
+    }
+    checker.out
+}
+
+struct Checker<'a> {
+    sheet: &'a Stylesheet,
+    targets: &'a Targets,
+    out: Vec<Unsupported>,
+}
+
+impl Checker<'_> {
+    fn rule(&mut self, rule: &Rule) {
+        match rule {
+            Rule::Style(rule) => self.selector(rule.selector),
+            Rule::At(rule) => {
+                if rule.name == "supports" {
+                    return;
+                }
+                self.check(Kind::AtRule, &rule.name, rule.name_span, || format!("@{}", rule.name));
+                self.values(rule.prelude);
+            }
+        }
+        if let Some(block) = rule.block() {
+            self.block(block);
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        for item in &block.items {
+            match item {
+                Item::Declaration(decl) => self.declaration(block, decl),
+                Item::Rule(rule) => self.rule(rule),
+            }
+        }
+    }
+
+    fn declaration(&mut self, block: &Block, decl: &Declaration) {
+        let name = decl.name.to_ascii_lowercase();
+        if name.starts_with("--") {
+            return;
+        }
+        let has_fallback = block.items.iter().any(|item| match item {
+            Item::Declaration(other) => {
+                let other = other.name.to_ascii_lowercase();
+                other != name && properties::strip_vendor_prefix(&other) == name
+            }
+            Item::Rule(_) => false,
+        });
+        if !has_fallback {
+            self.check(Kind::Property, &name, decl.name_span, || name.clone());
+        }
+
+        let text = self.sheet.text.as_str();
+        let value = decl.value.text(text).trim().to_ascii_lowercase();
+        let key = format!("{name}:{value}");
+        self.check(Kind::Value, &key, decl.value, || format!("{name}: {value}"));
+        self.values(decl.value);
+    }
+
+    fn selector(&mut self, span: Span) {
+        let sheet = self.sheet;
+        let tokens = sheet.tokens_in(span);
+        let mut i = 0;
+        while i < tokens.len() {
+            if tokens[i].kind != TokenKind::Colon {
+                i += 1;
+                continue;
+            }
+            let start = tokens[i].span.start;
+            let element = tokens.get(i + 1).is_some_and(|t| t.kind == TokenKind::Colon);
+            if element {
+                i += 1;
+            }
+            let Some(name) = tokens.get(i + 1) else { break };
+            let (kind, colons) = match element {
+                true => (Kind::PseudoElement, "::"),
+                false => (Kind::PseudoClass, ":"),
+            };
+            let feature = name.name(&sheet.text).to_ascii_lowercase();
+            match name.kind {
+                TokenKind::Ident => {
+                    let span = Span::new(start, name.span.end);
+                    self.check(kind, &feature, span, || format!("{colons}{feature}"));
+                }
+                TokenKind::Function => {
+                    let span = Span::new(start, name.span.end - 1);
+                    self.check(kind, &feature, span, || format!("{colons}{feature}()"));
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+
+    fn values(&mut self, span: Span) {
+        let sheet = self.sheet;
+        let text = sheet.text.as_str();
+        for token in sheet.tokens_in(span) {
+            match token.kind {
+                TokenKind::Function => {
+                    let name = token.name(text).to_ascii_lowercase();
+                    let span = Span::new(token.span.start, token.span.end - 1);
+                    self.check(Kind::Function, &name, span, || format!("{name}()"));
+                }
+                TokenKind::Dimension { unit } => {
+                    let name = text[token.span.start + unit..token.span.end].to_ascii_lowercase();
+                    let span = Span::new(token.span.start + unit, token.span.end);
+                    self.check(Kind::Unit, &name, span, || format!("{name} unit"));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn check(&mut self, kind: Kind, name: &str, span: Span, feature: impl FnOnce() -> String) {
+        let Some((_, _, support)) = FEATURES.iter().find(|(k, n, _)| *k == kind && *n == name) else {
+            return;
+        };
+        let browsers: Vec<_> = support
+            .iter()
+            .filter(|(browser, added)| self.targets.any_older(*browser, *added))
+            .copied()
+            .collect();
+        if !browsers.is_empty() {
+            self.out.push(Unsupported {
+                span,
+                feature: feature(),
+                browsers,
+            });
+        }
+    }
+}
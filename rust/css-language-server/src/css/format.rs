@@ -0,0 +1,513 @@
+use super::{
+    Span,
+    syntax::{Block, Declaration, Item, Rule, Stylesheet},
+    token::{Token, TokenKind, tokenize},
+};
+
+/// Pretty-prints a stylesheet with one declaration per line, one selector of
+/// a list per line and nested blocks indented by `indent`. Comments are kept,
+/// and so is a single blank line where the source had one or more. Returns
+/// `None` if the stylesheet has syntax errors, since error recovery skips
+/// over text that would then be lost.
+pub fn format(sheet: &Stylesheet, indent: &str) -> Option<String> {
+    if !sheet.errors.is_empty() {
+        return None;
+    }
+    let mut out = Printer::new(sheet, Layout::Pretty(indent)).print().out;
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    // Comments in places the printer does not look, such as between a
+    // selector and its block, would be dropped.
+    (comments(&sheet.text, &sheet.tokens) == comments(&out, &tokenize(&out))).then_some(out)
+}
+
+/// A position in the output of the printer, and the position in the source
+/// it was printed from, as byte offsets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mapping {
+    pub generated: usize,
+    pub source: usize,
+}
+
+/// A stylesheet printed by `minify`.
+pub struct Minified {
+    pub text: String,
+    /// Where each rule, selector, declaration and value starts, in order.
+    pub mappings: Vec<Mapping>,
+}
+
+/// Prints a stylesheet on one line, without comments and without the
+/// whitespace and semicolons it does not need. Returns `None` if the
+/// stylesheet has syntax errors, like `format`.
+pub fn minify(sheet: &Stylesheet) -> Option<Minified> {
+    if !sheet.errors.is_empty() {
+        return None;
+    }
+    let printer = Printer::new(sheet, Layout::Minified).print();
+    Some(Minified {
+        text: printer.out,
+        mappings: printer.mappings,
+    })
+}
+
+fn comments<'a>(text: &'a str, tokens: &[Token]) -> Vec<&'a str> {
+    tokens
+        .iter()
+        .filter(|t| t.kind == TokenKind::Comment)
+        .map(|t| t.text(text))
+        .collect()
+}
+
+/// What a rule list or block contains.
+#[derive(Clone, Copy)]
+enum Node<'a> {
+    Rule(&'a Rule),
+    Declaration(&'a Declaration),
+}
+
+impl Node<'_> {
+    fn span(self) -> Span {
+        match self {
+            Node::Rule(Rule::Style(rule)) => Span::new(rule.selector.start, rule.block.span.end),
+            Node::Rule(Rule::At(rule)) => {
+                let end = match &rule.block {
+                    Some(block) => block.span.end,
+                    None => rule.prelude.end.max(rule.name_span.end),
+                };
+                Span::new(rule.name_span.start, end)
+            }
+            Node::Declaration(decl) => decl.span,
+        }
+    }
+}
+
+/// How the printer lays out the stylesheet.
+#[derive(Clone, Copy, PartialEq)]
+enum Layout<'a> {
+    /// Nested blocks are indented by the string.
+    Pretty(&'a str),
+    Minified,
+}
+
+/// How whitespace is normalized in a run of tokens.
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    /// Combinators are surrounded by single spaces.
+    Selector,
+    Prelude,
+    /// Line breaks outside of functions are kept, as in the rows of
+    /// `grid-template-areas`.
+    Value,
+}
+
+struct Printer<'a> {
+    sheet: &'a Stylesheet,
+    layout: Layout<'a>,
+    depth: usize,
+    out: String,
+    mappings: Vec<Mapping>,
+}
+
+impl<'a> Printer<'a> {
+    fn new(sheet: &'a Stylesheet, layout: Layout<'a>) -> Self {
+        Self {
+            sheet,
+            layout,
+            depth: 0,
+            out: String::new(),
+            mappings: vec![],
+        }
+    }
+
+    fn print(mut self) -> Self {
+        let nodes: Vec<Node> = self.sheet.rules.iter().map(Node::Rule).collect();
+        self.nodes(&nodes, Span::new(0, self.sheet.text.len()));
+        self
+    }
+
+    fn text(&self, span: Span) -> &str {
+        span.text(&self.sheet.text)
+    }
+
+    fn minified(&self) -> bool {
+        self.layout == Layout::Minified
+    }
+
+    /// The indentation of `depth` levels.
+    fn indent(&self, depth: usize) -> String {
+        match self.layout {
+            Layout::Pretty(indent) => indent.repeat(depth),
+            Layout::Minified => String::new(),
+        }
+    }
+
+    /// Records that the output continues with the source at `offset`.
+    fn map(&mut self, offset: usize) {
+        self.mappings.push(Mapping {
+            generated: self.out.len(),
+            source: offset,
+        });
+    }
+
+    fn start_line(&mut self, blank: bool) {
+        if self.minified() {
+            return;
+        }
+        if !self.out.is_empty() {
+            self.out.push('\n');
+            if blank {
+                self.out.push('\n');
+            }
+        }
+        self.out.push_str(&self.indent(self.depth));
+    }
+
+    /// Whether the whitespace between `start` and `end` includes a blank
+    /// line.
+    fn has_blank_line(&self, start: usize, end: usize) -> bool {
+        self.sheet
+            .tokens_in(Span::new(start, end))
+            .iter()
+            .any(|t| t.kind == TokenKind::Whitespace && t.text(&self.sheet.text).matches('\n').count() > 1)
+    }
+
+    /// Prints `nodes`, which lie within `span`, with the comments between
+    /// them.
+    fn nodes(&mut self, nodes: &[Node], span: Span) {
+        let mut cursor = span.start;
+        let mut first = true;
+        let starts = nodes.iter().map(|node| node.span().start).chain([span.end]);
+        for (i, start) in starts.enumerate() {
+            let comments: Vec<Span> = self
+                .sheet
+                .tokens_in(Span::new(cursor, start))
+                .iter()
+                .filter(|t| t.kind == TokenKind::Comment && !self.minified())
+                .map(|t| t.span)
+                .collect();
+            for comment in comments {
+                let before = Span::new(cursor, comment.start);
+                if !first && !self.text(before).contains('\n') {
+                    // A comment after a node on the same line stays there.
+                    self.out.push(' ');
+                } else {
+                    let blank = !first && self.has_blank_line(cursor, comment.start);
+                    self.start_line(blank);
+                }
+                let comment = self.text(comment).to_string();
+                self.out.push_str(&comment);
+                cursor = before.end + comment.len();
+                first = false;
+            }
+            let Some(&node) = nodes.get(i) else { break };
+            let blank = !first && self.has_blank_line(cursor, start);
+            self.start_line(blank);
+            self.map(start);
+            match node {
+                Node::Rule(rule) => self.rule(rule),
+                Node::Declaration(decl) => self.declaration(decl),
+            }
+            cursor = node.span().end;
+            first = false;
+        }
+    }
+
+    fn rule(&mut self, rule: &Rule) {
+        match rule {
+            Rule::Style(rule) => {
+                let separator = match self.layout {
+                    Layout::Pretty(_) => format!(",\n{}", self.indent(self.depth)),
+                    Layout::Minified => ",".to_string(),
+                };
+                for (i, selector) in split_list(self.sheet, rule.selector).into_iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(&separator);
+                        self.map(selector.start);
+                    }
+                    let selector = self.inline(selector, Mode::Selector);
+                    self.out.push_str(&selector);
+                }
+                self.block(&rule.block);
+            }
+            Rule::At(rule) => {
+                let name = self.text(rule.name_span).to_string();
+                self.out.push_str(&name);
+                if rule.prelude.start < rule.prelude.end {
+                    let prelude = self.inline(rule.prelude, Mode::Prelude);
+                    self.out.push(' ');
+                    self.out.push_str(&prelude);
+                }
+                match &rule.block {
+                    Some(block) => self.block(block),
+                    None => self.out.push(';'),
+                }
+            }
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        let inner = Span::new(block.span.start + 1, block.span.end - 1);
+        let empty = block.items.is_empty() && !self.sheet.tokens_in(inner).iter().any(|t| t.kind == TokenKind::Comment);
+        let open = match self.minified() {
+            true => "{",
+            false => " {",
+        };
+        self.out.push_str(open);
+        if empty {
+            self.out.push('}');
+            return;
+        }
+        let nodes: Vec<Node> = block
+            .items
+            .iter()
+            .map(|item| match item {
+                Item::Rule(rule) => Node::Rule(rule),
+                Item::Declaration(decl) => Node::Declaration(decl),
+            })
+            .collect();
+        self.depth += 1;
+        self.nodes(&nodes, inner);
+        self.depth -= 1;
+        if self.minified() && matches!(nodes.last(), Some(Node::Declaration(_))) {
+            self.out.pop();
+        }
+        self.start_line(false);
+        self.out.push('}');
+    }
+
+    fn declaration(&mut self, decl: &Declaration) {
+        let name = self.text(decl.name_span).to_string();
+        self.out.push_str(&name);
+        self.out.push(':');
+        // Custom properties may hold any tokens, so their values are kept as
+        // written.
+        let value = match decl.name.starts_with("--") {
+            true => self.text(decl.value).to_string(),
+            false => self.inline(decl.value, Mode::Value),
+        };
+        if !value.is_empty() {
+            if !self.minified() {
+                self.out.push(' ');
+            }
+            self.map(decl.value.start);
+            self.out.push_str(&value);
+        }
+        if decl.important {
+            let important = match self.minified() {
+                true => "!important",
+                false => " !important",
+            };
+            self.out.push_str(important);
+        }
+        self.out.push(';');
+    }
+
+    /// The tokens of `span` with whitespace collapsed to single spaces, none
+    /// inside parentheses and brackets, and a space after each comma. When
+    /// minified, there is none after commas, around combinators or after the
+    /// colon of a media feature either, and comments are dropped.
+    fn inline(&self, span: Span, mode: Mode) -> String {
+        let text = self.sheet.text.as_str();
+        let mut out = String::new();
+        let mut depth = 0;
+        // Whitespace to print before the next token: a space or a line break.
+        let mut space: Option<&str> = None;
+        let mut after_open = false;
+        let newline = format!("\n{}", self.indent(self.depth + 1));
+        let minified = self.minified();
+        for token in self.sheet.tokens_in(span) {
+            let token_text = token.text(text);
+            match token.kind {
+                // A comment separates the tokens around it, like whitespace.
+                TokenKind::Comment if minified => {
+                    if !after_open && space.is_none() {
+                        space = Some(" ");
+                    }
+                    continue;
+                }
+                TokenKind::Whitespace => {
+                    if !after_open {
+                        let line_break = !minified && mode == Mode::Value && depth == 0 && token_text.contains('\n');
+                        space = match (line_break, space) {
+                            (true, _) | (false, Some("\n")) => Some("\n"),
+                            _ => Some(" "),
+                        };
+                    }
+                    continue;
+                }
+                TokenKind::Comma => {
+                    out.push(',');
+                    space = (!minified).then_some(" ");
+                    after_open = minified;
+                    continue;
+                }
+                TokenKind::RightParen | TokenKind::RightBracket => {
+                    out.push_str(token_text);
+                    depth -= 1;
+                    space = None;
+                    after_open = false;
+                    continue;
+                }
+                TokenKind::Delim if mode == Mode::Selector && depth == 0 && matches!(token_text, ">" | "+" | "~") => {
+                    if !minified {
+                        out.push(' ');
+                    }
+                    out.push_str(token_text);
+                    space = (!minified).then_some(" ");
+                    after_open = true;
+                    continue;
+                }
+                _ => {}
+            }
+            match space.take() {
+                Some("\n") => out.push_str(&newline),
+                Some(_) => out.push(' '),
+                None => {}
+            }
+            out.push_str(token_text);
+            after_open = matches!(
+                token.kind,
+                TokenKind::Function | TokenKind::LeftParen | TokenKind::LeftBracket
+            );
+            if after_open {
+                depth += 1;
+            }
+            if minified && mode == Mode::Prelude && token.kind == TokenKind::Colon {
+                after_open = true;
+            }
+        }
+        out.trim_start().to_string()
+    }
+}
+
+/// The items of a comma-separated list, trimmed.
+fn split_list(sheet: &Stylesheet, span: Span) -> Vec<Span> {
+    let mut items = vec![];
+    let mut start = span.start;
+    let mut depth = 0;
+    for token in sheet.tokens_in(span) {
+        match token.kind {
+            TokenKind::Function | TokenKind::LeftParen | TokenKind::LeftBracket => depth += 1,
+            TokenKind::RightParen | TokenKind::RightBracket => depth -= 1,
+            TokenKind::Comma if depth == 0 => {
+                items.push(Span::new(start, token.span.start));
+                start = token.span.end;
+            }
+            _ => {}
+        }
+    }
+    items.push(Span::new(start, span.end));
+    items
+        .into_iter()
+        .map(|item| {
+            let tokens = sheet.tokens_in(item);
+            let first = tokens.iter().find(|t| !t.is_trivia());
+            let last = tokens.iter().rev().find(|t| !t.is_trivia());
+            match (first, last) {
+                (Some(first), Some(last)) => Span::new(first.span.start, last.span.end),
+                _ => Span::new(item.start, item.start),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::parser::parse;
+
+    const FIXTURES: &[&str] = &[
+        include_str!("../../benches/fixtures/components.css"),
+        include_str!("../../benches/fixtures/reset.css"),
+        include_str!("../../benches/fixtures/utilities.css"),
+    ];
+
+    const SAMPLE: &str = "/* head */\n@import url(a.css) screen;\na,b>c{color:red;margin:0 auto!important}\n\n\n\n\
+                          @media (min-width: 600px){.x{--y:  { a } ;width:calc(1px + 2px)}}\n\
+                          .a { &:hover { color: blue } }\n";
+
+    fn pretty(text: &str) -> String {
+        format(&parse(text), "  ").unwrap()
+    }
+
+    #[test]
+    fn pretty_printing() {
+        assert_eq!(
+            pretty(SAMPLE),
+            "/* head */\n\
+             @import url(a.css) screen;\n\
+             a,\n\
+             b > c {\n  color: red;\n  margin: 0 auto !important;\n}\n\
+             \n\
+             @media (min-width: 600px) {\n  .x {\n    --y: { a };\n    width: calc(1px + 2px);\n  }\n}\n\
+             .a {\n  &:hover {\n    color: blue;\n  }\n}\n"
+        );
+        assert_eq!(pretty(""), "");
+    }
+
+    #[test]
+    fn syntax_errors_and_misplaced_comments() {
+        assert_eq!(format(&parse("a { color: red"), "  "), None);
+        // The printer does not look between a selector and its block.
+        assert_eq!(format(&parse("a /* x */ { color: red }"), "  "), None);
+    }
+
+    #[test]
+    fn idempotence() {
+        for text in FIXTURES.iter().chain([&SAMPLE]) {
+            let once = pretty(text);
+            assert_eq!(pretty(&once), once);
+            let tabs = format(&parse(text), "\t").unwrap();
+            assert_eq!(format(&parse(&tabs), "\t").unwrap(), tabs);
+        }
+    }
+
+    #[test]
+    fn minified() {
+        assert_eq!(
+            minify(&parse(SAMPLE)).unwrap().text,
+            "@import url(a.css) screen;a,b>c{color:red;margin:0 auto!important}\
+             @media (min-width:600px){.x{--y:{ a };width:calc(1px + 2px)}}.a{&:hover{color:blue}}"
+        );
+        assert!(minify(&parse("a { color: red")).is_none());
+    }
+
+    /// Minifying keeps every rule and declaration, which pretty-print as
+    /// the source does once its comments are gone.
+    #[test]
+    fn minify_then_reparse() {
+        for text in FIXTURES.iter().chain([&SAMPLE]) {
+            let sheet = parse(text);
+            let minified = minify(&sheet).unwrap();
+            let reparsed = parse(&minified.text);
+            assert!(reparsed.errors.is_empty(), "{:?}", reparsed.errors);
+            assert_eq!(minify(&reparsed).unwrap().text, minified.text);
+
+            let names = |sheet: &Stylesheet| -> Vec<String> {
+                sheet.declarations().iter().map(|decl| decl.name.clone()).collect()
+            };
+            assert_eq!(names(&reparsed), names(&sheet));
+            assert_eq!(reparsed.at_rules().len(), sheet.at_rules().len());
+
+            let without_comments: String = sheet
+                .tokens
+                .iter()
+                .filter(|t| t.kind != TokenKind::Comment)
+                .map(|t| t.text(text))
+                .collect();
+            // The printer keeps at-rule preludes as written, where
+            // minifying drops the space after the colon of a media feature.
+            let normalize = |text: String| text.replace("\n\n", "\n").replace(": ", ":");
+            assert_eq!(normalize(pretty(&minified.text)), normalize(pretty(&without_comments)));
+
+            // Mappings point at the starts of tokens, in order.
+            let generated: Vec<usize> = minified.mappings.iter().map(|m| m.generated).collect();
+            assert!(generated.is_sorted());
+            for mapping in &minified.mappings {
+                assert!(reparsed.tokens.iter().any(|t| t.span.start == mapping.generated));
+                assert!(sheet.tokens.iter().any(|t| t.span.start == mapping.source));
+            }
+        }
+    }
+}
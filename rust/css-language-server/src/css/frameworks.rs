@@ -0,0 +1,89 @@
+/// A CSS framework that extends the language with its own at-rules.
+///
+/// Frameworks are a plugin point: adding one here makes its at-rules known to
+/// diagnostics and lets them opt into completions. Which frameworks are
+/// enabled is configured with `initializationOptions`.
+#[derive(Debug)]
+pub struct Framework {
+    pub name: &'static str,
+    pub at_rules: &'static [FrameworkAtRule],
+}
+
+#[derive(Debug)]
+pub struct FrameworkAtRule {
+    pub name: &'static str,
+    pub kind: AtRuleKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AtRuleKind {
+    Other,
+    /// The prelude is a list of utility classes, as in `@apply flex p-4`.
+    Apply,
+    /// The prelude names a new utility class, as in `@utility tab-4 { ... }`.
+    Utility,
+}
+
+impl Framework {
+    pub fn at_rule(&self, name: &str) -> Option<&'static FrameworkAtRule> {
+        self.at_rules.iter().find(|at_rule| at_rule.name == name)
+    }
+}
+
+pub const FRAMEWORKS: &[Framework] = &[TAILWIND];
+
+pub fn find(name: &str) -> Option<&'static Framework> {
+    FRAMEWORKS
+        .iter()
+        .find(|framework| framework.name.eq_ignore_ascii_case(name))
+}
+
+const TAILWIND: Framework = Framework {
+    name: "tailwind",
+    at_rules: &[
+        FrameworkAtRule {
+            name: "apply",
+            kind: AtRuleKind::Apply,
+        },
+        FrameworkAtRule {
+            name: "config",
+            kind: AtRuleKind::Other,
+        },
+        FrameworkAtRule {
+            name: "custom-variant",
+            kind: AtRuleKind::Other,
+        },
+        FrameworkAtRule {
+            name: "plugin",
+            kind: AtRuleKind::Other,
+        },
+        FrameworkAtRule {
+            name: "reference",
+            kind: AtRuleKind::Other,
+        },
+        FrameworkAtRule {
+            name: "screen",
+            kind: AtRuleKind::Other,
+        },
+        FrameworkAtRule {
+            name: "source",
+            kind: AtRuleKind::Other,
+        },
+        FrameworkAtRule {
+            name: "tailwind",
+            kind: AtRuleKind::Other,
+        },
+        FrameworkAtRule {
+            name: "theme",
+            kind: AtRuleKind::Other,
+        },
+        FrameworkAtRule {
+            name: "utility",
+            kind: AtRuleKind::Utility,
+        },
+        FrameworkAtRule {
+            name: "variant",
+            kind: AtRuleKind::Other,
+        },
+    ],
+};
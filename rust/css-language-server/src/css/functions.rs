@@ -0,0 +1,269 @@
+use super::{
+    properties,
+    syntax::{Call, Stylesheet},
+    token::TokenKind,
+};
+
+/// The parameters of a CSS function, for signature help.
+pub struct Signature {
+    pub name: &'static str,
+    pub documentation: &'static str,
+    /// Names and descriptions. The last parameter takes every remaining
+    /// argument, such as the color stops of a gradient or the fallback of
+    /// `var()`, which may itself contain commas.
+    pub parameters: &'static [(&'static str, &'static str)],
+}
+
+impl Signature {
+    /// `name(a, b, c)`.
+    pub fn label(&self) -> String {
+        let parameters: Vec<&str> = self.parameters.iter().map(|(name, _)| *name).collect();
+        format!("{}({})", self.name, parameters.join(", "))
+    }
+
+    /// The offsets of each parameter in `label()`, in UTF-16 code units.
+    pub fn parameter_offsets(&self) -> Vec<(usize, usize)> {
+        let mut offset = self.name.len() + 1;
+        self.parameters
+            .iter()
+            .map(|(name, _)| {
+                let span = (offset, offset + name.encode_utf16().count());
+                offset = span.1 + ", ".len();
+                span
+            })
+            .collect()
+    }
+}
+
+const LINEAR_GRADIENT: &[(&str, &str)] = &[
+    (
+        "direction",
+        "An angle, or `to` followed by a side or corner. Defaults to `to bottom`.",
+    ),
+    (
+        "color-stop, …",
+        "Colors, each optionally followed by one or two stop positions.",
+    ),
+];
+
+const RADIAL_GRADIENT: &[(&str, &str)] = &[
+    (
+        "shape size at position",
+        "The ending shape, `circle` or `ellipse`, its size and its center. Defaults to `ellipse farthest-corner at center`.",
+    ),
+    (
+        "color-stop, …",
+        "Colors, each optionally followed by one or two stop positions.",
+    ),
+];
+
+const CONIC_GRADIENT: &[(&str, &str)] = &[
+    (
+        "from angle at position",
+        "The starting angle and the center. Defaults to `from 0deg at center`.",
+    ),
+    (
+        "color-stop, …",
+        "Colors, each optionally followed by one or two angles.",
+    ),
+];
+
+const SIGNATURES: &[Signature] = &[
+    Signature {
+        name: "calc",
+        documentation: "Computes a value from a mathematical expression.",
+        parameters: &[(
+            "expression",
+            "Numbers, dimensions and percentages combined with `+`, `-`, `*` and `/`.",
+        )],
+    },
+    Signature {
+        name: "clamp",
+        documentation: "Clamps a value between a minimum and a maximum.",
+        parameters: &[
+            ("min", "The smallest allowed value."),
+            ("preferred", "The value used while it lies between the bounds."),
+            ("max", "The largest allowed value."),
+        ],
+    },
+    Signature {
+        name: "color-mix",
+        documentation: "Mixes two colors in a given color space.",
+        parameters: &[
+            (
+                "method",
+                "`in` followed by the color space to interpolate in, such as `in oklch`.",
+            ),
+            ("color1", "The first color, optionally followed by its percentage."),
+            ("color2", "The second color, optionally followed by its percentage."),
+        ],
+    },
+    Signature {
+        name: "conic-gradient",
+        documentation: "A gradient with colors transitioning around a center point.",
+        parameters: CONIC_GRADIENT,
+    },
+    Signature {
+        name: "linear-gradient",
+        documentation: "A gradient with colors transitioning along a straight line.",
+        parameters: LINEAR_GRADIENT,
+    },
+    Signature {
+        name: "minmax",
+        documentation: "A grid track size range.",
+        parameters: &[
+            ("min", "The minimum size of the track."),
+            ("max", "The maximum size of the track."),
+        ],
+    },
+    Signature {
+        name: "radial-gradient",
+        documentation: "A gradient with colors radiating from an origin.",
+        parameters: RADIAL_GRADIENT,
+    },
+    Signature {
+        name: "repeat",
+        documentation: "Repeats a fragment of a grid track list.",
+        parameters: &[
+            ("count", "The number of repetitions, or `auto-fill` or `auto-fit`."),
+            ("tracks", "The track sizes to repeat."),
+        ],
+    },
+    Signature {
+        name: "repeating-conic-gradient",
+        documentation: "A conic gradient that repeats its color stops.",
+        parameters: CONIC_GRADIENT,
+    },
+    Signature {
+        name: "repeating-linear-gradient",
+        documentation: "A linear gradient that repeats its color stops.",
+        parameters: LINEAR_GRADIENT,
+    },
+    Signature {
+        name: "repeating-radial-gradient",
+        documentation: "A radial gradient that repeats its color stops.",
+        parameters: RADIAL_GRADIENT,
+    },
+    Signature {
+        name: "var",
+        documentation: "Substitutes the value of a custom property.",
+        parameters: &[
+            ("name", "The custom property, such as `--main-color`."),
+            ("fallback", "The value used when the custom property is not defined."),
+        ],
+    },
+];
+
+/// The signature of a function, ignoring vendor prefixes.
+pub fn signature(name: &str) -> Option<&'static Signature> {
+    let name = properties::strip_vendor_prefix(name);
+    SIGNATURES.iter().find(|signature| signature.name == name)
+}
+
+/// The innermost call before `offset` with a known signature, and the index
+/// of the parameter being written.
+pub fn active_call(sheet: &Stylesheet, offset: usize) -> Option<(&'static Signature, usize)> {
+    sheet.calls_at(offset).into_iter().find_map(|call| {
+        let signature = signature(&call.name)?;
+        let argument = gradient_argument(sheet, signature, &call).unwrap_or(call.argument);
+        Some((signature, argument.min(signature.parameters.len() - 1)))
+    })
+}
+
+/// The first argument of a gradient is optional. When it is a color stop,
+/// every argument is a color stop.
+fn gradient_argument(sheet: &Stylesheet, signature: &Signature, call: &Call) -> Option<usize> {
+    if !signature.name.ends_with("gradient") || call.argument == 0 {
+        return None;
+    }
+    let text = sheet.text.as_str();
+    let first = sheet.tokens[sheet.tokens.partition_point(|t| t.span.start < call.start)..]
+        .iter()
+        .find(|t| !t.is_trivia())?;
+    let direction = match first.kind {
+        TokenKind::Dimension { .. } | TokenKind::Number => true,
+        TokenKind::Ident => matches!(
+            first.text(text).to_ascii_lowercase().as_str(),
+            "to" | "from"
+                | "at"
+                | "in"
+                | "circle"
+                | "ellipse"
+                | "closest-side"
+                | "closest-corner"
+                | "farthest-side"
+                | "farthest-corner"
+        ),
+        _ => false,
+    };
+    (!direction).then_some(call.argument + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::parser::parse;
+
+    /// The name of the active call and its parameter at the `|` in `text`.
+    fn active(text: &str) -> Option<(&'static str, usize)> {
+        let offset = text.find('|').unwrap();
+        let sheet = parse(&text.replace('|', ""));
+        active_call(&sheet, offset).map(|(signature, parameter)| (signature.name, parameter))
+    }
+
+    #[test]
+    fn labels() {
+        let clamp = signature("clamp").unwrap();
+        assert_eq!(clamp.label(), "clamp(min, preferred, max)");
+        assert_eq!(clamp.parameter_offsets(), [(6, 9), (11, 20), (22, 25)]);
+        // `…` is one UTF-16 code unit but three bytes.
+        let gradient = signature("linear-gradient").unwrap();
+        assert_eq!(gradient.label(), "linear-gradient(direction, color-stop, …)");
+        assert_eq!(gradient.parameter_offsets(), [(16, 25), (27, 40)]);
+    }
+
+    #[test]
+    fn vendor_prefixes() {
+        assert_eq!(signature("-webkit-linear-gradient").unwrap().name, "linear-gradient");
+        assert!(signature("rgb").is_none());
+    }
+
+    #[test]
+    fn active_parameters() {
+        assert_eq!(active("a { width: clamp(1px, |) }"), Some(("clamp", 1)));
+        assert_eq!(active("a { width: clamp(1px, calc(|)) }"), Some(("calc", 0)));
+        assert_eq!(active("a { width: clamp(1px, calc(2px), |) }"), Some(("clamp", 2)));
+        // Unknown functions are skipped for the call around them.
+        assert_eq!(active("a { width: clamp(rgb(1, |)) }"), Some(("clamp", 0)));
+        // Every comma of the fallback of `var()` is part of it.
+        assert_eq!(active("a { font: var(--a, x, y, |) }"), Some(("var", 1)));
+        assert_eq!(active("a { width: calc(1px) |}"), None);
+        // A parenthesis left open in an earlier declaration does not count.
+        assert_eq!(active("a { width: calc(1px; color: |red }"), None);
+    }
+
+    #[test]
+    fn gradient_color_stops() {
+        assert_eq!(
+            active("a { background: linear-gradient(to right, |) }"),
+            Some(("linear-gradient", 1))
+        );
+        assert_eq!(
+            active("a { background: linear-gradient(45deg, red, |) }"),
+            Some(("linear-gradient", 1))
+        );
+        // Without a direction, the first argument is already a color stop.
+        assert_eq!(
+            active("a { background: linear-gradient(red, |) }"),
+            Some(("linear-gradient", 1))
+        );
+        assert_eq!(
+            active("a { background: linear-gradient(|) }"),
+            Some(("linear-gradient", 0))
+        );
+        assert_eq!(
+            active("a { background: radial-gradient(circle, red, |) }"),
+            Some(("radial-gradient", 1))
+        );
+    }
+}
@@ -0,0 +1,161 @@
+use super::{
+    Span,
+    descriptors::descriptors,
+    properties,
+    syntax::{Item, Rule, Stylesheet},
+    token::TokenKind,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HighlightKind {
+    /// Type, universal and pseudo-class selectors.
+    Selector,
+    Class,
+    Id,
+    Property,
+    /// Custom properties, both declared and referenced.
+    Variable,
+    Function,
+    Unit,
+    Number,
+    AtRule,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Highlight {
+    pub span: Span,
+    pub kind: HighlightKind,
+    /// A custom property declaration as opposed to a reference.
+    pub declaration: bool,
+    pub deprecated: bool,
+    pub unknown: bool,
+}
+
+impl Highlight {
+    fn new(span: Span, kind: HighlightKind) -> Self {
+        Self {
+            span,
+            kind,
+            declaration: false,
+            deprecated: false,
+            unknown: false,
+        }
+    }
+}
+
+/// Classifies the tokens of a stylesheet for syntax highlighting, in source
+/// order.
+pub fn highlights(sheet: &Stylesheet) -> Vec<Highlight> {
+    let mut out = vec![];
+    rules(sheet, &sheet.rules, &mut out);
+    out
+}
+
+fn rules<'a>(sheet: &Stylesheet, list: impl IntoIterator<Item = &'a Rule>, out: &mut Vec<Highlight>) {
+    for rule in list {
+        let descriptors = match rule {
+            Rule::At(rule) => descriptors(&rule.name),
+            Rule::Style(_) => None,
+        };
+        match rule {
+            Rule::Style(rule) => selector(sheet, rule.selector, out),
+            Rule::At(rule) => {
+                out.push(Highlight::new(rule.name_span, HighlightKind::AtRule));
+                values(sheet, rule.prelude, out);
+            }
+        }
+        let Some(block) = rule.block() else { continue };
+        for item in &block.items {
+            match item {
+                Item::Declaration(decl) => {
+                    let mut highlight = Highlight::new(decl.name_span, HighlightKind::Property);
+                    if decl.name.starts_with("--") {
+                        highlight.kind = HighlightKind::Variable;
+                        highlight.declaration = true;
+                    } else if let Some(descriptors) = descriptors {
+                        highlight.unknown = !descriptors.accepts(&decl.name);
+                    } else {
+                        highlight.deprecated = properties::is_deprecated(&decl.name);
+                        highlight.unknown = !properties::is_known(&decl.name);
+                    }
+                    out.push(highlight);
+                    values(sheet, decl.value, out);
+                }
+                Item::Rule(rule) => rules(sheet, [rule], out),
+            }
+        }
+    }
+}
+
+fn selector(sheet: &Stylesheet, span: Span, out: &mut Vec<Highlight>) {
+    let tokens = sheet.tokens_in(span);
+    let text = sheet.text.as_str();
+    let mut brackets = 0;
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+        match token.kind {
+            TokenKind::LeftBracket => brackets += 1,
+            TokenKind::RightBracket => brackets -= 1,
+            _ if brackets > 0 => {}
+            TokenKind::Delim if token.text(text) == "." => {
+                if let Some(name) = tokens.get(i + 1).filter(|t| t.kind == TokenKind::Ident) {
+                    let span = Span::new(token.span.start, name.span.end);
+                    out.push(Highlight::new(span, HighlightKind::Class));
+                    i += 1;
+                }
+            }
+            TokenKind::Delim if matches!(token.text(text), "*" | "&") => {
+                out.push(Highlight::new(token.span, HighlightKind::Selector));
+            }
+            TokenKind::Hash => out.push(Highlight::new(token.span, HighlightKind::Id)),
+            TokenKind::Ident => out.push(Highlight::new(token.span, HighlightKind::Selector)),
+            TokenKind::Colon => {
+                let start = token.span.start;
+                if tokens.get(i + 1).is_some_and(|t| t.kind == TokenKind::Colon) {
+                    i += 1;
+                }
+                match tokens.get(i + 1) {
+                    Some(name) if name.kind == TokenKind::Ident => {
+                        let span = Span::new(start, name.span.end);
+                        out.push(Highlight::new(span, HighlightKind::Selector));
+                        i += 1;
+                    }
+                    Some(name) if name.kind == TokenKind::Function => {
+                        let span = Span::new(start, name.span.end - 1);
+                        out.push(Highlight::new(span, HighlightKind::Selector));
+                        i += 1;
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
+fn values(sheet: &Stylesheet, span: Span, out: &mut Vec<Highlight>) {
+    let text = sheet.text.as_str();
+    for token in sheet.tokens_in(span) {
+        let Span { start, end } = token.span;
+        match token.kind {
+            TokenKind::Number => out.push(Highlight::new(token.span, HighlightKind::Number)),
+            TokenKind::Percentage => {
+                out.push(Highlight::new(Span::new(start, end - 1), HighlightKind::Number));
+                out.push(Highlight::new(Span::new(end - 1, end), HighlightKind::Unit));
+            }
+            TokenKind::Dimension { unit } => {
+                out.push(Highlight::new(Span::new(start, start + unit), HighlightKind::Number));
+                out.push(Highlight::new(Span::new(start + unit, end), HighlightKind::Unit));
+            }
+            TokenKind::Function => {
+                out.push(Highlight::new(Span::new(start, end - 1), HighlightKind::Function));
+            }
+            TokenKind::Ident if token.text(text).starts_with("--") => {
+                out.push(Highlight::new(token.span, HighlightKind::Variable));
+            }
+            _ => {}
+        }
+    }
+}
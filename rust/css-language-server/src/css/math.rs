@@ -0,0 +1,795 @@
+use std::collections::BTreeMap;
+
+use super::{
+    Span, properties,
+    syntax::{Stylesheet, matching_close},
+    token::{Token, TokenKind},
+};
+
+/// The math functions whose arguments are type-checked.
+const MATH_FUNCTIONS: &[&str] = &["calc", "clamp", "max", "min"];
+
+/// The base types of CSS Values 4, in the order of the exponents of `Type`.
+const BASE_TYPES: &[&str] = &["length", "angle", "time", "frequency", "resolution", "percentage"];
+const PERCENT: usize = 5;
+
+/// The size of an absolute unit in the canonical unit of its type.
+pub type Conversion = Option<(f64, &'static str)>;
+
+/// Sorted. A unit, the index of its base type, and its conversion. Relative
+/// units are kept as they are.
+const UNITS: &[(&str, usize, Conversion)] = &[
+    ("cap", 0, None),
+    ("ch", 0, None),
+    ("cm", 0, Some((96.0 / 2.54, "px"))),
+    ("cqb", 0, None),
+    ("cqh", 0, None),
+    ("cqi", 0, None),
+    ("cqmax", 0, None),
+    ("cqmin", 0, None),
+    ("cqw", 0, None),
+    ("deg", 1, Some((1.0, "deg"))),
+    ("dpcm", 4, Some((2.54 / 96.0, "dppx"))),
+    ("dpi", 4, Some((1.0 / 96.0, "dppx"))),
+    ("dppx", 4, Some((1.0, "dppx"))),
+    ("dvb", 0, None),
+    ("dvh", 0, None),
+    ("dvi", 0, None),
+    ("dvmax", 0, None),
+    ("dvmin", 0, None),
+    ("dvw", 0, None),
+    ("em", 0, None),
+    ("ex", 0, None),
+    ("grad", 1, Some((0.9, "deg"))),
+    ("hz", 3, Some((1.0, "hz"))),
+    ("ic", 0, None),
+    ("in", 0, Some((96.0, "px"))),
+    ("khz", 3, Some((1000.0, "hz"))),
+    ("lh", 0, None),
+    ("lvb", 0, None),
+    ("lvh", 0, None),
+    ("lvi", 0, None),
+    ("lvmax", 0, None),
+    ("lvmin", 0, None),
+    ("lvw", 0, None),
+    ("mm", 0, Some((96.0 / 25.4, "px"))),
+    ("ms", 2, Some((0.001, "s"))),
+    ("pc", 0, Some((16.0, "px"))),
+    ("pt", 0, Some((96.0 / 72.0, "px"))),
+    ("px", 0, Some((1.0, "px"))),
+    ("q", 0, Some((96.0 / 101.6, "px"))),
+    ("rad", 1, Some((180.0 / std::f64::consts::PI, "deg"))),
+    ("rcap", 0, None),
+    ("rch", 0, None),
+    ("rem", 0, None),
+    ("rex", 0, None),
+    ("ric", 0, None),
+    ("rlh", 0, None),
+    ("s", 2, Some((1.0, "s"))),
+    ("svb", 0, None),
+    ("svh", 0, None),
+    ("svi", 0, None),
+    ("svmax", 0, None),
+    ("svmin", 0, None),
+    ("svw", 0, None),
+    ("turn", 1, Some((360.0, "deg"))),
+    ("vb", 0, None),
+    ("vh", 0, None),
+    ("vi", 0, None),
+    ("vmax", 0, None),
+    ("vmin", 0, None),
+    ("vw", 0, None),
+    ("x", 4, Some((1.0, "dppx"))),
+];
+
+/// An invalid math expression, which browsers drop along with the whole
+/// declaration.
+#[derive(Debug, Clone)]
+pub struct MathError {
+    pub span: Span,
+    pub message: String,
+}
+
+/// The result of a math function, as shown on hover.
+#[derive(Debug, Clone)]
+pub struct Evaluation {
+    pub span: Span,
+    /// The type it resolves to, such as `length`.
+    pub kind: String,
+    /// The simplified value, when it can be computed statically, such as
+    /// `16px` or `calc(50% + 16px)`.
+    pub value: Option<String>,
+}
+
+/// The exponents of each base type. A number has none.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Type([i8; 6]);
+
+impl Type {
+    fn base(index: usize) -> Self {
+        let mut exponents = [0; 6];
+        exponents[index] = 1;
+        Self(exponents)
+    }
+
+    fn is_number(self) -> bool {
+        self.0 == [0; 6]
+    }
+
+    /// The single base type with an exponent of one, if any.
+    fn single(self) -> Option<usize> {
+        let mut bases = self.0.iter().enumerate().filter(|(_, e)| **e != 0);
+        match (bases.next(), bases.next()) {
+            (Some((base, 1)), None) => Some(base),
+            _ => None,
+        }
+    }
+
+    /// The type of a sum, if the types can be added. A percentage can be
+    /// added to any other dimension it may resolve against.
+    fn add(self, other: Self) -> Option<Self> {
+        let percent = Self::base(PERCENT);
+        if self == other {
+            Some(self)
+        } else if self == percent && other.single().is_some() {
+            Some(other)
+        } else if other == percent && self.single().is_some() {
+            Some(self)
+        } else {
+            None
+        }
+    }
+
+    fn multiply(self, other: Self, sign: i8) -> Self {
+        let mut exponents = self.0;
+        for (exponent, other) in exponents.iter_mut().zip(other.0) {
+            *exponent += sign * other;
+        }
+        Self(exponents)
+    }
+
+    fn name(self) -> String {
+        if self.is_number() {
+            return "number".to_string();
+        }
+        let parts: Vec<String> = self
+            .0
+            .iter()
+            .zip(BASE_TYPES)
+            .filter(|(exponent, _)| **exponent != 0)
+            .map(|(exponent, name)| match exponent {
+                1 => name.to_string(),
+                2 => format!("{name}²"),
+                3 => format!("{name}³"),
+                -1 => format!("{name}⁻¹"),
+                _ => format!("{name}^{exponent}"),
+            })
+            .collect();
+        parts.join("·")
+    }
+}
+
+/// A value as a sum of terms per unit, with absolute units converted to the
+/// canonical unit of their type. The number term has an empty unit.
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Sum(BTreeMap<String, f64>);
+
+impl Sum {
+    fn unit(unit: &str, n: f64) -> Self {
+        Self(BTreeMap::from([(unit.to_string(), n)]))
+    }
+
+    /// The value of a plain number.
+    fn number(&self) -> Option<f64> {
+        match self.0.iter().collect::<Vec<_>>()[..] {
+            [(unit, n)] if unit.is_empty() => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// The unit and value of a sum with a single term.
+    fn single(&self) -> Option<(&str, f64)> {
+        match self.0.iter().collect::<Vec<_>>()[..] {
+            [(unit, n)] => Some((unit, *n)),
+            _ => None,
+        }
+    }
+
+    fn add(mut self, other: Sum, sign: f64) -> Self {
+        for (unit, n) in other.0 {
+            *self.0.entry(unit).or_default() += sign * n;
+        }
+        if self.0.len() > 1 {
+            self.0.retain(|_, n| *n != 0.0);
+        }
+        if self.0.is_empty() {
+            self.0.insert(String::new(), 0.0);
+        }
+        self
+    }
+
+    fn scale(mut self, factor: f64) -> Self {
+        self.0.values_mut().for_each(|n| *n *= factor);
+        self
+    }
+
+    fn to_css(&self) -> String {
+        if let Some((unit, n)) = self.single() {
+            return format!("{}{unit}", format_number(n));
+        }
+        let mut out = String::from("calc(");
+        for (i, (unit, n)) in self.0.iter().enumerate() {
+            match (i, *n < 0.0) {
+                (0, _) => out.push_str(&format_number(*n)),
+                (_, true) => out.push_str(&format!(" - {}", format_number(-n))),
+                (_, false) => out.push_str(&format!(" + {}", format_number(*n))),
+            }
+            out.push_str(unit);
+        }
+        out.push(')');
+        out
+    }
+}
+
+fn format_number(n: f64) -> String {
+    let rounded = format!("{n:.4}");
+    rounded.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// A parsed operand: its type and value, either unknown when it involves
+/// `var()` and the like or after an error.
+#[derive(Debug, Clone)]
+struct Term {
+    span: Span,
+    ty: Option<Type>,
+    value: Option<Sum>,
+}
+
+impl Term {
+    fn unknown(span: Span) -> Self {
+        Self {
+            span,
+            ty: None,
+            value: None,
+        }
+    }
+}
+
+/// Type errors in the math functions of the stylesheet, such as
+/// `calc(10px + 2s)`.
+pub fn check(sheet: &Stylesheet) -> Vec<MathError> {
+    let mut errors = vec![];
+    for (open, close) in math_functions(sheet) {
+        evaluate(sheet, open, close, &mut errors);
+    }
+    errors
+}
+
+/// The type and simplified value of the outermost math function around
+/// `offset`, if it is valid.
+pub fn evaluate_at(sheet: &Stylesheet, offset: usize) -> Option<Evaluation> {
+    let (open, close) = math_functions(sheet).into_iter().find(|&(open, close)| {
+        let end = sheet.tokens.get(close).map_or(sheet.text.len(), |t| t.span.end);
+        sheet.tokens[open].span.start <= offset && offset <= end
+    })?;
+    let mut errors = vec![];
+    let term = evaluate(sheet, open, close, &mut errors);
+    if !errors.is_empty() {
+        return None;
+    }
+    Some(Evaluation {
+        span: term.span,
+        kind: term.ty?.name(),
+        value: term.value.map(|value| value.to_css()),
+    })
+}
+
+/// The token ranges of math functions that are not inside another one.
+fn math_functions(sheet: &Stylesheet) -> Vec<(usize, usize)> {
+    let text = sheet.text.as_str();
+    let mut out = vec![];
+    let mut i = 0;
+    while i < sheet.tokens.len() {
+        let token = sheet.tokens[i];
+        if token.kind == TokenKind::Function && is_math_function(token.name(text)) {
+            let close = matching_close(&sheet.tokens, i);
+            out.push((i, close));
+            i = close;
+        }
+        i += 1;
+    }
+    out
+}
+
+/// The base type of a unit, such as `length` for `em`, and for an absolute
+/// unit its size in the canonical unit of that type.
+pub fn unit(unit: &str) -> Option<(&'static str, Conversion)> {
+    let lower = unit.to_ascii_lowercase();
+    let index = UNITS.binary_search_by(|(name, _, _)| name.cmp(&lower.as_str())).ok()?;
+    let (_, base, conversion) = UNITS[index];
+    Some((BASE_TYPES[base], conversion))
+}
+
+fn is_math_function(name: &str) -> bool {
+    let name = properties::strip_vendor_prefix(name).to_ascii_lowercase();
+    MATH_FUNCTIONS.contains(&name.as_str())
+}
+
+/// Checks the math function opened by `tokens[open]`, including that it
+/// resolves to a single type.
+fn evaluate(sheet: &Stylesheet, open: usize, close: usize, errors: &mut Vec<MathError>) -> Term {
+    let before = errors.len();
+    let mut parser = Parser {
+        sheet,
+        tokens: &sheet.tokens,
+        pos: open,
+        end: close,
+        errors,
+    };
+    let term = parser.function();
+    if parser.errors.len() == before
+        && let Some(ty) = term.ty
+        && !ty.is_number()
+        && ty.single().is_none()
+    {
+        parser.error(
+            term.span,
+            format!(
+                "This expression resolves to `{}`, which is not a valid CSS type",
+                ty.name()
+            ),
+        );
+        return Term::unknown(term.span);
+    }
+    term
+}
+
+struct Parser<'a> {
+    sheet: &'a Stylesheet,
+    tokens: &'a [Token],
+    /// The next token to read.
+    pos: usize,
+    /// The end of the tokens to read: the closing parenthesis of the current
+    /// function or parenthesized expression.
+    end: usize,
+    errors: &'a mut Vec<MathError>,
+}
+
+impl Parser<'_> {
+    fn error(&mut self, span: Span, message: impl Into<String>) {
+        self.errors.push(MathError {
+            span,
+            message: message.into(),
+        });
+    }
+
+    fn text(&self, token: Token) -> &str {
+        token.text(&self.sheet.text)
+    }
+
+    /// The index of the next token that is not whitespace or a comment.
+    fn peek(&self) -> Option<usize> {
+        (self.pos..self.end).find(|&i| !self.tokens[i].is_trivia())
+    }
+
+    fn is_trivia(&self, index: usize) -> bool {
+        self.tokens.get(index).is_some_and(|t| t.is_trivia())
+    }
+
+    /// Where a missing value would be.
+    fn here(&self) -> Span {
+        let offset = match self.tokens.get(self.end) {
+            Some(token) => token.span.start,
+            None => self.sheet.text.len(),
+        };
+        Span::new(offset, offset)
+    }
+
+    /// Parses the function at `pos`, up to its closing parenthesis.
+    fn function(&mut self) -> Term {
+        let open = self.pos;
+        let close = matching_close(self.tokens, open);
+        let token = self.tokens[open];
+        let end = self.tokens.get(close).map_or(self.sheet.text.len(), |t| t.span.end);
+        let span = Span::new(token.span.start, end);
+        let name = properties::strip_vendor_prefix(token.name(&self.sheet.text)).to_ascii_lowercase();
+        self.pos = close + 1;
+        if !MATH_FUNCTIONS.contains(&name.as_str()) {
+            return Term::unknown(span);
+        }
+
+        let outer = std::mem::replace(&mut self.end, close);
+        self.pos = open + 1;
+        // `None` stands for the `none` bounds of `clamp()`.
+        let mut arguments = vec![];
+        let mut failed = false;
+        loop {
+            let none = self.peek().filter(|&i| {
+                let token = self.tokens[i];
+                name == "clamp" && token.kind == TokenKind::Ident && self.text(token).eq_ignore_ascii_case("none")
+            });
+            let argument = match none {
+                Some(i) => {
+                    self.pos = i + 1;
+                    None
+                }
+                None => Some(self.sum()),
+            };
+            arguments.push(argument);
+            match self.peek() {
+                Some(i) if self.tokens[i].kind == TokenKind::Comma && name != "calc" => self.pos = i + 1,
+                Some(i) => {
+                    self.error(self.tokens[i].span, "Expected an operator");
+                    failed = true;
+                    break;
+                }
+                None => break,
+            }
+        }
+        self.end = outer;
+        self.pos = close + 1;
+
+        if name == "clamp" && !failed && arguments.len() != 3 {
+            self.error(span, format!("`clamp()` takes 3 arguments, found {}", arguments.len()));
+            return Term::unknown(span);
+        }
+        if failed || arguments.iter().flatten().any(|term| term.ty.is_none()) {
+            return Term::unknown(span);
+        }
+        let terms: Vec<Term> = arguments.into_iter().flatten().collect();
+        if name == "calc" {
+            return Term {
+                span,
+                ..terms[0].clone()
+            };
+        }
+
+        let mut ty = terms[0].ty;
+        for term in &terms[1..] {
+            let (Some(a), Some(b)) = (ty, term.ty) else { break };
+            ty = a.add(b);
+            if ty.is_none() {
+                self.error(
+                    span,
+                    format!(
+                        "The arguments of `{name}()` have incompatible types: {} and {}",
+                        a.name(),
+                        b.name()
+                    ),
+                );
+                return Term::unknown(span);
+            }
+        }
+        let singles: Option<Vec<(&str, f64)>> = terms.iter().map(|t| t.value.as_ref()?.single()).collect();
+        let value = singles
+            .filter(|singles| singles.iter().all(|(unit, _)| *unit == singles[0].0))
+            .and_then(|singles| {
+                let unit = singles[0].0;
+                let values = singles.iter().map(|(_, n)| *n);
+                let n = match name.as_str() {
+                    "min" => values.fold(f64::INFINITY, f64::min),
+                    "max" => values.fold(f64::NEG_INFINITY, f64::max),
+                    // `clamp(min, val, max)` is `max(min, min(val, max))`.
+                    _ if singles.len() == 3 => singles[0].1.max(singles[1].1.min(singles[2].1)),
+                    _ => return None,
+                };
+                Some(Sum::unit(unit, n))
+            });
+        Term { span, ty, value }
+    }
+
+    /// `product (('+' | '-') product)*`.
+    fn sum(&mut self) -> Term {
+        let mut left = self.product();
+        while let Some(i) = self.peek() {
+            let token = self.tokens[i];
+            let text = self.text(token).to_string();
+            let sign = match token.kind {
+                TokenKind::Delim if text == "+" || text == "-" => {
+                    if !self.is_trivia(i.wrapping_sub(1)) || !self.is_trivia(i + 1) {
+                        self.error(token.span, format!("`{text}` must be surrounded by whitespace"));
+                    }
+                    self.pos = i + 1;
+                    if text == "+" { 1.0 } else { -1.0 }
+                }
+                // `100% -10px` is two values rather than a subtraction.
+                TokenKind::Number | TokenKind::Percentage | TokenKind::Dimension { .. }
+                    if text.starts_with(['+', '-']) =>
+                {
+                    let operator = Span::new(token.span.start, token.span.start + 1);
+                    self.error(operator, format!("`{}` must be surrounded by whitespace", &text[..1]));
+                    1.0
+                }
+                _ => break,
+            };
+            let right = self.product();
+            left = self.add(left, right, sign);
+        }
+        left
+    }
+
+    fn add(&mut self, left: Term, right: Term, sign: f64) -> Term {
+        let span = Span::new(left.span.start, right.span.end);
+        let (Some(a), Some(b)) = (left.ty, right.ty) else {
+            return Term::unknown(span);
+        };
+        let Some(ty) = a.add(b) else {
+            let message = match sign > 0.0 {
+                true => format!("Cannot add {} and {}", a.name(), b.name()),
+                false => format!("Cannot subtract {} from {}", b.name(), a.name()),
+            };
+            let hint = match a.is_number() || b.is_number() {
+                true => "; numbers must have a unit, such as `0px`",
+                false => "",
+            };
+            self.error(span, format!("{message}{hint}"));
+            return Term::unknown(span);
+        };
+        let value = match (left.value, right.value) {
+            (Some(a), Some(b)) => Some(a.add(b, sign)),
+            _ => None,
+        };
+        Term {
+            span,
+            ty: Some(ty),
+            value,
+        }
+    }
+
+    /// `value (('*' | '/') value)*`.
+    fn product(&mut self) -> Term {
+        let mut left = self.value();
+        while let Some(i) = self.peek() {
+            let token = self.tokens[i];
+            let divide = match (token.kind, self.text(token)) {
+                (TokenKind::Delim, "*") => false,
+                (TokenKind::Delim, "/") => true,
+                _ => break,
+            };
+            self.pos = i + 1;
+            let right = self.value();
+            let span = Span::new(left.span.start, right.span.end);
+            left = match (left.ty, right.ty) {
+                (Some(a), Some(b)) => {
+                    let value = match (left.value, right.value) {
+                        (Some(a), Some(b)) => match (divide, a.number(), b.number()) {
+                            (true, _, Some(n)) if n != 0.0 => Some(a.scale(1.0 / n)),
+                            (true, _, _) => match (a.single(), b.single()) {
+                                (Some((x, n)), Some((y, m))) if x == y && m != 0.0 => Some(Sum::unit("", n / m)),
+                                _ => None,
+                            },
+                            (false, Some(n), _) => Some(b.scale(n)),
+                            (false, _, Some(n)) => Some(a.scale(n)),
+                            (false, None, None) => None,
+                        },
+                        _ => None,
+                    };
+                    Term {
+                        span,
+                        ty: Some(a.multiply(b, if divide { -1 } else { 1 })),
+                        value,
+                    }
+                }
+                _ => Term::unknown(span),
+            };
+        }
+        left
+    }
+
+    /// A number, dimension, percentage, constant, parenthesized expression or
+    /// function.
+    fn value(&mut self) -> Term {
+        let Some(i) = self.peek() else {
+            let here = self.here();
+            self.error(here, "Expected a value");
+            return Term::unknown(here);
+        };
+        let token = self.tokens[i];
+        let text = self.text(token).to_string();
+        self.pos = i + 1;
+        let number = |text: &str| text.trim_start_matches('+').parse::<f64>().ok();
+        match token.kind {
+            TokenKind::Number => Term {
+                span: token.span,
+                ty: Some(Type::default()),
+                value: number(&text).map(|n| Sum::unit("", n)),
+            },
+            TokenKind::Percentage => Term {
+                span: token.span,
+                ty: Some(Type::base(PERCENT)),
+                value: number(&text[..text.len() - 1]).map(|n| Sum::unit("%", n)),
+            },
+            TokenKind::Dimension { unit } => self.dimension(token, &text[..unit], &text[unit..]),
+            TokenKind::Ident => {
+                let value = match text.to_ascii_lowercase().as_str() {
+                    "e" => Some(std::f64::consts::E),
+                    "pi" => Some(std::f64::consts::PI),
+                    "infinity" | "-infinity" | "nan" => None,
+                    _ => return Term::unknown(token.span),
+                };
+                Term {
+                    span: token.span,
+                    ty: Some(Type::default()),
+                    value: value.map(|n| Sum::unit("", n)),
+                }
+            }
+            TokenKind::LeftParen => {
+                let close = matching_close(self.tokens, i);
+                let close = close.min(self.end);
+                let outer = std::mem::replace(&mut self.end, close);
+                let inner = self.sum();
+                if let Some(extra) = self.peek() {
+                    self.error(self.tokens[extra].span, "Expected an operator");
+                }
+                self.end = outer;
+                self.pos = close + 1;
+                let end = self.tokens.get(close).map_or(self.sheet.text.len(), |t| t.span.end);
+                Term {
+                    span: Span::new(token.span.start, end),
+                    ..inner
+                }
+            }
+            TokenKind::Function => {
+                self.pos = i;
+                self.function()
+            }
+            _ => {
+                self.error(token.span, "Expected a value");
+                Term::unknown(token.span)
+            }
+        }
+    }
+
+    fn dimension(&mut self, token: Token, number: &str, unit: &str) -> Term {
+        let lower = unit.to_ascii_lowercase();
+        let n = number.trim_start_matches('+').parse::<f64>().ok();
+        if lower == "fr" {
+            self.error(token.span, "`fr` cannot be used in math functions");
+            return Term::unknown(token.span);
+        }
+        let Ok(index) = UNITS.binary_search_by(|(name, _, _)| name.cmp(&lower.as_str())) else {
+            // `10px-2px` lexes as a single dimension with unit `px-2px`.
+            if let Some((unit, _)) = lower.split_once('-')
+                && UNITS.iter().any(|(name, _, _)| *name == unit)
+            {
+                let offset = token.span.start + number.len() + unit.len();
+                self.error(Span::new(offset, offset + 1), "`-` must be surrounded by whitespace");
+            }
+            return Term::unknown(token.span);
+        };
+        let (_, base, absolute) = UNITS[index];
+        let value = n.map(|n| match absolute {
+            Some((factor, canonical)) => Sum::unit(canonical, n * factor),
+            None => Sum::unit(&lower, n),
+        });
+        Term {
+            span: token.span,
+            ty: Some(Type::base(base)),
+            value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::parser::parse;
+
+    fn errors(value: &str) -> Vec<(String, String)> {
+        let text = format!("a {{ width: {value} }}");
+        check(&parse(&text))
+            .into_iter()
+            .map(|error| (error.span.text(&text).to_string(), error.message))
+            .collect()
+    }
+
+    fn error(value: &str) -> String {
+        let errors = errors(value);
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        errors[0].1.clone()
+    }
+
+    /// The type and simplified value of `value`.
+    fn evaluate(value: &str) -> Option<(String, Option<String>)> {
+        let text = format!("a {{ width: {value} }}");
+        let evaluation = evaluate_at(&parse(&text), 11)?;
+        assert_eq!(evaluation.span.text(&text), value);
+        Some((evaluation.kind, evaluation.value))
+    }
+
+    fn simplified(value: &str) -> String {
+        evaluate(value).and_then(|(_, value)| value).unwrap()
+    }
+
+    #[test]
+    fn unit_errors() {
+        assert_eq!(error("calc(10px + 2s)"), "Cannot add length and time");
+        assert_eq!(error("calc(10px - 2s)"), "Cannot subtract time from length");
+        assert_eq!(
+            error("calc(1px + 2)"),
+            "Cannot add length and number; numbers must have a unit, such as `0px`"
+        );
+        assert_eq!(
+            error("max(1px, 2s)"),
+            "The arguments of `max()` have incompatible types: length and time"
+        );
+        assert_eq!(
+            error("calc(1px * 2px)"),
+            "This expression resolves to `length²`, which is not a valid CSS type"
+        );
+        assert_eq!(
+            error("calc(1px * 2s)"),
+            "This expression resolves to `length·time`, which is not a valid CSS type"
+        );
+        assert_eq!(errors("calc(10px + 2s)")[0].0, "10px + 2s");
+    }
+
+    #[test]
+    fn syntax_errors() {
+        assert_eq!(
+            errors("calc(10px+2px)"),
+            [("+".to_string(), "`+` must be surrounded by whitespace".to_string())]
+        );
+        assert_eq!(error("calc(1px -2px)"), "`-` must be surrounded by whitespace");
+        assert_eq!(error("clamp(1px, 2px)"), "`clamp()` takes 3 arguments, found 2");
+        assert_eq!(
+            errors("calc(1px 2px)"),
+            [("2px".to_string(), "Expected an operator".to_string())]
+        );
+        assert_eq!(error("calc()"), "Expected a value");
+        // Errors make the value unknown.
+        assert_eq!(evaluate("calc(10px + 2s)"), None);
+    }
+
+    #[test]
+    fn valid_expressions() {
+        for value in [
+            "calc(10px + 5%)",
+            "calc(100% - 2 * 8px)",
+            "min(1px, 2em)",
+            "calc(var(--a) + 1px)",
+            "clamp(1rem, 2.5vw, 2rem)",
+            "calc(1px / 2px)",
+        ] {
+            assert_eq!(errors(value), [], "{value}");
+        }
+    }
+
+    #[test]
+    fn simplification() {
+        assert_eq!(simplified("calc(1in + 4px)"), "100px");
+        assert_eq!(simplified("calc(2 * 3px)"), "6px");
+        assert_eq!(simplified("calc(2 * (1px + 2px))"), "6px");
+        assert_eq!(simplified("calc(10px / 3)"), "3.3333px");
+        assert_eq!(simplified("calc(1s + 500ms)"), "1.5s");
+        assert_eq!(simplified("calc(1turn + 90deg)"), "450deg");
+        assert_eq!(simplified("CALC(1PX + 2PX)"), "3px");
+        assert_eq!(simplified("-webkit-calc(1px + 1px)"), "2px");
+        // Relative units and percentages are kept apart.
+        assert_eq!(simplified("calc(10px + 5%)"), "calc(5% + 10px)");
+        assert_eq!(simplified("calc(1px + 1em)"), "calc(1em + 1px)");
+        assert_eq!(simplified("calc(100% - 2 * 8px)"), "calc(100% - 16px)");
+    }
+
+    #[test]
+    fn types() {
+        assert_eq!(
+            evaluate("calc(1px / 2px)"),
+            Some(("number".to_string(), Some("0.5".to_string())))
+        );
+        // `min()` of values in different units has no static value.
+        assert_eq!(evaluate("min(1px, 2em)"), Some(("length".to_string(), None)));
+        // Nor a type, with `var()`.
+        assert_eq!(evaluate("calc(var(--a) + 1px)"), None);
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(unit("PX"), Some(("length", Some((1.0, "px")))));
+        assert_eq!(unit("em"), Some(("length", None)));
+        assert_eq!(unit("ms"), Some(("time", Some((0.001, "s")))));
+        assert_eq!(unit("x"), Some(("resolution", Some((1.0, "dppx")))));
+        assert_eq!(unit("furlong"), None);
+        assert!(UNITS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+}
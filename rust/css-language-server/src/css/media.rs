@@ -0,0 +1,1228 @@
+use super::{
+    Span, math,
+    syntax::{AtRule, Stylesheet},
+    token::{Token, TokenKind},
+};
+
+/// A problem in the prelude of an `@media` or `@container` rule.
+#[derive(Debug, Clone)]
+pub struct QueryIssue {
+    pub span: Span,
+    pub message: String,
+    pub kind: IssueKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueKind {
+    /// A syntax error or a value of the wrong type, which makes browsers
+    /// drop the query.
+    Invalid,
+    /// An unknown feature or a deprecated media type, which never matches.
+    Unknown,
+    /// A valid query that can never match, such as `(min-width: 900px) and
+    /// (max-width: 600px)`.
+    Contradiction,
+}
+
+/// The type of the values a feature is compared with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ValueType {
+    Length,
+    Ratio,
+    Resolution,
+    Integer,
+    /// `0` or `1`, as for `grid`.
+    Boolean,
+    Keywords(&'static [&'static str]),
+}
+
+impl ValueType {
+    /// Whether the feature can be compared with `min-`, `max-` and `<`.
+    fn is_range(self) -> bool {
+        matches!(
+            self,
+            ValueType::Length | ValueType::Ratio | ValueType::Resolution | ValueType::Integer
+        )
+    }
+
+    fn describe(self) -> String {
+        match self {
+            ValueType::Length => "a length".to_string(),
+            ValueType::Ratio => "a ratio such as `16/9`".to_string(),
+            ValueType::Resolution => "a resolution".to_string(),
+            ValueType::Integer => "an integer".to_string(),
+            ValueType::Boolean => "`0` or `1`".to_string(),
+            ValueType::Keywords(keywords) => {
+                let keywords: Vec<String> = keywords.iter().map(|k| format!("`{k}`")).collect();
+                format!("one of {}", keywords.join(", "))
+            }
+        }
+    }
+}
+
+/// Sorted. The features of Media Queries Level 5.
+const MEDIA_FEATURES: &[(&str, ValueType)] = &[
+    ("any-hover", ValueType::Keywords(&["none", "hover"])),
+    ("any-pointer", ValueType::Keywords(&["none", "coarse", "fine"])),
+    ("aspect-ratio", ValueType::Ratio),
+    ("color", ValueType::Integer),
+    ("color-gamut", ValueType::Keywords(&["srgb", "p3", "rec2020"])),
+    ("color-index", ValueType::Integer),
+    ("device-aspect-ratio", ValueType::Ratio),
+    ("device-height", ValueType::Length),
+    ("device-posture", ValueType::Keywords(&["continuous", "folded"])),
+    ("device-width", ValueType::Length),
+    (
+        "display-mode",
+        ValueType::Keywords(&[
+            "fullscreen",
+            "standalone",
+            "minimal-ui",
+            "browser",
+            "picture-in-picture",
+            "window-controls-overlay",
+        ]),
+    ),
+    ("dynamic-range", ValueType::Keywords(&["standard", "high"])),
+    ("forced-colors", ValueType::Keywords(&["none", "active"])),
+    ("grid", ValueType::Boolean),
+    ("height", ValueType::Length),
+    ("hover", ValueType::Keywords(&["none", "hover"])),
+    ("inverted-colors", ValueType::Keywords(&["none", "inverted"])),
+    ("monochrome", ValueType::Integer),
+    ("orientation", ValueType::Keywords(&["portrait", "landscape"])),
+    ("overflow-block", ValueType::Keywords(&["none", "scroll", "paged"])),
+    ("overflow-inline", ValueType::Keywords(&["none", "scroll"])),
+    ("pointer", ValueType::Keywords(&["none", "coarse", "fine"])),
+    ("prefers-color-scheme", ValueType::Keywords(&["light", "dark"])),
+    (
+        "prefers-contrast",
+        ValueType::Keywords(&["no-preference", "less", "more", "custom"]),
+    ),
+    (
+        "prefers-reduced-data",
+        ValueType::Keywords(&["no-preference", "reduce"]),
+    ),
+    (
+        "prefers-reduced-motion",
+        ValueType::Keywords(&["no-preference", "reduce"]),
+    ),
+    (
+        "prefers-reduced-transparency",
+        ValueType::Keywords(&["no-preference", "reduce"]),
+    ),
+    ("resolution", ValueType::Resolution),
+    ("scan", ValueType::Keywords(&["interlace", "progressive"])),
+    ("scripting", ValueType::Keywords(&["none", "initial-only", "enabled"])),
+    ("update", ValueType::Keywords(&["none", "slow", "fast"])),
+    ("video-dynamic-range", ValueType::Keywords(&["standard", "high"])),
+    ("width", ValueType::Length),
+];
+
+/// Sorted. The size features of container queries.
+const CONTAINER_FEATURES: &[(&str, ValueType)] = &[
+    ("aspect-ratio", ValueType::Ratio),
+    ("block-size", ValueType::Length),
+    ("height", ValueType::Length),
+    ("inline-size", ValueType::Length),
+    ("orientation", ValueType::Keywords(&["portrait", "landscape"])),
+    ("width", ValueType::Length),
+];
+
+const MEDIA_TYPES: &[&str] = &["all", "print", "screen"];
+
+/// Media types of CSS 2 that match nothing since Media Queries Level 4.
+const DEPRECATED_MEDIA_TYPES: &[&str] = &[
+    "aural",
+    "braille",
+    "embossed",
+    "handheld",
+    "projection",
+    "speech",
+    "tty",
+    "tv",
+];
+
+/// The unit `em` and `rem` are compared in. In queries both are relative to
+/// the initial font size rather than to any element.
+const EM: &str = "em";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+impl Op {
+    /// The operator with its sides swapped: `a < b` is `b > a`.
+    fn flip(self) -> Self {
+        match self {
+            Op::Lt => Op::Gt,
+            Op::Le => Op::Ge,
+            Op::Eq => Op::Eq,
+            Op::Ge => Op::Le,
+            Op::Gt => Op::Lt,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Number(f64),
+    /// A number with its lowercased unit, including `%`.
+    Dimension(f64, String),
+    Ratio(f64, f64),
+    /// Lowercased.
+    Ident(String),
+    /// A function such as `calc()`, which is not checked.
+    Other,
+}
+
+#[derive(Debug, Clone)]
+struct Operand {
+    span: Span,
+    value: Value,
+}
+
+/// A test of one feature, such as `(min-width: 600px)`, `(hover)` or
+/// `(400px < width < 800px)`.
+#[derive(Debug, Clone)]
+struct Feature {
+    /// Lowercased, without `min-` or `max-`.
+    name: String,
+    ty: ValueType,
+    /// How the feature compares with values; empty for a boolean test.
+    /// `(min-width: 600px)` is `width >= 600px`.
+    tests: Vec<(Op, Operand)>,
+}
+
+#[derive(Debug, Clone)]
+enum Condition {
+    Feature(Feature),
+    Not(Box<Condition>),
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    /// A function such as `style()`, or an unknown feature, which is not
+    /// evaluated.
+    Other(Span),
+}
+
+#[derive(Debug, Clone)]
+struct Query {
+    span: Span,
+    /// `not` before a media type, which negates the whole query.
+    not: bool,
+    /// Lowercased.
+    media_type: Option<String>,
+    container_name: Option<String>,
+    condition: Option<Condition>,
+}
+
+/// Syntax errors, invalid values, unknown features and contradictions in the
+/// `@media` and `@container` rules of the stylesheet.
+pub fn check(sheet: &Stylesheet) -> Vec<QueryIssue> {
+    let mut issues = vec![];
+    for rule in sheet.at_rules() {
+        let Some((queries, rule_issues)) = parse(sheet, rule) else {
+            continue;
+        };
+        issues.extend(rule_issues);
+        // A negated contradiction always matches, which is not worth a
+        // warning.
+        for query in queries.iter().filter(|query| !query.not) {
+            contradictions(&sheet.text, query, &mut issues);
+        }
+    }
+    issues
+}
+
+/// A description of when the `@media` or `@container` rule whose prelude
+/// is at `offset` applies, such as "applies when width is between 600px and
+/// 900px", and the span of the prelude. Invalid preludes have none.
+pub fn explain_at(sheet: &Stylesheet, offset: usize) -> Option<(Span, String)> {
+    let rule = sheet
+        .at_rules()
+        .into_iter()
+        .find(|rule| rule.name_span.start <= offset && offset <= rule.prelude.end)?;
+    let (queries, issues) = parse(sheet, rule)?;
+    if issues.iter().any(|issue| issue.kind == IssueKind::Invalid) {
+        return None;
+    }
+    let span = match queries.as_slice() {
+        [first, .., last] => Span::new(first.span.start, last.span.end),
+        [query] => query.span,
+        [] => rule.prelude,
+    };
+    let text = sheet.text.as_str();
+    let explanation = match queries.as_slice() {
+        [] => "Always applies".to_string(),
+        [query] => capitalize(&explain_query(text, query)),
+        queries => {
+            let mut out = "Applies when any of these queries matches:\n".to_string();
+            for query in queries {
+                out.push_str(&format!(
+                    "\n- `{}` {}",
+                    query.span.text(text),
+                    explain_query(text, query)
+                ));
+            }
+            out
+        }
+    };
+    Some((span, explanation))
+}
+
+/// The queries of an `@media` or `@container` rule, and the issues in them.
+fn parse(sheet: &Stylesheet, rule: &AtRule) -> Option<(Vec<Query>, Vec<QueryIssue>)> {
+    let container = match rule.name.as_str() {
+        "media" => false,
+        "container" => true,
+        _ => return None,
+    };
+    let tokens: Vec<Token> = sheet
+        .tokens_in(rule.prelude)
+        .iter()
+        .copied()
+        .filter(|t| !t.is_trivia())
+        .collect();
+    let mut parser = Parser {
+        text: &sheet.text,
+        end: tokens.len(),
+        tokens,
+        pos: 0,
+        container,
+        prelude_start: rule.prelude.start,
+        issues: vec![],
+    };
+    let queries = parser.query_list();
+    Some((queries, parser.issues))
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<Token>,
+    /// The next token to read.
+    pos: usize,
+    /// The end of the tokens to read: the end of the current query or
+    /// parentheses.
+    end: usize,
+    container: bool,
+    /// Where a missing first token would be.
+    prelude_start: usize,
+    issues: Vec<QueryIssue>,
+}
+
+impl Parser<'_> {
+    fn error(&mut self, span: Span, message: impl Into<String>) {
+        self.issue(span, IssueKind::Invalid, message);
+    }
+
+    fn issue(&mut self, span: Span, kind: IssueKind, message: impl Into<String>) {
+        self.issues.push(QueryIssue {
+            span,
+            message: message.into(),
+            kind,
+        });
+    }
+
+    fn text(&self, token: Token) -> &str {
+        token.text(self.text)
+    }
+
+    fn peek(&self) -> Option<Token> {
+        self.tokens[..self.end].get(self.pos).copied()
+    }
+
+    /// The lowercased text of the next token, if it is an identifier.
+    fn peek_ident(&self) -> Option<String> {
+        self.peek()
+            .filter(|t| t.kind == TokenKind::Ident)
+            .map(|t| self.text(t).to_ascii_lowercase())
+    }
+
+    /// Where a missing token would be.
+    fn here(&self) -> Span {
+        let offset = match self.pos.min(self.tokens.len()).checked_sub(1) {
+            Some(last) => self.tokens[last].span.end,
+            None => self.prelude_start,
+        };
+        Span::new(offset, offset)
+    }
+
+    /// The span of the tokens from `start` to `end`.
+    fn span(&self, start: usize, end: usize) -> Span {
+        match (
+            self.tokens.get(start),
+            end.checked_sub(1).and_then(|last| self.tokens.get(last)),
+        ) {
+            (Some(first), Some(last)) if start < end => Span::new(first.span.start, last.span.end),
+            _ => self.here(),
+        }
+    }
+
+    /// The index of the parenthesis closing the one at `open`, or the end of
+    /// the tokens if it is missing.
+    fn close(&self, open: usize) -> usize {
+        let mut depth = 0;
+        for (i, token) in self.tokens.iter().enumerate().skip(open) {
+            match token.kind {
+                TokenKind::LeftParen | TokenKind::Function => depth += 1,
+                TokenKind::RightParen => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return i;
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.tokens.len()
+    }
+
+    fn query_list(&mut self) -> Vec<Query> {
+        let mut queries = vec![];
+        if self.tokens.is_empty() {
+            if self.container {
+                self.error(self.here(), "Expected a container query");
+            }
+            return queries;
+        }
+        let mut start = 0;
+        loop {
+            // The next comma outside of parentheses.
+            let mut end = start;
+            while end < self.tokens.len() && self.tokens[end].kind != TokenKind::Comma {
+                end = match self.tokens[end].kind {
+                    TokenKind::LeftParen | TokenKind::Function => self.close(end) + 1,
+                    _ => end + 1,
+                };
+            }
+            let end = end.min(self.tokens.len());
+            self.pos = start;
+            self.end = end;
+            match start < end {
+                true => queries.push(self.query()),
+                false => self.error(self.here(), "Expected a query"),
+            }
+            if end >= self.tokens.len() {
+                break;
+            }
+            start = end + 1;
+            if start == self.tokens.len() {
+                self.pos = start;
+                self.error(self.here(), "Expected a query after `,`");
+                break;
+            }
+        }
+        queries
+    }
+
+    /// Parses the query from `pos` to `end`.
+    fn query(&mut self) -> Query {
+        let span = self.span(self.pos, self.end);
+        let mut query = Query {
+            span,
+            not: false,
+            media_type: None,
+            container_name: None,
+            condition: None,
+        };
+        if self.container {
+            if let Some(name) = self.peek_ident()
+                && !matches!(name.as_str(), "not" | "and" | "or" | "none")
+            {
+                query.container_name = self.peek().map(|t| self.text(t).to_string());
+                self.pos += 1;
+            }
+            if self.pos < self.end || query.container_name.is_none() {
+                query.condition = Some(self.condition(true));
+            }
+        } else {
+            let second = self.tokens[..self.end].get(self.pos + 1);
+            if let Some(modifier) = self.peek_ident()
+                && matches!(modifier.as_str(), "not" | "only")
+                && second.is_some_and(|t| t.kind == TokenKind::Ident)
+            {
+                query.not = modifier == "not";
+                self.pos += 1;
+            }
+            match self.peek_ident() {
+                Some(media_type) if media_type != "not" => {
+                    let token = self.tokens[self.pos];
+                    self.pos += 1;
+                    self.media_type(token, &media_type);
+                    query.media_type = Some(media_type);
+                    if let Some(token) = self.peek() {
+                        match self.peek_ident().as_deref() {
+                            Some("and") => {
+                                self.pos += 1;
+                                query.condition = Some(self.condition(false));
+                            }
+                            _ if token.kind == TokenKind::Function
+                                && token.name(self.text).eq_ignore_ascii_case("and") =>
+                            {
+                                self.error(token.span, "Expected a space before `(` in `and(`");
+                                self.pos = self.end;
+                            }
+                            _ => self.error(token.span, "Expected `and`"),
+                        }
+                    }
+                }
+                _ => query.condition = Some(self.condition(true)),
+            }
+        }
+        if self.pos < self.end {
+            let span = self.span(self.pos, self.end);
+            self.error(span, format!("Unexpected `{}`", span.text(self.text)));
+        }
+        query
+    }
+
+    fn media_type(&mut self, token: Token, media_type: &str) {
+        if MEDIA_TYPES.contains(&media_type) {
+            return;
+        }
+        if DEPRECATED_MEDIA_TYPES.contains(&media_type) {
+            self.issue(
+                token.span,
+                IssueKind::Unknown,
+                format!("The media type `{media_type}` is deprecated and never matches"),
+            );
+        } else if matches!(media_type, "and" | "or" | "only" | "layer") {
+            self.error(token.span, format!("`{media_type}` is not a media type"));
+        } else {
+            self.issue(
+                token.span,
+                IssueKind::Unknown,
+                format!("Unknown media type `{media_type}`"),
+            );
+        }
+    }
+
+    /// A condition of features combined with `not`, `and` and `or`. After a
+    /// media type, `or` is not allowed.
+    fn condition(&mut self, allow_or: bool) -> Condition {
+        if self.peek_ident().as_deref() == Some("not") {
+            self.pos += 1;
+            return Condition::Not(Box::new(self.in_parens()));
+        }
+        let mut items = vec![self.in_parens()];
+        let mut operator: Option<String> = None;
+        while let Some(token) = self.peek() {
+            let word = match token.kind {
+                TokenKind::Ident => self.text(token).to_ascii_lowercase(),
+                TokenKind::Function if matches!(token.name(self.text).to_ascii_lowercase().as_str(), "and" | "or") => {
+                    self.error(
+                        token.span,
+                        format!("Expected a space before `(` in `{}`", self.text(token)),
+                    );
+                    self.pos = self.close(self.pos) + 1;
+                    continue;
+                }
+                _ => break,
+            };
+            if !matches!(word.as_str(), "and" | "or") {
+                break;
+            }
+            if word == "or" && !allow_or {
+                self.error(
+                    token.span,
+                    "`or` cannot follow a media type; wrap the conditions in parentheses",
+                );
+            } else if operator.as_ref().is_some_and(|operator| *operator != word) {
+                self.error(token.span, "`and` and `or` cannot be mixed without parentheses");
+            }
+            operator.get_or_insert(word);
+            self.pos += 1;
+            items.push(self.in_parens());
+        }
+        match operator.as_deref() {
+            None => items.pop().unwrap_or(Condition::Other(self.here())),
+            Some("and") => Condition::And(items),
+            _ => Condition::Or(items),
+        }
+    }
+
+    /// A feature or condition in parentheses, or a function such as
+    /// `style()`.
+    fn in_parens(&mut self) -> Condition {
+        let Some(token) = self.peek() else {
+            let here = self.here();
+            self.error(here, "Expected `(`");
+            return Condition::Other(here);
+        };
+        match token.kind {
+            TokenKind::LeftParen | TokenKind::Function => {
+                let open = self.pos;
+                let close = self.close(open).min(self.end);
+                let span = match self.tokens.get(close) {
+                    Some(paren) if close < self.end => Span::new(token.span.start, paren.span.end),
+                    _ => {
+                        self.error(token.span, "Expected `)`");
+                        self.pos = close + 1;
+                        return Condition::Other(self.span(open, close));
+                    }
+                };
+                self.pos = close + 1;
+                if token.kind == TokenKind::Function {
+                    return Condition::Other(span);
+                }
+                let inner = self.tokens.get(open + 1).filter(|_| open + 1 < close);
+                let nested = inner.is_some_and(|t| {
+                    matches!(t.kind, TokenKind::LeftParen | TokenKind::Function)
+                        || (t.kind == TokenKind::Ident && self.text(*t).eq_ignore_ascii_case("not"))
+                });
+                if !nested {
+                    return self.feature(open + 1, close, span);
+                }
+                let (outer_pos, outer_end) = (self.pos, self.end);
+                self.pos = open + 1;
+                self.end = close;
+                let condition = self.condition(true);
+                if self.pos < self.end {
+                    let rest = self.span(self.pos, self.end);
+                    self.error(rest, format!("Unexpected `{}`", rest.text(self.text)));
+                }
+                self.pos = outer_pos;
+                self.end = outer_end;
+                condition
+            }
+            _ => {
+                self.pos += 1;
+                self.error(token.span, "Expected `(`");
+                Condition::Other(token.span)
+            }
+        }
+    }
+
+    /// The feature test in the tokens from `start` to `end`, inside
+    /// parentheses spanning `span`.
+    fn feature(&mut self, start: usize, end: usize, span: Span) -> Condition {
+        let Some(&first) = self.tokens.get(start).filter(|_| start < end) else {
+            self.error(span, "Expected a feature");
+            return Condition::Other(span);
+        };
+        if first.kind == TokenKind::Ident {
+            let name = self.text(first).to_ascii_lowercase();
+            if start + 1 == end {
+                return self.plain(first.span, &name, None, span);
+            }
+            if self.tokens[start + 1].kind == TokenKind::Colon {
+                let value = self.operand(start + 2, end);
+                return self.plain(first.span, &name, Some(value), span);
+            }
+        }
+
+        // A range: operands separated by `<`, `<=`, `>`, `>=` or `=`.
+        let mut operands = vec![];
+        let mut operators = vec![];
+        let mut operand_start = start;
+        let mut i = start;
+        while i < end {
+            let token = self.tokens[i];
+            let text = self.text(token);
+            if token.kind != TokenKind::Delim || !matches!(text, "<" | ">" | "=") {
+                i += 1;
+                continue;
+            }
+            let next = self.tokens.get(i + 1).filter(|next| {
+                i + 1 < end && next.span.start == token.span.end && text != "=" && self.text(**next) == "="
+            });
+            let op = match (text, next.is_some()) {
+                ("<", false) => Op::Lt,
+                ("<", true) => Op::Le,
+                (">", false) => Op::Gt,
+                (">", true) => Op::Ge,
+                _ => Op::Eq,
+            };
+            let op_end = if next.is_some() { i + 2 } else { i + 1 };
+            operands.push((operand_start, i));
+            operators.push((op, self.span(i, op_end)));
+            i = op_end;
+            operand_start = i;
+        }
+        operands.push((operand_start, end));
+        if operators.is_empty() {
+            self.error(span, "Expected `:` or a comparison such as `width >= 600px`");
+            return Condition::Other(span);
+        }
+        if operators.len() > 2 {
+            self.error(operators[2].1, "A range has at most two comparisons");
+            return Condition::Other(span);
+        }
+
+        // The name is the middle of three operands, or else the side that is
+        // an identifier.
+        let is_name = |parser: &Self, (start, end): (usize, usize)| {
+            end == start + 1 && parser.tokens[start].kind == TokenKind::Ident
+        };
+        let name_index = match operands.len() {
+            3 => 1,
+            _ if is_name(self, operands[0]) => 0,
+            _ => 1,
+        };
+        let (name_start, name_end) = operands[name_index];
+        if !is_name(self, operands[name_index]) {
+            let name_span = self.span(name_start, name_end);
+            self.error(name_span, "Expected a feature name");
+            return Condition::Other(span);
+        }
+        let name_token = self.tokens[name_start];
+        let name = self.text(name_token).to_ascii_lowercase();
+        let mut tests = vec![];
+        if operands.len() == 3 {
+            let (first, second) = (operators[0].0, operators[1].0);
+            let less = |op| matches!(op, Op::Lt | Op::Le);
+            let greater = |op| matches!(op, Op::Gt | Op::Ge);
+            if !(less(first) && less(second) || greater(first) && greater(second)) {
+                let span = Span::new(operators[0].1.start, operators[1].1.end);
+                self.error(span, "Both comparisons of a range must point the same way");
+                return Condition::Other(span);
+            }
+            tests.push((first.flip(), self.operand(operands[0].0, operands[0].1)));
+            tests.push((second, self.operand(operands[2].0, operands[2].1)));
+        } else if name_index == 0 {
+            tests.push((operators[0].0, self.operand(operands[1].0, operands[1].1)));
+        } else {
+            tests.push((operators[0].0.flip(), self.operand(operands[0].0, operands[0].1)));
+        }
+
+        if name.starts_with('-') {
+            return Condition::Other(span);
+        }
+        let Some(ty) = self.feature_type(&name) else {
+            self.unknown_feature(name_token.span, &name);
+            return Condition::Other(span);
+        };
+        if !ty.is_range() {
+            self.error(
+                name_token.span,
+                format!("`{name}` is not a range feature; compare it with `:` instead"),
+            );
+            return Condition::Other(span);
+        }
+        self.feature_tests(name, ty, tests, span)
+    }
+
+    /// A boolean test such as `(hover)`, or a plain test such as
+    /// `(min-width: 600px)`.
+    fn plain(&mut self, name_span: Span, name: &str, value: Option<Operand>, span: Span) -> Condition {
+        // Vendor prefixed features, such as `-webkit-min-device-pixel-ratio`,
+        // are not checked.
+        if name.starts_with('-') {
+            return Condition::Other(span);
+        }
+        let prefixed = name
+            .strip_prefix("min-")
+            .map(|name| (Op::Ge, name))
+            .or_else(|| name.strip_prefix("max-").map(|name| (Op::Le, name)));
+        let (op, feature) = match prefixed {
+            Some((op, feature)) if self.feature_type(name).is_none() => (op, feature),
+            _ => (Op::Eq, name),
+        };
+        let Some(ty) = self.feature_type(feature) else {
+            self.unknown_feature(name_span, name);
+            return Condition::Other(span);
+        };
+        if op != Op::Eq && !ty.is_range() {
+            self.error(
+                name_span,
+                format!("`{feature}` is not a range feature, so `{name}` does not exist"),
+            );
+            return Condition::Other(span);
+        }
+        let Some(value) = value else {
+            if op != Op::Eq {
+                self.error(name_span, format!("`{name}` needs a value"));
+                return Condition::Other(span);
+            }
+            return Condition::Feature(Feature {
+                name: feature.to_string(),
+                ty,
+                tests: vec![],
+            });
+        };
+        self.feature_tests(feature.to_string(), ty, vec![(op, value)], span)
+    }
+
+    fn feature_tests(&mut self, name: String, ty: ValueType, tests: Vec<(Op, Operand)>, span: Span) -> Condition {
+        let mut valid = true;
+        for (_, operand) in &tests {
+            if !matches_type(&operand.value, ty) {
+                self.error(operand.span, format!("Expected {} for `{name}`", ty.describe()));
+                valid = false;
+            }
+        }
+        match valid {
+            true => Condition::Feature(Feature { name, ty, tests }),
+            false => Condition::Other(span),
+        }
+    }
+
+    fn feature_type(&self, name: &str) -> Option<ValueType> {
+        let features = if self.container {
+            CONTAINER_FEATURES
+        } else {
+            MEDIA_FEATURES
+        };
+        let index = features.binary_search_by(|(feature, _)| feature.cmp(&name)).ok()?;
+        Some(features[index].1)
+    }
+
+    fn unknown_feature(&mut self, span: Span, name: &str) {
+        let kind = if self.container { "container" } else { "media" };
+        self.issue(span, IssueKind::Unknown, format!("Unknown {kind} feature `{name}`"));
+    }
+
+    /// The value in the tokens from `start` to `end`.
+    fn operand(&mut self, start: usize, end: usize) -> Operand {
+        let span = self.span(start, end);
+        let tokens = &self.tokens[start.min(end)..end];
+        let number = |token: &Token, text: &str| token.text(text).trim_start_matches('+').parse::<f64>().ok();
+        let value = match tokens {
+            [token] => match token.kind {
+                TokenKind::Number => number(token, self.text).map(Value::Number),
+                TokenKind::Percentage => {
+                    let text = self.text(*token);
+                    text[..text.len() - 1]
+                        .parse()
+                        .ok()
+                        .map(|n| Value::Dimension(n, "%".to_string()))
+                }
+                TokenKind::Dimension { unit } => {
+                    let text = self.text(*token);
+                    let n = text[..unit].trim_start_matches('+').parse().ok();
+                    n.map(|n| Value::Dimension(n, text[unit..].to_ascii_lowercase()))
+                }
+                TokenKind::Ident => Some(Value::Ident(self.text(*token).to_ascii_lowercase())),
+                _ => None,
+            },
+            [a, slash, b]
+                if a.kind == TokenKind::Number
+                    && b.kind == TokenKind::Number
+                    && slash.kind == TokenKind::Delim
+                    && slash.text(self.text) == "/" =>
+            {
+                number(a, self.text)
+                    .zip(number(b, self.text))
+                    .map(|(a, b)| Value::Ratio(a, b))
+            }
+            [function, ..] if function.kind == TokenKind::Function && self.close(start) + 1 == end => {
+                Some(Value::Other)
+            }
+            _ => None,
+        };
+        let value = value.unwrap_or_else(|| {
+            let message = match start >= end {
+                true => "Expected a value".to_string(),
+                false => format!("Expected a value instead of `{}`", span.text(self.text)),
+            };
+            self.error(span, message);
+            Value::Other
+        });
+        Operand { span, value }
+    }
+}
+
+fn matches_type(value: &Value, ty: ValueType) -> bool {
+    match (value, ty) {
+        (Value::Other, _) => true,
+        (Value::Number(n), ValueType::Length) => *n == 0.0,
+        (Value::Dimension(_, unit), ValueType::Length) => math::unit(unit).is_some_and(|(base, _)| base == "length"),
+        (Value::Number(n), ValueType::Ratio) => *n >= 0.0,
+        (Value::Ratio(a, b), ValueType::Ratio) => *a >= 0.0 && *b >= 0.0,
+        (Value::Dimension(_, unit), ValueType::Resolution) => {
+            math::unit(unit).is_some_and(|(base, _)| base == "resolution")
+        }
+        (Value::Ident(ident), ValueType::Resolution) => ident == "infinite",
+        (Value::Number(n), ValueType::Integer) => n.fract() == 0.0 && *n >= 0.0,
+        (Value::Number(n), ValueType::Boolean) => *n == 0.0 || *n == 1.0,
+        (Value::Ident(ident), ValueType::Keywords(keywords)) => keywords.contains(&ident.as_str()),
+        _ => false,
+    }
+}
+
+/// A value as a number in a canonical unit, so that values in the same unit
+/// can be compared. Relative lengths other than `em` and `rem` cannot be.
+fn magnitude(value: &Value) -> Option<(f64, &'static str)> {
+    match value {
+        Value::Number(n) => Some((*n, "")),
+        Value::Ratio(a, b) => Some((a / b, "")),
+        Value::Dimension(n, unit) if unit == "em" || unit == "rem" => Some((*n, EM)),
+        Value::Dimension(n, unit) => {
+            let (_, conversion) = math::unit(unit)?;
+            let (factor, canonical) = conversion?;
+            Some((n * factor, canonical))
+        }
+        Value::Ident(ident) if ident == "infinite" => Some((f64::INFINITY, "dppx")),
+        _ => None,
+    }
+}
+
+/// A lower or upper bound of a feature.
+#[derive(Debug, Clone, Copy)]
+struct Bound {
+    value: f64,
+    inclusive: bool,
+    span: Span,
+}
+
+/// The tightest bounds the tests put on a feature, in one canonical unit.
+/// Tests in other units are left out.
+#[derive(Debug, Default)]
+struct Interval {
+    unit: Option<&'static str>,
+    lower: Option<Bound>,
+    upper: Option<Bound>,
+}
+
+impl Interval {
+    fn add(&mut self, op: Op, operand: &Operand) {
+        let Some((value, unit)) = magnitude(&operand.value) else {
+            return;
+        };
+        if *self.unit.get_or_insert(unit) != unit {
+            return;
+        }
+        let bound = |inclusive| Bound {
+            value,
+            inclusive,
+            span: operand.span,
+        };
+        let tighter_lower = |current: Option<Bound>, new: Bound| match current {
+            Some(current) if current.value > new.value || (current.value == new.value && !current.inclusive) => current,
+            _ => new,
+        };
+        let tighter_upper = |current: Option<Bound>, new: Bound| match current {
+            Some(current) if current.value < new.value || (current.value == new.value && !current.inclusive) => current,
+            _ => new,
+        };
+        if matches!(op, Op::Gt | Op::Ge | Op::Eq) {
+            self.lower = Some(tighter_lower(self.lower, bound(op != Op::Gt)));
+        }
+        if matches!(op, Op::Lt | Op::Le | Op::Eq) {
+            self.upper = Some(tighter_upper(self.upper, bound(op != Op::Lt)));
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        match (self.lower, self.upper) {
+            (Some(lower), Some(upper)) => {
+                lower.value > upper.value || (lower.value == upper.value && !(lower.inclusive && upper.inclusive))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The features that must all hold for a condition to, such as both sides of
+/// an `and`.
+fn conjunction<'a>(condition: &'a Condition, out: &mut Vec<&'a Feature>) {
+    match condition {
+        Condition::Feature(feature) => out.push(feature),
+        Condition::And(items) => {
+            for item in items {
+                conjunction(item, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Reports each feature that the condition of a query requires to lie in an
+/// empty range. The `not` of the query is left to the caller.
+fn contradictions(text: &str, query: &Query, issues: &mut Vec<QueryIssue>) {
+    let Some(condition) = &query.condition else { return };
+    let mut features = vec![];
+    conjunction(condition, &mut features);
+    let mut names: Vec<&str> = vec![];
+    for feature in &features {
+        let name = feature.name.as_str();
+        if names.contains(&name) || !feature.ty.is_range() {
+            continue;
+        }
+        names.push(name);
+        let mut interval = Interval::default();
+        for feature in features.iter().filter(|feature| feature.name == name) {
+            for (op, operand) in &feature.tests {
+                interval.add(*op, operand);
+            }
+        }
+        if let (true, Some(lower), Some(upper)) = (interval.is_empty(), interval.lower, interval.upper) {
+            let at_least = if lower.inclusive { "at least" } else { "more than" };
+            let at_most = if upper.inclusive { "at most" } else { "less than" };
+            issues.push(QueryIssue {
+                span: query.span,
+                message: format!(
+                    "This query never matches: `{name}` cannot be {at_least} {} and {at_most} {}",
+                    lower.span.text(text),
+                    upper.span.text(text),
+                ),
+                kind: IssueKind::Contradiction,
+            });
+        }
+    }
+}
+
+/// When a query applies, such as "applies to `screen` media when width is at
+/// least 600px".
+fn explain_query(text: &str, query: &Query) -> String {
+    let mut contradicted = vec![];
+    contradictions(text, query, &mut contradicted);
+    if !contradicted.is_empty() {
+        return match query.not {
+            true => "always applies".to_string(),
+            false => "never applies".to_string(),
+        };
+    }
+    let condition = query.condition.as_ref().map(|condition| explain(text, condition));
+    if let Some(name) = &query.container_name {
+        return match condition {
+            Some(condition) => format!("applies when {condition} in the nearest `{name}` container"),
+            None => format!("applies inside a `{name}` container"),
+        };
+    }
+    let media_type = query.media_type.as_deref().unwrap_or("all");
+    match (query.not, media_type, condition) {
+        (false, "all", Some(condition)) => format!("applies when {condition}"),
+        (false, "all", None) => "always applies".to_string(),
+        (false, _, Some(condition)) => format!("applies to `{media_type}` media when {condition}"),
+        (false, _, None) => format!("applies to `{media_type}` media"),
+        (true, "all", None) => "never applies".to_string(),
+        (true, "all", Some(condition)) => format!("applies unless {condition}"),
+        (true, _, Some(condition)) => format!("applies unless the media is `{media_type}` and {condition}"),
+        (true, _, None) => format!("applies to all media but `{media_type}`"),
+    }
+}
+
+fn explain(text: &str, condition: &Condition) -> String {
+    match condition {
+        Condition::Feature(feature) => explain_feature(text, feature),
+        Condition::Not(inner) => format!("not ({})", explain(text, inner)),
+        Condition::And(items) | Condition::Or(items) => {
+            let and = matches!(condition, Condition::And(_));
+            // `(min-width: 600px) and (max-width: 900px)` reads as one range.
+            let mut merged: Vec<Condition> = vec![];
+            for item in items {
+                if let Condition::Feature(feature) = item
+                    && and
+                    && feature.ty.is_range()
+                    && let Some(Condition::Feature(previous)) = merged.iter_mut().find(
+                        |previous| matches!(previous, Condition::Feature(previous) if previous.name == feature.name),
+                    )
+                {
+                    previous.tests.extend(feature.tests.iter().cloned());
+                    continue;
+                }
+                merged.push(item.clone());
+            }
+            let separator = if and { " and " } else { " or " };
+            let items: Vec<String> = merged
+                .iter()
+                .map(|item| match item {
+                    Condition::And(_) | Condition::Or(_) => format!("({})", explain(text, item)),
+                    _ => explain(text, item),
+                })
+                .collect();
+            items.join(separator)
+        }
+        Condition::Other(span) => format!("`{}` holds", span.text(text)),
+    }
+}
+
+fn explain_feature(text: &str, feature: &Feature) -> String {
+    let name = &feature.name;
+    if feature.tests.is_empty() {
+        return match feature.ty {
+            ValueType::Keywords(_) => format!("{name} is not `none`"),
+            _ => format!("{name} is not 0"),
+        };
+    }
+    if !feature.ty.is_range() {
+        let values: Vec<&str> = feature
+            .tests
+            .iter()
+            .map(|(_, operand)| operand.span.text(text))
+            .collect();
+        return format!("{name} is {}", values.join(" and "));
+    }
+    let mut units = feature
+        .tests
+        .iter()
+        .map(|(_, operand)| magnitude(&operand.value).map(|(_, unit)| unit));
+    let first = units.next().flatten();
+    if first.is_none() || !units.all(|unit| unit == first) {
+        // Values such as `calc()` cannot be compared, so each test is
+        // described on its own.
+        let tests: Vec<String> = feature
+            .tests
+            .iter()
+            .map(|(op, operand)| {
+                let comparison = match op {
+                    Op::Lt => "less than ",
+                    Op::Le => "at most ",
+                    Op::Eq => "",
+                    Op::Ge => "at least ",
+                    Op::Gt => "more than ",
+                };
+                format!("{comparison}{}", operand.span.text(text))
+            })
+            .collect();
+        return format!("{name} is {}", tests.join(" and "));
+    }
+    let mut interval = Interval::default();
+    for (op, operand) in &feature.tests {
+        interval.add(*op, operand);
+    }
+    match (interval.lower, interval.upper) {
+        (Some(lower), Some(upper)) if lower.value == upper.value => format!("{name} is {}", lower.span.text(text)),
+        (Some(lower), Some(upper)) if lower.inclusive && upper.inclusive => {
+            format!(
+                "{name} is between {} and {}",
+                lower.span.text(text),
+                upper.span.text(text)
+            )
+        }
+        (lower, upper) => {
+            let mut bounds = vec![];
+            if let Some(lower) = lower {
+                let comparison = if lower.inclusive { "at least" } else { "more than" };
+                bounds.push(format!("{comparison} {}", lower.span.text(text)));
+            }
+            if let Some(upper) = upper {
+                let comparison = if upper.inclusive { "at most" } else { "less than" };
+                bounds.push(format!("{comparison} {}", upper.span.text(text)));
+            }
+            format!("{name} is {}", bounds.join(" and "))
+        }
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::parser::parse;
+
+    fn issues(text: &str) -> Vec<(IssueKind, String)> {
+        check(&parse(text))
+            .into_iter()
+            .map(|issue| (issue.kind, issue.message))
+            .collect()
+    }
+
+    fn explanation(text: &str) -> Option<String> {
+        explain_at(&parse(text), 1).map(|(_, explanation)| explanation)
+    }
+
+    #[test]
+    fn valid_queries() {
+        assert!(issues("@media screen and (min-width: 600px), print {}").is_empty());
+        assert!(issues("@media (400px <= width < 900px) {}").is_empty());
+        assert!(issues("@container sidebar (min-width: 20em) {}").is_empty());
+    }
+
+    #[test]
+    fn invalid_and_unknown() {
+        assert_eq!(
+            issues("@media screen and(min-width: 600px) {}"),
+            [(IssueKind::Invalid, "Expected a space before `(` in `and(`".to_string())]
+        );
+        assert_eq!(
+            issues("@media (min-width: red) {}"),
+            [(IssueKind::Invalid, "Expected a length for `width`".to_string())]
+        );
+        assert_eq!(
+            issues("@media (min-wdth: 600px) {}"),
+            [(IssueKind::Unknown, "Unknown media feature `min-wdth`".to_string())]
+        );
+        assert_eq!(
+            issues("@media tv {}"),
+            [(
+                IssueKind::Unknown,
+                "The media type `tv` is deprecated and never matches".to_string()
+            )]
+        );
+        assert_eq!(issues("@container {}")[0].1, "Expected a container query");
+    }
+
+    #[test]
+    fn contradictions() {
+        assert_eq!(
+            issues("@media (min-width: 900px) and (max-width: 600px) {}"),
+            [(
+                IssueKind::Contradiction,
+                "This query never matches: `width` cannot be at least 900px and at most 600px".to_string()
+            )]
+        );
+        assert_eq!(issues("@media (width > 600px) and (width < 600px) {}").len(), 1);
+        assert!(issues("@media (width >= 600px) and (width <= 600px) {}").is_empty());
+        // Bounds in different units are not compared.
+        assert!(issues("@media (min-width: 900px) and (max-width: 30em) {}").is_empty());
+        // Either side of `or` may hold.
+        assert!(issues("@media (min-width: 900px) or (max-width: 600px) {}").is_empty());
+    }
+
+    #[test]
+    fn negated_contradictions_always_match() {
+        let text = "@media not all and (min-width: 900px) and (max-width: 600px) {}";
+        assert!(issues(text).is_empty());
+        assert_eq!(explanation(text).as_deref(), Some("Always applies"));
+        assert_eq!(
+            explanation("@media not screen and (min-width: 900px) and (max-width: 600px) {}").as_deref(),
+            Some("Always applies")
+        );
+    }
+
+    #[test]
+    fn explanations() {
+        assert_eq!(explanation("@media {}").as_deref(), Some("Always applies"));
+        assert_eq!(
+            explanation("@media (min-width: 600px) and (max-width: 900px) {}").as_deref(),
+            Some("Applies when width is between 600px and 900px")
+        );
+        assert_eq!(
+            explanation("@media screen and (width > 600px) {}").as_deref(),
+            Some("Applies to `screen` media when width is more than 600px")
+        );
+        assert_eq!(
+            explanation("@media not print {}").as_deref(),
+            Some("Applies to all media but `print`")
+        );
+        assert_eq!(
+            explanation("@media (min-width: 900px) and (max-width: 600px) {}").as_deref(),
+            Some("Never applies")
+        );
+        assert_eq!(
+            explanation("@container card (min-width: 20em) {}").as_deref(),
+            Some("Applies when width is at least 20em in the nearest `card` container")
+        );
+        assert_eq!(
+            explanation("@media print, (orientation: landscape) {}").as_deref(),
+            Some(
+                "Applies when any of these queries matches:\n\n- `print` applies to `print` media\n\
+                 - `(orientation: landscape)` applies when orientation is landscape"
+            )
+        );
+        // Invalid preludes have no explanation.
+        assert_eq!(explanation("@media (min-width: red) {}"), None);
+    }
+
+    #[test]
+    fn explain_at_spans_the_queries() {
+        let sheet = parse("@media screen, print {}");
+        let (span, _) = explain_at(&sheet, 3).unwrap();
+        assert_eq!(span.text(&sheet.text), "screen, print");
+        assert!(explain_at(&sheet, 22).is_none());
+    }
+}
@@ -0,0 +1,695 @@
+use super::{
+    Span,
+    syntax::{AtRule, Block, Declaration, Item, Rule, StyleRule, Stylesheet, SyntaxError},
+    token::{Token, TokenKind, tokenize},
+};
+
+pub fn parse(text: &str) -> Stylesheet {
+    parse_rules(text).0
+}
+
+/// Parses `text` and returns whether it ended inside a rule, in which case
+/// text appended to it would continue that rule.
+fn parse_rules(text: &str) -> (Stylesheet, bool) {
+    let tokens = tokenize(text);
+    let mut parser = Parser {
+        text,
+        tokens: &tokens,
+        pos: 0,
+        errors: vec![],
+        truncated: false,
+    };
+    let rules = parser.rule_list(true);
+    let errors = parser.errors;
+    let truncated = parser.truncated;
+    let sheet = Stylesheet {
+        text: text.to_string(),
+        tokens,
+        rules,
+        errors,
+    };
+    (sheet, truncated)
+}
+
+/// The part of a stylesheet that `reparse` parsed again.
+#[derive(Debug, Clone, Copy)]
+pub struct Reparsed {
+    /// The text parsed again, in the old text. What comes before is
+    /// unchanged.
+    pub span: Span,
+    /// How far what comes after moved.
+    pub delta: isize,
+}
+
+/// Parses the text of `old` after replacing `edit` with `replacement`.
+///
+/// Only the top-level rules the edit touches are lexed and parsed again; the
+/// others are kept and moved. When the edited rules cannot be parsed on their
+/// own, because the edit leaves a block, comment or string open that would
+/// swallow the rules after it, the whole text is parsed instead.
+pub fn reparse(old: Stylesheet, edit: Span, replacement: &str) -> (Stylesheet, Reparsed) {
+    let Stylesheet {
+        mut text,
+        mut tokens,
+        mut rules,
+        errors,
+    } = old;
+    let old_len = text.len();
+    text.replace_range(edit.start..edit.end, replacement);
+    let delta = replacement.len() as isize - (edit.end - edit.start) as isize;
+
+    // The parser is between rules at the start of every top-level rule, so
+    // parsing can resume there. The edited text runs from the start of the
+    // rule containing the edit to the start of the first rule after it. An
+    // edit at the very start of a rule could join it to the text before, so
+    // then the rule before is parsed again too.
+    let starts: Vec<usize> = rules.iter().map(Rule::start).collect();
+    let first = starts.partition_point(|&start| start < edit.start).saturating_sub(1);
+    let start = starts
+        .get(first)
+        .copied()
+        .filter(|&start| start < edit.start)
+        .unwrap_or(0);
+    let last = starts.partition_point(|&start| start <= edit.end);
+    let end = starts.get(last).copied().unwrap_or(old_len);
+
+    let (middle, truncated) = parse_rules(&text[start..end.wrapping_add_signed(delta)]);
+    if last < rules.len() && (truncated || !ends_between_tokens(&middle)) {
+        let reparsed = Reparsed {
+            span: Span::new(0, old_len),
+            delta,
+        };
+        return (parse(&text), reparsed);
+    }
+
+    let to = tokens.partition_point(|t| t.span.start < end);
+    for token in &mut tokens[to..] {
+        token.span.shift(delta);
+    }
+    let from = tokens.partition_point(|t| t.span.end <= start);
+    tokens.splice(
+        from..to,
+        middle.tokens.into_iter().map(|mut token| {
+            token.span.shift(start as isize);
+            token
+        }),
+    );
+
+    for rule in &mut rules[last..] {
+        rule.shift(delta);
+    }
+    rules.splice(
+        first..last,
+        middle.rules.into_iter().map(|mut rule| {
+            rule.shift(start as isize);
+            rule
+        }),
+    );
+
+    // Errors are reported in source order of the top-level rules.
+    let (mut before, after): (Vec<_>, Vec<_>) = errors.into_iter().partition(|e| e.span.start < start);
+    let moved = |by: isize| {
+        move |mut error: SyntaxError| {
+            error.span.shift(by);
+            error
+        }
+    };
+    before.extend(middle.errors.into_iter().map(moved(start as isize)));
+    before.extend(after.into_iter().filter(|e| e.span.start >= end).map(moved(delta)));
+
+    let sheet = Stylesheet {
+        text,
+        tokens,
+        rules,
+        errors: before,
+    };
+    (
+        sheet,
+        Reparsed {
+            span: Span::new(start, end),
+            delta,
+        },
+    )
+}
+
+/// Whether the last token of `sheet` is complete, so that the text after it
+/// starts a new token. Whitespace is followed by a rule, which does not start
+/// with whitespace.
+fn ends_between_tokens(sheet: &Stylesheet) -> bool {
+    let Some(token) = sheet.tokens.last() else {
+        return true;
+    };
+    match token.kind {
+        TokenKind::Whitespace | TokenKind::Semicolon | TokenKind::RightBrace | TokenKind::Cdo | TokenKind::Cdc => true,
+        TokenKind::Comment => {
+            let text = token.text(&sheet.text);
+            text.len() >= 4 && text.ends_with("*/")
+        }
+        _ => false,
+    }
+}
+
+/// What an at-rule's block contains.
+enum Contents {
+    Rules,
+    Declarations,
+    /// Tailwind's `@theme`, whose declarations may reset a namespace of
+    /// variables, as in `--color-*: initial`.
+    Theme,
+}
+
+/// Inside a style rule, conditional group rules hold declarations as well as
+/// nested rules, which apply to the elements the style rule matches.
+fn block_contents(at_rule: &str, in_style_rule: bool) -> Contents {
+    match at_rule {
+        "media" | "supports" | "layer" | "container" | "document" | "scope" | "starting-style" | "-moz-document"
+            if in_style_rule =>
+        {
+            Contents::Declarations
+        }
+        "media" | "supports" | "layer" | "container" | "document" | "scope" | "starting-style" | "-moz-document" => {
+            Contents::Rules
+        }
+        // Keyframe rules such as `from { ... }` parse like style rules.
+        "keyframes" | "-webkit-keyframes" | "-moz-keyframes" | "-o-keyframes" => Contents::Rules,
+        "theme" => Contents::Theme,
+        _ => Contents::Declarations,
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    tokens: &'a [Token],
+    pos: usize,
+    errors: Vec<SyntaxError>,
+    /// Whether the text ended inside a rule.
+    truncated: bool,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_kind(&self) -> Option<TokenKind> {
+        self.peek().map(|t| t.kind)
+    }
+
+    /// Offset of the current token, or the end of the text at EOF.
+    fn offset(&self) -> usize {
+        self.peek().map_or(self.text.len(), |t| t.span.start)
+    }
+
+    fn error(&mut self, span: Span, message: impl Into<String>) {
+        self.errors.push(SyntaxError {
+            span,
+            message: message.into(),
+        });
+    }
+
+    fn skip_trivia(&mut self) {
+        while self.peek().is_some_and(|t| t.is_trivia()) {
+            self.pos += 1;
+        }
+    }
+
+    /// Consumes a function, parenthesis, bracket or block together with
+    /// everything up to its closing token.
+    fn skip_nested(&mut self) {
+        let mut depth = 0usize;
+        while let Some(kind) = self.peek_kind() {
+            self.pos += 1;
+            match kind {
+                TokenKind::Function | TokenKind::LeftParen | TokenKind::LeftBracket | TokenKind::LeftBrace => {
+                    depth += 1
+                }
+                TokenKind::RightParen | TokenKind::RightBracket | TokenKind::RightBrace => {
+                    depth = depth.saturating_sub(1);
+                }
+                _ => {}
+            }
+            if depth == 0 {
+                break;
+            }
+        }
+    }
+
+    /// End offset of the last non-trivia token before the current position,
+    /// bounded below by `start`.
+    fn trimmed_end(&self, start: usize) -> usize {
+        self.tokens[..self.pos]
+            .iter()
+            .rev()
+            .find(|t| !t.is_trivia())
+            .map_or(start, |t| t.span.end.max(start))
+    }
+
+    fn rule_list(&mut self, top_level: bool) -> Vec<Rule> {
+        let mut rules = vec![];
+        loop {
+            self.skip_trivia();
+            match self.peek_kind() {
+                None => break,
+                Some(TokenKind::Cdo | TokenKind::Cdc) if top_level => self.pos += 1,
+                Some(TokenKind::RightBrace) if !top_level => break,
+                Some(TokenKind::RightBrace) => {
+                    let span = self.tokens[self.pos].span;
+                    self.error(span, "unexpected '}'");
+                    self.pos += 1;
+                }
+                Some(TokenKind::AtKeyword) => rules.push(Rule::At(self.at_rule(false))),
+                Some(_) => {
+                    if let Some(rule) = self.style_rule() {
+                        rules.push(Rule::Style(rule));
+                    }
+                }
+            }
+        }
+        rules
+    }
+
+    fn at_rule(&mut self, in_style_rule: bool) -> AtRule {
+        let keyword = self.tokens[self.pos];
+        let name = keyword.name(self.text).to_ascii_lowercase();
+        self.pos += 1;
+        self.skip_trivia();
+
+        let start = self.offset();
+        while let Some(kind) = self.peek_kind() {
+            match kind {
+                TokenKind::Semicolon | TokenKind::LeftBrace | TokenKind::RightBrace => break,
+                _ => self.skip_nested(),
+            }
+        }
+        let prelude = Span::new(start, self.trimmed_end(start));
+        let block = match self.peek_kind() {
+            Some(TokenKind::LeftBrace) => Some(self.block(block_contents(&name, in_style_rule))),
+            Some(TokenKind::Semicolon) => {
+                self.pos += 1;
+                None
+            }
+            // A `}` is left for the enclosing block to consume.
+            Some(_) => None,
+            None => {
+                self.truncated = true;
+                None
+            }
+        };
+        AtRule {
+            name,
+            name_span: keyword.span,
+            prelude,
+            block,
+        }
+    }
+
+    fn style_rule(&mut self) -> Option<StyleRule> {
+        let start = self.offset();
+        loop {
+            match self.peek_kind() {
+                None => {
+                    let end = self.trimmed_end(start);
+                    self.error(Span::new(start, end), "expected '{'");
+                    self.truncated = true;
+                    return None;
+                }
+                Some(TokenKind::LeftBrace) => {
+                    let selector = Span::new(start, self.trimmed_end(start));
+                    if selector.start == selector.end {
+                        let span = self.tokens[self.pos].span;
+                        self.error(span, "expected selector");
+                    }
+                    let block = self.block(Contents::Declarations);
+                    return Some(StyleRule { selector, block });
+                }
+                Some(TokenKind::RightBrace) => {
+                    let end = self.trimmed_end(start);
+                    self.error(Span::new(start, end), "expected '{'");
+                    return None;
+                }
+                Some(_) => self.skip_nested(),
+            }
+        }
+    }
+
+    fn block(&mut self, contents: Contents) -> Block {
+        let open = self.tokens[self.pos].span;
+        self.pos += 1;
+        let items = match contents {
+            Contents::Rules => self.rule_list(false).into_iter().map(Item::Rule).collect(),
+            Contents::Declarations => self.declaration_list(false),
+            Contents::Theme => self.declaration_list(true),
+        };
+        if self.peek_kind() == Some(TokenKind::RightBrace) {
+            self.pos += 1;
+        } else {
+            self.error(open, "unclosed block");
+            self.truncated = true;
+        }
+        Block {
+            span: Span::new(open.start, self.trimmed_end(open.end)),
+            items,
+        }
+    }
+
+    fn declaration_list(&mut self, theme: bool) -> Vec<Item> {
+        let mut items = vec![];
+        loop {
+            self.skip_trivia();
+            match self.peek_kind() {
+                None | Some(TokenKind::RightBrace) => break,
+                Some(TokenKind::Semicolon) => self.pos += 1,
+                Some(TokenKind::AtKeyword) => items.push(Item::Rule(Rule::At(self.at_rule(true)))),
+                Some(_) if self.at_nested_rule() => {
+                    if let Some(rule) = self.style_rule() {
+                        items.push(Item::Rule(Rule::Style(rule)));
+                    }
+                }
+                Some(TokenKind::Ident) => {
+                    if let Some(decl) = self.declaration(theme) {
+                        items.push(Item::Declaration(decl));
+                    }
+                }
+                Some(_) => {
+                    let start = self.offset();
+                    self.recover();
+                    let end = self.trimmed_end(start);
+                    self.error(Span::new(start, end), "expected declaration");
+                }
+            }
+        }
+        items
+    }
+
+    /// Whether a `{` comes before the end of the current declaration, which
+    /// makes it a nested style rule such as `&:hover { ... }` or `a:hover {
+    /// ... }`. Only custom properties may have blocks in their values.
+    fn at_nested_rule(&mut self) -> bool {
+        let start = self.pos;
+        let custom_property = self
+            .peek()
+            .is_some_and(|t| t.kind == TokenKind::Ident && t.text(self.text).starts_with("--"))
+            && self.tokens[start + 1..]
+                .iter()
+                .find(|t| !t.is_trivia())
+                .is_some_and(|t| t.kind == TokenKind::Colon);
+        if custom_property {
+            return false;
+        }
+        let mut found = false;
+        while let Some(kind) = self.peek_kind() {
+            match kind {
+                TokenKind::LeftBrace => {
+                    found = true;
+                    break;
+                }
+                TokenKind::Semicolon | TokenKind::RightBrace => break,
+                _ => self.skip_nested(),
+            }
+        }
+        self.pos = start;
+        found
+    }
+
+    /// Skips to the end of the current declaration.
+    fn recover(&mut self) {
+        while let Some(kind) = self.peek_kind() {
+            match kind {
+                TokenKind::Semicolon | TokenKind::RightBrace => break,
+                _ => self.skip_nested(),
+            }
+        }
+    }
+
+    /// Parses a declaration. In `@theme`, the name may end with `-*`.
+    fn declaration(&mut self, theme: bool) -> Option<Declaration> {
+        let mut name_span = self.tokens[self.pos].span;
+        self.pos += 1;
+        if theme
+            && name_span.text(self.text).starts_with("--")
+            && name_span.text(self.text).ends_with('-')
+            && let Some(star) = self
+                .peek()
+                .filter(|t| t.kind == TokenKind::Delim && t.text(self.text) == "*")
+        {
+            name_span.end = star.span.end;
+            self.pos += 1;
+        }
+        let name = name_span.text(self.text).to_string();
+        self.skip_trivia();
+        if self.peek_kind() != Some(TokenKind::Colon) {
+            self.recover();
+            self.error(name_span, "expected ':'");
+            return None;
+        }
+        self.pos += 1;
+        self.skip_trivia();
+
+        let start = self.pos;
+        let offset = self.offset();
+        self.recover();
+        let end = self.trimmed_end(name_span.end);
+        let mut value = self.tokens[start..self.pos]
+            .iter()
+            .filter(|t| !t.is_trivia())
+            .collect::<Vec<_>>();
+        let important = ends_with_important(self.text, &value);
+        if important {
+            value.truncate(value.len() - 2);
+        }
+        let value = match (value.first(), value.last()) {
+            (Some(first), Some(last)) => Span::new(first.span.start, last.span.end),
+            _ => Span::new(offset, offset),
+        };
+
+        Some(Declaration {
+            // An empty value after whitespace is past the last token.
+            span: Span::new(name_span.start, end.max(value.end)),
+            name,
+            name_span,
+            value,
+            important,
+        })
+    }
+}
+
+/// Whether the tokens of a value, without trivia, end with `!important`, in
+/// any case and with any whitespace or comments after the `!`.
+fn ends_with_important(text: &str, value: &[&Token]) -> bool {
+    match value {
+        [.., bang, ident] => {
+            bang.kind == TokenKind::Delim
+                && bang.text(text) == "!"
+                && ident.kind == TokenKind::Ident
+                && ident.text(text).eq_ignore_ascii_case("important")
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(sheet: &Stylesheet) -> Vec<(&str, &str)> {
+        sheet
+            .errors
+            .iter()
+            .map(|error| (error.span.text(&sheet.text), error.message.as_str()))
+            .collect()
+    }
+
+    fn names(sheet: &Stylesheet) -> Vec<&str> {
+        sheet.declarations().iter().map(|decl| decl.name.as_str()).collect()
+    }
+
+    /// The at-rules and functions of Tailwind CSS v4, as documented.
+    #[test]
+    fn tailwind_at_rules() {
+        let text = r#"@import "tailwindcss";
+@source "../node_modules/@my-company/ui-lib";
+@plugin "@tailwindcss/typography";
+@config "../../tailwind.config.js";
+@reference "../../app.css";
+@custom-variant theme-midnight (&:where([data-theme="midnight"] *));
+@theme {
+  --font-display: "Satoshi", "sans-serif";
+  --color-*: initial;
+  --breakpoint-3xl: 120rem;
+  --color-avocado-100: oklch(0.99 0 0);
+}
+@theme inline {
+  --font-sans: var(--font-inter);
+}
+@utility tab-4 {
+  tab-size: 4;
+}
+@layer components {
+  .card {
+    @apply rounded-b-lg shadow-md;
+    background-color: --alpha(var(--color-lime-300) / 50%);
+    margin: --spacing(4);
+    @variant dark {
+      background: black;
+    }
+  }
+}
+@media screen {
+  @tailwind utilities;
+}
+"#;
+        let sheet = parse(text);
+        assert_eq!(errors(&sheet), []);
+        let at_rules: Vec<&str> = sheet.at_rules().iter().map(|rule| rule.name.as_str()).collect();
+        assert_eq!(
+            at_rules,
+            [
+                "import",
+                "source",
+                "plugin",
+                "config",
+                "reference",
+                "custom-variant",
+                "theme",
+                "theme",
+                "utility",
+                "layer",
+                "apply",
+                "variant",
+                "media",
+                "tailwind"
+            ]
+        );
+        let theme = sheet.declarations()[1];
+        assert_eq!(theme.name, "--color-*");
+        assert_eq!(theme.name_span.text(text), "--color-*");
+        assert_eq!(theme.value.text(text), "initial");
+    }
+
+    #[test]
+    fn important() {
+        let text = "a { a: red !IMPORTANT; b: red ! /* x */ important; c: red !imp; d: red!; e: !important }";
+        let sheet = parse(text);
+        let decls: Vec<(&str, bool)> = sheet
+            .declarations()
+            .iter()
+            .map(|decl| (decl.value.text(text), decl.important))
+            .collect();
+        assert_eq!(
+            decls,
+            [
+                ("red", true),
+                ("red", true),
+                ("red !imp", false),
+                ("red!", false),
+                ("", true)
+            ]
+        );
+    }
+
+    #[test]
+    fn theme_namespace_resets() {
+        let sheet = parse("@theme { --*: initial; --color-*: initial; --font-* : initial }");
+        assert_eq!(errors(&sheet), []);
+        assert_eq!(names(&sheet), ["--*", "--color-*", "--font-*"]);
+        // Elsewhere, and with whitespace before the `*`, it is not a name.
+        let sheet = parse("a { --color-*: initial; } @theme { --color- *: initial }");
+        assert_eq!(
+            errors(&sheet),
+            [("--color-", "expected ':'"), ("--color-", "expected ':'")]
+        );
+    }
+
+    /// Checks that reparsing after an edit gives the same rules, tokens and
+    /// errors as parsing the edited text from scratch.
+    fn assert_reparses(text: &str, edit: Span, replacement: &str) {
+        let mut edited = text.to_string();
+        edited.replace_range(edit.start..edit.end, replacement);
+        let (sheet, reparsed) = reparse(parse(text), edit, replacement);
+        let context = format!("replacing {:?} in {text:?} with {replacement:?}", edit.text(text));
+        assert_eq!(format!("{sheet:?}"), format!("{:?}", parse(&edited)), "{context}");
+        assert!(
+            reparsed.span.start <= edit.start && edit.end <= reparsed.span.end,
+            "{context}"
+        );
+        assert_eq!(reparsed.delta, edited.len() as isize - text.len() as isize, "{context}");
+    }
+
+    #[test]
+    fn reparse_matches_a_full_parse() {
+        let text = "a { color: red }\n@media print { b { margin: 0 } }\nc{}d { width: calc(1px + 2em) }";
+        let cases = [
+            // Inside a declaration, a block and between rules.
+            (Span::new(11, 14), "blue"),
+            (Span::new(44, 45), "1px; padding: 2px"),
+            (Span::new(16, 17), "\n\n"),
+            // Joining two rules, or splitting one.
+            (Span::new(15, 17), ""),
+            (Span::new(4, 4), "} e { "),
+            // At the start and end of the text.
+            (Span::new(0, 0), "x "),
+            (Span::new(0, 1), ""),
+            (Span::new(text.len(), text.len()), " e { }"),
+            (Span::new(0, text.len()), ""),
+            // Leaving a block, comment or string open swallows the rules after.
+            (Span::new(3, 3), "{"),
+            (Span::new(16, 16), "/*"),
+            (Span::new(11, 14), "'red"),
+            (Span::new(15, 16), ""),
+        ];
+        for (edit, replacement) in cases {
+            assert_reparses(text, edit, replacement);
+        }
+        // Closing them again brings the rules after back.
+        assert_reparses("a { /* b { } c { }", Span::new(12, 12), "*/");
+        assert_reparses("a { b: 'c; } d { }", Span::new(10, 10), "'");
+        assert_reparses("a { b { } c { }", Span::new(15, 15), " }");
+    }
+
+    #[test]
+    fn reparse_across_rules() {
+        // From the value of the first rule to the selector of the third.
+        let text = "a { color: red }\nb { margin: 0 }\nc { padding: 0 }\ne {}";
+        let (sheet, reparsed) = reparse(parse(text), Span::new(11, 34), "blue } d");
+        assert_eq!(sheet.text, "a { color: blue } d { padding: 0 }\ne {}");
+        let selectors: Vec<&str> = sheet
+            .rules
+            .iter()
+            .map(|rule| match rule {
+                Rule::Style(rule) => rule.selector.text(&sheet.text),
+                Rule::At(rule) => rule.name.as_str(),
+            })
+            .collect();
+        assert_eq!(selectors, ["a", "d", "e"]);
+        assert_eq!(names(&sheet), ["color", "padding"]);
+        assert_eq!(errors(&sheet), []);
+        // The rules from the first to the one after the edit are parsed again.
+        assert_eq!((reparsed.span.start, reparsed.span.end), (0, 50));
+        assert_eq!(reparsed.delta, -15);
+    }
+
+    #[test]
+    fn reparse_any_edit() {
+        let text = "a{b:c}/* d */e{f:g;h:'i'}@j k{l{}}";
+        let replacements = ["", "}", "{", ";", "/*", "\"", " x{y:z} "];
+        for start in 0..=text.len() {
+            for end in start..=text.len() {
+                for replacement in replacements {
+                    assert_reparses(text, Span::new(start, end), replacement);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reparse_errors() {
+        let text = "a { color red }\nb { width: 1px }\nc { : x }";
+        let (sheet, _) = reparse(parse(text), Span::new(9, 9), ":");
+        assert_eq!(errors(&sheet), [(": x", "expected declaration")]);
+        let (sheet, _) = reparse(parse(text), Span::new(20, 20), "height: 2px; ");
+        assert_eq!(
+            errors(&sheet),
+            [("color", "expected ':'"), (": x", "expected declaration")]
+        );
+    }
+}
@@ -0,0 +1,676 @@
+use super::{
+    compat::{
+        Browser::{self, *},
+        Targets, Version,
+    },
+    properties,
+};
+
+type Support = &'static [(Browser, Option<Version>)];
+
+/// Properties that have needed a vendor prefix, with the first version of each
+/// browser that supports the unprefixed form. `None` means the prefix is still
+/// required. Sorted by property, then prefix.
+const PREFIXES: &[(&str, &str, Support)] = &[
+    (
+        "align-content",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(29, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "align-items",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(29, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "align-self",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(29, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "animation",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(43, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "animation-delay",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(43, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "animation-direction",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(43, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "animation-duration",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(43, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "animation-fill-mode",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(43, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "animation-iteration-count",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(43, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "animation-name",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(43, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "animation-play-state",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(43, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "animation-timing-function",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(43, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    ("appearance", "-moz-", &[(Firefox, Some(Version(80, 0)))]),
+    (
+        "appearance",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(84, 0))),
+            (Edge, Some(Version(84, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        "backdrop-filter",
+        "-webkit-",
+        &[(Safari, Some(Version(18, 0))), (IosSafari, Some(Version(18, 0)))],
+    ),
+    (
+        "backface-visibility",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(36, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    ("border-radius", "-moz-", &[(Firefox, Some(Version(4, 0)))]),
+    (
+        "border-radius",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(5, 0))),
+            (Safari, Some(Version(5, 0))),
+            (IosSafari, Some(Version(4, 0))),
+        ],
+    ),
+    (
+        "box-decoration-break",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(130, 0))),
+            (Edge, Some(Version(130, 0))),
+            (Safari, None),
+            (IosSafari, None),
+        ],
+    ),
+    ("box-shadow", "-moz-", &[(Firefox, Some(Version(4, 0)))]),
+    (
+        "box-shadow",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(10, 0))),
+            (Safari, Some(Version(5, 1))),
+            (IosSafari, Some(Version(5, 0))),
+        ],
+    ),
+    ("box-sizing", "-moz-", &[(Firefox, Some(Version(29, 0)))]),
+    (
+        "box-sizing",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(10, 0))),
+            (Safari, Some(Version(5, 1))),
+            (IosSafari, Some(Version(5, 0))),
+        ],
+    ),
+    (
+        "clip-path",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(55, 0))),
+            (Safari, Some(Version(13, 1))),
+            (IosSafari, Some(Version(13, 4))),
+        ],
+    ),
+    ("column-count", "-moz-", &[(Firefox, Some(Version(52, 0)))]),
+    (
+        "column-count",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(50, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    ("column-gap", "-moz-", &[(Firefox, Some(Version(52, 0)))]),
+    (
+        "column-gap",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(50, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    ("column-rule", "-moz-", &[(Firefox, Some(Version(52, 0)))]),
+    (
+        "column-rule",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(50, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    ("column-width", "-moz-", &[(Firefox, Some(Version(52, 0)))]),
+    (
+        "column-width",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(50, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    ("columns", "-moz-", &[(Firefox, Some(Version(52, 0)))]),
+    (
+        "columns",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(50, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "filter",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(53, 0))),
+            (Safari, Some(Version(9, 1))),
+            (IosSafari, Some(Version(9, 3))),
+        ],
+    ),
+    (
+        "flex",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(29, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "flex-basis",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(29, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "flex-direction",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(29, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "flex-flow",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(29, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "flex-grow",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(29, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "flex-shrink",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(29, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "flex-wrap",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(29, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    ("font-feature-settings", "-moz-", &[(Firefox, Some(Version(34, 0)))]),
+    (
+        "font-feature-settings",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(48, 0))),
+            (Safari, Some(Version(9, 1))),
+            (IosSafari, Some(Version(9, 3))),
+        ],
+    ),
+    (
+        "hyphens",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(88, 0))),
+            (Edge, Some(Version(88, 0))),
+            (Safari, Some(Version(17, 0))),
+            (IosSafari, Some(Version(17, 0))),
+        ],
+    ),
+    ("initial-letter", "-webkit-", &[(Safari, None), (IosSafari, None)]),
+    (
+        "justify-content",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(29, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "mask",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(120, 0))),
+            (Edge, Some(Version(120, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        "mask-clip",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(120, 0))),
+            (Edge, Some(Version(120, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        "mask-image",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(120, 0))),
+            (Edge, Some(Version(120, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        "mask-origin",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(120, 0))),
+            (Edge, Some(Version(120, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        "mask-position",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(120, 0))),
+            (Edge, Some(Version(120, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        "mask-repeat",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(120, 0))),
+            (Edge, Some(Version(120, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        "mask-size",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(120, 0))),
+            (Edge, Some(Version(120, 0))),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    (
+        "order",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(29, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "perspective",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(36, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "perspective-origin",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(36, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "print-color-adjust",
+        "-webkit-",
+        &[
+            (Chrome, None),
+            (Edge, None),
+            (Safari, Some(Version(15, 4))),
+            (IosSafari, Some(Version(15, 4))),
+        ],
+    ),
+    ("tab-size", "-moz-", &[(Firefox, Some(Version(91, 0)))]),
+    (
+        "text-emphasis",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(99, 0))),
+            (Edge, Some(Version(99, 0))),
+            (Safari, Some(Version(7, 0))),
+            (IosSafari, Some(Version(7, 0))),
+        ],
+    ),
+    (
+        "text-emphasis-color",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(99, 0))),
+            (Edge, Some(Version(99, 0))),
+            (Safari, Some(Version(7, 0))),
+            (IosSafari, Some(Version(7, 0))),
+        ],
+    ),
+    (
+        "text-emphasis-position",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(99, 0))),
+            (Edge, Some(Version(99, 0))),
+            (Safari, Some(Version(7, 0))),
+            (IosSafari, Some(Version(7, 0))),
+        ],
+    ),
+    (
+        "text-emphasis-style",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(99, 0))),
+            (Edge, Some(Version(99, 0))),
+            (Safari, Some(Version(7, 0))),
+            (IosSafari, Some(Version(7, 0))),
+        ],
+    ),
+    ("text-size-adjust", "-webkit-", &[(Safari, None), (IosSafari, None)]),
+    (
+        "transform",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(36, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "transform-origin",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(36, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    (
+        "transform-style",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(36, 0))),
+            (Safari, Some(Version(9, 0))),
+            (IosSafari, Some(Version(9, 0))),
+        ],
+    ),
+    ("transition", "-moz-", &[(Firefox, Some(Version(16, 0)))]),
+    (
+        "transition",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(26, 0))),
+            (Safari, Some(Version(6, 1))),
+            (IosSafari, Some(Version(7, 0))),
+        ],
+    ),
+    (
+        "transition-delay",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(26, 0))),
+            (Safari, Some(Version(6, 1))),
+            (IosSafari, Some(Version(7, 0))),
+        ],
+    ),
+    (
+        "transition-duration",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(26, 0))),
+            (Safari, Some(Version(6, 1))),
+            (IosSafari, Some(Version(7, 0))),
+        ],
+    ),
+    (
+        "transition-property",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(26, 0))),
+            (Safari, Some(Version(6, 1))),
+            (IosSafari, Some(Version(7, 0))),
+        ],
+    ),
+    (
+        "transition-timing-function",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(26, 0))),
+            (Safari, Some(Version(6, 1))),
+            (IosSafari, Some(Version(7, 0))),
+        ],
+    ),
+    ("user-select", "-moz-", &[(Firefox, Some(Version(69, 0)))]),
+    (
+        "user-select",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(54, 0))),
+            (Edge, Some(Version(79, 0))),
+            (Safari, None),
+            (IosSafari, None),
+        ],
+    ),
+    (
+        "writing-mode",
+        "-webkit-",
+        &[
+            (Chrome, Some(Version(48, 0))),
+            (Safari, Some(Version(10, 1))),
+            (IosSafari, Some(Version(10, 3))),
+        ],
+    ),
+];
+
+fn support(property: &str, prefix: &str) -> Option<Support> {
+    PREFIXES
+        .binary_search_by(|(p, x, _)| (*p, *x).cmp(&(property, prefix)))
+        .ok()
+        .map(|i| PREFIXES[i].2)
+}
+
+/// Prefixes that `property` needs in at least one of `targets`.
+pub fn needed(property: &str, targets: &Targets) -> Vec<&'static str> {
+    let property = property.to_ascii_lowercase();
+    PREFIXES
+        .iter()
+        .filter(|(p, _, support)| {
+            *p == property
+                && support
+                    .iter()
+                    .any(|(browser, fixed_in)| targets.any_older(*browser, *fixed_in))
+        })
+        .map(|(_, prefix, _)| *prefix)
+        .collect()
+}
+
+/// Whether a prefixed property such as `-webkit-transform` is not needed by any
+/// of `targets`. Prefixed properties that have no standard equivalent, or no
+/// support data, are never obsolete.
+pub fn is_obsolete(name: &str, targets: &Targets) -> bool {
+    let name = name.to_ascii_lowercase();
+    let unprefixed = properties::strip_vendor_prefix(&name);
+    if unprefixed == name || !properties::is_standard(unprefixed) || properties::is_vendor_only(&name) {
+        return false;
+    }
+    let prefix = &name[..name.len() - unprefixed.len()];
+    match support(unprefixed, prefix) {
+        Some(support) => !support
+            .iter()
+            .any(|(browser, fixed_in)| targets.any_older(*browser, *fixed_in)),
+        // Without data, the prefix may well be needed: Chrome only supports
+        // `background-clip: text` unprefixed since 120.
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sorted() {
+        assert!(PREFIXES.windows(2).all(|w| (w[0].0, w[0].1) < (w[1].0, w[1].1)));
+    }
+
+    #[test]
+    fn needed_prefixes() {
+        let targets = Targets::default();
+        assert_eq!(needed("user-select", &targets), ["-webkit-"]);
+        assert_eq!(needed("transform", &targets), Vec::<&str>::new());
+        let old = Targets::from_json(&serde_json::json!({ "chrome": "30", "firefox": "60" })).unwrap();
+        assert_eq!(needed("transform", &old), ["-webkit-"]);
+        assert_eq!(needed("User-Select", &old), ["-moz-", "-webkit-"]);
+    }
+
+    #[test]
+    fn obsolete() {
+        let targets = Targets::default();
+        assert!(is_obsolete("-webkit-transform", &targets));
+        assert!(is_obsolete("-WEBKIT-TRANSFORM", &targets));
+        assert!(is_obsolete("-moz-user-select", &targets));
+        // Safari still needs it.
+        assert!(!is_obsolete("-webkit-user-select", &targets));
+        // Not prefixed, or without a standard equivalent.
+        assert!(!is_obsolete("transform", &targets));
+        assert!(!is_obsolete("-webkit-line-clamp", &targets));
+        assert!(!is_obsolete("-webkit-tap-highlight-color", &targets));
+        // No support data.
+        assert!(!is_obsolete("-webkit-background-clip", &targets));
+        assert!(!is_obsolete("-ms-transform", &targets));
+
+        let old = Targets::from_json(&serde_json::json!({ "chrome": "30" })).unwrap();
+        assert!(!is_obsolete("-webkit-transform", &old));
+        // A browser that is not targeted does not need its prefix.
+        let chrome = Targets::from_json(&serde_json::json!({ "chrome": "120" })).unwrap();
+        assert!(is_obsolete("-webkit-user-select", &chrome));
+    }
+}
@@ -0,0 +1,651 @@
+use super::{
+    Span, color, math,
+    syntax::{Item, Stylesheet, matching_close},
+    token::{Token, TokenKind},
+};
+
+/// A custom property registered with a valid `@property` rule.
+#[derive(Debug, Clone)]
+pub struct Registration {
+    pub name: String,
+    pub syntax: Syntax,
+    /// The `syntax` descriptor without its quotes, as shown in messages.
+    pub source: String,
+}
+
+/// The grammar of a `syntax` descriptor, such as `"<length> | auto"`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Syntax {
+    /// `"*"`, which any value matches.
+    Universal,
+    /// Alternatives separated by `|`.
+    Components(Vec<Component>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Component {
+    kind: ComponentKind,
+    multiplier: Option<Multiplier>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ComponentKind {
+    Type(DataType),
+    /// An identifier matched as written, such as `auto`.
+    Keyword(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Multiplier {
+    /// `+`, a space-separated list.
+    Space,
+    /// `#`, a comma-separated list.
+    Comma,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DataType {
+    Angle,
+    Color,
+    CustomIdent,
+    Image,
+    Integer,
+    Length,
+    LengthPercentage,
+    Number,
+    Percentage,
+    Resolution,
+    String,
+    Time,
+    TransformFunction,
+    TransformList,
+    Url,
+}
+
+/// Sorted. The data types a `syntax` descriptor may name.
+const DATA_TYPES: &[(&str, DataType)] = &[
+    ("angle", DataType::Angle),
+    ("color", DataType::Color),
+    ("custom-ident", DataType::CustomIdent),
+    ("image", DataType::Image),
+    ("integer", DataType::Integer),
+    ("length", DataType::Length),
+    ("length-percentage", DataType::LengthPercentage),
+    ("number", DataType::Number),
+    ("percentage", DataType::Percentage),
+    ("resolution", DataType::Resolution),
+    ("string", DataType::String),
+    ("time", DataType::Time),
+    ("transform-function", DataType::TransformFunction),
+    ("transform-list", DataType::TransformList),
+    ("url", DataType::Url),
+];
+
+/// Keywords every property accepts, which no syntax may use as a keyword.
+pub const CSS_WIDE_KEYWORDS: &[&str] = &["inherit", "initial", "revert", "revert-layer", "unset"];
+
+/// Functions whose value is only known at computed-value time, so values
+/// using them are not checked.
+const SUBSTITUTIONS: &[&str] = &["attr", "env", "var"];
+
+const MATH_FUNCTIONS: &[&str] = &["calc", "clamp", "max", "min"];
+
+/// Functions with a numeric result that the math checker does not evaluate.
+const OTHER_MATH_FUNCTIONS: &[&str] = &[
+    "abs", "acos", "asin", "atan", "atan2", "cos", "exp", "hypot", "log", "mod", "pow", "rem", "round", "sign", "sin",
+    "sqrt", "tan",
+];
+
+const COLOR_FUNCTIONS: &[&str] = &[
+    "color",
+    "color-mix",
+    "hsl",
+    "hsla",
+    "hwb",
+    "lab",
+    "lch",
+    "light-dark",
+    "oklab",
+    "oklch",
+    "rgb",
+    "rgba",
+];
+
+const IMAGE_FUNCTIONS: &[&str] = &[
+    "conic-gradient",
+    "cross-fade",
+    "element",
+    "image",
+    "image-set",
+    "linear-gradient",
+    "paint",
+    "radial-gradient",
+    "repeating-conic-gradient",
+    "repeating-linear-gradient",
+    "repeating-radial-gradient",
+];
+
+const TRANSFORM_FUNCTIONS: &[&str] = &[
+    "matrix",
+    "matrix3d",
+    "perspective",
+    "rotate",
+    "rotate3d",
+    "rotatex",
+    "rotatey",
+    "rotatez",
+    "scale",
+    "scale3d",
+    "scalex",
+    "scaley",
+    "scalez",
+    "skew",
+    "skewx",
+    "skewy",
+    "translate",
+    "translate3d",
+    "translatex",
+    "translatey",
+    "translatez",
+];
+
+/// One value of a list, or the comma between two.
+enum Part<'a> {
+    Value(&'a [Token]),
+    Comma,
+}
+
+impl Syntax {
+    /// Parses the contents of a `syntax` string, returning why it is invalid
+    /// otherwise.
+    pub fn parse(source: &str) -> Result<Syntax, String> {
+        let source = source.trim();
+        if source == "*" {
+            return Ok(Syntax::Universal);
+        }
+        if source.is_empty() {
+            return Err("The syntax is empty; use `\"*\"` to accept any value".to_string());
+        }
+        let mut components = vec![];
+        for part in source.split('|') {
+            let part = part.trim();
+            if part.is_empty() {
+                return Err("Expected a component on both sides of `|`".to_string());
+            }
+            let (body, multiplier) = match part.as_bytes()[part.len() - 1] {
+                b'+' => (&part[..part.len() - 1], Some(Multiplier::Space)),
+                b'#' => (&part[..part.len() - 1], Some(Multiplier::Comma)),
+                _ => (part, None),
+            };
+            let kind = if let Some(name) = body.strip_prefix('<').and_then(|body| body.strip_suffix('>')) {
+                let Ok(index) = DATA_TYPES.binary_search_by(|(n, _)| n.cmp(&name)) else {
+                    return Err(format!("Unknown data type `<{name}>`"));
+                };
+                let ty = DATA_TYPES[index].1;
+                if ty == DataType::TransformList && multiplier.is_some() {
+                    return Err("`<transform-list>` is already a list and cannot take a multiplier".to_string());
+                }
+                ComponentKind::Type(ty)
+            } else if is_ident(body) && !is_reserved(body) {
+                ComponentKind::Keyword(body.to_string())
+            } else {
+                return Err(format!(
+                    "Expected a data type such as `<length>` or a keyword instead of `{part}`"
+                ));
+            };
+            components.push(Component { kind, multiplier });
+        }
+        Ok(Syntax::Components(components))
+    }
+
+    /// Whether the value in `span` matches, or `None` when that is only known
+    /// at computed-value time, as for `var()` and the CSS-wide keywords.
+    pub fn matches(&self, sheet: &Stylesheet, span: Span) -> Option<bool> {
+        let text = sheet.text.as_str();
+        let tokens = sheet.tokens_in(span);
+        let substituted = tokens.iter().any(|t| {
+            t.kind == TokenKind::Function && SUBSTITUTIONS.contains(&t.name(text).to_ascii_lowercase().as_str())
+        });
+        let mut values = tokens.iter().filter(|t| !t.is_trivia());
+        let css_wide = values.next().is_some_and(|t| {
+            t.kind == TokenKind::Ident && CSS_WIDE_KEYWORDS.contains(&t.text(text).to_ascii_lowercase().as_str())
+        }) && values.next().is_none();
+        if substituted || css_wide {
+            return None;
+        }
+        let Syntax::Components(components) = self else {
+            return Some(true);
+        };
+        let parts = parts(tokens);
+        Some(components.iter().any(|component| component.matches(sheet, &parts)))
+    }
+}
+
+impl Component {
+    fn matches(&self, sheet: &Stylesheet, parts: &[Part]) -> bool {
+        let single = |part: &Part| match part {
+            Part::Value(value) => self.matches_value(sheet, value),
+            Part::Comma => false,
+        };
+        match self.multiplier {
+            _ if self.kind == ComponentKind::Type(DataType::TransformList) => {
+                !parts.is_empty() && parts.iter().all(single)
+            }
+            None => parts.len() == 1 && single(&parts[0]),
+            Some(Multiplier::Space) => !parts.is_empty() && parts.iter().all(single),
+            Some(Multiplier::Comma) => {
+                parts.len() % 2 == 1
+                    && parts.iter().enumerate().all(|(i, part)| match i % 2 {
+                        0 => single(part),
+                        _ => matches!(part, Part::Comma),
+                    })
+            }
+        }
+    }
+
+    fn matches_value(&self, sheet: &Stylesheet, value: &[Token]) -> bool {
+        let text = sheet.text.as_str();
+        let ty = match &self.kind {
+            ComponentKind::Keyword(keyword) => {
+                return value.len() == 1 && value[0].kind == TokenKind::Ident && value[0].text(text) == keyword;
+            }
+            ComponentKind::Type(ty) => *ty,
+        };
+        let token = value[0];
+        if token.kind == TokenKind::Function {
+            let name = token.name(text).to_ascii_lowercase();
+            let name = name.as_str();
+            return match ty {
+                _ if MATH_FUNCTIONS.contains(&name) => match math::evaluate_at(sheet, token.span.start) {
+                    Some(evaluation) => numeric_type_matches(ty, &evaluation.kind),
+                    None => is_numeric(ty),
+                },
+                _ if OTHER_MATH_FUNCTIONS.contains(&name) => is_numeric(ty),
+                DataType::Color => COLOR_FUNCTIONS.contains(&name),
+                DataType::Image => IMAGE_FUNCTIONS.contains(&name) || name == "url" || name == "src",
+                DataType::Url => name == "url" || name == "src",
+                DataType::TransformFunction | DataType::TransformList => TRANSFORM_FUNCTIONS.contains(&name),
+                _ => false,
+            };
+        }
+        if value.len() != 1 {
+            return false;
+        }
+        let token_text = token.text(text);
+        match token.kind {
+            TokenKind::Number => match ty {
+                DataType::Number => true,
+                DataType::Integer => !token_text.contains(['.', 'e', 'E']),
+                DataType::Length | DataType::LengthPercentage => token_text.parse::<f64>().is_ok_and(|n| n == 0.0),
+                _ => false,
+            },
+            TokenKind::Percentage => matches!(ty, DataType::Percentage | DataType::LengthPercentage),
+            TokenKind::Dimension { unit } => {
+                let Some((base, _)) = math::unit(&token_text[unit..]) else {
+                    return false;
+                };
+                numeric_type_matches(ty, base)
+            }
+            TokenKind::Hash => ty == DataType::Color && color::parse_hex(token.name(text)).is_some(),
+            TokenKind::Ident => match ty {
+                DataType::Color => {
+                    token_text.eq_ignore_ascii_case("currentcolor") || color::named(token_text).is_some()
+                }
+                DataType::CustomIdent => !is_reserved(token_text),
+                _ => false,
+            },
+            TokenKind::String => ty == DataType::String,
+            TokenKind::Url => matches!(ty, DataType::Image | DataType::Url),
+            _ => false,
+        }
+    }
+}
+
+/// Splits the tokens of a value into its top-level values and commas. A
+/// function or parenthesized group is one value.
+fn parts(tokens: &[Token]) -> Vec<Part<'_>> {
+    let mut parts = vec![];
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+        match token.kind {
+            _ if token.is_trivia() => {}
+            TokenKind::Comma => parts.push(Part::Comma),
+            TokenKind::Function | TokenKind::LeftParen | TokenKind::LeftBracket => {
+                let close = matching_close(tokens, i).min(tokens.len() - 1);
+                parts.push(Part::Value(&tokens[i..=close]));
+                i = close;
+            }
+            _ => parts.push(Part::Value(&tokens[i..=i])),
+        }
+        i += 1;
+    }
+    parts
+}
+
+fn is_numeric(ty: DataType) -> bool {
+    matches!(
+        ty,
+        DataType::Angle
+            | DataType::Integer
+            | DataType::Length
+            | DataType::LengthPercentage
+            | DataType::Number
+            | DataType::Percentage
+            | DataType::Resolution
+            | DataType::Time
+    )
+}
+
+/// Whether a numeric value of a base type such as `length`, as named by the
+/// math checker, is of the data type.
+fn numeric_type_matches(ty: DataType, base: &str) -> bool {
+    match ty {
+        DataType::Angle => base == "angle",
+        DataType::Integer | DataType::Number => base == "number",
+        DataType::Length => base == "length",
+        DataType::LengthPercentage => base == "length" || base == "percentage",
+        DataType::Percentage => base == "percentage",
+        DataType::Resolution => base == "resolution",
+        DataType::Time => base == "time",
+        _ => false,
+    }
+}
+
+fn is_ident(text: &str) -> bool {
+    let body = text.strip_prefix('-').unwrap_or(text);
+    body.starts_with(|c: char| c.is_alphabetic() || c == '_' || c == '-')
+        && body.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Whether an identifier cannot be a keyword of a syntax or a
+/// `<custom-ident>`.
+fn is_reserved(ident: &str) -> bool {
+    let lower = ident.to_ascii_lowercase();
+    lower == "default" || CSS_WIDE_KEYWORDS.contains(&lower.as_str())
+}
+
+/// The contents of a string token without its quotes.
+pub fn string_contents(text: &str) -> &str {
+    let closed = text.len() > 1 && text.ends_with(&text[..1]);
+    &text[1..if closed { text.len() - 1 } else { text.len() }]
+}
+
+/// The custom properties registered by valid `@property` rules: those with a
+/// valid `syntax`, an `inherits` descriptor and, unless the syntax is `"*"`,
+/// an `initial-value`.
+pub fn registrations(sheet: &Stylesheet) -> Vec<Registration> {
+    let text = sheet.text.as_str();
+    let mut out = vec![];
+    for at_rule in sheet.at_rules() {
+        let name = at_rule.prelude.text(text);
+        let Some(block) = &at_rule.block else { continue };
+        if at_rule.name != "property" || !name.starts_with("--") {
+            continue;
+        }
+        let descriptor = |wanted: &str| {
+            block.items.iter().rev().find_map(|item| match item {
+                Item::Declaration(decl) if decl.name.eq_ignore_ascii_case(wanted) => Some(decl),
+                _ => None,
+            })
+        };
+        let Some(syntax) = descriptor("syntax") else { continue };
+        let [string] = sheet.tokens_in(syntax.value) else {
+            continue;
+        };
+        if string.kind != TokenKind::String || descriptor("inherits").is_none() {
+            continue;
+        }
+        let source = string_contents(string.text(text));
+        let Ok(syntax) = Syntax::parse(source) else { continue };
+        if syntax != Syntax::Universal && descriptor("initial-value").is_none() {
+            continue;
+        }
+        out.push(Registration {
+            name: name.to_string(),
+            syntax,
+            source: source.trim().to_string(),
+        });
+    }
+    out
+}
+
+/// A value set for a registered custom property that its syntax does not
+/// match, which makes the property take its initial value.
+#[derive(Debug, Clone)]
+pub struct Mismatch {
+    pub span: Span,
+    pub message: String,
+}
+
+/// The declarations of registered custom properties whose values do not match
+/// the syntax they are registered with. The first registration of a name
+/// wins, so the registrations of the stylesheet itself should come first.
+pub fn check(sheet: &Stylesheet, registrations: &[Registration]) -> Vec<Mismatch> {
+    let text = sheet.text.as_str();
+    let mut out = vec![];
+    for decl in sheet.declarations() {
+        let Some(registration) = registrations.iter().find(|r| r.name == decl.name) else {
+            continue;
+        };
+        if registration.syntax.matches(sheet, decl.value) != Some(false) {
+            continue;
+        }
+        let value = match decl.value.text(text) {
+            "" => "an empty value".to_string(),
+            value => format!("`{value}`"),
+        };
+        out.push(Mismatch {
+            span: if decl.value.start == decl.value.end {
+                decl.name_span
+            } else {
+                decl.value
+            },
+            message: format!(
+                "`{}` is registered with the syntax `{}`, which {value} does not match",
+                decl.name, registration.source
+            ),
+        });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::parser::parse;
+
+    /// Whether the value of a custom property matches the syntax.
+    fn matches(syntax: &str, value: &str) -> Option<bool> {
+        let text = format!("a {{ --x: {value} }}");
+        let sheet = parse(&text);
+        let decl = sheet.declarations()[0];
+        Syntax::parse(syntax).unwrap().matches(&sheet, decl.value)
+    }
+
+    #[test]
+    fn parse_syntax() {
+        assert_eq!(Syntax::parse(" * "), Ok(Syntax::Universal));
+        assert_eq!(
+            Syntax::parse("<length> | auto"),
+            Ok(Syntax::Components(vec![
+                Component {
+                    kind: ComponentKind::Type(DataType::Length),
+                    multiplier: None,
+                },
+                Component {
+                    kind: ComponentKind::Keyword("auto".to_string()),
+                    multiplier: None,
+                },
+            ]))
+        );
+        assert_eq!(
+            Syntax::parse("<color>#"),
+            Ok(Syntax::Components(vec![Component {
+                kind: ComponentKind::Type(DataType::Color),
+                multiplier: Some(Multiplier::Comma),
+            }]))
+        );
+        let errors = [
+            ("", "The syntax is empty; use `\"*\"` to accept any value"),
+            ("<length> |", "Expected a component on both sides of `|`"),
+            ("<size>", "Unknown data type `<size>`"),
+            (
+                "<transform-list>+",
+                "`<transform-list>` is already a list and cannot take a multiplier",
+            ),
+            (
+                "inherit",
+                "Expected a data type such as `<length>` or a keyword instead of `inherit`",
+            ),
+            (
+                "1px",
+                "Expected a data type such as `<length>` or a keyword instead of `1px`",
+            ),
+        ];
+        for (syntax, error) in errors {
+            assert_eq!(Syntax::parse(syntax), Err(error.to_string()), "{syntax}");
+        }
+    }
+
+    #[test]
+    fn data_types() {
+        let cases = [
+            ("<length>", "10px", true),
+            ("<length>", "0", true),
+            ("<length>", "10", false),
+            ("<length>", "50%", false),
+            ("<length-percentage>", "50%", true),
+            ("<number>", "1.5", true),
+            ("<integer>", "2", true),
+            ("<integer>", "1.5", false),
+            ("<angle>", "90deg", true),
+            ("<time>", "200ms", true),
+            ("<time>", "200px", false),
+            ("<resolution>", "2dppx", true),
+            ("<color>", "#fff", true),
+            ("<color>", "#ffff0", false),
+            ("<color>", "rebeccapurple", true),
+            ("<color>", "currentColor", true),
+            ("<color>", "rgb(0 0 0)", true),
+            ("<color>", "10px", false),
+            ("<custom-ident>", "slide", true),
+            ("<custom-ident>", "default", false),
+            ("<string>", "'a'", true),
+            ("<url>", "url(a.png)", true),
+            ("<image>", "linear-gradient(red, blue)", true),
+            ("<transform-function>", "rotate(45deg)", true),
+            ("<transform-list>", "rotate(45deg) scale(2)", true),
+            ("auto", "auto", true),
+            ("auto", "none", false),
+        ];
+        for (syntax, value, expected) in cases {
+            assert_eq!(matches(syntax, value), Some(expected), "{value} against {syntax}");
+        }
+    }
+
+    #[test]
+    fn math_functions() {
+        assert_eq!(matches("<length>", "calc(1px + 2em)"), Some(true));
+        assert_eq!(matches("<length>", "calc(2 * 3)"), Some(false));
+        assert_eq!(matches("<number>", "calc(2 * 3)"), Some(true));
+        assert_eq!(matches("<angle>", "min(10deg, 20deg)"), Some(true));
+    }
+
+    #[test]
+    fn multipliers() {
+        assert_eq!(matches("<length>+", "1px 2px 3px"), Some(true));
+        assert_eq!(matches("<length>+", "1px, 2px"), Some(false));
+        assert_eq!(matches("<length>#", "1px, 2px"), Some(true));
+        assert_eq!(matches("<length>#", "1px 2px"), Some(false));
+        assert_eq!(matches("<length>#", "1px,"), Some(false));
+        assert_eq!(matches("<length>", "1px 2px"), Some(false));
+        assert_eq!(matches("<length> | auto", "auto"), Some(true));
+    }
+
+    #[test]
+    fn known_at_computed_value_time() {
+        assert_eq!(matches("<length>", "inherit"), None);
+        assert_eq!(matches("<length>", "UNSET"), None);
+        assert_eq!(matches("<length>", "var(--y)"), None);
+        assert_eq!(matches("<length>", "calc(var(--y) * 2)"), None);
+        assert_eq!(matches("*", "anything at all"), Some(true));
+    }
+
+    #[test]
+    fn string_contents_without_quotes() {
+        assert_eq!(string_contents("'<length>'"), "<length>");
+        assert_eq!(string_contents("\"*\""), "*");
+        assert_eq!(string_contents("'unclosed"), "unclosed");
+        assert_eq!(string_contents("'"), "");
+    }
+
+    #[test]
+    fn valid_registrations() {
+        let sheet = parse(
+            "@property --size { syntax: '<length>'; inherits: false; initial-value: 0px }
+             @property --any { syntax: '*'; inherits: true }
+             @property --no-inherits { syntax: '*' }
+             @property --no-initial { syntax: '<color>'; inherits: false }
+             @property --bad { syntax: '<size>'; inherits: false; initial-value: 0 }
+             @property --unquoted { syntax: <length>; inherits: false; initial-value: 0 }
+             @property not-custom { syntax: '*'; inherits: false }",
+        );
+        let registrations: Vec<(String, Syntax, String)> = registrations(&sheet)
+            .into_iter()
+            .map(|r| (r.name, r.syntax, r.source))
+            .collect();
+        assert_eq!(
+            registrations,
+            vec![
+                (
+                    "--size".to_string(),
+                    Syntax::parse("<length>").unwrap(),
+                    "<length>".to_string()
+                ),
+                ("--any".to_string(), Syntax::Universal, "*".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn mismatches() {
+        let text = "@property --size { syntax: '<length>'; inherits: false; initial-value: 0px }
+            a { --size: red; --size: 10px; --size: inherit; --size: var(--b); --size:; --other: red }";
+        let sheet = parse(text);
+        let mismatches: Vec<(&str, String)> = check(&sheet, &registrations(&sheet))
+            .into_iter()
+            .map(|m| (m.span.text(text), m.message))
+            .collect();
+        assert_eq!(
+            mismatches,
+            vec![
+                (
+                    "red",
+                    "`--size` is registered with the syntax `<length>`, which `red` does not match".to_string()
+                ),
+                (
+                    "--size",
+                    "`--size` is registered with the syntax `<length>`, which an empty value does not match"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn first_registration_wins() {
+        let text = "a { --size: 10px }";
+        let sheet = parse(text);
+        let registration = |syntax: &str| Registration {
+            name: "--size".to_string(),
+            syntax: Syntax::parse(syntax).unwrap(),
+            source: syntax.to_string(),
+        };
+        assert!(check(&sheet, &[registration("<length>"), registration("<color>")]).is_empty());
+        assert_eq!(
+            check(&sheet, &[registration("<color>"), registration("<length>")]).len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,506 @@
+use super::{
+    Span,
+    syntax::{self, Item, Rule, Stylesheet},
+    token::{Token, TokenKind, tokenize},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NameKind {
+    Class,
+    Id,
+}
+
+/// A class or id selector, such as `.a` or `#b`.
+#[derive(Debug, Clone)]
+pub struct SelectorName {
+    pub kind: NameKind,
+    /// The unescaped name without the `.` or `#`.
+    pub name: String,
+    /// Includes the `.` or `#`.
+    pub span: Span,
+    /// Inside a functional pseudo-class such as `:not()` or `:is()`, where
+    /// the name need not match anything for the selector to match.
+    pub nested: bool,
+}
+
+/// Every class and id selector in the stylesheet, in source order.
+pub fn names(sheet: &Stylesheet) -> Vec<SelectorName> {
+    rule_names(sheet, &sheet.rules)
+}
+
+/// The names in the selectors of some rules of a stylesheet.
+pub fn rule_names(sheet: &Stylesheet, rules: &[Rule]) -> Vec<SelectorName> {
+    fn walk(sheet: &Stylesheet, rule: &Rule, out: &mut Vec<SelectorName>) {
+        if let Rule::Style(rule) = rule {
+            selector_names(sheet, rule.selector, out);
+        }
+        let Some(block) = rule.block() else { return };
+        for item in &block.items {
+            if let Item::Rule(rule) = item {
+                walk(sheet, rule, out);
+            }
+        }
+    }
+
+    let mut out = vec![];
+    for rule in rules {
+        walk(sheet, rule, &mut out);
+    }
+    out
+}
+
+fn selector_names(sheet: &Stylesheet, span: Span, out: &mut Vec<SelectorName>) {
+    let text = sheet.text.as_str();
+    let tokens = sheet.tokens_in(span);
+    let mut depth = 0;
+    let mut brackets = 0;
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+        match token.kind {
+            TokenKind::LeftBracket => brackets += 1,
+            TokenKind::RightBracket => brackets -= 1,
+            _ if brackets > 0 => {}
+            TokenKind::Function | TokenKind::LeftParen => depth += 1,
+            TokenKind::RightParen => depth -= 1,
+            TokenKind::Delim if token.text(text) == "." => {
+                if let Some(name) = tokens.get(i + 1).filter(|t| t.kind == TokenKind::Ident) {
+                    out.push(SelectorName {
+                        kind: NameKind::Class,
+                        name: unescape(name.text(text)),
+                        span: Span::new(token.span.start, name.span.end),
+                        nested: depth > 0,
+                    });
+                    i += 1;
+                }
+            }
+            TokenKind::Hash => out.push(SelectorName {
+                kind: NameKind::Id,
+                name: unescape(token.name(text)),
+                span: token.span,
+                nested: depth > 0,
+            }),
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
+/// Resolves the escapes in an identifier, e.g. `sm\:p-4` to `sm:p-4`.
+fn unescape(ident: &str) -> String {
+    let mut out = String::with_capacity(ident.len());
+    let mut chars = ident.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        let mut hex = String::new();
+        while hex.len() < 6 && chars.peek().is_some_and(char::is_ascii_hexdigit) {
+            hex.extend(chars.next());
+        }
+        if hex.is_empty() {
+            out.extend(chars.next());
+            continue;
+        }
+        if chars.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            chars.next();
+        }
+        let code = u32::from_str_radix(&hex, 16).unwrap_or(0xFFFD);
+        out.push(char::from_u32(code).filter(|&c| c != '\0').unwrap_or('\u{FFFD}'));
+    }
+    out
+}
+
+/// Selector specificity as (ids, classes, types).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Specificity(pub u32, pub u32, pub u32);
+
+impl std::ops::Add for Specificity {
+    type Output = Specificity;
+
+    fn add(self, other: Self) -> Self {
+        Specificity(self.0 + other.0, self.1 + other.1, self.2 + other.2)
+    }
+}
+
+/// Pseudo-elements that may be written with a single colon.
+const LEGACY_PSEUDO_ELEMENTS: &[&str] = &["after", "before", "first-letter", "first-line"];
+
+/// Splits a selector list into its complex selectors, without surrounding
+/// whitespace.
+pub fn complex_selectors(sheet: &Stylesheet, span: Span) -> Vec<Span> {
+    split_list(sheet.tokens_in(span))
+        .into_iter()
+        .filter_map(|tokens| Some(Span::new(tokens.first()?.span.start, tokens.last()?.span.end)))
+        .collect()
+}
+
+/// Splits tokens on top-level commas, trimming trivia.
+fn split_list(tokens: &[Token]) -> Vec<&[Token]> {
+    let mut out = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        match token.kind {
+            TokenKind::Function | TokenKind::LeftParen | TokenKind::LeftBracket => depth += 1,
+            TokenKind::RightParen | TokenKind::RightBracket => depth -= 1,
+            TokenKind::Comma if depth == 0 => {
+                out.push(trim(&tokens[start..i]));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    out.push(trim(&tokens[start..]));
+    out.retain(|tokens| !tokens.is_empty());
+    out
+}
+
+fn trim(tokens: &[Token]) -> &[Token] {
+    let start = tokens.iter().position(|t| !t.is_trivia()).unwrap_or(tokens.len());
+    let end = tokens.iter().rposition(|t| !t.is_trivia()).map_or(start, |i| i + 1);
+    &tokens[start..end]
+}
+
+/// The specificity of a complex selector, such as one resolved by
+/// `resolve`.
+pub fn specificity(selector: &str) -> Specificity {
+    tokens_specificity(selector, &tokenize(selector))
+}
+
+/// The complex selectors of a style rule as if it were not nested: each `&`
+/// stands for `:is()` of the selectors of the parent rule, and a selector
+/// without `&` is relative to them, as a descendant unless it starts with a
+/// combinator. Outside of a style rule, where `parent` is empty, `&` is
+/// `:scope`.
+pub fn resolve(sheet: &Stylesheet, span: Span, parent: &[String]) -> Vec<String> {
+    let text = sheet.text.as_str();
+    // A single parent is written as is where that reads the same, which is
+    // at the start of the selector or when it is a single compound.
+    let (bare, wrapped) = match parent {
+        [] => (":scope".to_string(), ":scope".to_string()),
+        [selector] if split_compounds(selector) => (selector.clone(), format!(":is({selector})")),
+        [selector] => (selector.clone(), selector.clone()),
+        selectors => {
+            let list = format!(":is({})", selectors.join(", "));
+            (list.clone(), list)
+        }
+    };
+    complex_selectors(sheet, span)
+        .into_iter()
+        .map(|complex| {
+            let tokens = sheet.tokens_in(complex);
+            let is_nesting = |token: &Token| token.kind == TokenKind::Delim && token.text(text) == "&";
+            if !tokens.iter().any(is_nesting) {
+                return match parent.is_empty() {
+                    true => complex.text(text).to_string(),
+                    false => format!("{bare} {}", complex.text(text)),
+                };
+            }
+            let mut out = String::new();
+            let mut copied = complex.start;
+            for (i, token) in tokens.iter().enumerate().filter(|(_, token)| is_nesting(token)) {
+                out.push_str(&text[copied..token.span.start]);
+                out.push_str(if i == 0 { &bare } else { &wrapped });
+                copied = token.span.end;
+            }
+            out.push_str(&text[copied..complex.end]);
+            out
+        })
+        .collect()
+}
+
+/// Whether a complex selector has combinators, so that it cannot be
+/// written in the middle of another without `:is()`.
+fn split_compounds(selector: &str) -> bool {
+    let tokens = tokenize(selector);
+    let mut depth = 0;
+    tokens.iter().any(|token| {
+        match token.kind {
+            TokenKind::Function | TokenKind::LeftParen | TokenKind::LeftBracket => depth += 1,
+            TokenKind::RightParen | TokenKind::RightBracket => depth -= 1,
+            _ => {}
+        }
+        depth == 0
+            && (token.is_trivia()
+                || (token.kind == TokenKind::Delim && matches!(token.text(selector), ">" | "+" | "~")))
+    })
+}
+
+/// The style rule around `offset` if it is nested in another, with its
+/// selectors resolved.
+pub fn nested_rule_at(sheet: &Stylesheet, offset: usize) -> Option<(Span, Vec<String>)> {
+    fn walk(
+        sheet: &Stylesheet,
+        rules: &mut dyn Iterator<Item = &Rule>,
+        parent: &[String],
+        offset: usize,
+    ) -> Option<(Span, Vec<String>)> {
+        for rule in rules {
+            let Some(block) = rule.block() else { continue };
+            if offset < rule.start() || block.span.end < offset {
+                continue;
+            }
+            let selectors = match rule {
+                Rule::Style(style) => {
+                    let selectors = resolve(sheet, style.selector, parent);
+                    let span = style.selector;
+                    if !parent.is_empty() && span.start <= offset && offset <= span.end {
+                        return Some((span, selectors));
+                    }
+                    selectors
+                }
+                Rule::At(_) => parent.to_vec(),
+            };
+            let mut nested = block.items.iter().filter_map(|item| match item {
+                Item::Rule(rule) => Some(rule),
+                Item::Declaration(_) => None,
+            });
+            return walk(sheet, &mut nested, &selectors, offset);
+        }
+        None
+    }
+
+    walk(sheet, &mut sheet.rules.iter(), &[], offset)
+}
+
+fn tokens_specificity(text: &str, tokens: &[Token]) -> Specificity {
+    let mut total = Specificity::default();
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+        match token.kind {
+            TokenKind::Hash => total.0 += 1,
+            TokenKind::Delim if token.text(text) == "." => {
+                total.1 += 1;
+                i += 1;
+            }
+            TokenKind::LeftBracket => {
+                total.1 += 1;
+                i = syntax::matching_close(tokens, i);
+            }
+            TokenKind::Ident => total.2 += 1,
+            TokenKind::Colon => {
+                let element = tokens.get(i + 1).is_some_and(|t| t.kind == TokenKind::Colon);
+                if element {
+                    i += 1;
+                }
+                let Some(name) = tokens.get(i + 1) else { break };
+                i += 1;
+                let lower = name.name(text).to_ascii_lowercase();
+                match name.kind {
+                    _ if element || LEGACY_PSEUDO_ELEMENTS.contains(&lower.as_str()) => {
+                        total.2 += 1;
+                        if name.kind == TokenKind::Function {
+                            i = syntax::matching_close(tokens, i);
+                        }
+                    }
+                    TokenKind::Function => {
+                        let close = syntax::matching_close(tokens, i);
+                        let args = &tokens[i + 1..close.min(tokens.len())];
+                        total = total + pseudo_function(text, &lower, args);
+                        i = close;
+                    }
+                    _ => total.1 += 1,
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    total
+}
+
+/// The specificity of a functional pseudo-class given its arguments.
+fn pseudo_function(text: &str, name: &str, args: &[Token]) -> Specificity {
+    let most_specific = |tokens: &[Token]| {
+        split_list(tokens)
+            .into_iter()
+            .map(|selector| tokens_specificity(text, selector))
+            .max()
+            .unwrap_or_default()
+    };
+    match name {
+        "where" => Specificity::default(),
+        "is" | "not" | "has" | "matches" | "-webkit-any" | "-moz-any" => most_specific(args),
+        "nth-child" | "nth-last-child" => {
+            let of = args
+                .iter()
+                .position(|t| t.kind == TokenKind::Ident && t.text(text).eq_ignore_ascii_case("of"));
+            let selectors = of.map_or(Specificity::default(), |of| most_specific(&args[of + 1..]));
+            Specificity(0, 1, 0) + selectors
+        }
+        _ => Specificity(0, 1, 0),
+    }
+}
+
+/// The simple selectors of the last compound selector of a complex selector,
+/// the one naming the element it matches. Pseudo-elements are kept apart
+/// since they name a different box.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Subject {
+    pub simple: Vec<String>,
+    pub pseudo_element: Option<String>,
+}
+
+impl Subject {
+    /// Whether the two compounds may match the same element: one of them is
+    /// a less specific form of the other, like `.btn` and `.btn:hover`.
+    pub fn overlaps(&self, other: &Subject) -> bool {
+        let subset = |a: &Subject, b: &Subject| a.simple.iter().all(|s| b.simple.contains(s));
+        self.pseudo_element == other.pseudo_element && (subset(self, other) || subset(other, self))
+    }
+}
+
+pub fn subject(selector: &str) -> Subject {
+    let text = selector;
+    let tokens = &tokenize(selector)[..];
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        match token.kind {
+            TokenKind::Function | TokenKind::LeftParen | TokenKind::LeftBracket => depth += 1,
+            TokenKind::RightParen | TokenKind::RightBracket => depth -= 1,
+            _ if depth > 0 => {}
+            TokenKind::Whitespace | TokenKind::Comment => start = i + 1,
+            TokenKind::Delim if matches!(token.text(text), ">" | "+" | "~") => start = i + 1,
+            _ => {}
+        }
+    }
+
+    let compound = &tokens[start..];
+    let mut subject = Subject {
+        simple: vec![],
+        pseudo_element: None,
+    };
+    let mut i = 0;
+    while i < compound.len() {
+        let begin = i;
+        match compound[i].kind {
+            TokenKind::Delim if compound[i].text(text) == "." => i += 1,
+            TokenKind::LeftBracket => i = syntax::matching_close(compound, i),
+            TokenKind::Colon => {
+                if compound.get(i + 1).is_some_and(|t| t.kind == TokenKind::Colon) {
+                    i += 1;
+                }
+                i += 1;
+                if compound.get(i).is_some_and(|t| t.kind == TokenKind::Function) {
+                    i = syntax::matching_close(compound, i);
+                }
+            }
+            _ => {}
+        }
+        let end = i.min(compound.len() - 1);
+        let simple = Span::new(compound[begin].span.start, compound[end].span.end).text(text);
+        let simple = simple.to_ascii_lowercase();
+        let name = simple.trim_start_matches(':');
+        if simple.starts_with("::") || (simple.starts_with(':') && LEGACY_PSEUDO_ELEMENTS.contains(&name)) {
+            subject.pseudo_element = Some(name.to_string());
+        } else if simple != "*" {
+            subject.simple.push(simple);
+        }
+        i += 1;
+    }
+    subject
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::parser::parse;
+
+    /// The resolved selectors of the nested rule whose selector has the `|`.
+    fn nested(marked: &str) -> Option<Vec<String>> {
+        let offset = marked.find('|').unwrap();
+        let sheet = parse(&marked.replace('|', ""));
+        let (span, selectors) = nested_rule_at(&sheet, offset)?;
+        assert!(span.start <= offset && offset <= span.end);
+        Some(selectors)
+    }
+
+    fn resolved(marked: &str) -> Vec<String> {
+        nested(marked).unwrap()
+    }
+
+    #[test]
+    fn leading_nesting_selector() {
+        assert_eq!(resolved(".a { &:hover| {} }"), [".a:hover"]);
+        assert_eq!(resolved(".a .b { &.c| {} }"), [".a .b.c"]);
+        assert_eq!(resolved(".a { & > .b| {} }"), [".a > .b"]);
+    }
+
+    #[test]
+    fn nesting_selector_elsewhere() {
+        // A single compound reads the same without `:is()`.
+        assert_eq!(resolved(".a { .b &| {} }"), [".b .a"]);
+        assert_eq!(resolved(".a .b { .c &| {} }"), [".c :is(.a .b)"]);
+        assert_eq!(resolved(".a > .b { & + &| {} }"), [".a > .b + :is(.a > .b)"]);
+    }
+
+    #[test]
+    fn implicit_descendant() {
+        assert_eq!(resolved(".a { .b| {} }"), [".a .b"]);
+        assert_eq!(resolved(".a { > .b| {} }"), [".a > .b"]);
+        assert_eq!(resolved(".a { .b { .c| {} } }"), [".a .b .c"]);
+        // Conditional rules are transparent.
+        assert_eq!(resolved(".a { @media print { .b:hover| {} } }"), [".a .b:hover"]);
+    }
+
+    #[test]
+    fn several_parents() {
+        assert_eq!(resolved(".a, #b { .c| {} }"), [":is(.a, #b) .c"]);
+        assert_eq!(
+            resolved(".a, #b { &.c, & + &| {} }"),
+            [":is(.a, #b).c", ":is(.a, #b) + :is(.a, #b)"]
+        );
+        assert_eq!(
+            resolved(".a, .b { .c, .d { &:hover| {} } }"),
+            [":is(:is(.a, .b) .c, :is(.a, .b) .d):hover"]
+        );
+    }
+
+    #[test]
+    fn outside_nested_rules() {
+        assert_eq!(nested(".a| {}"), None);
+        assert_eq!(nested(".a { .b { co|lor: red } }"), None);
+        assert_eq!(nested("@media print { .a| {} }"), None);
+        // Where `&` is `:scope`.
+        let sheet = parse("& .a {}");
+        let Rule::Style(rule) = &sheet.rules[0] else {
+            unreachable!()
+        };
+        assert_eq!(resolve(&sheet, rule.selector, &[]), [":scope .a"]);
+    }
+
+    #[test]
+    fn resolved_specificity() {
+        let cases = [
+            (".a { &:hover| {} }", Specificity(0, 2, 0)),
+            (".a { .b| {} }", Specificity(0, 2, 0)),
+            // `:is()` counts its most specific argument.
+            (".a, #b { .c| {} }", Specificity(1, 1, 0)),
+            (".a, #b { & + &| {} }", Specificity(2, 0, 0)),
+            (".a p { .c &| {} }", Specificity(0, 2, 1)),
+        ];
+        for (marked, expected) in cases {
+            let selectors = resolved(marked);
+            assert_eq!(specificity(&selectors[0]), expected, "{marked}");
+        }
+    }
+
+    #[test]
+    fn specificities() {
+        let cases = [
+            ("p::before", Specificity(0, 0, 2)),
+            ("a:before", Specificity(0, 0, 2)),
+            (":where(#a) p", Specificity(0, 0, 1)),
+            (":not(.a, #b)", Specificity(1, 0, 0)),
+            ("li:nth-child(2n of .a, #b)", Specificity(1, 1, 1)),
+            ("[type=text]:focus", Specificity(0, 2, 0)),
+        ];
+        for (selector, expected) in cases {
+            assert_eq!(specificity(selector), expected, "{selector}");
+        }
+    }
+}
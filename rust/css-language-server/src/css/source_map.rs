@@ -0,0 +1,115 @@
+//! Version 3 source maps, as specified by ECMA-426, for the output of the
+//! printer.
+
+use serde_json::{Value, json};
+
+use super::format::Mapping;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The source map of `generated`, which is served as `file`, back to
+/// `source`, which is served as `source_name`. The source is embedded, so the
+/// map is enough to show it.
+pub fn source_map(file: &str, generated: &str, source_name: &str, source: &str, mappings: &[Mapping]) -> Value {
+    json!({
+        "version": 3,
+        "file": file,
+        "sources": [source_name],
+        "sourcesContent": [source],
+        "names": [],
+        "mappings": encode(generated, source, mappings),
+    })
+}
+
+/// The `mappings` field: a group of segments per generated line, each
+/// segment relative to the one before.
+fn encode(generated: &str, source: &str, mappings: &[Mapping]) -> String {
+    let mut out = String::new();
+    let mut generated_cursor = Cursor::new(generated);
+    let mut source_cursor = Cursor::new(source);
+    let (mut line, mut column) = (0, 0);
+    let (mut source_line, mut source_column) = (0, 0);
+    let mut first = true;
+    let mut last = None;
+    for mapping in mappings {
+        // Of several mappings to the same output, the first is kept.
+        if last == Some(mapping.generated) {
+            continue;
+        }
+        last = Some(mapping.generated);
+        let (generated_line, generated_column) = generated_cursor.advance(mapping.generated);
+        let (mapped_line, mapped_column) = source_cursor.advance(mapping.source);
+        while line < generated_line {
+            out.push(';');
+            line += 1;
+            column = 0;
+            first = true;
+        }
+        if !first {
+            out.push(',');
+        }
+        first = false;
+        vlq(&mut out, generated_column as i64 - column as i64);
+        vlq(&mut out, 0);
+        vlq(&mut out, mapped_line as i64 - source_line as i64);
+        vlq(&mut out, mapped_column as i64 - source_column as i64);
+        column = generated_column;
+        (source_line, source_column) = (mapped_line, mapped_column);
+    }
+    out
+}
+
+/// Appends `value` as a base64 variable-length quantity: five bits per digit,
+/// least significant first, with the sign in the lowest bit.
+fn vlq(out: &mut String, value: i64) {
+    let mut rest = (value.unsigned_abs() << 1) | u64::from(value < 0);
+    loop {
+        let mut digit = (rest & 31) as usize;
+        rest >>= 5;
+        if rest > 0 {
+            digit |= 32;
+        }
+        out.push(BASE64[digit] as char);
+        if rest == 0 {
+            break;
+        }
+    }
+}
+
+/// Converts byte offsets of a text to zero-based lines and columns in UTF-16
+/// code units. Offsets are expected in ascending order, so that a minified
+/// stylesheet on a single line is not counted over for each one.
+struct Cursor<'a> {
+    text: &'a str,
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            offset: 0,
+            line: 0,
+            column: 0,
+        }
+    }
+
+    fn advance(&mut self, offset: usize) -> (usize, usize) {
+        if offset < self.offset {
+            *self = Cursor::new(self.text);
+        }
+        for c in self.text[self.offset..offset].chars() {
+            match c {
+                '\n' => {
+                    self.line += 1;
+                    self.column = 0;
+                }
+                c => self.column += c.len_utf16(),
+            }
+        }
+        self.offset = offset;
+        (self.line, self.column)
+    }
+}
@@ -0,0 +1,326 @@
+use super::{
+    Span,
+    parser::Reparsed,
+    properties,
+    selector::{self, NameKind},
+    syntax::{Item, Rule, Stylesheet},
+    token::TokenKind,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    CustomProperty,
+    Keyframes,
+    Layer,
+    Class,
+    Id,
+}
+
+/// An occurrence of a name that can be declared in one place and referenced
+/// in others.
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub kind: SymbolKind,
+    /// The unescaped name, without the `.` or `#` of selectors.
+    pub name: String,
+    /// The name as written, without the `.` or `#` of selectors or the quotes
+    /// of a keyframes name string.
+    pub span: Span,
+    /// A custom property, `@keyframes` or `@layer` statement declaration as
+    /// opposed to a reference. Selectors are neither.
+    pub declaration: bool,
+}
+
+/// Keywords of `animation` that are not keyframes names.
+const ANIMATION_KEYWORDS: &[&str] = &[
+    "alternate",
+    "alternate-reverse",
+    "auto",
+    "backwards",
+    "both",
+    "ease",
+    "ease-in",
+    "ease-in-out",
+    "ease-out",
+    "forwards",
+    "infinite",
+    "inherit",
+    "initial",
+    "linear",
+    "none",
+    "normal",
+    "paused",
+    "reverse",
+    "revert",
+    "revert-layer",
+    "running",
+    "step-end",
+    "step-start",
+    "unset",
+];
+
+/// Every custom property, keyframes name, layer name, class and id in the
+/// stylesheet, in source order.
+pub fn symbols(sheet: &Stylesheet) -> Vec<Symbol> {
+    rule_symbols(sheet, &sheet.rules)
+}
+
+/// Updates the symbols of a stylesheet after `reparse`. Only those of the
+/// rules parsed again are collected again; the others move.
+pub fn update(symbols: &mut Vec<Symbol>, sheet: &Stylesheet, reparsed: Reparsed) {
+    let Span { start, end } = reparsed.span;
+    let from = symbols.partition_point(|symbol| symbol.span.start < start);
+    let to = symbols.partition_point(|symbol| symbol.span.start < end);
+    for symbol in &mut symbols[to..] {
+        symbol.span.shift(reparsed.delta);
+    }
+    let end = end.wrapping_add_signed(reparsed.delta);
+    let first = sheet.rules.partition_point(|rule| rule.start() < start);
+    let last = sheet.rules.partition_point(|rule| rule.start() < end);
+    symbols.splice(from..to, rule_symbols(sheet, &sheet.rules[first..last]));
+}
+
+/// The symbols of some top-level rules of a stylesheet, in source order.
+fn rule_symbols(sheet: &Stylesheet, list: &[Rule]) -> Vec<Symbol> {
+    let mut out: Vec<Symbol> = selector::rule_names(sheet, list)
+        .into_iter()
+        .map(|name| Symbol {
+            kind: match name.kind {
+                NameKind::Class => SymbolKind::Class,
+                NameKind::Id => SymbolKind::Id,
+            },
+            name: name.name,
+            span: Span::new(name.span.start + 1, name.span.end),
+            declaration: false,
+        })
+        .collect();
+    rules(sheet, list, &mut out);
+    out.sort_by_key(|symbol| symbol.span.start);
+    out
+}
+
+fn rules<'a>(sheet: &Stylesheet, list: impl IntoIterator<Item = &'a Rule>, out: &mut Vec<Symbol>) {
+    let text = sheet.text.as_str();
+    for rule in list {
+        if let Rule::At(rule) = rule {
+            let name = properties::strip_vendor_prefix(&rule.name);
+            let prelude = sheet.tokens_in(rule.prelude).iter().find(|t| !t.is_trivia());
+            match (name, prelude) {
+                ("keyframes", Some(token)) if matches!(token.kind, TokenKind::Ident | TokenKind::String) => {
+                    let span = match token.kind {
+                        TokenKind::String => string_contents(text, token.span),
+                        _ => token.span,
+                    };
+                    if span.start < span.end {
+                        out.push(Symbol {
+                            kind: SymbolKind::Keyframes,
+                            name: span.text(text).to_string(),
+                            span,
+                            declaration: true,
+                        });
+                    }
+                }
+                ("property", Some(token)) if token.kind == TokenKind::Ident && token.text(text).starts_with("--") => {
+                    out.push(Symbol {
+                        kind: SymbolKind::CustomProperty,
+                        name: token.text(text).to_string(),
+                        span: token.span,
+                        declaration: true,
+                    });
+                }
+                // The statement form declares the order of layers, which the
+                // block form and `@import ... layer()` then refer to.
+                ("layer", _) => layers(sheet, rule.prelude, rule.block.is_none(), out),
+                ("import", _) => {
+                    let tokens = sheet.tokens_in(rule.prelude);
+                    let layer = tokens
+                        .iter()
+                        .position(|t| t.kind == TokenKind::Function && t.text(text).eq_ignore_ascii_case("layer("));
+                    if let Some(i) = layer {
+                        let end = tokens[i..].iter().find(|t| t.kind == TokenKind::RightParen);
+                        let end = end.map_or(rule.prelude.end, |t| t.span.start);
+                        layers(sheet, Span::new(tokens[i].span.end, end), false, out);
+                    }
+                    values(sheet, rule.prelude, false, out);
+                }
+                _ => values(sheet, rule.prelude, false, out),
+            }
+        }
+        let Some(block) = rule.block() else { continue };
+        for item in &block.items {
+            match item {
+                Item::Rule(rule) => rules(sheet, [rule], out),
+                Item::Declaration(decl) => {
+                    // `--color-*` in `@theme` resets a namespace of
+                    // variables rather than declaring one.
+                    if decl.name.starts_with("--") && !decl.name.ends_with('*') {
+                        out.push(Symbol {
+                            kind: SymbolKind::CustomProperty,
+                            name: decl.name.clone(),
+                            span: decl.name_span,
+                            declaration: true,
+                        });
+                    }
+                    let name = properties::strip_vendor_prefix(&decl.name).to_ascii_lowercase();
+                    let animation = matches!(name.as_str(), "animation" | "animation-name");
+                    values(sheet, decl.value, animation, out);
+                }
+            }
+        }
+    }
+}
+
+/// Custom property references in a value and, in `animation`, the keyframes
+/// names outside of functions.
+fn values(sheet: &Stylesheet, span: Span, animation: bool, out: &mut Vec<Symbol>) {
+    let text = sheet.text.as_str();
+    let mut depth = 0;
+    for token in sheet.tokens_in(span) {
+        match token.kind {
+            TokenKind::Function | TokenKind::LeftParen => depth += 1,
+            TokenKind::RightParen => depth -= 1,
+            TokenKind::Ident if token.text(text).starts_with("--") => out.push(Symbol {
+                kind: SymbolKind::CustomProperty,
+                name: token.text(text).to_string(),
+                span: token.span,
+                declaration: false,
+            }),
+            TokenKind::Ident | TokenKind::String if animation && depth == 0 => {
+                let (name, span) = match token.kind {
+                    TokenKind::String => {
+                        let span = string_contents(text, token.span);
+                        (span.text(text), span)
+                    }
+                    _ => (token.text(text), token.span),
+                };
+                if !name.is_empty() && !ANIMATION_KEYWORDS.contains(&name.to_ascii_lowercase().as_str()) {
+                    out.push(Symbol {
+                        kind: SymbolKind::Keyframes,
+                        name: name.to_string(),
+                        span,
+                        declaration: false,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The comma separated layer names in `span`, such as `base, theme.dark`.
+fn layers(sheet: &Stylesheet, span: Span, declaration: bool, out: &mut Vec<Symbol>) {
+    let text = sheet.text.as_str();
+    let mut name: Option<Span> = None;
+    let push = |name: Option<Span>, out: &mut Vec<Symbol>| {
+        if let Some(mut span) = name {
+            if span.text(text).ends_with('.') {
+                span.end -= 1;
+            }
+            out.push(Symbol {
+                kind: SymbolKind::Layer,
+                name: span.text(text).to_string(),
+                span,
+                declaration,
+            });
+        }
+    };
+    for token in sheet.tokens_in(span).iter().filter(|t| !t.is_trivia()) {
+        match (token.kind, name) {
+            (TokenKind::Comma, _) => push(name.take(), out),
+            (TokenKind::Ident, None) => name = Some(token.span),
+            // A segment of a dotted name: `.` then an identifier, unspaced.
+            (_, Some(span)) if span.end == token.span.start => {
+                let segment = match span.text(text).ends_with('.') {
+                    true => token.kind == TokenKind::Ident,
+                    false => token.kind == TokenKind::Delim && token.text(text) == ".",
+                };
+                if segment {
+                    name = Some(Span::new(span.start, token.span.end));
+                }
+            }
+            _ => {}
+        }
+    }
+    push(name, out);
+}
+
+/// The contents of a string token without its quotes. A string left open at
+/// the end of the text has no closing quote.
+fn string_contents(text: &str, span: Span) -> Span {
+    let token = span.text(text);
+    let closed = token.len() > 1 && token.ends_with(&token[..1]);
+    Span::new(span.start + 1, if closed { span.end - 1 } else { span.end })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::parser::{parse, reparse};
+
+    fn listed(text: &str, symbols: &[Symbol]) -> Vec<(SymbolKind, String, String, bool)> {
+        symbols
+            .iter()
+            .map(|symbol| {
+                let written = symbol.span.text(text).to_string();
+                (symbol.kind, symbol.name.clone(), written, symbol.declaration)
+            })
+            .collect()
+    }
+
+    fn spans(symbols: &[Symbol]) -> Vec<(usize, usize)> {
+        symbols
+            .iter()
+            .map(|symbol| (symbol.span.start, symbol.span.end))
+            .collect()
+    }
+
+    #[test]
+    fn kinds() {
+        let text = "@layer base, theme.dark;\n\
+                    @import url(a.css) layer(theme.light);\n\
+                    @property --x { syntax: '*' }\n\
+                    @keyframes \"spin\" {}\n\
+                    @layer base { .a#b { --y: var(--x); animation: 1s spin infinite, 'fade' } }";
+        use SymbolKind::*;
+        let symbol = |kind, name: &str, declaration| (kind, name.to_string(), name.to_string(), declaration);
+        assert_eq!(
+            listed(text, &symbols(&parse(text))),
+            [
+                symbol(Layer, "base", true),
+                symbol(Layer, "theme.dark", true),
+                symbol(Layer, "theme.light", false),
+                symbol(CustomProperty, "--x", true),
+                symbol(Keyframes, "spin", true),
+                symbol(Layer, "base", false),
+                symbol(Class, "a", false),
+                symbol(Id, "b", false),
+                symbol(CustomProperty, "--y", true),
+                symbol(CustomProperty, "--x", false),
+                symbol(Keyframes, "spin", false),
+                symbol(Keyframes, "fade", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn update_matches_a_full_collection() {
+        let text = ".a{--b:var(--c)}@keyframes 'd'{}#e{animation:d}@layer f{.g{}}";
+        let replacements = ["", "}", "{", ".x", "--y:1;", "'", " h{animation:'i'} "];
+        for start in 0..=text.len() {
+            for end in start..=text.len() {
+                for replacement in replacements {
+                    let old = parse(text);
+                    let mut updated = symbols(&old);
+                    let (sheet, reparsed) = reparse(old, Span::new(start, end), replacement);
+                    update(&mut updated, &sheet, reparsed);
+                    assert_eq!(
+                        listed(&sheet.text, &updated),
+                        listed(&sheet.text, &symbols(&sheet)),
+                        "{start}..{end} replaced with {replacement:?} in {text:?}"
+                    );
+                    assert_eq!(spans(&updated), spans(&symbols(&sheet)));
+                }
+            }
+        }
+    }
+}
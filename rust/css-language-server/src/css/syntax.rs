@@ -0,0 +1,243 @@
+use super::{
+    Span,
+    token::{Token, TokenKind},
+};
+
+/// A parsed stylesheet. It owns its source text and the full token stream, so
+/// every node can be mapped back to the exact bytes it was parsed from.
+#[derive(Debug)]
+pub struct Stylesheet {
+    pub text: String,
+    pub tokens: Vec<Token>,
+    pub rules: Vec<Rule>,
+    pub errors: Vec<SyntaxError>,
+}
+
+#[derive(Debug)]
+pub enum Rule {
+    Style(StyleRule),
+    At(AtRule),
+}
+
+/// A rule such as `a:hover { color: red }`.
+#[derive(Debug)]
+pub struct StyleRule {
+    pub selector: Span,
+    pub block: Block,
+}
+
+/// A rule such as `@media screen { ... }` or `@import "a.css";`.
+#[derive(Debug)]
+pub struct AtRule {
+    /// The lowercased name without the `@`.
+    pub name: String,
+    pub name_span: Span,
+    pub prelude: Span,
+    pub block: Option<Block>,
+}
+
+/// The contents of a `{ ... }` block; `span` includes the braces.
+#[derive(Debug)]
+pub struct Block {
+    pub span: Span,
+    pub items: Vec<Item>,
+}
+
+#[derive(Debug)]
+pub enum Item {
+    Declaration(Declaration),
+    Rule(Rule),
+}
+
+#[derive(Debug)]
+pub struct Declaration {
+    /// From the start of the name to the end of the value, including
+    /// `!important` but not the `;`.
+    pub span: Span,
+    /// The property name as written. Custom properties are case-sensitive, so
+    /// this is not lowercased.
+    pub name: String,
+    pub name_span: Span,
+    /// The value without surrounding whitespace and without `!important`.
+    pub value: Span,
+    /// Whether the value is followed by `!important`.
+    pub important: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct SyntaxError {
+    pub span: Span,
+    pub message: String,
+}
+
+impl Rule {
+    pub fn block(&self) -> Option<&Block> {
+        match self {
+            Rule::Style(rule) => Some(&rule.block),
+            Rule::At(rule) => rule.block.as_ref(),
+        }
+    }
+
+    /// The offset of the first token of the rule.
+    pub fn start(&self) -> usize {
+        match self {
+            Rule::Style(rule) => rule.selector.start,
+            Rule::At(rule) => rule.name_span.start,
+        }
+    }
+
+    /// Moves every span of the rule by `delta` bytes.
+    pub fn shift(&mut self, delta: isize) {
+        match self {
+            Rule::Style(rule) => {
+                rule.selector.shift(delta);
+                rule.block.shift(delta);
+            }
+            Rule::At(rule) => {
+                rule.name_span.shift(delta);
+                rule.prelude.shift(delta);
+                if let Some(block) = &mut rule.block {
+                    block.shift(delta);
+                }
+            }
+        }
+    }
+}
+
+impl Block {
+    fn shift(&mut self, delta: isize) {
+        self.span.shift(delta);
+        for item in &mut self.items {
+            match item {
+                Item::Declaration(decl) => {
+                    decl.span.shift(delta);
+                    decl.name_span.shift(delta);
+                    decl.value.shift(delta);
+                }
+                Item::Rule(rule) => rule.shift(delta),
+            }
+        }
+    }
+}
+
+impl Stylesheet {
+    /// Tokens that lie entirely within `span`.
+    pub fn tokens_in(&self, span: Span) -> &[Token] {
+        let start = self.tokens.partition_point(|t| t.span.start < span.start);
+        let end = self.tokens.partition_point(|t| t.span.end <= span.end);
+        &self.tokens[start..end.max(start)]
+    }
+
+    /// Every declaration in the stylesheet, including those nested in
+    /// at-rules, in source order.
+    pub fn declarations(&self) -> Vec<&Declaration> {
+        fn walk<'a>(rules: &'a [Rule], out: &mut Vec<&'a Declaration>) {
+            for rule in rules {
+                let Some(block) = rule.block() else { continue };
+                for item in &block.items {
+                    match item {
+                        Item::Declaration(decl) => out.push(decl),
+                        Item::Rule(rule) => walk(std::slice::from_ref(rule), out),
+                    }
+                }
+            }
+        }
+
+        let mut out = vec![];
+        walk(&self.rules, &mut out);
+        out
+    }
+
+    /// The unclosed function calls before `offset`, innermost first. The
+    /// search starts after the last `;`, `{` or `}`, so that a parenthesis left
+    /// open in an earlier declaration does not count.
+    pub fn calls_at(&self, offset: usize) -> Vec<Call> {
+        let end = self.tokens.partition_point(|t| t.span.end <= offset);
+        let tokens = &self.tokens[..end];
+        let start = tokens
+            .iter()
+            .rposition(|t| {
+                matches!(
+                    t.kind,
+                    TokenKind::Semicolon | TokenKind::LeftBrace | TokenKind::RightBrace
+                )
+            })
+            .map_or(0, |i| i + 1);
+
+        // Parentheses and brackets that are not calls are kept as `None`.
+        let mut stack: Vec<Option<Call>> = vec![];
+        for token in &tokens[start..] {
+            match token.kind {
+                TokenKind::Function => stack.push(Some(Call {
+                    name: token.name(&self.text).to_ascii_lowercase(),
+                    start: token.span.end,
+                    argument: 0,
+                })),
+                TokenKind::LeftParen | TokenKind::LeftBracket => stack.push(None),
+                TokenKind::RightParen | TokenKind::RightBracket => {
+                    stack.pop();
+                }
+                TokenKind::Comma => {
+                    if let Some(Some(call)) = stack.last_mut() {
+                        call.argument += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+        stack.into_iter().rev().flatten().collect()
+    }
+
+    /// Every at-rule in the stylesheet, including nested ones, in source
+    /// order.
+    pub fn at_rules(&self) -> Vec<&AtRule> {
+        fn walk<'a>(rules: &'a [Rule], out: &mut Vec<&'a AtRule>) {
+            for rule in rules {
+                if let Rule::At(at_rule) = rule {
+                    out.push(at_rule);
+                }
+                let Some(block) = rule.block() else { continue };
+                for item in &block.items {
+                    if let Item::Rule(rule) = item {
+                        walk(std::slice::from_ref(rule), out);
+                    }
+                }
+            }
+        }
+
+        let mut out = vec![];
+        walk(&self.rules, &mut out);
+        out
+    }
+}
+
+/// A function call whose arguments are being written, as found by
+/// `Stylesheet::calls_at`.
+#[derive(Debug, Clone)]
+pub struct Call {
+    /// The lowercased function name, without the `(`.
+    pub name: String,
+    /// The offset just after the `(`.
+    pub start: usize,
+    /// The index of the comma-separated argument being written.
+    pub argument: usize,
+}
+
+/// Index of the token that closes the block, function or parenthesis opened by
+/// `tokens[open]`, or `tokens.len()` if it is never closed.
+pub fn matching_close(tokens: &[Token], open: usize) -> usize {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        match token.kind {
+            TokenKind::Function | TokenKind::LeftParen | TokenKind::LeftBracket | TokenKind::LeftBrace => depth += 1,
+            TokenKind::RightParen | TokenKind::RightBracket | TokenKind::RightBrace => {
+                depth -= 1;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => {}
+        }
+    }
+    tokens.len()
+}
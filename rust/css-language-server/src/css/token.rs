@@ -0,0 +1,335 @@
+use super::Span;
+
+/// Token kinds from CSS Syntax Level 3.
+///
+/// Whitespace and comments are kept as tokens so that concatenating every
+/// token reproduces the source text exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Whitespace,
+    Comment,
+    Ident,
+    /// An identifier immediately followed by `(`. The span includes the `(`.
+    Function,
+    AtKeyword,
+    Hash,
+    String,
+    BadString,
+    Url,
+    BadUrl,
+    Delim,
+    Number,
+    Percentage,
+    /// A number followed by a unit; `unit` is the offset of the unit from the
+    /// start of the token.
+    Dimension {
+        unit: usize,
+    },
+    Cdo,
+    Cdc,
+    Colon,
+    Semicolon,
+    Comma,
+    LeftBracket,
+    RightBracket,
+    LeftParen,
+    RightParen,
+    LeftBrace,
+    RightBrace,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+impl Token {
+    pub fn is_trivia(&self) -> bool {
+        matches!(self.kind, TokenKind::Whitespace | TokenKind::Comment)
+    }
+
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        self.span.text(source)
+    }
+
+    /// The name of an ident, function, at-keyword or hash token, without its
+    /// sigils.
+    pub fn name<'a>(&self, source: &'a str) -> &'a str {
+        let text = self.text(source);
+        match self.kind {
+            TokenKind::Function => &text[..text.len() - 1],
+            TokenKind::AtKeyword | TokenKind::Hash => &text[1..],
+            _ => text,
+        }
+    }
+}
+
+pub fn tokenize(source: &str) -> Vec<Token> {
+    let mut lexer = Lexer {
+        bytes: source.as_bytes(),
+        pos: 0,
+    };
+    let mut tokens = vec![];
+    while lexer.pos < lexer.bytes.len() {
+        let start = lexer.pos;
+        let kind = lexer.next_kind();
+        tokens.push(Token {
+            kind,
+            span: Span::new(start, lexer.pos),
+        });
+    }
+    tokens
+}
+
+struct Lexer<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+fn is_whitespace(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\n' | b'\r' | 0x0C)
+}
+
+fn is_name_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_' || b >= 0x80
+}
+
+fn is_name(b: u8) -> bool {
+    is_name_start(b) || b.is_ascii_digit() || b == b'-'
+}
+
+impl Lexer<'_> {
+    fn peek(&self, n: usize) -> Option<u8> {
+        self.bytes.get(self.pos + n).copied()
+    }
+
+    fn next_kind(&mut self) -> TokenKind {
+        let b = self.bytes[self.pos];
+        match b {
+            b if is_whitespace(b) => {
+                while self.peek(0).is_some_and(is_whitespace) {
+                    self.pos += 1;
+                }
+                TokenKind::Whitespace
+            }
+            b'/' if self.peek(1) == Some(b'*') => {
+                self.pos += 2;
+                while self.pos < self.bytes.len() {
+                    if self.bytes[self.pos..].starts_with(b"*/") {
+                        self.pos += 2;
+                        break;
+                    }
+                    self.pos += 1;
+                }
+                TokenKind::Comment
+            }
+            b'"' | b'\'' => self.string(b),
+            b'#' => {
+                self.pos += 1;
+                if self.peek(0).is_some_and(is_name) || self.starts_escape(0) {
+                    self.name();
+                    TokenKind::Hash
+                } else {
+                    TokenKind::Delim
+                }
+            }
+            b'(' => self.single(TokenKind::LeftParen),
+            b')' => self.single(TokenKind::RightParen),
+            b'[' => self.single(TokenKind::LeftBracket),
+            b']' => self.single(TokenKind::RightBracket),
+            b'{' => self.single(TokenKind::LeftBrace),
+            b'}' => self.single(TokenKind::RightBrace),
+            b',' => self.single(TokenKind::Comma),
+            b':' => self.single(TokenKind::Colon),
+            b';' => self.single(TokenKind::Semicolon),
+            b'+' | b'.' if self.starts_number(0) => self.numeric(),
+            b'-' if self.starts_number(0) => self.numeric(),
+            b'-' if self.bytes[self.pos..].starts_with(b"-->") => {
+                self.pos += 3;
+                TokenKind::Cdc
+            }
+            b'-' if self.starts_ident(0) => self.ident_like(),
+            b'<' if self.bytes[self.pos..].starts_with(b"<!--") => {
+                self.pos += 4;
+                TokenKind::Cdo
+            }
+            b'@' if self.starts_ident(1) => {
+                self.pos += 1;
+                self.name();
+                TokenKind::AtKeyword
+            }
+            b'\\' if self.starts_escape(0) => self.ident_like(),
+            b if b.is_ascii_digit() => self.numeric(),
+            b if is_name_start(b) => self.ident_like(),
+            _ => self.single(TokenKind::Delim),
+        }
+    }
+
+    fn single(&mut self, kind: TokenKind) -> TokenKind {
+        self.pos += 1;
+        kind
+    }
+
+    fn starts_escape(&self, n: usize) -> bool {
+        self.peek(n) == Some(b'\\') && self.peek(n + 1).is_some_and(|b| b != b'\n')
+    }
+
+    fn starts_ident(&self, n: usize) -> bool {
+        match self.peek(n) {
+            Some(b'-') => self.peek(n + 1).is_some_and(|b| is_name_start(b) || b == b'-') || self.starts_escape(n + 1),
+            Some(b) if is_name_start(b) => true,
+            Some(b'\\') => self.starts_escape(n),
+            _ => false,
+        }
+    }
+
+    fn starts_number(&self, n: usize) -> bool {
+        let digit = |i| self.peek(i).is_some_and(|b: u8| b.is_ascii_digit());
+        match self.peek(n) {
+            Some(b'+' | b'-') => digit(n + 1) || (self.peek(n + 1) == Some(b'.') && digit(n + 2)),
+            Some(b'.') => digit(n + 1),
+            Some(b) => b.is_ascii_digit(),
+            None => false,
+        }
+    }
+
+    fn escape(&mut self) {
+        // Skip the backslash and the escaped code point. Hex escapes may be
+        // up to six digits long and swallow one trailing whitespace.
+        self.pos += 1;
+        if self.peek(0).is_some_and(|b| b.is_ascii_hexdigit()) {
+            let mut n = 0;
+            while n < 6 && self.peek(0).is_some_and(|b| b.is_ascii_hexdigit()) {
+                self.pos += 1;
+                n += 1;
+            }
+            if self.peek(0).is_some_and(is_whitespace) {
+                self.pos += 1;
+            }
+        } else if self.pos < self.bytes.len() {
+            self.pos += 1;
+            self.skip_continuation();
+        }
+    }
+
+    fn skip_continuation(&mut self) {
+        while self.peek(0).is_some_and(|b| b & 0xC0 == 0x80) {
+            self.pos += 1;
+        }
+    }
+
+    fn name(&mut self) {
+        loop {
+            match self.peek(0) {
+                Some(b) if is_name(b) => self.pos += 1,
+                Some(b'\\') if self.starts_escape(0) => self.escape(),
+                _ => break,
+            }
+        }
+    }
+
+    fn string(&mut self, quote: u8) -> TokenKind {
+        self.pos += 1;
+        while let Some(b) = self.peek(0) {
+            match b {
+                b if b == quote => {
+                    self.pos += 1;
+                    return TokenKind::String;
+                }
+                b'\n' => return TokenKind::BadString,
+                b'\\' => {
+                    self.pos += 1;
+                    if self.pos < self.bytes.len() {
+                        self.pos += 1;
+                        self.skip_continuation();
+                    }
+                }
+                _ => self.pos += 1,
+            }
+        }
+        TokenKind::String
+    }
+
+    fn numeric(&mut self) -> TokenKind {
+        let start = self.pos;
+        if matches!(self.peek(0), Some(b'+' | b'-')) {
+            self.pos += 1;
+        }
+        self.digits();
+        if self.peek(0) == Some(b'.') && self.peek(1).is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+            self.digits();
+        }
+        if matches!(self.peek(0), Some(b'e' | b'E')) {
+            let digit_at = if matches!(self.peek(1), Some(b'+' | b'-')) {
+                2
+            } else {
+                1
+            };
+            if self.peek(digit_at).is_some_and(|b| b.is_ascii_digit()) {
+                self.pos += digit_at;
+                self.digits();
+            }
+        }
+        if self.starts_ident(0) {
+            let unit = self.pos - start;
+            self.name();
+            TokenKind::Dimension { unit }
+        } else if self.peek(0) == Some(b'%') {
+            self.pos += 1;
+            TokenKind::Percentage
+        } else {
+            TokenKind::Number
+        }
+    }
+
+    fn digits(&mut self) {
+        while self.peek(0).is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+    }
+
+    fn ident_like(&mut self) -> TokenKind {
+        let start = self.pos;
+        self.name();
+        if self.peek(0) != Some(b'(') {
+            return TokenKind::Ident;
+        }
+        self.pos += 1;
+        if !self.bytes[start..self.pos - 1].eq_ignore_ascii_case(b"url") {
+            return TokenKind::Function;
+        }
+
+        let mut ws = 0;
+        while self.peek(ws).is_some_and(is_whitespace) {
+            ws += 1;
+        }
+        if matches!(self.peek(ws), Some(b'"' | b'\'')) {
+            return TokenKind::Function;
+        }
+        self.url()
+    }
+
+    fn url(&mut self) -> TokenKind {
+        let mut bad = false;
+        while let Some(b) = self.peek(0) {
+            match b {
+                b')' => {
+                    self.pos += 1;
+                    break;
+                }
+                b'\\' if self.starts_escape(0) => self.escape(),
+                b'"' | b'\'' | b'(' => {
+                    bad = true;
+                    self.pos += 1;
+                }
+                _ => {
+                    self.pos += 1;
+                    self.skip_continuation();
+                }
+            }
+        }
+        if bad { TokenKind::BadUrl } else { TokenKind::Url }
+    }
+}
@@ -0,0 +1,417 @@
+use std::path::{Component, Path, PathBuf};
+
+use lsp_types::Uri;
+
+use crate::{
+    css::{
+        Span,
+        selector::{NameKind, names},
+    },
+    document::Document,
+    line_index::{Encoding, LineIndex},
+    uri,
+};
+
+/// A JavaScript or TypeScript file that imports CSS Modules.
+#[derive(Debug)]
+pub struct Script {
+    pub text: String,
+    pub line_index: LineIndex,
+    pub imports: Vec<Import>,
+}
+
+/// An import of a CSS Module, e.g. `import styles from "./a.module.css"`.
+#[derive(Debug)]
+pub struct Import {
+    pub module: Uri,
+    /// The variable holding the exported classes, `styles` above.
+    pub binding: Option<String>,
+    /// The class names read from the module and where.
+    pub usages: Vec<(String, Span)>,
+    /// Whether the module is used in a way that could read any class, such
+    /// as `styles[variant]` or passing `styles` around.
+    pub dynamic: bool,
+}
+
+impl Import {
+    /// Whether the import reads `class`, either as written or in the camel
+    /// case form bundlers can export.
+    pub fn uses(&self, class: &str) -> bool {
+        self.dynamic || self.usages.iter().any(|(name, _)| reads(name, class))
+    }
+}
+
+/// Whether a stylesheet is a CSS Module, whose class names are local to the
+/// scripts importing it.
+pub fn is_module(uri: &Uri) -> bool {
+    uri.path().as_str().ends_with(".module.css")
+}
+
+/// Whether a file extension is that of a script that may import CSS Modules.
+pub fn is_script(extension: &str) -> bool {
+    matches!(
+        extension,
+        "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" | "vue"
+    )
+}
+
+/// Whether an editor language id is one of the script languages.
+pub fn is_script_language(language_id: &str) -> bool {
+    matches!(
+        language_id,
+        "javascript" | "javascriptreact" | "typescript" | "typescriptreact" | "vue"
+    )
+}
+
+/// Whether a script reading `name` from a module reads `class`.
+pub fn reads(name: &str, class: &str) -> bool {
+    name == class || name == camel_case(class)
+}
+
+/// `foo-bar` as `fooBar`.
+fn camel_case(class: &str) -> String {
+    let mut out = String::with_capacity(class.len());
+    let mut upper = false;
+    for c in class.chars() {
+        match c {
+            '-' if !out.is_empty() => upper = true,
+            c if upper => {
+                out.extend(c.to_uppercase());
+                upper = false;
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+impl Script {
+    /// Scans the imports of CSS Modules in a script at `path`. Relative
+    /// specifiers are resolved against its directory.
+    pub fn new(text: String, path: &Path, encoding: Encoding) -> Self {
+        let dir = path.parent().unwrap_or(Path::new("/"));
+        let mut imports = vec![];
+        let mut search = 0;
+        while let Some(found) = text[search..].find(".module.css") {
+            let end = search + found + ".module.css".len();
+            search = end;
+            let Some(quote) = text[end..].chars().next().filter(|c| matches!(c, '"' | '\'' | '`')) else {
+                continue;
+            };
+            let Some(start) = text[..end].rfind(quote) else {
+                continue;
+            };
+            let specifier = &text[start + 1..end];
+            if !specifier.starts_with('.') {
+                continue;
+            }
+            let Some(module) = uri::from_path(&normalize(&dir.join(specifier))) else {
+                continue;
+            };
+            imports.push(import(&text, start, module));
+        }
+        let line_index = LineIndex::new(&text, encoding);
+        Self {
+            text,
+            line_index,
+            imports,
+        }
+    }
+
+    /// The import whose binding is read at `offset`, as in `styles.a`, and
+    /// the offset of the `.` after it.
+    pub fn member_access_at(&self, offset: usize) -> Option<(&Import, usize)> {
+        let before = &self.text[..offset];
+        let partial = before.trim_end_matches(is_ident_char);
+        let dot = partial.strip_suffix('.')?;
+        let object = &dot[dot.trim_end_matches(is_ident_char).len()..];
+        let import = self
+            .imports
+            .iter()
+            .find(|import| import.binding.as_deref() == Some(object))?;
+        Some((import, dot.len()))
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+/// Resolves `.` and `..` without touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            component => out.push(component),
+        }
+    }
+    out
+}
+
+/// Reads the import statement or `require` call around the specifier that
+/// starts at `quote`, and the uses of what it binds.
+fn import(text: &str, quote: usize, module: Uri) -> Import {
+    let before = text[..quote].trim_end();
+    let mut import = Import {
+        module,
+        binding: None,
+        usages: vec![],
+        dynamic: false,
+    };
+
+    // `import ... from "..."`, or `const ... = require("...")`.
+    let clause = if let Some(from) = before.strip_suffix("from") {
+        let start = from.rfind("import").map_or(0, |i| i + "import".len());
+        Span::new(start, from.len())
+    } else if let Some(require) = before
+        .strip_suffix('(')
+        .and_then(|b| b.trim_end().strip_suffix("require"))
+    {
+        let Some(eq) = require.trim_end().strip_suffix('=') else {
+            return import;
+        };
+        let start = ["const", "let", "var"]
+            .iter()
+            .filter_map(|keyword| eq.rfind(keyword).map(|i| i + keyword.len()))
+            .max()
+            .unwrap_or(0);
+        Span::new(start, eq.len())
+    } else {
+        return import;
+    };
+
+    let clause_text = clause.text(text);
+    if let Some(open) = clause_text.find('{') {
+        let close = clause_text[open..].find('}').map_or(clause_text.len(), |i| open + i);
+        let mut offset = clause.start + open + 1;
+        for specifier in clause_text[open + 1..close].split(',') {
+            let name = specifier.split_whitespace().next().unwrap_or_default();
+            let name = name.split(':').next().unwrap_or_default();
+            if !name.is_empty() {
+                let start = offset + specifier.find(name).unwrap_or(0);
+                import
+                    .usages
+                    .push((name.to_string(), Span::new(start, start + name.len())));
+            }
+            offset += specifier.len() + 1;
+        }
+    }
+    let default = match clause_text.find("* as ") {
+        Some(i) => &clause_text[i + "* as ".len()..],
+        None => &clause_text[..clause_text.find('{').unwrap_or(clause_text.len())],
+    };
+    let binding = default.trim().trim_end_matches(',').trim();
+    if !binding.is_empty() && binding.chars().all(is_ident_char) {
+        member_accesses(text, binding, clause.end, &mut import);
+        import.binding = Some(binding.to_string());
+    }
+    import
+}
+
+/// Collects `binding.name` and `binding["name"]` after `start`. Any other use
+/// of the binding makes the import dynamic.
+fn member_accesses(text: &str, binding: &str, start: usize, import: &mut Import) {
+    let mut search = start;
+    while let Some(found) = text[search..].find(binding) {
+        let begin = search + found;
+        let end = begin + binding.len();
+        search = end;
+        let before = text[..begin].chars().next_back();
+        let after = text[end..].chars().next();
+        if before.is_some_and(|c| is_ident_char(c) || c == '.') || after.is_some_and(is_ident_char) {
+            continue;
+        }
+        let rest = &text[end..];
+        // `binding?.["name"]` is an index like `binding["name"]`.
+        let rest = rest
+            .strip_prefix("?.")
+            .filter(|rest| rest.starts_with('['))
+            .unwrap_or(rest);
+        let access = rest.strip_prefix("?.").or_else(|| rest.strip_prefix('.'));
+        if let Some(member) = access {
+            let len = member.len() - member.trim_start_matches(is_ident_char).len();
+            let start = text.len() - member.len();
+            import
+                .usages
+                .push((member[..len].to_string(), Span::new(start, start + len)));
+            continue;
+        }
+        if let Some(index) = rest.strip_prefix('[') {
+            let literal = index.chars().next().filter(|c| matches!(c, '"' | '\''));
+            let name = literal.and_then(|quote| {
+                let inner = &index[1..];
+                let close = inner.find(quote)?;
+                inner[close + 1..].starts_with(']').then_some(&inner[..close])
+            });
+            if let Some(name) = name {
+                let start = text.len() - index.len() + 1;
+                import
+                    .usages
+                    .push((name.to_string(), Span::new(start, start + name.len())));
+                continue;
+            }
+        }
+        import.dynamic = true;
+    }
+}
+
+/// The classes a module exports, in source order without repeats.
+pub fn classes(module: &Document) -> Vec<String> {
+    let mut classes: Vec<String> = vec![];
+    for region in &module.regions {
+        for name in names(&region.stylesheet) {
+            if name.kind == NameKind::Class && !classes.contains(&name.name) {
+                classes.push(name.name);
+            }
+        }
+    }
+    classes
+}
+
+/// A TypeScript declaration of the classes a module exports.
+pub fn declarations(classes: &[String]) -> String {
+    let mut out = String::from("declare const styles: {\n");
+    for class in classes {
+        let key = serde_json::Value::from(class.as_str());
+        out.push_str(&format!("  readonly {key}: string;\n"));
+    }
+    out.push_str("};\nexport default styles;\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(text: &str) -> Script {
+        Script::new(text.to_string(), Path::new("/p/src/App.tsx"), Encoding::default())
+    }
+
+    /// The module, binding, class names read and whether it is dynamic, for
+    /// each import, checking that the span of each name covers it.
+    fn imports(text: &str) -> Vec<(String, Option<String>, Vec<String>, bool)> {
+        let script = script(text);
+        script
+            .imports
+            .iter()
+            .map(|import| {
+                let names = import
+                    .usages
+                    .iter()
+                    .map(|(name, span)| {
+                        assert_eq!(span.text(text), name);
+                        name.clone()
+                    })
+                    .collect();
+                let module = import.module.as_str().to_string();
+                (module, import.binding.clone(), names, import.dynamic)
+            })
+            .collect()
+    }
+
+    fn strings(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn default_imports() {
+        let text = "import styles from './a.module.css';\n<p className={styles.title + styles?.['sub-title']} />";
+        assert_eq!(
+            imports(text),
+            [(
+                "file:///p/src/a.module.css".to_string(),
+                Some("styles".to_string()),
+                strings(&["title", "sub-title"]),
+                false
+            )]
+        );
+        // Any other use could read any class.
+        let text = "import s from \"../b.module.css\";\nconst c = s[variant];";
+        assert_eq!(
+            imports(text),
+            [(
+                "file:///p/b.module.css".to_string(),
+                Some("s".to_string()),
+                vec![],
+                true
+            )]
+        );
+    }
+
+    #[test]
+    fn named_imports_and_require() {
+        let text = "import { a, b as c } from './a.module.css';\nconst { d } = require('./a.module.css');";
+        assert_eq!(
+            imports(text),
+            [
+                (
+                    "file:///p/src/a.module.css".to_string(),
+                    None,
+                    strings(&["a", "b"]),
+                    false
+                ),
+                ("file:///p/src/a.module.css".to_string(), None, strings(&["d"]), false),
+            ]
+        );
+        let text = "import * as css from './a.module.css';\nconst x = css.y;";
+        assert_eq!(imports(text)[0].1.as_deref(), Some("css"));
+        assert_eq!(imports(text)[0].2, strings(&["y"]));
+    }
+
+    #[test]
+    fn bare_specifiers() {
+        assert_eq!(imports("import s from 'pkg/a.module.css';"), []);
+        assert_eq!(imports("const a = 'a.module.css.map';"), []);
+    }
+
+    #[test]
+    fn uses() {
+        let text = "import s from './a.module.css';\ns.fooBar;\ns.baz;";
+        let import = &script(text).imports[0];
+        assert!(import.uses("foo-bar"));
+        assert!(import.uses("fooBar"));
+        assert!(import.uses("baz"));
+        assert!(!import.uses("foo"));
+        assert_eq!(camel_case("-a-b--c"), "-aBC");
+    }
+
+    #[test]
+    fn member_access_at() {
+        let text = "import s from './a.module.css';\ns.ti";
+        let script = script(text);
+        let (import, dot) = script.member_access_at(text.len()).unwrap();
+        assert_eq!((import.binding.as_deref(), dot), (Some("s"), text.len() - 3));
+        assert!(script.member_access_at(text.len() - 3).is_none());
+    }
+
+    #[test]
+    fn classes_and_declarations() {
+        let module = Document::new(
+            "file:///p/src/a.module.css".parse().unwrap(),
+            "css".to_string(),
+            0,
+            ".a, #b .c-d:not(.e) {}\n.a:hover {}".to_string(),
+            Encoding::default(),
+        );
+        let classes = classes(&module);
+        assert_eq!(classes, ["a", "c-d", "e"]);
+        assert_eq!(
+            declarations(&classes),
+            "declare const styles: {\n  \
+               readonly \"a\": string;\n  \
+               readonly \"c-d\": string;\n  \
+               readonly \"e\": string;\n\
+             };\nexport default styles;\n"
+        );
+        assert_eq!(
+            declarations(&[]),
+            "declare const styles: {\n};\nexport default styles;\n"
+        );
+        // Keys are quoted as JSON strings.
+        assert!(declarations(&["a\"b".to_string()]).contains("readonly \"a\\\"b\": string;"));
+    }
+}
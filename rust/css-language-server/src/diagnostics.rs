@@ -0,0 +1,235 @@
+use lsp_types::{Diagnostic, DiagnosticSeverity, DiagnosticTag, NumberOrString, Range};
+
+use crate::{
+    config::Config,
+    css::{
+        at_rules::is_standard,
+        descriptors,
+        features::unsupported,
+        math,
+        media::{self, IssueKind},
+        registered::{self, Registration, registrations},
+        selector::{NameKind, names},
+    },
+    css_modules::is_module,
+    document::{Document, Region},
+    workspace::Workspace,
+};
+
+pub fn diagnostics(doc: &Document, config: &Config, workspace: &Workspace) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let registrations = registered_properties(doc, workspace);
+    for region in &doc.regions {
+        for error in &region.stylesheet.errors {
+            diagnostics.push(diagnostic(
+                doc.range(region, error.span),
+                DiagnosticSeverity::ERROR,
+                None,
+                error.message.clone(),
+            ));
+        }
+        for error in math::check(&region.stylesheet) {
+            if !region.contains(error.span) {
+                continue;
+            }
+            diagnostics.push(diagnostic(
+                doc.range(region, error.span),
+                DiagnosticSeverity::ERROR,
+                Some("invalid-math"),
+                error.message,
+            ));
+        }
+        for issue in media::check(&region.stylesheet) {
+            if !region.contains(issue.span) {
+                continue;
+            }
+            let (severity, code) = match issue.kind {
+                IssueKind::Invalid => (DiagnosticSeverity::ERROR, "invalid-query"),
+                IssueKind::Unknown => (DiagnosticSeverity::WARNING, "unknown-query-feature"),
+                IssueKind::Contradiction => (DiagnosticSeverity::WARNING, "contradictory-query"),
+            };
+            diagnostics.push(diagnostic(
+                doc.range(region, issue.span),
+                severity,
+                Some(code),
+                issue.message,
+            ));
+        }
+        for issue in descriptors::check(&region.stylesheet) {
+            if !region.contains(issue.span) {
+                continue;
+            }
+            let (severity, code) = match issue.kind {
+                descriptors::IssueKind::Unknown => (DiagnosticSeverity::WARNING, "unknown-descriptor"),
+                descriptors::IssueKind::Missing => (DiagnosticSeverity::ERROR, "missing-descriptor"),
+                descriptors::IssueKind::Invalid => (DiagnosticSeverity::ERROR, "invalid-descriptor"),
+            };
+            diagnostics.push(diagnostic(
+                doc.range(region, issue.span),
+                severity,
+                Some(code),
+                issue.message,
+            ));
+        }
+        for mismatch in registered::check(&region.stylesheet, &registrations) {
+            if !region.contains(mismatch.span) {
+                continue;
+            }
+            diagnostics.push(diagnostic(
+                doc.range(region, mismatch.span),
+                DiagnosticSeverity::WARNING,
+                Some("registered-property-type"),
+                mismatch.message,
+            ));
+        }
+        for at_rule in region.stylesheet.at_rules() {
+            let name = at_rule.name.as_str();
+            if is_standard(name) || config.is_framework_at_rule(name) || !region.contains(at_rule.name_span) {
+                continue;
+            }
+            diagnostics.push(diagnostic(
+                doc.range(region, at_rule.name_span),
+                DiagnosticSeverity::WARNING,
+                Some("unknown-at-rule"),
+                format!("Unknown at-rule `@{name}`"),
+            ));
+        }
+        for issue in unsupported(&region.stylesheet, &config.targets) {
+            if !region.contains(issue.span) {
+                continue;
+            }
+            let browsers: Vec<String> = issue
+                .browsers
+                .iter()
+                .map(|(browser, added)| match added {
+                    Some(added) => format!("{browser} before {added}"),
+                    None => browser.to_string(),
+                })
+                .collect();
+            diagnostics.push(diagnostic(
+                doc.range(region, issue.span),
+                DiagnosticSeverity::WARNING,
+                Some("compatibility"),
+                format!("`{}` is not supported by {}", issue.feature, browsers.join(", ")),
+            ));
+        }
+        if is_module(&doc.uri) {
+            module_diagnostics(doc, region, workspace, &mut diagnostics);
+            continue;
+        }
+        if !workspace.has_templates() {
+            continue;
+        }
+        for name in names(&region.stylesheet) {
+            if name.nested || !region.contains(name.span) || workspace.is_used(name.kind, &name.name) {
+                continue;
+            }
+            let kind = match name.kind {
+                NameKind::Class => "Class",
+                NameKind::Id => "Id",
+            };
+            diagnostics.push(Diagnostic {
+                tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+                ..diagnostic(
+                    doc.range(region, name.span),
+                    DiagnosticSeverity::HINT,
+                    Some("unused"),
+                    format!("{kind} `{}` is not used by any template", name.name),
+                )
+            });
+        }
+    }
+    diagnostics
+}
+
+/// A diagnostic of this server. Only syntax errors have no code.
+fn diagnostic(range: Range, severity: DiagnosticSeverity, code: Option<&str>, message: String) -> Diagnostic {
+    Diagnostic {
+        range,
+        severity: Some(severity),
+        code: code.map(|code| NumberOrString::String(code.to_string())),
+        source: Some("css".to_string()),
+        message,
+        ..Default::default()
+    }
+}
+
+/// The custom properties registered with `@property`, those of `doc` first so
+/// that they take precedence over registrations elsewhere in the workspace.
+fn registered_properties(doc: &Document, workspace: &Workspace) -> Vec<Registration> {
+    let others = workspace.files.values().filter(|other| other.uri != doc.uri);
+    std::iter::once(doc)
+        .chain(others)
+        .flat_map(|doc| doc.regions.iter().flat_map(|region| registrations(&region.stylesheet)))
+        .collect()
+}
+
+/// Reports the classes of a CSS Module that no importing script reads. A
+/// module that is never imported is left alone, since it may be used from
+/// outside the workspace.
+fn module_diagnostics(doc: &Document, region: &Region, workspace: &Workspace, diagnostics: &mut Vec<Diagnostic>) {
+    let imports: Vec<_> = workspace.module_imports(&doc.uri).map(|(_, import)| import).collect();
+    if imports.is_empty() {
+        return;
+    }
+    for name in names(&region.stylesheet) {
+        if name.kind != NameKind::Class || name.nested || imports.iter().any(|import| import.uses(&name.name)) {
+            continue;
+        }
+        diagnostics.push(Diagnostic {
+            tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+            ..diagnostic(
+                doc.range(region, name.span),
+                DiagnosticSeverity::HINT,
+                Some("unused"),
+                format!("Class `{}` is not used by any script importing this module", name.name),
+            )
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line_index::Encoding;
+
+    /// The messages of the unused hints for `text` given the `templates`.
+    fn unused(uri: &str, text: &str, templates: &[&str]) -> Vec<String> {
+        let mut workspace = Workspace::default();
+        for (i, template) in templates.iter().enumerate() {
+            workspace.set_template(&format!("file:///{i}.html").parse().unwrap(), template);
+        }
+        let doc = Document::new(
+            uri.parse().unwrap(),
+            "css".to_string(),
+            0,
+            text.to_string(),
+            Encoding::default(),
+        );
+        diagnostics(&doc, &Config::default(), &workspace)
+            .into_iter()
+            .filter(|diagnostic| diagnostic.code == Some(NumberOrString::String("unused".to_string())))
+            .map(|diagnostic| {
+                assert_eq!(diagnostic.tags, Some(vec![DiagnosticTag::UNNECESSARY]));
+                diagnostic.message
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unused_across_templates() {
+        let text = ".a, .b #c {}\n.e:not(.d) {}\n#a {}";
+        let templates = ["<p class=\"a\">", "<p id=\"c\" className={clsx('e')}>"];
+        assert_eq!(
+            unused("file:///a.css", text, &templates),
+            [
+                "Class `b` is not used by any template",
+                "Id `a` is not used by any template",
+            ]
+        );
+        // Without markup, every selector would look unused.
+        assert_eq!(unused("file:///a.css", text, &[]), Vec::<String>::new());
+        // The classes of CSS Modules are for scripts to use.
+        assert_eq!(unused("file:///a.module.css", text, &templates), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,334 @@
+use std::ops::Range;
+
+/// Lines of context around each change.
+const CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Edit {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// A unified diff of two texts, or an empty string if they are equal.
+pub fn unified(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    let a: Vec<&str> = old.split_inclusive('\n').collect();
+    let b: Vec<&str> = new.split_inclusive('\n').collect();
+    let edits = diff(&a, &b);
+    if edits.iter().all(|edit| matches!(edit, Edit::Equal(..))) {
+        return String::new();
+    }
+
+    let mut out = format!("--- {old_name}\n+++ {new_name}\n");
+    let changes: Vec<usize> = (0..edits.len())
+        .filter(|&i| !matches!(edits[i], Edit::Equal(..)))
+        .collect();
+    let mut i = 0;
+    while i < changes.len() {
+        // Changes separated by less than twice the context share a hunk.
+        let start = changes[i].saturating_sub(CONTEXT);
+        let mut last = changes[i];
+        while i + 1 < changes.len() && changes[i + 1] - last <= 2 * CONTEXT {
+            i += 1;
+            last = changes[i];
+        }
+        let end = (last + CONTEXT + 1).min(edits.len());
+        i += 1;
+
+        let hunk = &edits[start..end];
+        let (old_start, new_start) = position(&edits, start);
+        let old_len = hunk.iter().filter(|e| !matches!(e, Edit::Insert(_))).count();
+        let new_len = hunk.iter().filter(|e| !matches!(e, Edit::Delete(_))).count();
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(old_start, old_len),
+            range(new_start, new_len)
+        ));
+        for edit in hunk {
+            let (sign, line) = match *edit {
+                Edit::Equal(i, _) => (' ', a[i]),
+                Edit::Delete(i) => ('-', a[i]),
+                Edit::Insert(j) => ('+', b[j]),
+            };
+            out.push(sign);
+            out.push_str(line);
+            if !line.ends_with('\n') {
+                out.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+    }
+    out
+}
+
+/// The indexes of the old and new lines at `edits[index]`.
+fn position(edits: &[Edit], index: usize) -> (usize, usize) {
+    let old = edits[..index].iter().filter(|e| !matches!(e, Edit::Insert(_))).count();
+    let new = edits[..index].iter().filter(|e| !matches!(e, Edit::Delete(_))).count();
+    (old, new)
+}
+
+/// `start,len` with a 1-based start, which is the line before an empty range.
+fn range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{len}", start + 1),
+    }
+}
+
+/// How far the search for the shortest edit script goes, in edits from
+/// either end, before it settles for replacing the lines in between. The
+/// search takes time proportional to this times the number of lines, so a
+/// minified stylesheet, whose formatted text shares no line with it, is
+/// replaced at once instead of being searched to the end.
+const MAX_COST: usize = 1024;
+
+/// An edit script from `a` to `b`, by Myers' algorithm in linear space: the
+/// middle snake of the shortest script splits the lines in two halves, which
+/// are diffed in turn. The script is the shortest unless it needs more than
+/// about `2 * MAX_COST` edits.
+fn diff(a: &[&str], b: &[&str]) -> Vec<Edit> {
+    let mut edits = Vec::with_capacity(a.len() + b.len());
+    let size = (a.len() + b.len()).div_ceil(2).min(MAX_COST) + 2;
+    let mut forward = Diagonals::new(size);
+    let mut backward = Diagonals::new(size);
+    conquer(a, 0..a.len(), b, 0..b.len(), &mut forward, &mut backward, &mut edits);
+    // Each change deletes its old lines before inserting the new ones.
+    for change in edits.split_mut(|edit| matches!(edit, Edit::Equal(..))) {
+        change.sort_by_key(|edit| matches!(edit, Edit::Insert(_)));
+    }
+    edits
+}
+
+/// The furthest `x` reached on each diagonal `k = x - y`, for `k` from
+/// `-size` to `size`.
+struct Diagonals {
+    x: Vec<usize>,
+    size: isize,
+}
+
+impl Diagonals {
+    fn new(size: usize) -> Self {
+        Diagonals {
+            x: vec![0; 2 * size + 1],
+            size: size as isize,
+        }
+    }
+}
+
+impl std::ops::Index<isize> for Diagonals {
+    type Output = usize;
+
+    fn index(&self, k: isize) -> &usize {
+        &self.x[(k + self.size) as usize]
+    }
+}
+
+impl std::ops::IndexMut<isize> for Diagonals {
+    fn index_mut(&mut self, k: isize) -> &mut usize {
+        &mut self.x[(k + self.size) as usize]
+    }
+}
+
+fn conquer(
+    a: &[&str],
+    mut a_range: Range<usize>,
+    b: &[&str],
+    mut b_range: Range<usize>,
+    forward: &mut Diagonals,
+    backward: &mut Diagonals,
+    edits: &mut Vec<Edit>,
+) {
+    let prefix = common_prefix(&a[a_range.clone()], &b[b_range.clone()]);
+    for i in 0..prefix {
+        edits.push(Edit::Equal(a_range.start + i, b_range.start + i));
+    }
+    a_range.start += prefix;
+    b_range.start += prefix;
+    let suffix = common_suffix(&a[a_range.clone()], &b[b_range.clone()]);
+    a_range.end -= suffix;
+    b_range.end -= suffix;
+
+    if a_range.is_empty() || b_range.is_empty() {
+        edits.extend(a_range.clone().map(Edit::Delete));
+        edits.extend(b_range.clone().map(Edit::Insert));
+    } else if let Some((x, y)) = middle_snake(a, a_range.clone(), b, b_range.clone(), forward, backward) {
+        conquer(a, a_range.start..x, b, b_range.start..y, forward, backward, edits);
+        conquer(a, x..a_range.end, b, y..b_range.end, forward, backward, edits);
+    } else {
+        edits.extend(a_range.clone().map(Edit::Delete));
+        edits.extend(b_range.clone().map(Edit::Insert));
+    }
+
+    for i in 0..suffix {
+        edits.push(Edit::Equal(a_range.end + i, b_range.end + i));
+    }
+}
+
+/// The start of the middle snake of the shortest edit script between two
+/// ranges that differ in their first and last lines, searching forward from
+/// their start and backward from their end at once. `None` if the script is
+/// longer than `2 * MAX_COST` edits.
+fn middle_snake(
+    a: &[&str],
+    a_range: Range<usize>,
+    b: &[&str],
+    b_range: Range<usize>,
+    forward: &mut Diagonals,
+    backward: &mut Diagonals,
+) -> Option<(usize, usize)> {
+    let (a, b) = (&a[a_range.clone()], &b[b_range.clone()]);
+    let (n, m) = (a.len(), b.len());
+    let delta = n as isize - m as isize;
+    let odd = delta % 2 != 0;
+    // Backward, `x` counts the lines skipped from the end.
+    forward[1] = 0;
+    backward[1] = 0;
+    let max = (n + m).div_ceil(2).min(MAX_COST) as isize;
+    for d in 0..=max {
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = match k == -d || (k != d && forward[k - 1] < forward[k + 1]) {
+                true => forward[k + 1],
+                false => forward[k - 1] + 1,
+            };
+            let y = (x as isize - k) as usize;
+            let start = (x, y);
+            if x < n && y < m {
+                x += common_prefix(&a[x..], &b[y..]);
+            }
+            forward[k] = x;
+            let opposite = delta - k;
+            if odd && opposite.abs() < d && forward[k] + backward[opposite] >= n {
+                return Some((a_range.start + start.0, b_range.start + start.1));
+            }
+        }
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = match k == -d || (k != d && backward[k - 1] < backward[k + 1]) {
+                true => backward[k + 1],
+                false => backward[k - 1] + 1,
+            };
+            let mut y = (x as isize - k) as usize;
+            if x < n && y < m {
+                let common = common_suffix(&a[..n - x], &b[..m - y]);
+                x += common;
+                y += common;
+            }
+            backward[k] = x;
+            let opposite = delta - k;
+            if !odd && opposite.abs() <= d && backward[k] + forward[opposite] >= n {
+                return Some((a_range.start + n - x, b_range.start + m - y));
+            }
+        }
+    }
+    None
+}
+
+fn common_prefix(a: &[&str], b: &[&str]) -> usize {
+    a.iter().zip(b).take_while(|(a, b)| a == b).count()
+}
+
+fn common_suffix(a: &[&str], b: &[&str]) -> usize {
+    a.iter().rev().zip(b.iter().rev()).take_while(|(a, b)| a == b).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::{format::format, parser::parse};
+
+    /// Checks that the edits turn `a` into `b`, and returns how many lines
+    /// they keep.
+    fn kept(a: &[&str], b: &[&str], edits: &[Edit]) -> usize {
+        let (mut i, mut j) = (0, 0);
+        for edit in edits {
+            match *edit {
+                Edit::Equal(x, y) => {
+                    assert_eq!((x, y), (i, j));
+                    assert_eq!(a[x], b[y]);
+                    (i, j) = (i + 1, j + 1);
+                }
+                Edit::Delete(x) => {
+                    assert_eq!(x, i);
+                    i += 1;
+                }
+                Edit::Insert(y) => {
+                    assert_eq!(y, j);
+                    j += 1;
+                }
+            }
+        }
+        assert_eq!((i, j), (a.len(), b.len()));
+        edits.iter().filter(|edit| matches!(edit, Edit::Equal(..))).count()
+    }
+
+    /// The length of the longest common subsequence, by dynamic programming.
+    fn longest_common(a: &[&str], b: &[&str]) -> usize {
+        let mut row = vec![0; b.len() + 1];
+        for x in a {
+            let mut diagonal = 0;
+            for (j, y) in b.iter().enumerate() {
+                let above = row[j + 1];
+                row[j + 1] = if x == y { diagonal + 1 } else { above.max(row[j]) };
+                diagonal = above;
+            }
+        }
+        row[b.len()]
+    }
+
+    #[test]
+    fn shortest_edit_script() {
+        let lines = ["a", "b", "c"];
+        // A linear congruential generator, for lines of a small alphabet.
+        let mut seed = 7u32;
+        let mut random = |len: u32| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (seed >> 16) % len
+        };
+        for _ in 0..2000 {
+            let a: Vec<&str> = (0..random(12)).map(|_| lines[random(3) as usize]).collect();
+            let b: Vec<&str> = (0..random(12)).map(|_| lines[random(3) as usize]).collect();
+            assert_eq!(kept(&a, &b, &diff(&a, &b)), longest_common(&a, &b), "{a:?} to {b:?}");
+        }
+    }
+
+    #[test]
+    fn unified_hunks() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm";
+        assert_eq!(unified(old, old, "a/x.css", "b/x.css"), "");
+        assert_eq!(
+            unified(old, new, "a/x.css", "b/x.css"),
+            "--- a/x.css\n+++ b/x.css\n\
+             @@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n\
+             @@ -10,3 +10,4 @@\n j\n k\n l\n+m\n\\ No newline at end of file\n"
+        );
+    }
+
+    #[test]
+    fn replaces_lines_past_the_cutoff() {
+        let a: Vec<String> = (0..3 * MAX_COST).map(|i| format!("a{i}\n")).collect();
+        let b: Vec<String> = (0..3 * MAX_COST).map(|i| format!("b{i}\n")).collect();
+        let mut a: Vec<&str> = a.iter().map(String::as_str).collect();
+        let mut b: Vec<&str> = b.iter().map(String::as_str).collect();
+        a.insert(0, "same\n");
+        b.insert(0, "same\n");
+        let edits = diff(&a, &b);
+        assert_eq!(kept(&a, &b, &edits), 1);
+        assert_eq!(edits[1], Edit::Delete(1));
+        assert_eq!(edits[a.len()], Edit::Insert(1));
+    }
+
+    /// A minified stylesheet of 10000 rules shares no line with its formatted
+    /// text, which used to take gigabytes to diff.
+    #[test]
+    fn large_minified_stylesheet() {
+        let minified: String = (0..10_000)
+            .map(|i| format!(".c{i}{{color:red;margin:{i}px}}"))
+            .collect();
+        let formatted = format(&parse(&minified), "  ").unwrap();
+        let lines = formatted.lines().count();
+        let diff = unified(&minified, &formatted, "a/min.css", "b/min.css");
+        assert!(diff.starts_with(&format!("--- a/min.css\n+++ b/min.css\n@@ -1 +1,{lines} @@\n-.c0{{")));
+        assert_eq!(diff.lines().filter(|line| line.starts_with('+')).count(), lines + 1);
+    }
+}
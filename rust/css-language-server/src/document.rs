@@ -0,0 +1,157 @@
+use lsp_types::{Range, Uri};
+
+use crate::{
+    css::{
+        Span,
+        parser::{parse, reparse},
+        symbols::{self, Symbol, symbols},
+        syntax::Stylesheet,
+    },
+    html,
+    line_index::{Encoding, LineIndex},
+};
+
+/// A piece of CSS inside a document.
+///
+/// A CSS file is a single region covering the whole text. An HTML file has one
+/// region per `<style>` element and `style` attribute. Attribute values are
+/// wrapped in a synthetic rule so that they parse as a stylesheet; offsets in
+/// the stylesheet are mapped back to the host document with `to_host`.
+#[derive(Debug)]
+pub struct Region {
+    pub stylesheet: Stylesheet,
+    /// The names declared and referenced in the stylesheet.
+    pub symbols: Vec<Symbol>,
+    /// Offset of the CSS content in the host document.
+    host_start: usize,
+    /// Length of the synthetic text before the content.
+    prefix: usize,
+    /// Length of the content.
+    len: usize,
+}
+
+impl Region {
+    pub fn new(host_start: usize, prefix: &str, content: &str, suffix: &str) -> Self {
+        let stylesheet = parse(&format!("{prefix}{content}{suffix}"));
+        let symbols = symbols(&stylesheet);
+        Self {
+            stylesheet,
+            symbols,
+            host_start,
+            prefix: prefix.len(),
+            len: content.len(),
+        }
+    }
+
+    /// Replaces `edit` of a region that is a whole stylesheet, parsing
+    /// again only the rules around it.
+    fn edit(&mut self, edit: Span, replacement: &str) {
+        let old = std::mem::replace(&mut self.stylesheet, parse(""));
+        let (sheet, reparsed) = reparse(old, edit, replacement);
+        symbols::update(&mut self.symbols, &sheet, reparsed);
+        self.len = sheet.text.len();
+        self.stylesheet = sheet;
+    }
+
+    /// Whether `span` lies within the content rather than the synthetic text
+    /// around it.
+    pub fn contains(&self, span: Span) -> bool {
+        self.prefix <= span.start && span.end <= self.prefix + self.len
+    }
+
+    /// Maps an offset in the host document to an offset in the stylesheet, if
+    /// it lies within this region.
+    pub fn to_virtual(&self, host: usize) -> Option<usize> {
+        let offset = host.checked_sub(self.host_start)?;
+        (offset <= self.len).then_some(offset + self.prefix)
+    }
+
+    /// Maps an offset in the stylesheet to an offset in the host document.
+    /// Offsets inside the synthetic prefix or suffix are clamped to the
+    /// content.
+    pub fn to_host(&self, offset: usize) -> usize {
+        self.host_start + offset.saturating_sub(self.prefix).min(self.len)
+    }
+}
+
+#[derive(Debug)]
+pub struct Document {
+    pub uri: Uri,
+    pub language_id: String,
+    pub version: i32,
+    pub text: String,
+    pub line_index: LineIndex,
+    pub regions: Vec<Region>,
+}
+
+impl Document {
+    pub fn new(uri: Uri, language_id: String, version: i32, text: String, encoding: Encoding) -> Self {
+        let mut doc = Self {
+            uri,
+            language_id,
+            version,
+            text: String::new(),
+            line_index: LineIndex::new("", encoding),
+            regions: vec![],
+        };
+        doc.update(version, text);
+        doc
+    }
+
+    pub fn update(&mut self, version: i32, text: String) {
+        self.regions = regions(&self.language_id, &text);
+        self.version = version;
+        self.line_index = LineIndex::new(&text, self.line_index.encoding);
+        self.text = text;
+    }
+
+    /// Replaces `range` with `text`, as sent in an incremental change.
+    pub fn edit(&mut self, range: Range, text: &str) {
+        let start = self.line_index.offset(range.start);
+        let span = Span::new(start, self.line_index.offset(range.end).max(start));
+        self.text.replace_range(span.start..span.end, text);
+        self.line_index.edit(span, text);
+        match self.regions.as_mut_slice() {
+            [region] if is_stylesheet(&self.language_id) => region.edit(span, text),
+            _ => self.regions = regions(&self.language_id, &self.text),
+        }
+    }
+
+    /// The region containing `range` and the corresponding span in its
+    /// stylesheet. The end of the span is clamped to the region.
+    pub fn region_span(&self, range: Range) -> Option<(&Region, Span)> {
+        let start = self.line_index.offset(range.start);
+        let end = self.line_index.offset(range.end);
+        self.regions.iter().find_map(|region| {
+            let start = region.to_virtual(start)?;
+            let end = region.to_virtual(end).unwrap_or(region.prefix + region.len);
+            Some((region, Span::new(start, end.max(start))))
+        })
+    }
+
+    /// The range in the host document covered by `span` of `region`.
+    pub fn range(&self, region: &Region, span: Span) -> Range {
+        Range::new(
+            self.line_index.position(region.to_host(span.start)),
+            self.line_index.position(region.to_host(span.end)),
+        )
+    }
+}
+
+/// Whether a document is a stylesheet, rather than markup or a script with
+/// CSS inside.
+fn is_stylesheet(language_id: &str) -> bool {
+    !matches!(
+        language_id,
+        "html" | "vue" | "javascript" | "javascriptreact" | "typescript" | "typescriptreact"
+    )
+}
+
+fn regions(language_id: &str, text: &str) -> Vec<Region> {
+    match language_id {
+        "html" | "vue" => html::regions(text),
+        _ if is_stylesheet(language_id) => vec![Region::new(0, "", text, "")],
+        // Scripts are only indexed for the class names they use.
+        _ => vec![],
+    }
+}
@@ -0,0 +1,108 @@
+use std::{io::Write, path::PathBuf};
+
+use anyhow::{Context, Result, bail};
+use lsp_types::{FormattingOptions, Position, Range, TextEdit};
+
+use crate::{
+    css::{format::format, parser::parse},
+    diff::unified,
+    document::Document,
+    workspace::walk,
+};
+
+/// The indentation of one level for the editor's options.
+pub fn indent(options: &FormattingOptions) -> String {
+    match options.insert_spaces {
+        true => " ".repeat(options.tab_size as usize),
+        false => "\t".to_string(),
+    }
+}
+
+/// An edit replacing a CSS document with its formatted text, none if it is
+/// already formatted, or `None` if it cannot be formatted. Stylesheets
+/// embedded in HTML are left alone.
+pub fn formatting_edits(doc: &Document, options: &FormattingOptions) -> Option<Vec<TextEdit>> {
+    let [region] = &doc.regions[..] else { return None };
+    if doc.language_id != "css" {
+        return None;
+    }
+    let formatted = format(&region.stylesheet, &indent(options))?;
+    if formatted == doc.text {
+        return Some(vec![]);
+    }
+    let range = Range::new(Position::new(0, 0), doc.line_index.position(doc.text.len()));
+    Some(vec![TextEdit::new(range, formatted)])
+}
+
+/// The indentation of the command line formatter, the editor default.
+const INDENT: &str = "  ";
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// CSS files and directories to format. Directories are searched like the
+    /// editor indexes a workspace.
+    #[arg(default_value = ".")]
+    paths: Vec<PathBuf>,
+    /// Prints a diff for each file that is not formatted, and exits with a
+    /// non-zero status if there are any.
+    #[arg(long, conflicts_with = "write")]
+    check: bool,
+    /// Formats the files in place.
+    #[arg(long)]
+    write: bool,
+}
+
+/// Formats CSS files with the formatter of `textDocument/formatting`.
+/// Without `--check` or `--write`, the formatted files are printed. Returns
+/// whether the command failed: a file could not be formatted or, with
+/// `--check`, was not formatted.
+pub async fn run(args: Args) -> Result<bool> {
+    let root = std::env::current_dir()?;
+    let mut files = vec![];
+    for path in &args.paths {
+        if path.is_dir() {
+            let mut css = walk(path).await?;
+            css.retain(|file| file.extension().is_some_and(|e| e.eq_ignore_ascii_case("css")));
+            css.sort();
+            files.extend(css);
+        } else if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("css")) {
+            files.push(path.clone());
+        } else if !path.exists() {
+            bail!("cannot read `{}`", path.display());
+        } else {
+            bail!("`{}` is not a CSS file", path.display());
+        }
+    }
+
+    let mut failed = false;
+    let mut stdout = std::io::stdout().lock();
+    for file in files {
+        let text = tokio::fs::read_to_string(&file)
+            .await
+            .with_context(|| format!("cannot read `{}`", file.display()))?;
+        let name = file
+            .strip_prefix(&root)
+            .or_else(|_| file.strip_prefix("."))
+            .unwrap_or(&file)
+            .display()
+            .to_string();
+        let Some(formatted) = format(&parse(&text), INDENT) else {
+            eprintln!("error: cannot format `{name}`, it has syntax errors");
+            failed = true;
+            continue;
+        };
+        if args.check {
+            let diff = unified(&text, &formatted, &format!("a/{name}"), &format!("b/{name}"));
+            failed |= !diff.is_empty();
+            stdout.write_all(diff.as_bytes())?;
+        } else if args.write {
+            if formatted != text {
+                tokio::fs::write(&file, formatted).await?;
+            }
+        } else {
+            stdout.write_all(formatted.as_bytes())?;
+        }
+    }
+    stdout.flush()?;
+    Ok(failed)
+}
@@ -0,0 +1,91 @@
+//! The framing of the Language Server Protocol: messages preceded by a
+//! `Content-Length` header, as read from stdin and written to stdout.
+
+use std::fmt;
+
+use anyhow::{Context, Result, bail};
+use tokio::{
+    fs::OpenOptions,
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt},
+};
+
+/// The most bytes reserved for a body before it is read.
+const MAX_PREALLOCATION: usize = 1 << 20;
+
+/// Appends a line to the file named by `LOG_PATH`, if set and not empty.
+pub async fn save(msg: &str) -> Result<()> {
+    let Some(path) = std::env::var_os("LOG_PATH").filter(|path| !path.is_empty()) else {
+        return Ok(());
+    };
+    let mut f = OpenOptions::new().create(true).append(true).open(path).await?;
+    f.write_all(msg.as_bytes()).await?;
+    f.write_all("\r\n".as_bytes()).await?;
+    Ok(())
+}
+
+/// A JSON-RPC message with its `Content-Length` header.
+#[derive(Debug)]
+pub struct Message {
+    content_length: usize,
+    pub body: serde_json::Value,
+}
+
+impl Message {
+    pub fn new(body: serde_json::Value) -> Self {
+        let content_length = body.to_string().len();
+        Self { content_length, body }
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Content-Length: {}\r\n", self.content_length)?;
+        write!(f, "\r\n")?;
+        write!(f, "{}", self.body)
+    }
+}
+
+/// Reads one message. Returns `None` once the client has closed the stream.
+///
+/// Headers are read leniently: a `Content-Length` is found in any line that
+/// contains one, so that after a malformed frame the next well-formed header
+/// resynchronizes the stream. Stray blank lines before it are skipped.
+pub async fn parse_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Message>> {
+    let mut content_length = None;
+
+    loop {
+        let mut line = vec![];
+        if reader.read_until(b'\n', &mut line).await? == 0 {
+            return Ok(None);
+        }
+        let line = String::from_utf8_lossy(&line);
+        let line = line.trim_end_matches(['\r', '\n']);
+        save(&format!("header: '{line}'")).await?;
+
+        if line.is_empty() {
+            match content_length {
+                Some(_) => break,
+                None => continue,
+            }
+        }
+        let Some(start) = line.to_ascii_lowercase().find("content-length:") else {
+            continue;
+        };
+        let value = line[start + "content-length:".len()..].trim();
+        match value.parse::<usize>() {
+            Ok(length) => content_length = Some(length),
+            Err(_) => bail!("invalid Content-Length `{value}`"),
+        }
+    }
+
+    // The length is not trusted to preallocate: it may be far larger than
+    // the body that follows.
+    let content_length = content_length.unwrap_or_default();
+    let mut body = Vec::with_capacity(content_length.min(MAX_PREALLOCATION));
+    reader.take(content_length as u64).read_to_end(&mut body).await?;
+    if body.len() < content_length {
+        return Ok(None);
+    }
+    let value = serde_json::from_slice::<serde_json::Value>(&body).context("invalid JSON")?;
+    Ok(Some(Message::new(value)))
+}
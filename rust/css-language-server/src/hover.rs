@@ -0,0 +1,111 @@
+use lsp_types::{Hover, HoverContents, MarkupContent, MarkupKind, Position};
+
+use crate::{
+    css::{
+        math::evaluate_at,
+        media::explain_at,
+        selector::{Specificity, nested_rule_at, specificity},
+    },
+    document::Document,
+};
+
+/// Information about the value at `position`. Math functions show the type
+/// they resolve to and their simplified value, media and container queries
+/// when they apply, and nested selectors what they resolve to.
+pub fn hover(doc: &Document, position: Position) -> Option<Hover> {
+    let offset = doc.line_index.offset(position);
+    let (region, span, value) = doc.regions.iter().find_map(|region| {
+        let offset = region.to_virtual(offset)?;
+        if let Some(evaluation) = evaluate_at(&region.stylesheet, offset) {
+            let mut value = String::new();
+            if let Some(result) = &evaluation.value {
+                value.push_str(&format!("```css\n{result}\n```\n"));
+            }
+            value.push_str(&format!("Type: `{}`", evaluation.kind));
+            return Some((region, evaluation.span, value));
+        }
+        if let Some((span, explanation)) = explain_at(&region.stylesheet, offset) {
+            return Some((region, span, explanation));
+        }
+        let (span, selectors) = nested_rule_at(&region.stylesheet, offset)?;
+        let specificities: Vec<String> = selectors
+            .iter()
+            .map(|selector| {
+                let Specificity(a, b, c) = specificity(selector);
+                format!("`({a}, {b}, {c})`")
+            })
+            .collect();
+        let value = format!(
+            "```css\n{}\n```\nSpecificity: {}",
+            selectors.join(",\n"),
+            specificities.join(", ")
+        );
+        Some((region, span, value))
+    })?;
+
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }),
+        range: Some(doc.range(region, span)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line_index::Encoding;
+
+    /// The hover at the `|` marker, and the text it covers.
+    fn hover_at(language_id: &str, marked: &str) -> Option<(String, String)> {
+        let offset = marked.find('|').unwrap();
+        let text = marked.replace('|', "");
+        let doc = Document::new(
+            "file:///a".parse().unwrap(),
+            language_id.to_string(),
+            0,
+            text.clone(),
+            Encoding::default(),
+        );
+        let hover = hover(&doc, doc.line_index.position(offset))?;
+        let range = hover.range?;
+        let covered = text[doc.line_index.offset(range.start)..doc.line_index.offset(range.end)].to_string();
+        let HoverContents::Markup(markup) = hover.contents else {
+            return None;
+        };
+        Some((covered, markup.value))
+    }
+
+    #[test]
+    fn math() {
+        assert_eq!(
+            hover_at("css", "a { width: ca|lc(1px + 2px) }"),
+            Some((
+                "calc(1px + 2px)".to_string(),
+                "```css\n3px\n```\nType: `length`".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn embedded_in_html() {
+        let expected = Some((
+            "calc(1px + 2px)".to_string(),
+            "```css\n3px\n```\nType: `length`".to_string(),
+        ));
+        assert_eq!(
+            hover_at("html", "<style>\na { width: ca|lc(1px + 2px) }\n</style>"),
+            expected
+        );
+        assert_eq!(hover_at("html", "<p style=\"width: ca|lc(1px + 2px)\"></p>"), expected);
+        assert_eq!(hover_at("html", "<p sty|le=\"width: calc(1px + 2px)\"></p>"), None);
+    }
+
+    #[test]
+    fn nested_selectors() {
+        let (covered, value) = hover_at("css", ".a { &:ho|ver { color: red } }").unwrap();
+        assert_eq!(covered, "&:hover");
+        assert_eq!(value, "```css\n.a:hover\n```\nSpecificity: `(0, 2, 0)`");
+    }
+}
@@ -0,0 +1,157 @@
+use crate::document::Region;
+
+/// Extracts the CSS regions of an HTML document: the contents of `<style>`
+/// elements and the values of `style` attributes.
+///
+/// This is a tolerant scanner rather than a full HTML parser. It understands
+/// just enough (comments, tags, quoted attributes and raw text elements) to
+/// find CSS without being confused by markup inside scripts or comments.
+pub fn regions(text: &str) -> Vec<Region> {
+    let lower = text.to_ascii_lowercase();
+    let bytes = text.as_bytes();
+    let mut regions = vec![];
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        if bytes[pos..].starts_with(b"<!--") {
+            pos = find(&bytes[pos..], b"-->").map_or(bytes.len(), |i| pos + i + 3);
+            continue;
+        }
+        if bytes[pos] != b'<' || !bytes.get(pos + 1).is_some_and(u8::is_ascii_alphabetic) {
+            pos += 1;
+            continue;
+        }
+
+        pos += 1;
+        let name_start = pos;
+        while pos < bytes.len() && !is_tag_end(bytes[pos]) {
+            pos += 1;
+        }
+        let tag = &lower[name_start..pos];
+        pos = attributes(text, pos, &mut regions);
+
+        if tag == "style" || tag == "script" {
+            let close = format!("</{tag}");
+            let end = find(&lower.as_bytes()[pos..], close.as_bytes()).map_or(bytes.len(), |i| pos + i);
+            if tag == "style" {
+                regions.push(Region::new(pos, "", &text[pos..end], ""));
+            }
+            pos = end;
+        }
+    }
+
+    regions
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn is_tag_end(b: u8) -> bool {
+    b.is_ascii_whitespace() || b == b'>' || b == b'/'
+}
+
+/// Scans the attributes of a start tag beginning at `pos`, collecting `style`
+/// values. Returns the offset just past the end of the tag.
+fn attributes(text: &str, mut pos: usize, regions: &mut Vec<Region>) -> usize {
+    let bytes = text.as_bytes();
+    loop {
+        while pos < bytes.len() && (bytes[pos].is_ascii_whitespace() || bytes[pos] == b'/') {
+            pos += 1;
+        }
+        match bytes.get(pos) {
+            None => return pos,
+            Some(b'>') => return pos + 1,
+            _ => {}
+        }
+
+        let name_start = pos;
+        while pos < bytes.len() && !is_tag_end(bytes[pos]) && bytes[pos] != b'=' {
+            pos += 1;
+        }
+        let name = &text[name_start..pos];
+
+        let mut ws = pos;
+        while ws < bytes.len() && bytes[ws].is_ascii_whitespace() {
+            ws += 1;
+        }
+        if bytes.get(ws) != Some(&b'=') {
+            continue;
+        }
+        pos = ws + 1;
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+
+        let (value_start, value_end) = match bytes.get(pos) {
+            Some(&quote @ (b'"' | b'\'')) => {
+                let start = pos + 1;
+                let end = text[start..].find(quote as char).map_or(bytes.len(), |i| start + i);
+                pos = (end + 1).min(bytes.len());
+                (start, end)
+            }
+            _ => {
+                let start = pos;
+                while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() && bytes[pos] != b'>' {
+                    pos += 1;
+                }
+                (start, pos)
+            }
+        };
+
+        if name.eq_ignore_ascii_case("style") {
+            regions.push(Region::new(value_start, "*{", &text[value_start..value_end], "}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::Span;
+
+    /// The CSS of each region, as it appears in `text`.
+    fn contents(text: &str) -> Vec<&str> {
+        regions(text)
+            .iter()
+            .map(|region| &text[region.to_host(0)..region.to_host(usize::MAX)])
+            .collect()
+    }
+
+    #[test]
+    fn style_elements_and_attributes() {
+        let text = r#"<STYLE media="print">a { color: red }</style><p style="margin: 0" class=x>"#;
+        assert_eq!(contents(text), ["a { color: red }", "margin: 0"]);
+    }
+
+    #[test]
+    fn attribute_quotes() {
+        let text = "<p style='color: red'><p style=color:blue><p data-style=\"x\">";
+        assert_eq!(contents(text), ["color: red", "color:blue"]);
+    }
+
+    #[test]
+    fn skips_comments_and_scripts() {
+        let text = r#"<!-- <style>a {}</style> --><script>let s = "<style>b {}</style>";</script><style>c {}</style>"#;
+        assert_eq!(contents(text), ["c {}"]);
+    }
+
+    #[test]
+    fn unclosed_style_runs_to_the_end() {
+        assert_eq!(contents("<style>a { color: red }"), ["a { color: red }"]);
+    }
+
+    #[test]
+    fn attributes_parse_as_declarations() {
+        let text = r#"<div style="color: red; margin: 0">"#;
+        let [region] = &regions(text)[..] else {
+            panic!("expected one region")
+        };
+        let declarations = region.stylesheet.declarations();
+        assert_eq!(declarations.len(), 2);
+        assert!(region.stylesheet.errors.is_empty());
+        let name = declarations[1].name_span;
+        assert_eq!(&text[region.to_host(name.start)..region.to_host(name.end)], "margin");
+        assert!(!region.contains(Span::new(0, 1)));
+    }
+}
@@ -0,0 +1,6 @@
+//! The CSS parser and analyses behind the language server, and its message
+//! framing, as a library so that benchmarks and fuzz targets can drive them
+//! directly.
+
+pub mod css;
+pub mod framing;
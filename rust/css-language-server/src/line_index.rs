@@ -0,0 +1,319 @@
+use crate::css::Span;
+use lsp_types::{ClientCapabilities, Position, PositionEncodingKind};
+
+/// What the `character` of an LSP position counts. Until `initialize`
+/// agrees on one, and outside of a session, positions are in UTF-16 as the
+/// protocol requires by default.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Encoding {
+    Utf8,
+    #[default]
+    Utf16,
+    Utf32,
+}
+
+impl Encoding {
+    /// Picks UTF-8 if the client offers it, since it needs no conversion,
+    /// then UTF-32, and otherwise UTF-16, which every client supports.
+    pub fn negotiate(capabilities: &ClientCapabilities) -> Self {
+        let offered = capabilities
+            .general
+            .as_ref()
+            .and_then(|general| general.position_encodings.as_deref())
+            .unwrap_or_default();
+        if offered.contains(&PositionEncodingKind::UTF8) {
+            Encoding::Utf8
+        } else if offered.contains(&PositionEncodingKind::UTF32) {
+            Encoding::Utf32
+        } else {
+            Encoding::Utf16
+        }
+    }
+
+    pub fn kind(self) -> PositionEncodingKind {
+        match self {
+            Encoding::Utf8 => PositionEncodingKind::UTF8,
+            Encoding::Utf16 => PositionEncodingKind::UTF16,
+            Encoding::Utf32 => PositionEncodingKind::UTF32,
+        }
+    }
+}
+
+/// A character of more than one byte.
+#[derive(Debug, Clone, Copy)]
+struct Wide {
+    offset: usize,
+    utf8: usize,
+    utf16: usize,
+}
+
+/// Maps byte offsets in a text to LSP positions and back, in an encoding.
+/// Lines are found by binary search, and only lines with non-ASCII characters
+/// need any counting.
+#[derive(Debug, Default)]
+pub struct LineIndex {
+    /// The offset of the start of each line.
+    starts: Vec<usize>,
+    /// The characters of more than one byte, in order.
+    wide: Vec<Wide>,
+    len: usize,
+    pub encoding: Encoding,
+}
+
+impl LineIndex {
+    pub fn new(text: &str, encoding: Encoding) -> Self {
+        let mut starts = vec![0];
+        let mut wide = vec![];
+        for (offset, c) in text.char_indices() {
+            match c {
+                '\n' => starts.push(offset + 1),
+                c if !c.is_ascii() => wide.push(Wide {
+                    offset,
+                    utf8: c.len_utf8(),
+                    utf16: c.len_utf16(),
+                }),
+                _ => {}
+            }
+        }
+        Self {
+            starts,
+            wide,
+            len: text.len(),
+            encoding,
+        }
+    }
+
+    /// Updates the index after `edit` of the text is replaced with `text`.
+    /// Only the replaced lines are scanned; the others move.
+    pub fn edit(&mut self, edit: Span, text: &str) {
+        let delta = text.len() as isize - (edit.end - edit.start) as isize;
+        let inserted = Self::new(text, self.encoding);
+
+        let from = self.starts.partition_point(|&start| start <= edit.start);
+        let to = self.starts.partition_point(|&start| start <= edit.end);
+        for start in &mut self.starts[to..] {
+            *start = start.wrapping_add_signed(delta);
+        }
+        let starts = inserted.starts[1..].iter().map(|start| start + edit.start);
+        self.starts.splice(from..to, starts);
+
+        let from = self.wide.partition_point(|w| w.offset < edit.start);
+        let to = self.wide.partition_point(|w| w.offset < edit.end);
+        for w in &mut self.wide[to..] {
+            w.offset = w.offset.wrapping_add_signed(delta);
+        }
+        let wide = inserted.wide.iter().map(|w| Wide {
+            offset: w.offset + edit.start,
+            ..*w
+        });
+        self.wide.splice(from..to, wide);
+
+        self.len = self.len.wrapping_add_signed(delta);
+    }
+
+    /// The wide characters between two offsets.
+    fn wide_in(&self, start: usize, end: usize) -> &[Wide] {
+        let from = self.wide.partition_point(|w| w.offset < start);
+        let to = self.wide.partition_point(|w| w.offset < end);
+        &self.wide[from..to]
+    }
+
+    /// Converts a byte offset to a position, clamping offsets past the end
+    /// of the text.
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.len);
+        let line = self.starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.starts[line];
+        let character = match self.encoding {
+            Encoding::Utf8 => offset - start,
+            Encoding::Utf16 => {
+                let saved: usize = self.wide_in(start, offset).iter().map(|w| w.utf8 - w.utf16).sum();
+                offset - start - saved
+            }
+            Encoding::Utf32 => {
+                let saved: usize = self.wide_in(start, offset).iter().map(|w| w.utf8 - 1).sum();
+                offset - start - saved
+            }
+        };
+        Position::new(line as u32, character as u32)
+    }
+
+    /// Converts a position to a byte offset, clamping positions past the end
+    /// of a line or of the text. A position inside a character moves to its
+    /// start.
+    pub fn offset(&self, position: Position) -> usize {
+        let line = position.line as usize;
+        let Some(&start) = self.starts.get(line) else {
+            return self.len;
+        };
+        let end = self.starts.get(line + 1).map_or(self.len, |next| next - 1);
+        let mut offset = start;
+        let mut remaining = position.character as usize;
+        for w in self.wide_in(start, end) {
+            if remaining <= w.offset - offset {
+                break;
+            }
+            remaining -= w.offset - offset;
+            offset = w.offset;
+            let width = match self.encoding {
+                Encoding::Utf8 => w.utf8,
+                Encoding::Utf16 => w.utf16,
+                Encoding::Utf32 => 1,
+            };
+            if remaining < width {
+                return offset;
+            }
+            remaining -= width;
+            offset += w.utf8;
+        }
+        (offset + remaining).min(end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that editing an index gives the same positions and offsets as
+    /// indexing the edited text from scratch.
+    fn assert_edits(text: &str, edit: Span, replacement: &str, encoding: Encoding) {
+        let mut edited = text.to_string();
+        edited.replace_range(edit.start..edit.end, replacement);
+        let mut index = LineIndex::new(text, encoding);
+        index.edit(edit, replacement);
+        let expected = LineIndex::new(&edited, encoding);
+        let context = format!("replacing {:?} in {text:?} with {replacement:?}", edit.text(text));
+        assert_eq!(format!("{index:?}"), format!("{expected:?}"), "{context}");
+        for offset in (0..=edited.len() + 1).filter(|&offset| edited.is_char_boundary(offset.min(edited.len()))) {
+            assert_eq!(
+                index.position(offset),
+                expected.position(offset),
+                "{context} at {offset}"
+            );
+        }
+        for line in 0..=edited.lines().count() as u32 + 1 {
+            for character in 0..=12 {
+                let position = Position::new(line, character);
+                assert_eq!(
+                    index.offset(position),
+                    expected.offset(position),
+                    "{context} at {position:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn positions_and_offsets() {
+        let text = "a {\n  content: \"é😀\";\n}\n";
+        let index = LineIndex::new(text, Encoding::Utf16);
+        assert_eq!(index.position(16), Position::new(1, 12));
+        assert_eq!(index.position(18), Position::new(1, 13));
+        assert_eq!(index.position(22), Position::new(1, 15));
+        assert_eq!(index.offset(Position::new(1, 15)), 22);
+        // Inside the surrogate pair of the emoji.
+        assert_eq!(index.offset(Position::new(1, 14)), 18);
+        // Past the end of a line, and of the text.
+        assert_eq!(index.offset(Position::new(0, 10)), 3);
+        assert_eq!(index.offset(Position::new(9, 0)), text.len());
+        assert_eq!(index.position(100), Position::new(3, 0));
+
+        let index = LineIndex::new(text, Encoding::Utf8);
+        assert_eq!(index.position(22), Position::new(1, 18));
+        assert_eq!(index.offset(Position::new(1, 18)), 22);
+
+        let index = LineIndex::new(text, Encoding::Utf32);
+        assert_eq!(index.position(18), Position::new(1, 13));
+        assert_eq!(index.position(22), Position::new(1, 14));
+        assert_eq!(index.offset(Position::new(1, 14)), 22);
+    }
+
+    #[test]
+    fn crlf_and_astral_plane() {
+        // `𝒜` is four bytes, two UTF-16 code units and one UTF-32 one.
+        let text = "a {\r\n  content: \"𝒜\";\r\n}\r\n";
+        let index = LineIndex::new(text, Encoding::Utf16);
+        assert_eq!(index.position(17), Position::new(1, 12));
+        assert_eq!(index.position(21), Position::new(1, 14));
+        // The end of the line, before its `\r\n`.
+        assert_eq!(index.position(23), Position::new(1, 16));
+        assert_eq!(index.offset(Position::new(1, 16)), 23);
+        assert_eq!(index.position(25), Position::new(2, 0));
+        assert_eq!(index.position(28), Position::new(3, 0));
+        assert_eq!(index.offset(Position::new(1, 13)), 17);
+        let index = LineIndex::new(text, Encoding::Utf32);
+        assert_eq!(index.position(21), Position::new(1, 13));
+        assert_eq!(index.offset(Position::new(1, 15)), 23);
+
+        // Replacing it with a line break and another astral character.
+        let mut index = LineIndex::new(text, Encoding::Utf16);
+        index.edit(Span::new(17, 21), "x\r\n😀");
+        assert_eq!(index.position(17), Position::new(1, 12));
+        assert_eq!(index.position(20), Position::new(2, 0));
+        assert_eq!(index.position(24), Position::new(2, 2));
+        assert_eq!(index.offset(Position::new(2, 1)), 20);
+        assert_eq!(index.offset(Position::new(2, 2)), 24);
+        assert_eq!(index.position(28), Position::new(3, 0));
+        assert_eq!(index.position(31), Position::new(4, 0));
+    }
+
+    #[test]
+    fn negotiate() {
+        let offering = |encodings: Option<Vec<PositionEncodingKind>>| {
+            let capabilities = ClientCapabilities {
+                general: Some(lsp_types::GeneralClientCapabilities {
+                    position_encodings: encodings,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            Encoding::negotiate(&capabilities)
+        };
+        use PositionEncodingKind as Kind;
+        assert_eq!(
+            offering(Some(vec![Kind::UTF16, Kind::UTF32, Kind::UTF8])),
+            Encoding::Utf8
+        );
+        assert_eq!(offering(Some(vec![Kind::UTF32, Kind::UTF16])), Encoding::Utf32);
+        assert_eq!(offering(Some(vec![Kind::UTF16])), Encoding::Utf16);
+        assert_eq!(offering(None), Encoding::Utf16);
+        assert_eq!(Encoding::negotiate(&ClientCapabilities::default()), Encoding::Utf16);
+    }
+
+    #[test]
+    fn edit_matches_a_new_index() {
+        let text = "a {\n  content: \"é😀\";\n}\n\nb { width: 1px }";
+        let cases = [
+            // Within a line, adding and removing lines.
+            (Span::new(6, 13), "quotes"),
+            (Span::new(3, 3), "\n\n"),
+            (Span::new(3, 4), ""),
+            (Span::new(0, text.len()), ""),
+            (Span::new(text.len(), text.len()), "\nc {}\n"),
+            // Removing and inserting wide characters.
+            (Span::new(16, 22), ""),
+            (Span::new(0, 0), "ü\n€"),
+            (Span::new(16, 18), "日本\n語"),
+            // Joining the line of the wide characters with the next.
+            (Span::new(24, 25), " "),
+        ];
+        for encoding in [Encoding::Utf8, Encoding::Utf16, Encoding::Utf32] {
+            for (edit, replacement) in cases {
+                assert_edits(text, edit, replacement, encoding);
+            }
+        }
+    }
+
+    #[test]
+    fn edit_anywhere() {
+        let text = "é\n😀a\n\nb";
+        let boundaries: Vec<usize> = (0..=text.len()).filter(|&i| text.is_char_boundary(i)).collect();
+        for (i, &start) in boundaries.iter().enumerate() {
+            for &end in &boundaries[i..] {
+                for replacement in ["", "x", "\n", "ü\n", "\n😀\n"] {
+                    assert_edits(text, Span::new(start, end), replacement, Encoding::Utf16);
+                }
+            }
+        }
+    }
+}
@@ -1,145 +1,223 @@
-
+mod browserslist;
+mod cascade;
+mod check;
+mod code_action;
+mod code_lens;
+mod commands;
+mod completion;
+mod config;
+mod css_modules;
+mod diagnostics;
+mod diff;
+mod document;
+mod formatting;
+mod hover;
+mod html;
+mod line_index;
+mod notification;
+mod occurrences;
+mod panics;
+mod recording;
+mod references;
 mod request;
 mod response;
-mod notification;
+mod semantic_tokens;
+mod signature_help;
+mod state;
+mod template;
+mod uri;
+mod workspace;
 
 use std::path::PathBuf;
 
 use clap::Parser;
-use response::{send_error_response, send_method_not_found_response};
-use tokio::{fs::OpenOptions, io::{AsyncReadExt, AsyncWriteExt}};
+use css_language_server::{
+    css,
+    framing::{Message, parse_message, save},
+};
+use response::{log_error, send_error_response, send_method_not_found_response};
+use serde::de::DeserializeOwned;
+use state::State;
+use tokio::{io::BufReader, sync::mpsc};
 
 use anyhow::Result;
 
-async fn save(msg: &str) -> Result<()> {
-    let path = std::env::var("LOG_PATH")?;
-    let mut f = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(path)
-        .await?;
-    f.write_all(msg.as_bytes()).await?;
-    f.write_all("\r\n".as_bytes()).await?;
-    Ok(())
-}
-
-#[derive(Debug)]
-struct Message {
-    content_length: usize,
-    body: serde_json::Value,
-}
-
-impl Message {
-    fn new(body: serde_json::Value) -> Self {
-        let content_length = body.to_string().as_bytes().len();
-        Self {
-            content_length,
-            body,
+/// Sends each message from stdin to `sender`, until the client closes the
+/// stream. Malformed messages are sent as errors and skipped.
+async fn read_messages(sender: mpsc::UnboundedSender<Result<Message>>) {
+    let mut stdin = BufReader::new(tokio::io::stdin());
+    loop {
+        let message = match parse_message(&mut stdin).await {
+            Ok(Some(message)) => Ok(message),
+            Ok(None) => return,
+            Err(e) if e.is::<std::io::Error>() => return,
+            Err(e) => Err(e),
+        };
+        if sender.send(message).is_err() {
+            return;
         }
     }
 }
 
-impl ToString for Message {
-    fn to_string(&self) -> String {
-        let mut s = String::new();
-        s.push_str(&format!("Content-Length: {}\r\n", self.content_length));
-        s.push_str("\r\n");
-        s.push_str(&serde_json::to_string(&self.body).expect("valid json-rpc"));
-        s
-    }
+fn params<T: DeserializeOwned>(msg: &Message) -> Result<T> {
+    let params = msg.body.get("params").cloned().unwrap_or_default();
+    Ok(serde_json::from_value(params)?)
 }
 
-async fn parse_message() -> Result<Message> {
-    let mut is_ready = false;
-    let mut content_length = 0;
-    let mut body = vec![];
-
-    loop {
-        let mut stdin = tokio::io::stdin();
-        let mut msg = String::new();
-        stdin.read_to_string(&mut msg).await?;
-        
-        let lines = msg.split("\r\n").collect::<Vec<_>>();
-        for msg in lines {
-            save(&format!("{is_ready}-- '{msg}'")).await?;
-
-            if !is_ready {
-                if msg.is_empty() {
-                    is_ready = true;
-                    continue;
-                }
-                let mut kvs = msg.split(":");
-                let Some(k) = kvs.next() else { continue };
-                let k = k.trim();
-
-                if k == "Content-Length" {
-                    content_length = kvs.next()
-                        .unwrap_or_default()
-                        .trim()
-                        .parse::<usize>()
-                        .expect("content length is number");
-                }
-            } else {
-                let mut msg = msg.as_bytes().to_vec();
-                body.append(&mut msg);
-
-                let body_length = body.len();
-                if 0 < content_length && content_length <= body_length {
-                    let loaded = body.splice(..content_length, []).collect::<Vec<_>>();
-                    let value = serde_json::from_slice::<serde_json::Value>(&loaded)?;
-                    let msg = Message::new(value);
-
-                    return Ok(msg)
-                }
-            }
-        }
-    }
-}
-
-async fn dispatch(msg: Message) -> Result<()> {
-    let id = msg.body.as_object()
-        .and_then(|x| x.get("id"))
-        .and_then(|x| x.as_u64());
-    let method = msg.body.as_object()
+async fn dispatch(state: &mut State, msg: Message) -> Result<()> {
+    let id = msg.body.as_object().and_then(|x| x.get("id")).and_then(|x| x.as_u64());
+    let method = msg
+        .body
+        .as_object()
         .and_then(|x| x.get("method"))
         .and_then(|x| x.as_str().map(|s| s.to_string()));
     save(&format!("id: {id:?}, method: {method:?}")).await?;
-    
+
     match (id, method) {
         (None, None) => {
             send_error_response(None, -32600, "Received an invalid request").await?;
         }
-        (None, Some(method)) => {
-            match method.as_str() {
-                "initialized" => {
-                    save("got initialized notification").await?;
-                    notification::initialized().await?;
-                }
-                _ => {}
+        (None, Some(method)) => match method.as_str() {
+            "initialized" => {
+                save("got initialized notification").await?;
+                notification::initialized().await?;
             }
-        }
-        (Some(id), None) => {}
-        (Some(id), Some(method)) => {
-            match method.as_str() {
-                "initialize" => {
-                    save("got initialize method").await?;
-                    request::initialize(id).await?;
-                }
-                s => {
-                    save("Invalid method: '{s}'").await?;
-                    send_method_not_found_response(Some(id as u32), method).await?;
-                }
+            "textDocument/didOpen" => {
+                notification::did_open(state, params(&msg)?).await?;
             }
-        }
+            "textDocument/didChange" => {
+                notification::did_change(state, params(&msg)?).await?;
+            }
+            "textDocument/didClose" => {
+                notification::did_close(state, params(&msg)?).await?;
+            }
+            "textDocument/didSave" => {
+                notification::did_save(state, params(&msg)?).await?;
+            }
+            "workspace/didChangeWorkspaceFolders" => {
+                notification::did_change_workspace_folders(state, params(&msg)?).await?;
+            }
+            "$/cancelRequest" => {
+                notification::cancel_request(state, params(&msg)?).await?;
+            }
+            _ => {}
+        },
+        (Some(_), None) => {}
+        (Some(id), Some(method)) => match method.as_str() {
+            "initialize" => {
+                save("got initialize method").await?;
+                request::initialize(id, state, params(&msg)?).await?;
+            }
+            "textDocument/codeAction" => {
+                request::code_action(id, state, params(&msg)?).await?;
+            }
+            "textDocument/documentColor" => {
+                request::document_color(id, state, params(&msg)?).await?;
+            }
+            "textDocument/colorPresentation" => {
+                request::color_presentation(id, params(&msg)?).await?;
+            }
+            "textDocument/semanticTokens/full" => {
+                request::semantic_tokens_full(id, state, params(&msg)?).await?;
+            }
+            "textDocument/semanticTokens/full/delta" => {
+                request::semantic_tokens_full_delta(id, state, params(&msg)?).await?;
+            }
+            "textDocument/semanticTokens/range" => {
+                request::semantic_tokens_range(id, state, params(&msg)?).await?;
+            }
+            "textDocument/completion" => {
+                request::completion(id, state, params(&msg)?).await?;
+            }
+            "textDocument/codeLens" => {
+                request::code_lens(id, state, params(&msg)?).await?;
+            }
+            "codeLens/resolve" => {
+                request::code_lens_resolve(id, state, params(&msg)?).await?;
+            }
+            "textDocument/documentHighlight" => {
+                request::document_highlight(id, state, params(&msg)?).await?;
+            }
+            "textDocument/linkedEditingRange" => {
+                request::linked_editing_range(id, state, params(&msg)?).await?;
+            }
+            "textDocument/formatting" => {
+                request::formatting(id, state, params(&msg)?).await?;
+            }
+            "textDocument/hover" => {
+                request::hover(id, state, params(&msg)?).await?;
+            }
+            "textDocument/signatureHelp" => {
+                request::signature_help(id, state, params(&msg)?).await?;
+            }
+            "textDocument/references" => {
+                request::references(id, state, params(&msg)?).await?;
+            }
+            "workspace/executeCommand" => {
+                request::execute_command(id, state, params(&msg)?).await?;
+            }
+            "css/cascadeConflicts" => {
+                request::cascade_conflicts(id, state, params(&msg)?).await?;
+            }
+            "textDocument/diagnostic" => {
+                request::document_diagnostic(id, state, params(&msg)?).await?;
+            }
+            "workspace/diagnostic" => {
+                request::workspace_diagnostic(id, state, params(&msg)?).await?;
+            }
+            s => {
+                save(&format!("Invalid method: '{s}'")).await?;
+                send_method_not_found_response(Some(id as u32), method).await?;
+            }
+        },
     }
 
     Ok(())
 }
 
+/// Dispatches a message, isolating the server from its handler: an error or
+/// a panic is logged and, for a request, answered with an `InternalError`.
+async fn handle(state: &mut State, msg: Message) -> Result<()> {
+    let request = match msg.body.get("method") {
+        Some(_) => msg.body.get("id").and_then(|id| id.as_u64()),
+        None => None,
+    };
+    let error = match panics::catch_unwind(dispatch(state, msg)).await {
+        Ok(Ok(())) => return Ok(()),
+        Ok(Err(e)) => format!("{e:#}"),
+        Err(report) => report,
+    };
+    save(&format!("ERROR: {error}")).await?;
+    log_error(&error).await?;
+    if let Some(id) = request {
+        let message = error.lines().next().unwrap_or_default();
+        send_error_response(Some(id as u32), -32603, message).await?;
+    }
+    Ok(())
+}
+
 #[derive(Debug, Parser)]
 struct Cli {
     #[clap(short, long)]
     env: Option<PathBuf>,
+    /// Writes every message received and sent to a JSONL file, which the
+    /// `replay` command can run again.
+    #[clap(long)]
+    record: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum Command {
+    /// Reports the diagnostics an editor would show for CSS, HTML and Vue
+    /// files, and exits with a non-zero status if there are errors.
+    Check(check::Args),
+    /// Formats CSS files like the editor does.
+    Fmt(formatting::Args),
+    /// Runs a recorded session against a fresh server and diffs the messages
+    /// it sends, exiting with a non-zero status if they changed.
+    Replay(recording::Args),
 }
 
 #[tokio::main]
@@ -148,18 +226,48 @@ async fn main() -> Result<()> {
     if let Some(env) = cli.env {
         dotenvy::from_path(env)?;
     }
-    
+    if let Some(command) = cli.command {
+        let failed = match command {
+            Command::Check(args) => check::run(args).await?,
+            Command::Fmt(args) => formatting::run(args).await?,
+            Command::Replay(args) => recording::replay(args).await?,
+        };
+        if failed {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    panics::install_hook();
+    if let Some(path) = &cli.record {
+        recording::start(path)?;
+    }
+
+    let mut state = State::default();
+    // Messages are read on their own task, so that delayed diagnostics can
+    // be pushed while waiting for the next one.
+    let (sender, mut messages) = mpsc::unbounded_channel();
+    tokio::spawn(read_messages(sender));
     loop {
         save("loop starting point").await?;
-        match parse_message().await {
-            Ok(msg) => {
-                // save(msg.to_string().as_str()).await?;
-                let resp = dispatch(msg).await;
-                save(&format!("resp: {resp:?}")).await?;
+        let message = match state.diagnostics_deadline {
+            Some(deadline) => tokio::select! {
+                message = messages.recv() => message,
+                () = tokio::time::sleep_until(deadline) => {
+                    notification::publish_delayed(&mut state).await?;
+                    continue;
+                }
+            },
+            None => messages.recv().await,
+        };
+        match message {
+            None => return Ok(()),
+            Some(Ok(msg)) => {
+                recording::received(&msg.body)?;
+                handle(&mut state, msg).await?;
             }
-            Err(e) => {
+            Some(Err(e)) => {
                 save(&format!("ERROR: {e}")).await?;
-                send_error_response(None, -32700, "received an invalid JSON").await?;
+                send_error_response(None, -32700, &format!("{e:#}")).await?;
             }
         }
     }
@@ -1,9 +1,180 @@
-
 use anyhow::Result;
+use lsp_types::{
+    CancelParams, DidChangeTextDocumentParams, DidChangeWorkspaceFoldersParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DidSaveTextDocumentParams, NumberOrString, Uri,
+};
 
-use crate::response::log_message;
+use crate::{
+    css_modules::{classes, declarations, is_module, is_script_language},
+    diagnostics::diagnostics,
+    document::Document,
+    request::resume_workspace_diagnostic,
+    response::{log_message, publish_diagnostics, send_error_response},
+    state::State,
+    template::is_template_language,
+    uri,
+};
 
 pub async fn initialized() -> Result<()> {
     log_message("initialized").await?;
     Ok(())
-}
\ No newline at end of file
+}
+
+pub async fn did_open(state: &mut State, params: DidOpenTextDocumentParams) -> Result<()> {
+    let item = params.text_document;
+    let indexed = state.index_document(&item.uri, &item.language_id, &item.text);
+    let doc = Document::new(
+        item.uri.clone(),
+        item.language_id,
+        item.version,
+        item.text,
+        state.encoding,
+    );
+    state.documents.insert(item.uri.clone(), doc);
+    publish(state, &item.uri, indexed).await?;
+    resume_workspace_diagnostic(state).await
+}
+
+pub async fn did_change(state: &mut State, params: DidChangeTextDocumentParams) -> Result<()> {
+    let uri = params.text_document.uri;
+    let version = params.text_document.version;
+    let Some(doc) = state.documents.get_mut(&uri) else {
+        return Ok(());
+    };
+    for change in params.content_changes {
+        match change.range {
+            Some(range) => doc.edit(range, &change.text),
+            None => doc.update(version, change.text),
+        }
+    }
+    doc.version = version;
+
+    let indexed = is_template_language(&doc.language_id) || is_script_language(&doc.language_id);
+    if indexed {
+        let (language_id, text) = (doc.language_id.clone(), doc.text.clone());
+        state.index_document(&uri, &language_id, &text);
+    }
+    match state.pull_diagnostics {
+        // A held workspace request is answered once the edits settle.
+        true if state.pending_workspace_diagnostic.is_some() => state.delay_diagnostics(vec![]),
+        true => {}
+        false => {
+            let uris: Vec<Uri> = match indexed {
+                true => state
+                    .documents
+                    .keys()
+                    .filter(|other| state.same_folder(other, &uri))
+                    .cloned()
+                    .collect(),
+                false => vec![uri],
+            };
+            state.delay_diagnostics(uris);
+        }
+    }
+    Ok(())
+}
+
+pub async fn did_close(state: &mut State, params: DidCloseTextDocumentParams) -> Result<()> {
+    let uri = params.text_document.uri;
+    let Some(doc) = state.documents.remove(&uri) else {
+        return Ok(());
+    };
+    state.semantic_tokens.remove(&uri);
+    let workspace = &mut state.folder_mut(&uri).workspace;
+    workspace.reload(&uri).await;
+    if !workspace.files.contains_key(&uri) {
+        state.diagnostics.remove(&uri);
+    }
+    if !state.pull_diagnostics {
+        publish_diagnostics(&uri, None, vec![]).await?;
+    }
+    let indexed = is_template_language(&doc.language_id) || is_script_language(&doc.language_id);
+    publish(state, &uri, indexed).await?;
+    resume_workspace_diagnostic(state).await
+}
+
+pub async fn did_save(state: &State, params: DidSaveTextDocumentParams) -> Result<()> {
+    let uri = params.text_document.uri;
+    if !state.folder(&uri).config.module_declarations || !is_module(&uri) {
+        return Ok(());
+    }
+    let (Some(doc), Some(path)) = (state.documents.get(&uri), uri::to_path(&uri)) else {
+        return Ok(());
+    };
+    let mut path = path.into_os_string();
+    path.push(".d.ts");
+    tokio::fs::write(path, declarations(&classes(doc))).await?;
+    Ok(())
+}
+
+/// Adds and removes workspace folders. Open documents may move to another
+/// folder, so their diagnostics are pushed again.
+pub async fn did_change_workspace_folders(state: &mut State, params: DidChangeWorkspaceFoldersParams) -> Result<()> {
+    for folder in params.event.removed {
+        if let Some(root) = uri::to_path(&folder.uri) {
+            state.remove_folder(&root).await?;
+        }
+    }
+    for folder in params.event.added {
+        if let Some(root) = uri::to_path(&folder.uri) {
+            state.add_folder(root).await?;
+        }
+    }
+    if state.pull_diagnostics {
+        return resume_workspace_diagnostic(state).await;
+    }
+    for doc in state.documents.values() {
+        let folder = state.folder(&doc.uri);
+        let diagnostics = diagnostics(doc, &folder.config, &folder.workspace);
+        publish_diagnostics(&doc.uri, Some(doc.version), diagnostics).await?;
+    }
+    Ok(())
+}
+
+/// Pushes the diagnostics delayed by edits, and answers a held workspace
+/// request.
+pub async fn publish_delayed(state: &mut State) -> Result<()> {
+    state.diagnostics_deadline = None;
+    for uri in std::mem::take(&mut state.stale_diagnostics) {
+        // The document may have been closed since.
+        let Some(doc) = state.documents.get(&uri) else { continue };
+        let folder = state.folder(&uri);
+        let diagnostics = diagnostics(doc, &folder.config, &folder.workspace);
+        publish_diagnostics(&doc.uri, Some(doc.version), diagnostics).await?;
+    }
+    resume_workspace_diagnostic(state).await
+}
+
+/// Pushes the diagnostics of `uri`, or of every open document in its folder
+/// when the class names used by templates or scripts changed, since that
+/// affects which selectors are reported as unused.
+async fn publish(state: &State, uri: &Uri, index_changed: bool) -> Result<()> {
+    if state.pull_diagnostics {
+        return Ok(());
+    }
+    for doc in state.documents.values() {
+        if (index_changed && state.same_folder(&doc.uri, uri)) || doc.uri == *uri {
+            let folder = state.folder(&doc.uri);
+            let diagnostics = diagnostics(doc, &folder.config, &folder.workspace);
+            publish_diagnostics(&doc.uri, Some(doc.version), diagnostics).await?;
+        }
+    }
+    Ok(())
+}
+
+/// `$/cancelRequest`: only a held `workspace/diagnostic` request is still
+/// running when a cancellation arrives, and it is answered as cancelled.
+pub async fn cancel_request(state: &mut State, params: CancelParams) -> Result<()> {
+    let NumberOrString::Number(id) = params.id else {
+        return Ok(());
+    };
+    match state.pending_workspace_diagnostic.take() {
+        Some((pending, _)) if pending == id as u64 => {
+            send_error_response(Some(pending as u32), -32800, "request cancelled").await
+        }
+        pending => {
+            state.pending_workspace_diagnostic = pending;
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use lsp_types::{DocumentHighlight, DocumentHighlightKind, LinkedEditingRanges, Position, Range};
+
+use crate::{
+    css::symbols::{Symbol, SymbolKind},
+    document::Document,
+};
+
+/// Characters a linked edit may leave in a keyframes name or custom property.
+const WORD_PATTERN: &str = r"-{0,2}[_a-zA-Z\u00A0-\uFFFF][-_a-zA-Z0-9\u00A0-\uFFFF]*";
+
+/// Every occurrence in `doc` of the custom property, keyframes or layer name,
+/// class or id at `position`. Declarations are writes and references reads.
+pub fn document_highlights(doc: &Document, position: Position) -> Vec<DocumentHighlight> {
+    occurrences(doc, position)
+        .into_iter()
+        .map(|(range, symbol)| DocumentHighlight {
+            range,
+            kind: Some(match (symbol.kind, symbol.declaration) {
+                (SymbolKind::Class | SymbolKind::Id, _) => DocumentHighlightKind::TEXT,
+                (_, true) => DocumentHighlightKind::WRITE,
+                (_, false) => DocumentHighlightKind::READ,
+            }),
+        })
+        .collect()
+}
+
+/// The occurrences of the keyframes name or custom property at `position`,
+/// which are edited together, such as `@keyframes foo` and `animation: foo`.
+/// Selectors are not linked: renaming one rule's class does not rename the
+/// others. Nor are layer names, whose dots the word pattern does not allow.
+pub fn linked_editing_ranges(doc: &Document, position: Position) -> Option<LinkedEditingRanges> {
+    let occurrences = occurrences(doc, position);
+    let (_, symbol) = occurrences.first()?;
+    if matches!(symbol.kind, SymbolKind::Class | SymbolKind::Id | SymbolKind::Layer) {
+        return None;
+    }
+    Some(LinkedEditingRanges {
+        ranges: occurrences.into_iter().map(|(range, _)| range).collect(),
+        word_pattern: Some(WORD_PATTERN.to_string()),
+    })
+}
+
+fn occurrences(doc: &Document, position: Position) -> Vec<(Range, &Symbol)> {
+    let offset = doc.line_index.offset(position);
+    let target = doc.regions.iter().find_map(|region| {
+        let offset = region.to_virtual(offset)?;
+        region
+            .symbols
+            .iter()
+            .find(|symbol| symbol.span.start <= offset && offset <= symbol.span.end)
+    });
+    let Some(target) = target else {
+        return vec![];
+    };
+    doc.regions
+        .iter()
+        .flat_map(|region| {
+            region
+                .symbols
+                .iter()
+                .filter(|symbol| symbol.kind == target.kind && symbol.name == target.name)
+                .filter(|symbol| region.contains(symbol.span))
+                .map(|symbol| (doc.range(region, symbol.span), symbol))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line_index::Encoding;
+
+    /// The document without the `|` in `marked`, and the position of the `|`.
+    fn document(language_id: &str, marked: &str) -> (Document, Position) {
+        let offset = marked.find('|').unwrap();
+        let doc = Document::new(
+            "file:///a".parse().unwrap(),
+            language_id.to_string(),
+            0,
+            marked.replace('|', ""),
+            Encoding::default(),
+        );
+        let position = doc.line_index.position(offset);
+        (doc, position)
+    }
+
+    /// The offsets of `range` in `doc`.
+    fn offsets(doc: &Document, range: Range) -> (usize, usize) {
+        (doc.line_index.offset(range.start), doc.line_index.offset(range.end))
+    }
+
+    fn highlights(language_id: &str, marked: &str) -> Vec<(usize, usize, DocumentHighlightKind)> {
+        let (doc, position) = document(language_id, marked);
+        document_highlights(&doc, position)
+            .into_iter()
+            .map(|highlight| {
+                let (start, end) = offsets(&doc, highlight.range);
+                (start, end, highlight.kind.unwrap())
+            })
+            .collect()
+    }
+
+    fn linked(language_id: &str, marked: &str) -> Option<Vec<(usize, usize)>> {
+        let (doc, position) = document(language_id, marked);
+        let ranges = linked_editing_ranges(&doc, position)?;
+        Some(ranges.ranges.into_iter().map(|range| offsets(&doc, range)).collect())
+    }
+
+    #[test]
+    fn document_highlights_of_each_kind() {
+        use DocumentHighlightKind as Kind;
+        let text = "a { --x: 1; width: var(--|x) }\nb { --x: 2 }";
+        assert_eq!(
+            highlights("css", text),
+            [(4, 7, Kind::WRITE), (23, 26, Kind::READ), (34, 37, Kind::WRITE)]
+        );
+        let text = "@layer a|, b;\n@layer b {}\n@layer a {}";
+        assert_eq!(highlights("css", text), [(7, 8, Kind::WRITE), (32, 33, Kind::READ)]);
+        let text = ".a|.b {}\n.b .a:hover {}";
+        assert_eq!(highlights("css", text), [(1, 2, Kind::TEXT), (12, 13, Kind::TEXT)]);
+        assert_eq!(highlights("css", "a { co|lor: red }"), []);
+    }
+
+    #[test]
+    fn document_highlights_in_html() {
+        let text = "<style>.a { color: red }</style>\n<style>.a| {}</style>";
+        assert_eq!(
+            highlights("html", text),
+            [
+                (8, 9, DocumentHighlightKind::TEXT),
+                (41, 42, DocumentHighlightKind::TEXT)
+            ]
+        );
+        // The synthetic rule around a style attribute has no class.
+        let text = "<p style=\"--|x: 1; top: var(--x)\"></p>";
+        assert_eq!(
+            highlights("html", text),
+            [
+                (10, 13, DocumentHighlightKind::WRITE),
+                (27, 30, DocumentHighlightKind::READ)
+            ]
+        );
+    }
+
+    #[test]
+    fn linked_keyframes_names() {
+        // Written as an identifier or a string, without the quotes.
+        let text = "@keyframes \"sp|in\" {}\na { animation: 1s spin; animation-name: 'spin', fade }";
+        assert_eq!(linked("css", text), Some(vec![(12, 16), (39, 43), (62, 66)]));
+        let text = "@keyframes spin {}\na { animation: 1s sp|in infinite }";
+        assert_eq!(linked("css", text), Some(vec![(11, 15), (37, 41)]));
+    }
+
+    #[test]
+    fn linked_custom_properties() {
+        let text = "@property --x {}\na { --|x: 1; width: var(--x, 2px) }";
+        assert_eq!(linked("css", text), Some(vec![(10, 13), (21, 24), (40, 43)]));
+        let (doc, position) = document("css", "a { --|x: 1 }");
+        let ranges = linked_editing_ranges(&doc, position).unwrap();
+        assert_eq!(ranges.word_pattern.as_deref(), Some(WORD_PATTERN));
+    }
+
+    #[test]
+    fn unlinked_selectors_and_layers() {
+        assert_eq!(linked("css", ".a| {}\n.a {}"), None);
+        assert_eq!(linked("css", "#a| {}"), None);
+        assert_eq!(linked("css", "@layer a|; @layer a {}"), None);
+        assert_eq!(linked("css", "a { co|lor: red }"), None);
+    }
+}
@@ -0,0 +1,71 @@
+use std::{
+    backtrace::Backtrace,
+    cell::RefCell,
+    future::{Future, poll_fn},
+    panic::{self, AssertUnwindSafe},
+    pin::pin,
+    task::Poll,
+};
+
+thread_local! {
+    /// The report of the last panic on this thread. The hook runs on the
+    /// panicking thread right before the unwinding reaches `catch_unwind`,
+    /// so a panic on another thread cannot take its place.
+    static REPORT: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Makes panics print their message with a backtrace to stderr, and keeps
+/// that report for `catch_unwind` to return.
+pub fn install_hook() {
+    panic::set_hook(Box::new(|info| {
+        let message = info.payload_as_str().unwrap_or("Box<dyn Any>");
+        let location = info.location().map(|l| format!(" at {l}")).unwrap_or_default();
+        let backtrace = Backtrace::force_capture();
+        let report = format!("panicked{location}: {message}\n\nstack backtrace:\n{backtrace}");
+        eprintln!("{report}");
+        REPORT.set(Some(report));
+    }));
+}
+
+/// Runs `future`, returning the report of the panic if it panics.
+///
+/// The state the future borrows may be left half updated. That is accepted,
+/// as it is no worse than the server exiting.
+pub async fn catch_unwind<F: Future>(future: F) -> Result<F::Output, String> {
+    let mut future = pin!(future);
+    poll_fn(
+        |cx| match panic::catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(cx))) {
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(payload) => {
+                let report = REPORT.take();
+                let report = report.unwrap_or_else(|| match payload.downcast_ref::<&str>() {
+                    Some(message) => format!("panicked: {message}"),
+                    None => match payload.downcast_ref::<String>() {
+                        Some(message) => format!("panicked: {message}"),
+                        None => "panicked".to_string(),
+                    },
+                });
+                Poll::Ready(Err(report))
+            }
+        },
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn reports_the_panic_of_its_own_thread() {
+        install_hook();
+        // A panic on another thread leaves its report there.
+        let _ = std::thread::spawn(|| panic!("elsewhere")).join();
+        let report = catch_unwind(async { panic!("here") }).await.unwrap_err();
+        assert!(report.starts_with("panicked at src/panics.rs:"), "{report}");
+        assert!(report.contains(": here\n\nstack backtrace:"), "{report}");
+        assert!(!report.contains("elsewhere"));
+        assert_eq!(catch_unwind(async { 1 }).await, Ok(1));
+    }
+}
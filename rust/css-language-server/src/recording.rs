@@ -0,0 +1,122 @@
+use std::{
+    cell::RefCell,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result, bail};
+use serde_json::{Value, json};
+
+use crate::{
+    Message,
+    diff::unified,
+    handle,
+    notification::publish_delayed,
+    state::{DIAGNOSTICS_DELAY, State},
+};
+
+/// The file given with `--record`.
+static RECORDING: OnceLock<Mutex<File>> = OnceLock::new();
+
+tokio::task_local! {
+    /// The messages sent while replaying, instead of writing them to stdout.
+    static SENT: RefCell<Vec<Value>>;
+}
+
+/// Starts writing every message received and sent to `path`, one JSON object
+/// per line: `{"time": <ms since the Unix epoch>, "direction": "received" |
+/// "sent", "message": {...}}`.
+pub fn start(path: &Path) -> Result<()> {
+    let file = File::create(path).with_context(|| format!("cannot create `{}`", path.display()))?;
+    RECORDING
+        .set(Mutex::new(file))
+        .map_err(|_| anyhow::anyhow!("already recording"))
+}
+
+pub fn received(message: &Value) -> Result<()> {
+    record("received", message)
+}
+
+/// Records a message the server sends. Returns whether a replay captured it,
+/// in which case it must not be written to stdout.
+pub fn sent(message: &Value) -> Result<bool> {
+    record("sent", message)?;
+    Ok(SENT.try_with(|sent| sent.borrow_mut().push(message.clone())).is_ok())
+}
+
+fn record(direction: &str, message: &Value) -> Result<()> {
+    let Some(recording) = RECORDING.get() else {
+        return Ok(());
+    };
+    let time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+    let line = json!({
+        "time": time,
+        "direction": direction,
+        "message": message,
+    });
+    let mut file = recording.lock().map_err(|_| anyhow::anyhow!("recording poisoned"))?;
+    writeln!(file, "{line}")?;
+    Ok(())
+}
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// A file written with `--record`.
+    recording: PathBuf,
+}
+
+/// Sends the received messages of a recording to a fresh server and diffs
+/// what it sends back against the recorded messages. Returns whether they
+/// differ.
+pub async fn replay(args: Args) -> Result<bool> {
+    let text = tokio::fs::read_to_string(&args.recording)
+        .await
+        .with_context(|| format!("cannot read `{}`", args.recording.display()))?;
+    let mut received = vec![];
+    let mut expected = vec![];
+    for (i, line) in text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let entry: Value = serde_json::from_str(line).with_context(|| format!("line {}", i + 1))?;
+        let message = entry.get("message").cloned().unwrap_or_default();
+        let time = entry.get("time").and_then(Value::as_u64).unwrap_or_default();
+        match entry.get("direction").and_then(Value::as_str) {
+            Some("received") => received.push((time, message)),
+            Some("sent") => expected.push(message),
+            _ => bail!("line {}: expected a direction of `received` or `sent`", i + 1),
+        }
+    }
+
+    let actual = SENT
+        .scope(RefCell::new(vec![]), async {
+            let mut state = State::default();
+            // Delayed diagnostics are pushed where the recorded session paused
+            // long enough for them, according to its timestamps.
+            let delay = DIAGNOSTICS_DELAY.as_millis() as u64;
+            let mut deadline = None;
+            for (time, message) in received {
+                if deadline.is_some_and(|deadline| deadline <= time) {
+                    let _ = publish_delayed(&mut state).await;
+                    deadline = None;
+                }
+                let before = state.diagnostics_deadline;
+                let _ = handle(&mut state, Message::new(message)).await;
+                if state.diagnostics_deadline != before {
+                    deadline = Some(time + delay);
+                }
+            }
+            let _ = publish_delayed(&mut state).await;
+            SENT.with(|sent| sent.take())
+        })
+        .await;
+
+    let diff = unified(&pretty(&expected), &pretty(&actual), "recorded", "replayed");
+    print!("{diff}");
+    Ok(!diff.is_empty())
+}
+
+/// One message per block of lines, so that the diff points at fields.
+fn pretty(messages: &[Value]) -> String {
+    messages.iter().map(|message| format!("{message:#}\n")).collect()
+}
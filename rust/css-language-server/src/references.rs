@@ -0,0 +1,140 @@
+use lsp_types::{Location, Position, Range, Uri};
+
+use crate::{
+    css::{
+        Span,
+        selector::{NameKind, names},
+        symbols::SymbolKind,
+    },
+    css_modules::{is_module, reads},
+    line_index::LineIndex,
+    state::State,
+};
+
+/// What a reference search is looking for.
+enum Target {
+    /// A global class name or id, used by templates.
+    Name(NameKind, String),
+    /// A class of a CSS Module, used by the scripts importing it.
+    ModuleClass(Uri, String),
+}
+
+/// Finds the uses of the class or id at `position`, which may be a selector
+/// in a stylesheet, a usage in a template or a class read from a CSS Module
+/// by a script. Uses in markup and scripts are always included; selectors
+/// only with `include_declaration`.
+pub fn find_references(state: &State, uri: &Uri, position: Position, include_declaration: bool) -> Vec<Location> {
+    let Some(target) = target_at(state, uri, position) else {
+        return vec![];
+    };
+
+    let mut locations = vec![];
+    if include_declaration {
+        for doc in state.folder_documents(uri) {
+            let module = is_module(&doc.uri);
+            for region in &doc.regions {
+                for selector in names(&region.stylesheet) {
+                    let matches = match &target {
+                        Target::Name(kind, name) => !module && selector.kind == *kind && selector.name == *name,
+                        Target::ModuleClass(uri, name) => {
+                            doc.uri == *uri && selector.kind == NameKind::Class && selector.name == *name
+                        }
+                    };
+                    if matches && region.contains(selector.span) {
+                        locations.push(Location::new(doc.uri.clone(), doc.range(region, selector.span)));
+                    }
+                }
+            }
+        }
+    }
+    let workspace = &state.folder(uri).workspace;
+    match &target {
+        Target::Name(kind, name) => {
+            for (uri, template) in &workspace.templates {
+                for usage in &template.usages {
+                    if usage.kind == *kind && usage.name == *name {
+                        locations.push(location(uri, &template.line_index, usage.span));
+                    }
+                }
+            }
+        }
+        Target::ModuleClass(module, class) => {
+            for (uri, import) in workspace.module_imports(module) {
+                let line_index = &workspace.scripts[uri].line_index;
+                for (name, span) in &import.usages {
+                    if reads(name, class) {
+                        locations.push(location(uri, line_index, *span));
+                    }
+                }
+            }
+        }
+    }
+    locations
+}
+
+fn location(uri: &Uri, line_index: &LineIndex, span: Span) -> Location {
+    let range = Range::new(line_index.position(span.start), line_index.position(span.end));
+    Location::new(uri.clone(), range)
+}
+
+fn target_at(state: &State, uri: &Uri, position: Position) -> Option<Target> {
+    if let Some(doc) = state.document(uri) {
+        let offset = doc.line_index.offset(position);
+        let selector = doc.regions.iter().find_map(|region| {
+            let offset = region.to_virtual(offset)?;
+            names(&region.stylesheet)
+                .into_iter()
+                .find(|name| name.span.start <= offset && offset <= name.span.end)
+        });
+        if let Some(selector) = selector {
+            return Some(match selector.kind == NameKind::Class && is_module(uri) {
+                true => Target::ModuleClass(uri.clone(), selector.name),
+                false => Target::Name(selector.kind, selector.name),
+            });
+        }
+    }
+    let workspace = &state.folder(uri).workspace;
+    if let Some(script) = workspace.scripts.get(uri) {
+        let offset = script.line_index.offset(position);
+        for import in &script.imports {
+            let Some((name, _)) = import
+                .usages
+                .iter()
+                .find(|(_, span)| span.start <= offset && offset <= span.end)
+            else {
+                continue;
+            };
+            // Map a camel cased name back to the class as written.
+            let class = state.document(&import.module).and_then(|module| {
+                module.regions.iter().find_map(|region| {
+                    names(&region.stylesheet)
+                        .into_iter()
+                        .find(|selector| selector.kind == NameKind::Class && reads(name, &selector.name))
+                })
+            });
+            let class = class.map_or_else(|| name.clone(), |selector| selector.name);
+            return Some(Target::ModuleClass(import.module.clone(), class));
+        }
+    }
+    let template = workspace.templates.get(uri)?;
+    let offset = template.line_index.offset(position);
+    let usage = template.usage_at(offset)?;
+    Some(Target::Name(usage.kind, usage.name.clone()))
+}
+
+/// The references to a custom property, keyframes name or layer name in every
+/// open and indexed document in the folder of `uri`, such as `var(--gap)` for
+/// `--gap`.
+pub fn symbol_references(state: &State, uri: &Uri, kind: SymbolKind, name: &str) -> Vec<Location> {
+    let mut locations = vec![];
+    for doc in state.folder_documents(uri) {
+        for region in &doc.regions {
+            for symbol in &region.symbols {
+                if symbol.kind == kind && symbol.name == name && !symbol.declaration && region.contains(symbol.span) {
+                    locations.push(Location::new(doc.uri.clone(), doc.range(region, symbol.span)));
+                }
+            }
+        }
+    }
+    locations
+}
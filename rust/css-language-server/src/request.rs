@@ -1,18 +1,405 @@
+use std::{collections::HashMap, path::PathBuf};
+
 use anyhow::Result;
+use lsp_types::{
+    CodeActionOrCommand, CodeActionParams, CodeLens, CodeLensParams, Color, ColorInformation, ColorPresentation,
+    ColorPresentationParams, CompletionParams, DocumentColorParams, DocumentDiagnosticParams, DocumentDiagnosticReport,
+    DocumentFormattingParams, DocumentHighlightParams, ExecuteCommandParams, FullDocumentDiagnosticReport, HoverParams,
+    InitializeParams, LinkedEditingRangeParams, ReferenceParams, RelatedFullDocumentDiagnosticReport,
+    RelatedUnchangedDocumentDiagnosticReport, SemanticTokens, SemanticTokensDeltaParams, SemanticTokensParams,
+    SemanticTokensRangeParams, SignatureHelpParams, TextDocumentPositionParams, TextEdit,
+    UnchangedDocumentDiagnosticReport, Uri, WorkspaceDiagnosticParams, WorkspaceDiagnosticReport,
+    WorkspaceDiagnosticReportPartialResult, WorkspaceDocumentDiagnosticReport, WorkspaceFullDocumentDiagnosticReport,
+    WorkspaceUnchangedDocumentDiagnosticReport,
+};
 use serde_json::json;
 
-use crate::{response::send_message, save};
+use crate::{
+    cascade,
+    code_action::code_actions,
+    code_lens::{self, code_lenses},
+    commands::{self, COMMANDS},
+    completion::completions,
+    config::Config,
+    css::color::{Rgba, document_colors, presentations},
+    diagnostics::diagnostics,
+    formatting::formatting_edits,
+    hover,
+    line_index::Encoding,
+    occurrences::{document_highlights, linked_editing_ranges},
+    references::find_references,
+    response::{apply_edit, send_error_response, send_message, send_progress, send_response},
+    save,
+    semantic_tokens::{delta, legend, semantic_tokens},
+    signature_help,
+    state::State,
+    uri,
+};
+
+pub async fn initialize(id: u64, state: &mut State, params: InitializeParams) -> Result<()> {
+    let encoding = Encoding::negotiate(&params.capabilities);
+    state.encoding = encoding;
+    state.default_folder.workspace.encoding = encoding;
+    state.options = params.initialization_options.clone();
+    state.pull_diagnostics = params
+        .capabilities
+        .text_document
+        .as_ref()
+        .is_some_and(|capabilities| capabilities.diagnostic.is_some());
+    state.default_folder.config = Config::load(None, state.options.as_ref()).await?;
+    for root in workspace_roots(&params) {
+        state.add_folder(root).await?;
+    }
 
-pub async fn initialize(id: u64) -> Result<()> {
-    send_message(
-        json!({
-            "jsonrpc": "2.0",
-            "id": id,
-            "result": {
-                "capabilities": {}
+    send_message(json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "result": {
+            "capabilities": {
+                "positionEncoding": encoding.kind(),
+                "textDocumentSync": {
+                    "openClose": true,
+                    "change": 2,
+                    "save": true,
+                },
+                "colorProvider": true,
+                "codeActionProvider": {
+                    "codeActionKinds": ["refactor.rewrite", "refactor.extract"],
+                },
+                "semanticTokensProvider": {
+                    "legend": legend(),
+                    "full": { "delta": true },
+                    "range": true,
+                },
+                "completionProvider": {
+                    "triggerCharacters": ["."],
+                },
+                "referencesProvider": true,
+                "codeLensProvider": {
+                    "resolveProvider": true,
+                },
+                "hoverProvider": true,
+                "documentFormattingProvider": true,
+                "documentHighlightProvider": true,
+                "signatureHelpProvider": {
+                    "triggerCharacters": ["(", ","],
+                },
+                "linkedEditingRangeProvider": true,
+                "executeCommandProvider": {
+                    "commands": COMMANDS,
+                },
+                "workspace": {
+                    "workspaceFolders": {
+                        "supported": true,
+                        "changeNotifications": true,
+                    },
+                },
+                "diagnosticProvider": {
+                    "interFileDependencies": false,
+                    "workspaceDiagnostics": true,
+                },
             }
-        })
-    ).await?;
+        }
+    }))
+    .await?;
     save("send initialize response").await?;
     Ok(())
-}
\ No newline at end of file
+}
+
+/// The paths of the workspace folders, or of the deprecated root URI.
+fn workspace_roots(params: &InitializeParams) -> Vec<PathBuf> {
+    if let Some(folders) = &params.workspace_folders {
+        return folders.iter().filter_map(|folder| uri::to_path(&folder.uri)).collect();
+    }
+    #[allow(deprecated)]
+    params.root_uri.as_ref().and_then(uri::to_path).into_iter().collect()
+}
+
+pub async fn document_color(id: u64, state: &State, params: DocumentColorParams) -> Result<()> {
+    let mut colors = vec![];
+    if let Some(doc) = state.documents.get(&params.text_document.uri) {
+        for region in &doc.regions {
+            for (span, color) in document_colors(&region.stylesheet) {
+                colors.push(ColorInformation {
+                    range: doc.range(region, span),
+                    color: Color {
+                        red: color.red,
+                        green: color.green,
+                        blue: color.blue,
+                        alpha: color.alpha,
+                    },
+                });
+            }
+        }
+    }
+    send_response(id, serde_json::to_value(colors)?).await
+}
+
+pub async fn color_presentation(id: u64, params: ColorPresentationParams) -> Result<()> {
+    let color = Rgba {
+        red: params.color.red,
+        green: params.color.green,
+        blue: params.color.blue,
+        alpha: params.color.alpha,
+    };
+    let presentations = presentations(color)
+        .into_iter()
+        .map(|label| ColorPresentation {
+            text_edit: Some(TextEdit::new(params.range, label.clone())),
+            label,
+            additional_text_edits: None,
+        })
+        .collect::<Vec<_>>();
+    send_response(id, serde_json::to_value(presentations)?).await
+}
+
+pub async fn semantic_tokens_full(id: u64, state: &mut State, params: SemanticTokensParams) -> Result<()> {
+    let uri = params.text_document.uri;
+    let Some(doc) = state.documents.get(&uri) else {
+        return send_response(id, serde_json::Value::Null).await;
+    };
+    let data = semantic_tokens(doc, None);
+    let tokens = SemanticTokens {
+        result_id: Some(state.next_result_id()),
+        data,
+    };
+    send_response(id, serde_json::to_value(&tokens)?).await?;
+    state.semantic_tokens.insert(uri, tokens);
+    Ok(())
+}
+
+pub async fn semantic_tokens_full_delta(id: u64, state: &mut State, params: SemanticTokensDeltaParams) -> Result<()> {
+    let uri = params.text_document.uri;
+    let Some(doc) = state.documents.get(&uri) else {
+        return send_response(id, serde_json::Value::Null).await;
+    };
+    let data = semantic_tokens(doc, None);
+    let tokens = SemanticTokens {
+        result_id: Some(state.next_result_id()),
+        data,
+    };
+    let result = delta(state.semantic_tokens.get(&uri), &params.previous_result_id, &tokens);
+    send_response(id, serde_json::to_value(result)?).await?;
+    state.semantic_tokens.insert(uri, tokens);
+    Ok(())
+}
+
+pub async fn semantic_tokens_range(id: u64, state: &State, params: SemanticTokensRangeParams) -> Result<()> {
+    let Some(doc) = state.documents.get(&params.text_document.uri) else {
+        return send_response(id, serde_json::Value::Null).await;
+    };
+    let tokens = SemanticTokens {
+        result_id: None,
+        data: semantic_tokens(doc, Some(params.range)),
+    };
+    send_response(id, serde_json::to_value(tokens)?).await
+}
+
+pub async fn code_action(id: u64, state: &State, params: CodeActionParams) -> Result<()> {
+    let Some(doc) = state.documents.get(&params.text_document.uri) else {
+        return send_response(id, serde_json::Value::Null).await;
+    };
+    let only = params.context.only.unwrap_or_default();
+    let actions = code_actions(doc, params.range, &state.folder(&doc.uri).config.targets)
+        .into_iter()
+        .filter(|action| {
+            let kind = action.kind.as_ref().map_or("", |k| k.as_str());
+            only.is_empty() || only.iter().any(|o| kind.starts_with(o.as_str()))
+        })
+        .map(CodeActionOrCommand::CodeAction)
+        .collect::<Vec<_>>();
+    send_response(id, serde_json::to_value(actions)?).await
+}
+
+pub async fn completion(id: u64, state: &State, params: CompletionParams) -> Result<()> {
+    let position = params.text_document_position;
+    let items = completions(state, &position.text_document.uri, position.position);
+    send_response(id, serde_json::to_value(items)?).await
+}
+
+pub async fn references(id: u64, state: &State, params: ReferenceParams) -> Result<()> {
+    let position = params.text_document_position;
+    let locations = find_references(
+        state,
+        &position.text_document.uri,
+        position.position,
+        params.context.include_declaration,
+    );
+    send_response(id, serde_json::to_value(locations)?).await
+}
+
+pub async fn code_lens(id: u64, state: &State, params: CodeLensParams) -> Result<()> {
+    let lenses = state.document(&params.text_document.uri).map(code_lenses);
+    send_response(id, serde_json::to_value(lenses)?).await
+}
+
+pub async fn code_lens_resolve(id: u64, state: &State, lens: CodeLens) -> Result<()> {
+    let lens = code_lens::resolve(state, lens);
+    send_response(id, serde_json::to_value(lens)?).await
+}
+
+pub async fn document_highlight(id: u64, state: &State, params: DocumentHighlightParams) -> Result<()> {
+    let position = params.text_document_position_params;
+    let Some(doc) = state.document(&position.text_document.uri) else {
+        return send_response(id, serde_json::Value::Null).await;
+    };
+    let highlights = document_highlights(doc, position.position);
+    send_response(id, serde_json::to_value(highlights)?).await
+}
+
+pub async fn linked_editing_range(id: u64, state: &State, params: LinkedEditingRangeParams) -> Result<()> {
+    let position = params.text_document_position_params;
+    let Some(doc) = state.document(&position.text_document.uri) else {
+        return send_response(id, serde_json::Value::Null).await;
+    };
+    let ranges = linked_editing_ranges(doc, position.position);
+    send_response(id, serde_json::to_value(ranges)?).await
+}
+
+pub async fn formatting(id: u64, state: &State, params: DocumentFormattingParams) -> Result<()> {
+    let edits = state
+        .document(&params.text_document.uri)
+        .and_then(|doc| formatting_edits(doc, &params.options));
+    send_response(id, serde_json::to_value(edits)?).await
+}
+
+pub async fn hover(id: u64, state: &State, params: HoverParams) -> Result<()> {
+    let position = params.text_document_position_params;
+    let hover = state
+        .document(&position.text_document.uri)
+        .and_then(|doc| hover::hover(doc, position.position));
+    send_response(id, serde_json::to_value(hover)?).await
+}
+
+pub async fn signature_help(id: u64, state: &State, params: SignatureHelpParams) -> Result<()> {
+    let position = params.text_document_position_params;
+    let help = state
+        .document(&position.text_document.uri)
+        .and_then(|doc| signature_help::signature_help(doc, position.position));
+    send_response(id, serde_json::to_value(help)?).await
+}
+
+/// `workspace/executeCommand`: runs one of `commands::COMMANDS` and asks the
+/// client to apply its edit. The result is the URI of the file it creates.
+pub async fn execute_command(id: u64, state: &mut State, params: ExecuteCommandParams) -> Result<()> {
+    if !COMMANDS.contains(&params.command.as_str()) {
+        let message = format!("unknown command `{}`", params.command);
+        return send_error_response(Some(id as u32), -32602, &message).await;
+    }
+    let outcome = match commands::execute(state, &params.command, &params.arguments).await {
+        Ok(outcome) => outcome,
+        Err(e) => return send_error_response(Some(id as u32), -32803, &format!("{e:#}")).await,
+    };
+    apply_edit(state.next_request_id(), outcome.label, outcome.edit).await?;
+    send_response(id, serde_json::to_value(outcome.uri)?).await
+}
+
+/// `css/cascadeConflicts`: the declarations competing with the one at the
+/// position, winner first, or null when there is no declaration there.
+pub async fn cascade_conflicts(id: u64, state: &State, params: TextDocumentPositionParams) -> Result<()> {
+    let result = cascade::cascade_conflicts(state, &params.text_document.uri, params.position);
+    send_response(id, result).await
+}
+
+pub async fn document_diagnostic(id: u64, state: &mut State, params: DocumentDiagnosticParams) -> Result<()> {
+    let uri = params.text_document.uri;
+    let Some(doc) = state.documents.get(&uri) else {
+        return send_error_response(Some(id as u32), -32602, "unknown document").await;
+    };
+    let folder = state.folder(&uri);
+    let items = diagnostics(doc, &folder.config, &folder.workspace);
+    let (result_id, unchanged) = state.diagnostic_result(&uri, items.clone(), params.previous_result_id.as_deref());
+    let report = match unchanged {
+        true => DocumentDiagnosticReport::Unchanged(RelatedUnchangedDocumentDiagnosticReport {
+            related_documents: None,
+            unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport { result_id },
+        }),
+        false => DocumentDiagnosticReport::Full(RelatedFullDocumentDiagnosticReport {
+            related_documents: None,
+            full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                result_id: Some(result_id),
+                items,
+            },
+        }),
+    };
+    send_response(id, serde_json::to_value(report)?).await
+}
+
+/// Reports the diagnostics of every open and indexed document. With a partial
+/// result token, each document is streamed as `$/progress` and the final
+/// response is empty.
+///
+/// If no document changed since the client's previous results, the request
+/// is held open until one does, as the client would otherwise ask again
+/// right away. `resume_workspace_diagnostic` answers it then. Only one
+/// request is held: a newer one cancels it.
+pub async fn workspace_diagnostic(id: u64, state: &mut State, params: WorkspaceDiagnosticParams) -> Result<()> {
+    if let Some((held, _)) = state.pending_workspace_diagnostic.take() {
+        let message = "superseded by a newer workspace/diagnostic request";
+        send_error_response(Some(held as u32), -32802, message).await?;
+    }
+    let previous: HashMap<Uri, String> = params
+        .previous_result_ids
+        .iter()
+        .map(|previous| (previous.uri.clone(), previous.value.clone()))
+        .collect();
+    let mut uris: Vec<Uri> = state.documents.keys().cloned().collect();
+    for folder in state.folders.iter().chain([&state.default_folder]) {
+        let files = folder.workspace.files.keys();
+        uris.extend(files.filter(|uri| !state.documents.contains_key(*uri)).cloned());
+    }
+
+    let mut items = vec![];
+    for uri in uris {
+        let folder = state.folder(&uri);
+        let (doc, version) = match state.documents.get(&uri) {
+            Some(doc) => (doc, Some(doc.version as i64)),
+            None => match folder.workspace.files.get(&uri) {
+                Some(doc) => (doc, None),
+                None => continue,
+            },
+        };
+        let diagnostics = diagnostics(doc, &folder.config, &folder.workspace);
+        let (result_id, unchanged) =
+            state.diagnostic_result(&uri, diagnostics.clone(), previous.get(&uri).map(String::as_str));
+        items.push(match unchanged {
+            true => WorkspaceDocumentDiagnosticReport::Unchanged(WorkspaceUnchangedDocumentDiagnosticReport {
+                uri,
+                version,
+                unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport { result_id },
+            }),
+            false => WorkspaceDocumentDiagnosticReport::Full(WorkspaceFullDocumentDiagnosticReport {
+                uri,
+                version,
+                full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                    result_id: Some(result_id),
+                    items: diagnostics,
+                },
+            }),
+        });
+    }
+
+    let changed = items
+        .iter()
+        .any(|report| matches!(report, WorkspaceDocumentDiagnosticReport::Full(_)));
+    if !changed && !previous.is_empty() {
+        state.pending_workspace_diagnostic = Some((id, params));
+        return Ok(());
+    }
+    if let Some(token) = &params.partial_result_params.partial_result_token {
+        for report in std::mem::take(&mut items) {
+            let partial = WorkspaceDiagnosticReportPartialResult { items: vec![report] };
+            send_progress(token, serde_json::to_value(partial)?).await?;
+        }
+    }
+    send_response(id, serde_json::to_value(WorkspaceDiagnosticReport { items })?).await
+}
+
+/// Answers the held `workspace/diagnostic` request, if any, now that
+/// documents or the index may have changed. It is held again if nothing did.
+pub async fn resume_workspace_diagnostic(state: &mut State) -> Result<()> {
+    match state.pending_workspace_diagnostic.take() {
+        Some((id, params)) => workspace_diagnostic(id, state, params).await,
+        None => Ok(()),
+    }
+}
@@ -1,22 +1,83 @@
-use serde_json::json;
 use anyhow::Result;
+use lsp_types::{ApplyWorkspaceEditParams, Diagnostic, ProgressToken, PublishDiagnosticsParams, Uri, WorkspaceEdit};
+use serde_json::json;
 use tokio::io::AsyncWriteExt;
 
-use crate::Message;
+use crate::{Message, recording};
 
 pub async fn log_message(msg: &str) -> Result<()> {
+    log(3, msg).await
+}
+
+pub async fn log_error(msg: &str) -> Result<()> {
+    log(1, msg).await
+}
+
+async fn log(typ: u8, msg: &str) -> Result<()> {
     let s = json!({
         "jsonrpc": "2.0",
         "method": "window/logMessage",
         "params": {
-            "type": 3,
+            "type": typ,
             "message": msg,
         },
     });
     send_message(s).await
 }
 
+pub async fn send_response(id: u64, result: serde_json::Value) -> Result<()> {
+    send_message(json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "result": result,
+    }))
+    .await
+}
+
+pub async fn publish_diagnostics(uri: &Uri, version: Option<i32>, diagnostics: Vec<Diagnostic>) -> Result<()> {
+    let params = PublishDiagnosticsParams::new(uri.clone(), diagnostics, version);
+    send_message(json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": params,
+    }))
+    .await
+}
+
+/// Reports a partial result of a request that was sent with
+/// `partialResultToken`.
+pub async fn send_progress(token: &ProgressToken, value: serde_json::Value) -> Result<()> {
+    send_message(json!({
+        "jsonrpc": "2.0",
+        "method": "$/progress",
+        "params": {
+            "token": token,
+            "value": value,
+        },
+    }))
+    .await
+}
+
+/// Asks the client to apply `edit`, with `id` for its reply, which is not
+/// waited for.
+pub async fn apply_edit(id: u64, label: String, edit: WorkspaceEdit) -> Result<()> {
+    let params = ApplyWorkspaceEditParams {
+        label: Some(label),
+        edit,
+    };
+    send_message(json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "workspace/applyEdit",
+        "params": params,
+    }))
+    .await
+}
+
 pub async fn send_message(msg: serde_json::Value) -> Result<()> {
+    if recording::sent(&msg)? {
+        return Ok(());
+    }
     let msg = Message::new(msg);
 
     let mut stdout = tokio::io::stdout();
@@ -47,4 +108,4 @@ pub async fn send_method_not_found_response(id: Option<u32>, method: String) ->
         }
     });
     send_message(val).await
-}
\ No newline at end of file
+}
@@ -0,0 +1,313 @@
+use lsp_types::{
+    Range, SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokens, SemanticTokensDelta,
+    SemanticTokensEdit, SemanticTokensFullDeltaResult, SemanticTokensLegend,
+};
+
+use crate::{
+    css::highlight::{HighlightKind, highlights},
+    document::Document,
+};
+
+const TOKEN_TYPES: &[SemanticTokenType] = &[
+    SemanticTokenType::new("selector"),
+    SemanticTokenType::CLASS,
+    SemanticTokenType::new("id"),
+    SemanticTokenType::PROPERTY,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::FUNCTION,
+    SemanticTokenType::new("unit"),
+    SemanticTokenType::NUMBER,
+    SemanticTokenType::new("atRule"),
+];
+
+const TOKEN_MODIFIERS: &[SemanticTokenModifier] = &[
+    SemanticTokenModifier::DECLARATION,
+    SemanticTokenModifier::DEPRECATED,
+    SemanticTokenModifier::new("unknown"),
+];
+
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: TOKEN_MODIFIERS.to_vec(),
+    }
+}
+
+/// Index of `kind` in `TOKEN_TYPES`.
+fn token_type(kind: HighlightKind) -> u32 {
+    match kind {
+        HighlightKind::Selector => 0,
+        HighlightKind::Class => 1,
+        HighlightKind::Id => 2,
+        HighlightKind::Property => 3,
+        HighlightKind::Variable => 4,
+        HighlightKind::Function => 5,
+        HighlightKind::Unit => 6,
+        HighlightKind::Number => 7,
+        HighlightKind::AtRule => 8,
+    }
+}
+
+/// Semantic tokens for every CSS region of `doc`, optionally restricted to
+/// the tokens that start inside `range`.
+pub fn semantic_tokens(doc: &Document, range: Option<Range>) -> Vec<SemanticToken> {
+    let mut absolute = vec![];
+    for region in &doc.regions {
+        for highlight in highlights(&region.stylesheet) {
+            // Skip the synthetic rule wrapped around style attributes.
+            if !region.contains(highlight.span) {
+                continue;
+            }
+            let start = region.to_host(highlight.span.start);
+            let end = region.to_host(highlight.span.end);
+            let position = doc.line_index.position(start);
+            if range.is_some_and(|r| position < r.start || r.end <= position) {
+                continue;
+            }
+            let modifiers = [highlight.declaration, highlight.deprecated, highlight.unknown]
+                .iter()
+                .enumerate()
+                .filter(|(_, set)| **set)
+                .fold(0, |bits, (i, _)| bits | 1 << i);
+            // Tokens do not span lines.
+            let length = doc.line_index.position(end).character - position.character;
+            absolute.push((position, length, token_type(highlight.kind), modifiers));
+        }
+    }
+    absolute.sort_by_key(|(position, ..)| *position);
+
+    let mut tokens = vec![];
+    let mut prev = lsp_types::Position::default();
+    for (position, length, token_type, token_modifiers_bitset) in absolute {
+        let delta_line = position.line - prev.line;
+        let delta_start = if delta_line == 0 {
+            position.character - prev.character
+        } else {
+            position.character
+        };
+        tokens.push(SemanticToken {
+            delta_line,
+            delta_start,
+            length,
+            token_type,
+            token_modifiers_bitset,
+        });
+        prev = position;
+    }
+    tokens
+}
+
+/// The answer to `full/delta`: the edits from the `previous` tokens sent if
+/// the client still has them, as `previous_result_id` tells, and otherwise
+/// all the tokens.
+pub fn delta(
+    previous: Option<&SemanticTokens>,
+    previous_result_id: &str,
+    tokens: &SemanticTokens,
+) -> SemanticTokensFullDeltaResult {
+    match previous {
+        Some(previous) if previous.result_id.as_deref() == Some(previous_result_id) => {
+            SemanticTokensFullDeltaResult::TokensDelta(SemanticTokensDelta {
+                result_id: tokens.result_id.clone(),
+                edits: edits(&previous.data, &tokens.data),
+            })
+        }
+        _ => SemanticTokensFullDeltaResult::Tokens(tokens.clone()),
+    }
+}
+
+/// A single edit that turns `old` into `new`, or none if they are equal.
+fn edits(old: &[SemanticToken], new: &[SemanticToken]) -> Vec<SemanticTokensEdit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let deleted = old.len() - prefix - suffix;
+    let inserted = &new[prefix..new.len() - suffix];
+    if deleted == 0 && inserted.is_empty() {
+        return vec![];
+    }
+    // Edits are expressed in integers, five per token.
+    vec![SemanticTokensEdit {
+        start: (prefix * 5) as u32,
+        delete_count: (deleted * 5) as u32,
+        data: Some(inserted.to_vec()),
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line_index::Encoding;
+
+    fn document(language_id: &str, text: &str) -> Document {
+        Document::new(
+            "file:///a".parse().unwrap(),
+            language_id.to_string(),
+            0,
+            text.to_string(),
+            Encoding::default(),
+        )
+    }
+
+    /// The tokens with absolute positions, and the text they cover.
+    fn decoded(doc: &Document, tokens: &[SemanticToken]) -> Vec<(u32, u32, String, u32, u32)> {
+        let mut position = lsp_types::Position::default();
+        tokens
+            .iter()
+            .map(|token| {
+                position = match token.delta_line {
+                    0 => lsp_types::Position::new(position.line, position.character + token.delta_start),
+                    lines => lsp_types::Position::new(position.line + lines, token.delta_start),
+                };
+                let start = doc.line_index.offset(position);
+                let end = doc.line_index.offset(lsp_types::Position::new(
+                    position.line,
+                    position.character + token.length,
+                ));
+                (
+                    position.line,
+                    position.character,
+                    doc.text[start..end].to_string(),
+                    token.token_type,
+                    token.token_modifiers_bitset,
+                )
+            })
+            .collect()
+    }
+
+    fn result(result_id: &str, data: Vec<SemanticToken>) -> SemanticTokens {
+        SemanticTokens {
+            result_id: Some(result_id.to_string()),
+            data,
+        }
+    }
+
+    /// `old` with `edits` applied, spliced token by token.
+    fn apply(old: &[SemanticToken], edits: &[SemanticTokensEdit]) -> Vec<SemanticToken> {
+        let mut tokens = old.to_vec();
+        for edit in edits.iter().rev() {
+            assert_eq!((edit.start % 5, edit.delete_count % 5), (0, 0));
+            let start = edit.start as usize / 5;
+            let end = start + edit.delete_count as usize / 5;
+            tokens.splice(start..end, edit.data.clone().unwrap_or_default());
+        }
+        tokens
+    }
+
+    #[test]
+    fn encoding() {
+        let doc = document(
+            "css",
+            "a .b { color: red; --x: 1px }\n@media print {\n  #é { zoom: var(--x) }\n}",
+        );
+        let tokens = semantic_tokens(&doc, None);
+        assert_eq!(
+            decoded(&doc, &tokens),
+            [
+                (0, 0, "a".to_string(), 0, 0),
+                (0, 2, ".b".to_string(), 1, 0),
+                (0, 7, "color".to_string(), 3, 0),
+                (0, 19, "--x".to_string(), 4, 1),
+                (0, 24, "1".to_string(), 7, 0),
+                (0, 25, "px".to_string(), 6, 0),
+                (1, 0, "@media".to_string(), 8, 0),
+                (2, 2, "#é".to_string(), 2, 0),
+                (2, 7, "zoom".to_string(), 3, 0),
+                (2, 13, "var".to_string(), 5, 0),
+                (2, 17, "--x".to_string(), 4, 0),
+            ]
+        );
+        // Relative to the previous token on the same line, to the line start
+        // on a new one.
+        let deltas: Vec<_> = tokens.iter().map(|t| (t.delta_line, t.delta_start)).collect();
+        assert_eq!(
+            deltas,
+            [
+                (0, 0),
+                (0, 2),
+                (0, 5),
+                (0, 12),
+                (0, 5),
+                (0, 1),
+                (1, 0),
+                (1, 2),
+                (0, 5),
+                (0, 6),
+                (0, 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn lengths_in_utf16() {
+        let doc = document("css", ".😀é { color: red }");
+        let tokens = semantic_tokens(&doc, None);
+        assert_eq!((tokens[0].length, tokens[1].delta_start), (4, 7));
+        assert_eq!(decoded(&doc, &tokens)[0].2, ".😀é");
+    }
+
+    #[test]
+    fn style_attributes() {
+        let doc = document("html", "<p style=\"width: calc(1px + 2em)\"></p>");
+        assert_eq!(
+            decoded(&doc, &semantic_tokens(&doc, None)),
+            [
+                (0, 10, "width".to_string(), 3, 0),
+                (0, 17, "calc".to_string(), 5, 0),
+                (0, 22, "1".to_string(), 7, 0),
+                (0, 23, "px".to_string(), 6, 0),
+                (0, 28, "2".to_string(), 7, 0),
+                (0, 29, "em".to_string(), 6, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn range() {
+        let doc = document("css", "a { color: red }\nb { zoom: 1 }\ni { top: 0 }");
+        let range = Range::new(lsp_types::Position::new(1, 0), lsp_types::Position::new(2, 0));
+        let tokens = semantic_tokens(&doc, Some(range));
+        let texts: Vec<_> = decoded(&doc, &tokens).into_iter().map(|t| t.2).collect();
+        assert_eq!(texts, ["b", "zoom", "1"]);
+        // The first token is still relative to the start of the document.
+        assert_eq!((tokens[0].delta_line, tokens[0].delta_start), (1, 0));
+    }
+
+    #[test]
+    fn full_delta() {
+        let old = semantic_tokens(&document("css", "a { color: red }\nb { zoom: 1 }\ni { top: 0 }"), None);
+        let new = semantic_tokens(&document("css", "a { color: red }\nb { --z: 1px }\ni { top: 0 }"), None);
+
+        let previous = result("1", old.clone());
+        let tokens = result("2", new.clone());
+        let SemanticTokensFullDeltaResult::TokensDelta(edited) = delta(Some(&previous), "1", &tokens) else {
+            panic!("expected edits");
+        };
+        assert_eq!(edited.result_id.as_deref(), Some("2"));
+        assert_eq!(edited.edits.len(), 1);
+        // Only the tokens of the second rule change, and `b` stays.
+        assert_eq!((edited.edits[0].start, edited.edits[0].delete_count), (15, 10));
+        assert_eq!(apply(&old, &edited.edits), new);
+
+        // Nothing to send when nothing changed.
+        let SemanticTokensFullDeltaResult::TokensDelta(same) = delta(Some(&previous), "1", &result("3", old.clone()))
+        else {
+            panic!("expected edits");
+        };
+        assert!(same.edits.is_empty());
+
+        // Everything when the client's tokens are not the ones held.
+        assert_eq!(
+            delta(Some(&previous), "0", &tokens),
+            SemanticTokensFullDeltaResult::Tokens(tokens.clone())
+        );
+        assert_eq!(
+            delta(None, "1", &tokens),
+            SemanticTokensFullDeltaResult::Tokens(tokens.clone())
+        );
+    }
+}
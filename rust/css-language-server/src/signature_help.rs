@@ -0,0 +1,43 @@
+use lsp_types::{
+    Documentation, MarkupContent, MarkupKind, ParameterInformation, ParameterLabel, Position, SignatureHelp,
+    SignatureInformation,
+};
+
+use crate::{css::functions::active_call, document::Document};
+
+/// The parameters of the function call being written at `position`, such as
+/// `clamp(min, preferred, max)`, with the current argument highlighted.
+pub fn signature_help(doc: &Document, position: Position) -> Option<SignatureHelp> {
+    let offset = doc.line_index.offset(position);
+    let (signature, active) = doc.regions.iter().find_map(|region| {
+        let offset = region.to_virtual(offset)?;
+        active_call(&region.stylesheet, offset)
+    })?;
+
+    let parameters = signature
+        .parameters
+        .iter()
+        .zip(signature.parameter_offsets())
+        .map(|((_, documentation), (start, end))| ParameterInformation {
+            label: ParameterLabel::LabelOffsets([start as u32, end as u32]),
+            documentation: Some(markdown(documentation)),
+        })
+        .collect();
+    Some(SignatureHelp {
+        signatures: vec![SignatureInformation {
+            label: signature.label(),
+            documentation: Some(markdown(signature.documentation)),
+            parameters: Some(parameters),
+            active_parameter: Some(active as u32),
+        }],
+        active_signature: Some(0),
+        active_parameter: Some(active as u32),
+    })
+}
+
+fn markdown(value: &str) -> Documentation {
+    Documentation::MarkupContent(MarkupContent {
+        kind: MarkupKind::Markdown,
+        value: value.to_string(),
+    })
+}
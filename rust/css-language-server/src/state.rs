@@ -0,0 +1,245 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::Result;
+use lsp_types::{Diagnostic, SemanticTokens, Uri, WorkspaceDiagnosticParams};
+use serde_json::Value;
+use tokio::time::Instant;
+
+use crate::{
+    config::Config, css_modules::is_script_language, document::Document, line_index::Encoding,
+    template::is_template_language, uri, workspace::Workspace,
+};
+
+/// How long diagnostics wait for further edits before they are computed and
+/// pushed.
+pub const DIAGNOSTICS_DELAY: Duration = Duration::from_millis(200);
+
+/// A workspace folder, with its own configuration and index. A document
+/// belongs to the innermost folder containing it, and only sees the
+/// documents of that folder.
+#[derive(Debug, Default)]
+pub struct Folder {
+    /// `None` for the folder of the documents outside every workspace folder.
+    pub root: Option<PathBuf>,
+    pub config: Config,
+    pub workspace: Workspace,
+}
+
+impl Folder {
+    fn contains(&self, path: &Path) -> bool {
+        self.root.as_ref().is_some_and(|root| path.starts_with(root))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct State {
+    pub documents: BTreeMap<Uri, Document>,
+    /// The workspace folders, in the order they were added.
+    pub folders: Vec<Folder>,
+    /// The configuration of documents outside every workspace folder, and the
+    /// markup and scripts among them.
+    pub default_folder: Folder,
+    /// The encoding of positions agreed on in `initialize`.
+    pub encoding: Encoding,
+    /// The `initializationOptions`, which configure every folder.
+    pub options: Option<Value>,
+    /// Whether the client pulls diagnostics, in which case they are not
+    /// published.
+    pub pull_diagnostics: bool,
+    /// The last full semantic tokens sent for each document, to compute
+    /// deltas against.
+    pub semantic_tokens: HashMap<Uri, SemanticTokens>,
+    /// The last pulled diagnostics of each document, with their result id.
+    pub diagnostics: HashMap<Uri, (String, Vec<Diagnostic>)>,
+    /// Open documents whose pushed diagnostics are out of date.
+    pub stale_diagnostics: Vec<Uri>,
+    /// When to push `stale_diagnostics`. Each edit moves it back.
+    pub diagnostics_deadline: Option<Instant>,
+    /// A `workspace/diagnostic` request held open until diagnostics change.
+    pub pending_workspace_diagnostic: Option<(u64, WorkspaceDiagnosticParams)>,
+    next_result_id: u64,
+    next_request_id: u64,
+}
+
+impl State {
+    /// The folder a document belongs to: the innermost workspace folder
+    /// containing it, or else the default folder.
+    pub fn folder(&self, uri: &Uri) -> &Folder {
+        let index = self.folder_index(uri);
+        index.map_or(&self.default_folder, |index| &self.folders[index])
+    }
+
+    pub fn folder_mut(&mut self, uri: &Uri) -> &mut Folder {
+        match self.folder_index(uri) {
+            Some(index) => &mut self.folders[index],
+            None => &mut self.default_folder,
+        }
+    }
+
+    fn folder_index(&self, uri: &Uri) -> Option<usize> {
+        let path = uri::to_path(uri)?;
+        let folders = self
+            .folders
+            .iter()
+            .enumerate()
+            .filter(|(_, folder)| folder.contains(&path));
+        folders
+            .max_by_key(|(_, folder)| folder.root.as_ref().map(|root| root.components().count()))
+            .map(|(i, _)| i)
+    }
+
+    /// Whether two documents belong to the same folder.
+    pub fn same_folder(&self, a: &Uri, b: &Uri) -> bool {
+        self.folder_index(a) == self.folder_index(b)
+    }
+
+    /// An open document, or else an indexed file of its folder.
+    pub fn document(&self, uri: &Uri) -> Option<&Document> {
+        self.documents
+            .get(uri)
+            .or_else(|| self.folder(uri).workspace.files.get(uri))
+    }
+
+    /// Every open document and indexed file in the folder of `uri`.
+    pub fn folder_documents(&self, uri: &Uri) -> impl Iterator<Item = &Document> {
+        let open = self.documents.values().filter(|doc| self.same_folder(&doc.uri, uri));
+        let closed = self
+            .folder(uri)
+            .workspace
+            .files
+            .values()
+            .filter(|doc| !self.documents.contains_key(&doc.uri));
+        open.chain(closed)
+    }
+
+    /// Loads the configuration of a new workspace folder and indexes it. The
+    /// files of folders nested in it are left to those folders, and the
+    /// folders it is nested in leave its files to it.
+    pub async fn add_folder(&mut self, root: PathBuf) -> Result<()> {
+        if self.folders.iter().any(|folder| folder.root.as_ref() == Some(&root)) {
+            return Ok(());
+        }
+        let config = Config::load(Some(&root), self.options.as_ref()).await?;
+        let workspace = self.index(&root).await?;
+        self.default_folder.workspace.exclude(&root);
+        for folder in &mut self.folders {
+            if folder.contains(&root) {
+                folder.workspace.exclude(&root);
+            }
+        }
+        self.folders.push(Folder {
+            root: Some(root.clone()),
+            config,
+            workspace,
+        });
+        self.index_open_documents(&root);
+        Ok(())
+    }
+
+    /// Removes a workspace folder, giving its files back to the folder it was
+    /// nested in, or else the open ones to the default folder.
+    pub async fn remove_folder(&mut self, root: &Path) -> Result<()> {
+        self.folders.retain(|folder| folder.root.as_deref() != Some(root));
+        let mut reindexed = vec![root.to_path_buf()];
+        for i in 0..self.folders.len() {
+            let Some(outer) = self.folders[i].root.clone().filter(|outer| root.starts_with(outer)) else {
+                continue;
+            };
+            self.folders[i].workspace = self.index(&outer).await?;
+            reindexed.push(outer);
+        }
+        for dir in reindexed {
+            self.index_open_documents(&dir);
+        }
+        Ok(())
+    }
+
+    /// Updates the markup and imports of the index of its folder from an open
+    /// document. Returns whether the document is indexed at all.
+    pub fn index_document(&mut self, uri: &Uri, language_id: &str, text: &str) -> bool {
+        let template = is_template_language(language_id);
+        let workspace = &mut self.folder_mut(uri).workspace;
+        if template {
+            workspace.set_template(uri, text);
+        }
+        let script = is_script_language(language_id);
+        if script {
+            workspace.set_script(uri, text);
+        }
+        template || script
+    }
+
+    /// Indexes the open documents under `dir` again, as indexing reads the
+    /// markup and scripts from disk rather than from the open documents.
+    fn index_open_documents(&mut self, dir: &Path) {
+        let open: Vec<(Uri, String, String)> = self
+            .documents
+            .values()
+            .filter(|doc| uri::to_path(&doc.uri).is_some_and(|path| path.starts_with(dir)))
+            .map(|doc| (doc.uri.clone(), doc.language_id.clone(), doc.text.clone()))
+            .collect();
+        for (uri, language_id, text) in &open {
+            self.index_document(uri, language_id, text);
+        }
+    }
+
+    /// Indexes the files under `root`, other than those of the folders nested
+    /// in it.
+    async fn index(&self, root: &Path) -> Result<Workspace> {
+        let mut workspace = Workspace::index(root, self.encoding).await?;
+        for folder in &self.folders {
+            if let Some(nested) = &folder.root
+                && nested != root
+                && nested.starts_with(root)
+            {
+                workspace.exclude(nested);
+            }
+        }
+        Ok(workspace)
+    }
+
+    /// Pushes the diagnostics of `uris` once no edit has come for
+    /// `DIAGNOSTICS_DELAY`, rather than on every keystroke.
+    pub fn delay_diagnostics(&mut self, uris: impl IntoIterator<Item = Uri>) {
+        for uri in uris {
+            if !self.stale_diagnostics.contains(&uri) {
+                self.stale_diagnostics.push(uri);
+            }
+        }
+        self.diagnostics_deadline = Some(Instant::now() + DIAGNOSTICS_DELAY);
+    }
+
+    pub fn next_result_id(&mut self) -> String {
+        self.next_result_id += 1;
+        self.next_result_id.to_string()
+    }
+
+    /// The id of the next request to the client.
+    pub fn next_request_id(&mut self) -> u64 {
+        self.next_request_id += 1;
+        self.next_request_id
+    }
+
+    /// Records the diagnostics pulled for `uri` and returns their result id,
+    /// along with whether they are unchanged since the client's `previous`
+    /// result.
+    pub fn diagnostic_result(
+        &mut self,
+        uri: &Uri,
+        diagnostics: Vec<Diagnostic>,
+        previous: Option<&str>,
+    ) -> (String, bool) {
+        if let Some((id, last)) = self.diagnostics.get(uri)
+            && *last == diagnostics
+        {
+            return (id.clone(), previous == Some(id.as_str()));
+        }
+        let id = self.next_result_id();
+        self.diagnostics.insert(uri.clone(), (id.clone(), diagnostics));
+        (id, false)
+    }
+}
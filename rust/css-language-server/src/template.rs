@@ -0,0 +1,314 @@
+use crate::{
+    css::{Span, selector::NameKind},
+    line_index::{Encoding, LineIndex},
+};
+
+/// A class name or id used by markup, such as `a` in `class="a b"`.
+#[derive(Debug, Clone)]
+pub struct Usage {
+    pub kind: NameKind,
+    pub name: String,
+    pub span: Span,
+}
+
+/// A file whose markup uses class names and ids: HTML, JSX/TSX or a Vue
+/// single-file component.
+#[derive(Debug)]
+pub struct Template {
+    pub line_index: LineIndex,
+    pub usages: Vec<Usage>,
+}
+
+impl Template {
+    pub fn new(text: String, encoding: Encoding) -> Self {
+        let usages = usages(&text);
+        let line_index = LineIndex::new(&text, encoding);
+        Self { line_index, usages }
+    }
+
+    pub fn usage_at(&self, offset: usize) -> Option<&Usage> {
+        self.usages
+            .iter()
+            .find(|usage| usage.span.start <= offset && offset <= usage.span.end)
+    }
+}
+
+/// Whether a file extension is that of a template.
+pub fn is_template(extension: &str) -> bool {
+    matches!(extension, "html" | "htm" | "jsx" | "tsx" | "vue")
+}
+
+/// Whether an editor language id is one of the template languages.
+pub fn is_template_language(language_id: &str) -> bool {
+    matches!(language_id, "html" | "javascriptreact" | "typescriptreact" | "vue")
+}
+
+/// Finds the class names and ids used by the markup.
+///
+/// Like the HTML scanner, this is tolerant rather than exact. It looks for
+/// `class`, `className` and `id` attributes, and for the dynamic bindings of
+/// JSX (`className={...}`) and Vue (`:class="..."`). In dynamic bindings, the
+/// words of string literals and the keys of object literals count as class
+/// names, which covers the usual `clsx` and `{ active: isActive }` idioms.
+fn usages(text: &str) -> Vec<Usage> {
+    let bytes = text.as_bytes();
+    let mut usages = vec![];
+    let mut pos = 0;
+    while pos < bytes.len() {
+        if !is_attribute_start(bytes, pos) {
+            pos += 1;
+            continue;
+        }
+        let name_start = pos;
+        while pos < bytes.len() && is_attribute_char(bytes[pos]) {
+            pos += 1;
+        }
+        if bytes.get(pos) != Some(&b'=') {
+            continue;
+        }
+        let name = &text[name_start..pos];
+        pos += 1;
+        let (kind, dynamic) = match name {
+            "class" | "className" | "class:list" => (NameKind::Class, false),
+            ":class" | "v-bind:class" | "[ngClass]" | "[class]" => (NameKind::Class, true),
+            "id" => (NameKind::Id, false),
+            ":id" | "v-bind:id" => (NameKind::Id, true),
+            _ => continue,
+        };
+        match bytes.get(pos) {
+            Some(&quote @ (b'"' | b'\'')) => {
+                let start = pos + 1;
+                let end = text[start..].find(quote as char).map_or(bytes.len(), |i| start + i);
+                match dynamic {
+                    true => expression(text, Span::new(start, end), kind, &mut usages),
+                    false => words(text, Span::new(start, end), kind, &mut usages),
+                }
+                pos = end;
+            }
+            Some(b'{') => {
+                let end = closing_brace(bytes, pos);
+                expression(text, Span::new(pos + 1, end), kind, &mut usages);
+                pos = end;
+            }
+            _ => {}
+        }
+    }
+    usages
+}
+
+fn is_attribute_start(bytes: &[u8], pos: usize) -> bool {
+    let after_space = pos > 0 && bytes[pos - 1].is_ascii_whitespace();
+    after_space
+        && bytes
+            .get(pos)
+            .is_some_and(|&b| b.is_ascii_alphabetic() || b == b':' || b == b'[')
+}
+
+fn is_attribute_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b':' | b'[' | b']')
+}
+
+/// Offset of the `}` matching the `{` at `open`, skipping strings.
+fn closing_brace(bytes: &[u8], open: usize) -> usize {
+    let mut depth = 0;
+    let mut pos = open;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return pos;
+                }
+            }
+            quote @ (b'"' | b'\'' | b'`') => pos = string_end(bytes, pos, quote),
+            _ => {}
+        }
+        pos += 1;
+    }
+    bytes.len()
+}
+
+/// Offset of the quote closing the string that starts at `start`.
+fn string_end(bytes: &[u8], start: usize, quote: u8) -> usize {
+    let mut pos = start + 1;
+    while pos < bytes.len() && bytes[pos] != quote {
+        if bytes[pos] == b'\\' {
+            pos += 1;
+        }
+        pos += 1;
+    }
+    pos.min(bytes.len())
+}
+
+/// Collects the words of the string literals and the keys of the object
+/// literals in a JavaScript expression.
+fn expression(text: &str, span: Span, kind: NameKind, usages: &mut Vec<Usage>) {
+    let bytes = text.as_bytes();
+    let mut pos = span.start;
+    while pos < span.end {
+        match bytes[pos] {
+            quote @ (b'"' | b'\'' | b'`') => {
+                let end = string_end(bytes, pos, quote).min(span.end);
+                let mut start = pos + 1;
+                // Interpolations of template literals are not class names.
+                while let Some(i) = text[start..end].find("${") {
+                    words(text, Span::new(start, start + i), kind, usages);
+                    start = closing_brace(bytes, start + i + 1).min(end);
+                }
+                words(text, Span::new(start.min(end), end), kind, usages);
+                pos = end + 1;
+            }
+            b if b.is_ascii_alphabetic() || b == b'_' || b == b'$' => {
+                let start = pos;
+                while pos < span.end && (bytes[pos].is_ascii_alphanumeric() || matches!(bytes[pos], b'_' | b'$' | b'-'))
+                {
+                    pos += 1;
+                }
+                let after_brace_or_comma = matches!(prev_non_space(bytes, start), Some(b'{' | b','));
+                let key = next_non_space(bytes, pos) == Some(b':');
+                if after_brace_or_comma && key {
+                    usages.push(Usage {
+                        kind,
+                        name: text[start..pos].to_string(),
+                        span: Span::new(start, pos),
+                    });
+                }
+            }
+            _ => pos += 1,
+        }
+    }
+}
+
+fn next_non_space(bytes: &[u8], pos: usize) -> Option<u8> {
+    bytes.get(pos..)?.iter().copied().find(|b| !b.is_ascii_whitespace())
+}
+
+fn prev_non_space(bytes: &[u8], pos: usize) -> Option<u8> {
+    bytes[..pos].iter().rev().copied().find(|b| !b.is_ascii_whitespace())
+}
+
+/// Template syntax in attribute values, whose contents may contain spaces.
+const TEMPLATE_SYNTAX: &[(&str, &str)] = &[("{{", "}}"), ("{%", "%}"), ("<%", "%>"), ("${", "}")];
+
+/// Collects the whitespace separated words of a static attribute value.
+/// Template syntax is part of the word around it, spaces and all.
+fn words(text: &str, span: Span, kind: NameKind, usages: &mut Vec<Usage>) {
+    let mut start = None;
+    let mut i = span.start;
+    while i < span.end {
+        let rest = &text[i..span.end];
+        let syntax = TEMPLATE_SYNTAX
+            .iter()
+            .find_map(|(open, close)| rest.strip_prefix(open).map(|inner| (open.len(), inner, close)));
+        if let Some((open, inner, close)) = syntax {
+            start.get_or_insert(i);
+            i = inner.find(close).map_or(span.end, |j| i + open + j + close.len());
+            continue;
+        }
+        let c = rest.chars().next().unwrap_or_default();
+        match (c.is_whitespace(), start) {
+            (false, None) => start = Some(i),
+            (true, Some(s)) => {
+                push_word(text, Span::new(s, i), kind, usages);
+                start = None;
+            }
+            _ => {}
+        }
+        i += c.len_utf8();
+    }
+    if let Some(s) = start {
+        push_word(text, Span::new(s, span.end), kind, usages);
+    }
+}
+
+fn push_word(text: &str, span: Span, kind: NameKind, usages: &mut Vec<Usage>) {
+    let name = span.text(text);
+    // Skip template syntax such as `{{ cls }}` or `<%= cls %>`.
+    if name.contains(['{', '}', '<', '>', '$']) {
+        return;
+    }
+    usages.push(Usage {
+        kind,
+        name: name.to_string(),
+        span,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The kind and name of each usage, checking that its span covers the
+    /// name.
+    fn found(text: &str) -> Vec<(NameKind, String)> {
+        usages(text)
+            .into_iter()
+            .map(|usage| {
+                assert_eq!(usage.span.text(text), usage.name);
+                (usage.kind, usage.name)
+            })
+            .collect()
+    }
+
+    fn classes(names: &[&str]) -> Vec<(NameKind, String)> {
+        names.iter().map(|name| (NameKind::Class, name.to_string())).collect()
+    }
+
+    #[test]
+    fn static_attributes() {
+        assert_eq!(
+            found("<div class=\"a  b\n c\" id='d'><p className=\"e\" data-class=\"f\">"),
+            [
+                (NameKind::Class, "a".to_string()),
+                (NameKind::Class, "b".to_string()),
+                (NameKind::Class, "c".to_string()),
+                (NameKind::Id, "d".to_string()),
+                (NameKind::Class, "e".to_string()),
+            ]
+        );
+        // Only attributes, after whitespace.
+        assert_eq!(found("<p>class=\"a\"</p>"), []);
+        // A value left open runs to the end of the text.
+        assert_eq!(found("<p class=\"a b"), classes(&["a", "b"]));
+    }
+
+    #[test]
+    fn template_syntax() {
+        assert_eq!(found("<p class=\"a {{ b }} c-${d} e\">"), classes(&["a", "e"]));
+        assert_eq!(found("<p class=\"{% if b %}a{% endif %} c\">"), classes(&["c"]));
+        assert_eq!(found("<p class=\"<%= a %> b\">"), classes(&["b"]));
+    }
+
+    #[test]
+    fn jsx_bindings() {
+        let text = "<p className={clsx(\"a b\", { c: on, 'd-e': off }, `f ${g ? 'h' : ''} i`)} />";
+        assert_eq!(found(text), classes(&["a", "b", "c", "d-e", "f", "i"]));
+        // Identifiers other than object keys are not class names.
+        assert_eq!(found("<p className={styles.a} />"), []);
+    }
+
+    #[test]
+    fn vue_and_angular_bindings() {
+        let text = "<p :class=\"{ a: x, b: y }\" v-bind:id=\"'c'\" [ngClass]=\"['d', e]\">";
+        assert_eq!(
+            found(text),
+            [
+                (NameKind::Class, "a".to_string()),
+                (NameKind::Class, "b".to_string()),
+                (NameKind::Id, "c".to_string()),
+                (NameKind::Class, "d".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn usage_at() {
+        let template = Template::new("<p class=\"ab cd\">".to_string(), Encoding::default());
+        let name = |offset| template.usage_at(offset).map(|usage| usage.name.as_str());
+        assert_eq!(
+            (name(10), name(12), name(13), name(16)),
+            (Some("ab"), Some("ab"), Some("cd"), None)
+        );
+    }
+}
@@ -0,0 +1,29 @@
+use std::path::{Path, PathBuf};
+
+use lsp_types::Uri;
+
+/// The file system path of a `file:` URI.
+pub fn to_path(uri: &Uri) -> Option<PathBuf> {
+    if !uri.scheme()?.as_str().eq_ignore_ascii_case("file") {
+        return None;
+    }
+    let path = uri.path().as_estr().decode().into_string_lossy();
+    Some(PathBuf::from(path.as_ref()))
+}
+
+/// The `file:` URI of an absolute path.
+pub fn from_path(path: &Path) -> Option<Uri> {
+    format!("file://{}", escape(path.to_str()?)).parse().ok()
+}
+
+/// Percent-encodes a path for a URI, keeping its slashes.
+pub fn escape(path: &str) -> String {
+    let mut escaped = String::new();
+    for &b in path.as_bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => escaped.push(b as char),
+            _ => escaped.push_str(&format!("%{b:02X}")),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,212 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use lsp_types::Uri;
+
+use crate::{
+    css::selector::NameKind,
+    css_modules::{self, Import, Script},
+    document::Document,
+    line_index::Encoding,
+    template::{self, Template},
+    uri,
+};
+
+/// The stylesheets and markup under the workspace root, read from disk.
+/// Open documents take precedence over the files here.
+#[derive(Debug, Default)]
+pub struct Workspace {
+    /// Files containing CSS.
+    pub files: BTreeMap<Uri, Document>,
+    /// Files whose markup uses class names and ids. Kept in sync with open
+    /// documents.
+    pub templates: BTreeMap<Uri, Template>,
+    /// Scripts importing CSS Modules. Kept in sync with open documents.
+    pub scripts: BTreeMap<Uri, Script>,
+    /// The encoding of the positions in the files.
+    pub encoding: Encoding,
+    used: HashSet<(NameKind, String)>,
+}
+
+/// The language of a file containing CSS, by extension.
+pub fn language_id(extension: &str) -> Option<&'static str> {
+    match extension {
+        "css" => Some("css"),
+        "html" | "htm" => Some("html"),
+        "vue" => Some("vue"),
+        _ => None,
+    }
+}
+
+/// Directories that are not worth indexing.
+const SKIPPED: &[&str] = &["node_modules", "target"];
+
+/// The files under `root`, skipping hidden and `SKIPPED` directories.
+pub async fn walk(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if entry.file_type().await?.is_dir() {
+                if !name.starts_with('.') && !SKIPPED.contains(&name.as_ref()) {
+                    dirs.push(path);
+                }
+                continue;
+            }
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+impl Workspace {
+    /// Reads the files under `root`. A folder deleted since the client
+    /// opened it has none.
+    pub async fn index(root: &Path, encoding: Encoding) -> Result<Self> {
+        let mut workspace = Workspace {
+            encoding,
+            ..Default::default()
+        };
+        let paths = match tokio::fs::try_exists(root).await? {
+            true => walk(root).await?,
+            false => vec![],
+        };
+        for path in paths {
+            if let Some(uri) = uri::from_path(&path) {
+                workspace.read(&uri, &path).await;
+            }
+        }
+        workspace.update_used();
+        Ok(workspace)
+    }
+
+    /// Re-reads a file, e.g. after the editor closed it and its unsaved
+    /// changes were discarded.
+    pub async fn reload(&mut self, uri: &Uri) {
+        self.files.remove(uri);
+        self.templates.remove(uri);
+        self.scripts.remove(uri);
+        if let Some(path) = uri::to_path(uri) {
+            self.read(uri, &path).await;
+        }
+        self.update_used();
+    }
+
+    async fn read(&mut self, uri: &Uri, path: &Path) {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        let language_id = language_id(&extension);
+        let template = template::is_template(&extension);
+        let script = css_modules::is_script(&extension);
+        if language_id.is_none() && !template && !script {
+            return;
+        }
+        let Ok(text) = tokio::fs::read_to_string(path).await else {
+            return;
+        };
+        if template {
+            self.templates
+                .insert(uri.clone(), Template::new(text.clone(), self.encoding));
+        }
+        if script && text.contains(".module.css") {
+            self.scripts
+                .insert(uri.clone(), Script::new(text.clone(), path, self.encoding));
+        }
+        if let Some(language_id) = language_id {
+            self.files.insert(
+                uri.clone(),
+                Document::new(uri.clone(), language_id.to_string(), 0, text, self.encoding),
+            );
+        }
+    }
+
+    /// Forgets the files under `dir`, which belong to another workspace
+    /// folder.
+    pub fn exclude(&mut self, dir: &Path) {
+        let outside = |uri: &Uri| !uri::to_path(uri).is_some_and(|path| path.starts_with(dir));
+        self.files.retain(|uri, _| outside(uri));
+        self.templates.retain(|uri, _| outside(uri));
+        self.scripts.retain(|uri, _| outside(uri));
+        self.update_used();
+    }
+
+    /// Replaces the markup of a template with the text of an open document.
+    pub fn set_template(&mut self, uri: &Uri, text: &str) {
+        self.templates
+            .insert(uri.clone(), Template::new(text.to_string(), self.encoding));
+        self.update_used();
+    }
+
+    /// Replaces a script with the text of an open document.
+    pub fn set_script(&mut self, uri: &Uri, text: &str) {
+        let Some(path) = uri::to_path(uri) else { return };
+        self.scripts
+            .insert(uri.clone(), Script::new(text.to_string(), &path, self.encoding));
+    }
+
+    /// The imports of a CSS Module, with the scripts they are in.
+    pub fn module_imports<'a>(&'a self, module: &'a Uri) -> impl Iterator<Item = (&'a Uri, &'a Import)> {
+        self.scripts.iter().flat_map(move |(uri, script)| {
+            script
+                .imports
+                .iter()
+                .filter(move |import| import.module == *module)
+                .map(move |import| (uri, import))
+        })
+    }
+
+    /// Whether unused selectors can be told apart: without any markup in the
+    /// workspace, every selector would look unused.
+    pub fn has_templates(&self) -> bool {
+        !self.templates.is_empty()
+    }
+
+    /// Whether any template uses the class name or id.
+    pub fn is_used(&self, kind: NameKind, name: &str) -> bool {
+        self.used.contains(&(kind, name.to_string()))
+    }
+
+    fn update_used(&mut self) {
+        self.used = self
+            .templates
+            .values()
+            .flat_map(|template| &template.usages)
+            .map(|usage| (usage.kind, usage.name.clone()))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn used_names_follow_templates() {
+        let mut workspace = Workspace::default();
+        let (a, b): (Uri, Uri) = ("file:///a.html".parse().unwrap(), "file:///b.vue".parse().unwrap());
+        assert!(!workspace.has_templates());
+        workspace.set_template(&a, "<p class=\"x\" id=\"y\">");
+        workspace.set_template(&b, "<p :class=\"{ z: on }\">");
+        assert!(workspace.has_templates());
+        assert!(workspace.is_used(NameKind::Class, "x"));
+        assert!(workspace.is_used(NameKind::Id, "y"));
+        assert!(!workspace.is_used(NameKind::Class, "y"));
+        assert!(workspace.is_used(NameKind::Class, "z"));
+        // Editing one template forgets what it no longer uses.
+        workspace.set_template(&a, "<p class=\"w\">");
+        assert!(!workspace.is_used(NameKind::Class, "x"));
+        assert!(workspace.is_used(NameKind::Class, "z"));
+        workspace.exclude(Path::new("/"));
+        assert!(!workspace.has_templates());
+        assert!(!workspace.is_used(NameKind::Class, "w"));
+    }
+}
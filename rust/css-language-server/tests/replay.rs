@@ -0,0 +1,34 @@
+//! Replays the sessions in `tests/recordings`, which were written with
+//! `--record`, and fails if the server now answers differently. A session
+//! whose change in behavior is intended can be recorded again.
+
+use std::process::Command;
+
+#[test]
+fn recordings() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/recordings");
+    let mut recordings: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "jsonl"))
+        .collect();
+    recordings.sort();
+    assert!(!recordings.is_empty());
+
+    let mut failed = vec![];
+    for recording in recordings {
+        let output = Command::new(env!("CARGO_BIN_EXE_css-language-server"))
+            .arg("replay")
+            .arg(&recording)
+            .env_remove("LOG_PATH")
+            .output()
+            .unwrap();
+        if !output.status.success() {
+            eprintln!("{}:", recording.display());
+            eprintln!("{}", String::from_utf8_lossy(&output.stdout));
+            eprintln!("{}", String::from_utf8_lossy(&output.stderr));
+            failed.push(recording);
+        }
+    }
+    assert!(failed.is_empty(), "replaying {failed:?} gave different messages");
+}